@@ -181,6 +181,36 @@ CREATE TABLE IF NOT EXISTS backup_records (
 CREATE INDEX IF NOT EXISTS idx_backup_records_created_at ON backup_records(created_at);
 CREATE INDEX IF NOT EXISTS idx_backup_records_type ON backup_records(backup_type);
 
+-- 客户端备份记录（备份管理器使用）
+CREATE SEQUENCE IF NOT EXISTS backup_id_seq;
+
+CREATE TABLE IF NOT EXISTS backups (
+    id INTEGER PRIMARY KEY DEFAULT nextval('backup_id_seq'),
+    file_path VARCHAR NOT NULL, -- 备份文件路径
+    service_version VARCHAR NOT NULL, -- 备份时的服务版本
+    backup_type VARCHAR NOT NULL, -- manual/pre-upgrade
+    status VARCHAR NOT NULL, -- completed/failed
+    backup_mode VARCHAR NOT NULL DEFAULT 'FULL', -- FULL/INCREMENTAL/DIFFERENTIAL
+    parent_id INTEGER, -- 增量/差异备份所基于的备份
+    encryption_fingerprint VARCHAR, -- 加密密钥指纹（未加密为NULL）
+    backup_hash VARCHAR, -- 备份文件SHA-256
+    file_count BIGINT, -- 归档中的文件数量
+    backup_size BIGINT, -- 备份文件大小（字节）
+    storage_target VARCHAR NOT NULL DEFAULT 'local', -- 存储目标：local 或远程存储（如 s3://bucket/prefix）
+    compression_type VARCHAR NOT NULL DEFAULT 'gzip', -- 压缩格式：gzip/zstd/none
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- 兼容没有备份链、加密、校验、存储目标和压缩格式字段的旧备份表
+ALTER TABLE backups ADD COLUMN IF NOT EXISTS backup_mode VARCHAR DEFAULT 'FULL';
+ALTER TABLE backups ADD COLUMN IF NOT EXISTS parent_id INTEGER;
+ALTER TABLE backups ADD COLUMN IF NOT EXISTS encryption_fingerprint VARCHAR;
+ALTER TABLE backups ADD COLUMN IF NOT EXISTS backup_hash VARCHAR;
+ALTER TABLE backups ADD COLUMN IF NOT EXISTS file_count BIGINT;
+ALTER TABLE backups ADD COLUMN IF NOT EXISTS backup_size BIGINT;
+ALTER TABLE backups ADD COLUMN IF NOT EXISTS storage_target VARCHAR DEFAULT 'local';
+ALTER TABLE backups ADD COLUMN IF NOT EXISTS compression_type VARCHAR DEFAULT 'gzip';
+
 -- ========================================
 -- 升级管理表
 -- ========================================
@@ -280,7 +310,12 @@ INSERT OR REPLACE INTO app_config (config_key, config_value, config_type, catego
 -- 备份配置
 ('backup.retention_days', '30', 'NUMBER', 'backup', '备份保留天数', FALSE, TRUE, '30'),
 ('backup.auto_cleanup', 'true', 'BOOLEAN', 'backup', '自动清理过期备份', FALSE, TRUE, 'true'),
+('backup.keep_last', '0', 'NUMBER', 'backup', '至少保留最近的备份数量（0表示不启用）', FALSE, TRUE, '0'),
+('backup.keep_daily', '0', 'NUMBER', 'backup', '按天保留备份的天数（0表示不启用）', FALSE, TRUE, '0'),
+('backup.keep_weekly', '0', 'NUMBER', 'backup', '按周保留备份的周数（0表示不启用）', FALSE, TRUE, '0'),
+('backup.keep_monthly', '0', 'NUMBER', 'backup', '按月保留备份的月数（0表示不启用）', FALSE, TRUE, '0'),
 ('backup.compression_enabled', 'true', 'BOOLEAN', 'backup', '备份文件压缩', FALSE, TRUE, 'true'),
+('backup.compression', '"gzip"', 'STRING', 'backup', '备份压缩格式（gzip、zstd、none），zstd使用多线程压缩', FALSE, TRUE, '"gzip"'),
 ('backup.verify_after_backup', 'true', 'BOOLEAN', 'backup', '备份后文件校验', FALSE, TRUE, 'true'),
 
 -- 自动备份配置
@@ -288,12 +323,14 @@ INSERT OR REPLACE INTO app_config (config_key, config_value, config_type, catego
 ('auto_backup_schedule', '"0 2 * * *"', 'STRING', 'backup', '自动备份计划(cron表达式)', FALSE, TRUE, '"0 2 * * *"'),
 ('auto_backup_retention_days', '7', 'NUMBER', 'backup', '自动备份保留天数', FALSE, TRUE, '7'),
 ('auto_backup_directory', '"./backups"', 'STRING', 'backup', '自动备份目录', FALSE, TRUE, '"./backups"'),
+('auto_backup_hot', 'false', 'BOOLEAN', 'backup', '自动备份使用热备份（不停止服务）', FALSE, TRUE, 'false'),
 ('auto_backup_last_time', '""', 'STRING', 'backup', '上次自动备份时间', FALSE, FALSE, '""'),
 ('auto_backup_last_status', '""', 'STRING', 'backup', '上次自动备份状态', FALSE, FALSE, '""'),
 
 -- 升级配置
 ('upgrade.auto_backup_enabled', 'true', 'BOOLEAN', 'upgrade', '升级前自动备份', FALSE, TRUE, 'true'),
 ('upgrade.rollback_enabled', 'true', 'BOOLEAN', 'upgrade', '升级失败自动回滚', FALSE, TRUE, 'true'),
+('upgrade.health_check_timeout', '180', 'NUMBER', 'upgrade', '升级后等待服务健康运行的时间（秒），超时自动回滚', FALSE, TRUE, '180'),
 ('upgrade.auto_check_update', 'true', 'BOOLEAN', 'upgrade', '自动检查更新', FALSE, TRUE, 'true'),
 ('upgrade.check_interval_hours', '24', 'NUMBER', 'upgrade', '更新检查间隔（小时）', FALSE, TRUE, '24'),
 
@@ -59,11 +59,12 @@ use crate::api_config::ApiConfig;
 use crate::authenticated_client::AuthenticatedClient;
 use crate::downloader::{DownloadProgress, DownloadStatus, FileDownloader, DownloaderConfig};
 use crate::error::{DuckError, Result};
+use crate::package_signature;
 use chrono;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 use std::io::{self, Write};
 use futures::stream::StreamExt;
@@ -118,6 +119,13 @@ pub struct PackageInfo {
     pub hash: String,
     pub signature: String,
     pub size: u64,
+    /// 补丁包适用的基础版本（仅补丁包有）
+    #[serde(default)]
+    pub base_version: Option<String>,
+    /// 服务包的文件清单（相对 `docker/` 的路径 -> SHA-256，仅完整服务包有），
+    /// 用于校验补丁后的服务目录
+    #[serde(default)]
+    pub files: Option<BTreeMap<String, String>>,
 }
 
 /// 客户端更新清单响应
@@ -188,7 +196,7 @@ pub struct DockerVersionListResponse {
 }
 
 /// Docker版本信息
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct DockerVersion {
     pub version: String,
     pub release_date: String,
@@ -819,6 +827,46 @@ impl ApiClient {
         }
     }
 
+    /// 获取指定版本的Docker服务清单（用于安装非最新版本）
+    pub async fn get_docker_service_manifest_for_version(
+        &self,
+        version: &str,
+    ) -> Result<ServiceManifest> {
+        let url = self.config.get_docker_version_manifest_url(version);
+
+        let response = self.build_request(&url).send().await?;
+
+        if response.status().is_success() {
+            let manifest: ServiceManifest = response.json().await?;
+            Ok(manifest)
+        } else {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            error!("获取Docker服务 {} 版本清单失败: {} - {}", version, status, text);
+            Err(DuckError::Api(format!(
+                "获取Docker服务 {version} 版本清单失败: {status} - {text}"
+            )))
+        }
+    }
+
+    /// 获取目标版本的Docker服务清单（未指定版本或请求最新版本时使用最新版本的清单）
+    async fn get_docker_service_manifest_for_target(
+        &self,
+        version: Option<&str>,
+    ) -> Result<ServiceManifest> {
+        let manifest = self.get_docker_service_manifest().await?;
+        match version {
+            Some(target_version) if target_version != manifest.version => {
+                info!(
+                    "📌 请求版本 {} 不是服务器最新版本 {}，获取该版本的服务清单",
+                    target_version, manifest.version
+                );
+                self.get_docker_service_manifest_for_version(target_version).await
+            }
+            _ => Ok(manifest),
+        }
+    }
+
     /// 下载服务更新包（带哈希验证和优化及进度回调）
     pub async fn download_service_update_optimized_with_progress<F>(
         &self,
@@ -829,9 +877,9 @@ impl ApiClient {
     where
         F: Fn(DownloadProgress) + Send + Sync + 'static,
     {
-        // 1. 获取服务清单信息
+        // 1. 获取服务清单信息（请求的不是最新版本时获取该版本的清单）
         info!("🔍 获取服务版本信息...");
-        let manifest = self.get_docker_service_manifest().await?;
+        let manifest = self.get_docker_service_manifest_for_target(version).await?;
 
         info!("📋 服务清单信息:");
         info!("   版本: {}", manifest.version);
@@ -851,14 +899,21 @@ impl ApiClient {
         // 2. 检查版本参数
         if let Some(target_version) = version {
             if target_version != manifest.version {
-                warn!(
-                    "⚠️  请求版本 {} 与服务器版本 {} 不匹配",
-                    target_version, manifest.version
-                );
-                info!("   将下载服务器版本: {}", manifest.version);
+                return Err(DuckError::Api(format!(
+                    "服务器返回的服务清单版本 {} 与请求版本 {} 不匹配",
+                    manifest.version, target_version
+                )));
             }
         }
 
+        // 保存服务包签名，解压前使用内置公钥校验
+        if let Err(e) =
+            package_signature::save_signature(download_path, &manifest.packages.full.signature)
+                .await
+        {
+            warn!("⚠️  保存服务包签名失败，解压前的签名校验将无法通过: {}", e);
+        }
+
         // 3. 获取哈希文件路径
         let hash_file_path = download_path.with_extension("zip.hash");
         
@@ -1205,6 +1260,78 @@ impl ApiClient {
         .await
     }
 
+    /// 下载从已安装版本升级到目标版本的补丁包
+    ///
+    /// 返回目标版本服务清单中完整服务包的文件清单，用于校验补丁后的服务目录。
+    /// 服务清单中没有补丁包、补丁包的基础版本与 `installed_version` 不一致，或完整服务包没有
+    /// 文件清单时返回 `None`，由调用方使用完整服务包；补丁包必须提供哈希，下载后校验不通过时返回错误。
+    pub async fn download_service_patch(
+        &self,
+        patch_path: &Path,
+        version: Option<&str>,
+        installed_version: &str,
+    ) -> Result<Option<BTreeMap<String, String>>> {
+        let manifest = self.get_docker_service_manifest_for_target(version).await?;
+        let Some(patch) = manifest.packages.patch.as_ref() else {
+            info!("服务版本 {} 没有补丁包", manifest.version);
+            return Ok(None);
+        };
+        let full_files = match &manifest.packages.full.files {
+            Some(files) if !files.is_empty() => files.clone(),
+            _ => {
+                warn!("⚠️  完整服务包未提供文件清单，无法校验补丁，使用完整服务包");
+                return Ok(None);
+            }
+        };
+
+        let base_version = patch.base_version.as_deref().unwrap_or_default();
+        if base_version.trim_start_matches('v') != installed_version.trim_start_matches('v') {
+            info!(
+                "补丁包基础版本 {} 与已安装版本 {} 不一致，使用完整服务包",
+                base_version, installed_version
+            );
+            return Ok(None);
+        }
+        if patch.hash.is_empty() || patch.hash.eq_ignore_ascii_case("external") {
+            warn!("⚠️  补丁包未提供哈希，无法校验，使用完整服务包");
+            return Ok(None);
+        }
+
+        package_signature::save_signature(patch_path, &patch.signature).await?;
+
+        if patch_path.exists() && Self::verify_file_integrity(patch_path, &patch.hash).await? {
+            info!("✅ 补丁包已下载，跳过下载: {}", patch_path.display());
+            return Ok(Some(full_files));
+        }
+
+        let (download_url, use_auth) =
+            if patch.url.starts_with("http://") || patch.url.starts_with("https://") {
+                (patch.url.clone(), patch.url.contains(&self.config.base_url))
+            } else {
+                (format!("{}{}", self.config.base_url, patch.url), true)
+            };
+
+        if let Some(parent) = patch_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        info!(
+            "📥 下载补丁包 {} -> {}: {}",
+            base_version, manifest.version, download_url
+        );
+        self.download_service_update_from_url_with_auth(&download_url, patch_path, use_auth)
+            .await?;
+
+        if !Self::verify_file_integrity(patch_path, &patch.hash).await? {
+            let _ = tokio::fs::remove_file(patch_path).await;
+            return Err(DuckError::Api(format!(
+                "补丁包哈希校验失败: {}",
+                patch_path.display()
+            )));
+        }
+
+        Ok(Some(full_files))
+    }
+
     /// 保存哈希文件
     async fn save_hash_file(&self, hash_file_path: &Path, hash: &str, version: &str) -> Result<()> {
         let timestamp = chrono::Utc::now().to_rfc3339();
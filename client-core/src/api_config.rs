@@ -14,6 +14,8 @@ pub struct ApiEndpoints {
     pub docker_check_version: String,
     /// Docker版本列表更新端点
     pub docker_update_version_list: String,
+    /// 指定版本的Docker服务清单端点
+    pub docker_version_manifest: String,
     /// Docker完整服务包下载端点
     pub docker_download_full: String,
     /// 客户端自升级历史端点
@@ -42,6 +44,7 @@ impl Default for ApiConfig {
                 announcements: api::endpoints::ANNOUNCEMENTS.to_string(),
                 docker_check_version: api::endpoints::DOCKER_CHECK_VERSION.to_string(),
                 docker_update_version_list: api::endpoints::DOCKER_UPDATE_VERSION_LIST.to_string(),
+                docker_version_manifest: api::endpoints::DOCKER_VERSION_MANIFEST.to_string(),
                 docker_download_full: api::endpoints::DOCKER_DOWNLOAD_FULL.to_string(),
                 client_self_upgrade_history: api::endpoints::CLIENT_SELF_UPGRADE_HISTORY
                     .to_string(),
@@ -78,6 +81,15 @@ impl ApiConfig {
         self.get_endpoint_url(&self.endpoints.docker_update_version_list)
     }
 
+    /// 获取指定版本的Docker服务清单完整URL（替换version占位符）
+    pub fn get_docker_version_manifest_url(&self, version: &str) -> String {
+        let endpoint = self
+            .endpoints
+            .docker_version_manifest
+            .replace("{version}", version);
+        self.get_endpoint_url(&endpoint)
+    }
+
     /// 获取Docker完整服务包下载完整URL
     pub fn get_docker_download_full_url(&self) -> String {
         self.get_endpoint_url(&self.endpoints.docker_download_full)
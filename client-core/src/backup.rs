@@ -1,10 +1,26 @@
 use crate::{
     DuckError, Result,
+    backup_compression::CompressionWriter,
+    backup_encryption::BackupEncryption,
+    backup_retention::RetentionPolicy,
+    backup_storage::{BackupStorage, StoredObject},
+    constants::{
+        backup,
+        docker::{self, data_dirs},
+    },
     container::DockerManager,
-    database::{BackupRecord, BackupStatus, BackupType, Database},
+    database::{
+        BackupMode, BackupRecord, BackupStatus, BackupType, CompressionType, Database,
+        NewBackupRecord,
+    },
 };
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
 use walkdir::WalkDir;
 
 /// 备份管理器
@@ -13,6 +29,8 @@ pub struct BackupManager {
     storage_dir: PathBuf,
     database: Database,
     docker_manager: DockerManager,
+    encryption: BackupEncryption,
+    remote_storage: Option<Arc<dyn BackupStorage>>,
 }
 
 /// 备份选项
@@ -20,12 +38,18 @@ pub struct BackupManager {
 pub struct BackupOptions {
     /// 备份类型
     pub backup_type: BackupType,
+    /// 备份模式（全量、增量或差异）
+    pub backup_mode: BackupMode,
     /// 服务版本
     pub service_version: String,
     /// 要备份的目录列表
     pub source_dirs: Vec<PathBuf>,
-    /// 压缩级别 (0-9)
+    /// 压缩格式
+    pub compression_type: CompressionType,
+    /// 压缩级别（gzip 0-9，zstd 1-22）
     pub compression_level: u32,
+    /// 热备份：服务运行时备份，MySQL数据通过mysqldump导出，Redis先执行BGSAVE
+    pub hot_backup: bool,
 }
 
 /// 恢复选项
@@ -35,6 +59,435 @@ pub struct RestoreOptions {
     pub target_dir: PathBuf,
     /// 是否强制覆盖
     pub force_overwrite: bool,
+    /// 只恢复指定的路径（为空时恢复全部）
+    pub filter: RestoreFilter,
+}
+
+/// 选择性恢复：只恢复归档内路径（`{目录名}/{相对路径}`）在指定路径下的文件
+///
+/// 没有指定任何路径时恢复全部文件。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RestoreFilter {
+    paths: Vec<String>,
+}
+
+impl RestoreFilter {
+    /// 支持的服务预设
+    pub const SERVICE_PRESETS: [&str; 5] = ["mysql", "redis", "milvus", "upload", "config"];
+
+    /// 恢复指定路径（相对于Docker工作目录，如 `data/redis`、`upload/`）
+    pub fn with_path(mut self, path: &str) -> Result<Self> {
+        let normalized = normalize_archive_path(path)?;
+        if !self.paths.contains(&normalized) {
+            self.paths.push(normalized);
+        }
+        Ok(self)
+    }
+
+    /// 恢复指定服务的数据（预设：mysql、redis、milvus、upload、config）
+    pub fn with_service(self, service: &str) -> Result<Self> {
+        let paths: &[&str] = match service.trim().to_lowercase().as_str() {
+            "mysql" => &[data_dirs::MYSQL_DATA_DIR, backup::MYSQL_DUMP_ARCHIVE_PATH],
+            "redis" => &[data_dirs::REDIS_DATA_DIR],
+            "milvus" => &[data_dirs::MILVUS_DATA_DIR],
+            "upload" => &[docker::UPLOAD_DIR_NAME],
+            "config" => &[
+                docker::CONFIG_DIR_NAME,
+                docker::ENV_FILE_NAME,
+                docker::COMPOSE_FILE_NAME,
+            ],
+            _ => {
+                return Err(DuckError::Backup(format!(
+                    "未知的服务: {service}，可选: {}",
+                    Self::SERVICE_PRESETS.join(", ")
+                )));
+            }
+        };
+
+        paths
+            .iter()
+            .try_fold(self, |filter, path| filter.with_path(path))
+    }
+
+    /// 是否恢复全部文件
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// 要恢复的路径
+    pub fn paths(&self) -> &[String] {
+        &self.paths
+    }
+
+    /// 归档内路径是否需要恢复
+    pub fn matches(&self, archive_path: &str) -> bool {
+        self.paths.is_empty()
+            || self
+                .paths
+                .iter()
+                .any(|path| is_under_path(archive_path, path))
+    }
+}
+
+/// 把用户输入的路径（如 `./upload/`）转换为归档内路径格式（`upload`）
+fn normalize_archive_path(path: &str) -> Result<String> {
+    let normalized = path.trim().replace('\\', "/");
+    let normalized = normalized.trim_start_matches("./").trim_end_matches('/');
+    if normalized.is_empty()
+        || normalized.starts_with('/')
+        || normalized
+            .split('/')
+            .any(|component| component.is_empty() || component == "." || component == "..")
+    {
+        return Err(DuckError::Backup(format!("无效的备份内路径: {path}")));
+    }
+    Ok(normalized.to_string())
+}
+
+/// 归档内路径是否是指定路径本身或在其之下
+fn is_under_path(archive_path: &str, path: &str) -> bool {
+    archive_path
+        .strip_prefix(path)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// 归档内路径是否属于MySQL数据（数据目录下的文件或热备份的导出文件）
+fn is_mysql_data_path(archive_path: &str) -> bool {
+    is_under_path(archive_path, data_dirs::MYSQL_DATA_DIR)
+        || archive_path == backup::MYSQL_DUMP_ARCHIVE_PATH
+}
+
+/// 恢复路径在目录中的位置（空字符串表示目录本身）
+fn unit_path(dir: &Path, unit: &str) -> PathBuf {
+    if unit.is_empty() {
+        dir.to_path_buf()
+    } else {
+        dir.join(unit)
+    }
+}
+
+/// 在同一文件系统内移动文件或目录（自动创建目标的父目录）
+async fn move_path(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::rename(from, to).await.map_err(|e| {
+        DuckError::Backup(format!(
+            "移动 {} 到 {} 失败: {e}",
+            from.display(),
+            to.display()
+        ))
+    })
+}
+
+/// 删除文件或目录（不存在时忽略）
+async fn remove_path(path: &Path) -> Result<()> {
+    if path.is_dir() {
+        tokio::fs::remove_dir_all(path).await?;
+    } else if path.exists() {
+        tokio::fs::remove_file(path).await?;
+    }
+    Ok(())
+}
+
+/// 备份时单个文件的状态
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileSnapshot {
+    /// 文件大小（字节）
+    pub size: u64,
+    /// 修改时间（自UNIX纪元起的纳秒数）
+    pub modified: u64,
+    /// 文件内容的SHA-256
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+/// 备份快照：备份时源目录中所有文件的状态，按归档内路径（`{目录名}/{相对路径}`）索引
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BackupSnapshot {
+    pub files: BTreeMap<String, FileSnapshot>,
+}
+
+/// 备份清单中的单个文件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// 归档内路径
+    pub path: String,
+    /// 文件大小（字节）
+    pub size: u64,
+    /// 文件权限
+    pub mode: u32,
+    /// 文件内容的SHA-256
+    pub sha256: String,
+}
+
+/// 备份清单：归档中每个文件的路径、大小、权限和哈希，作为归档的最后一个条目保存
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub files: Vec<ManifestEntry>,
+    /// 备份元数据（早期版本创建的备份没有）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<BackupMetadata>,
+}
+
+/// 备份元数据：导入其他机器上创建的备份时用于创建备份记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupMetadata {
+    /// 备份时的服务版本
+    pub service_version: String,
+    /// 备份类型
+    pub backup_type: BackupType,
+    /// 备份模式（导出的备份总是全量备份）
+    pub backup_mode: BackupMode,
+    /// 压缩格式
+    pub compression_type: CompressionType,
+    /// 加密密钥指纹（未加密为None）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption_fingerprint: Option<String>,
+    /// 创建时间
+    pub created_at: DateTime<Utc>,
+}
+
+/// 备份文件的校验信息（记录到备份记录中）
+#[derive(Debug, Clone)]
+struct BackupChecksum {
+    /// 备份文件（加密后）的SHA-256
+    backup_hash: String,
+    /// 归档中的文件数量（不含清单）
+    file_count: i64,
+    /// 备份文件大小（字节）
+    backup_size: i64,
+}
+
+/// 源目录之外对归档内容的调整
+#[derive(Debug, Default)]
+struct ArchiveExtras {
+    /// 不打包的目录（归档内路径，如 `data/mysql`）
+    excluded_dirs: Vec<String>,
+    /// 额外打包的文件：(本地路径, 归档内路径)
+    extra_files: Vec<(PathBuf, String)>,
+    /// 写入备份清单的元数据
+    metadata: Option<BackupMetadata>,
+}
+
+/// 深度校验备份的结果
+#[derive(Debug, Clone, Default)]
+pub struct BackupVerification {
+    /// 备份文件哈希是否与记录一致（记录中没有哈希时为None）
+    pub backup_hash_matches: Option<bool>,
+    /// 读取归档时的错误（归档损坏或无法解密）
+    pub archive_error: Option<String>,
+    /// 归档中是否有备份清单（早期版本创建的备份没有清单）
+    pub manifest_found: bool,
+    /// 已校验的文件数量
+    pub checked_files: usize,
+    /// 清单中有但归档中缺失的文件
+    pub missing_files: Vec<String>,
+    /// 大小或哈希与清单不一致的文件
+    pub corrupt_files: Vec<String>,
+    /// 归档中有但清单中没有的文件
+    pub unexpected_files: Vec<String>,
+}
+
+impl BackupVerification {
+    /// 备份是否完整
+    pub fn is_valid(&self) -> bool {
+        self.backup_hash_matches != Some(false)
+            && self.archive_error.is_none()
+            && self.manifest_found
+            && self.missing_files.is_empty()
+            && self.corrupt_files.is_empty()
+            && self.unexpected_files.is_empty()
+    }
+}
+
+/// 备份中的单个文件（浏览备份内容时使用）
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackupEntry {
+    /// 归档内路径
+    pub path: String,
+    /// 文件大小（字节）
+    pub size: u64,
+    /// 修改时间
+    pub modified: Option<DateTime<Utc>>,
+    /// 文件内容的SHA-256（来自备份清单，早期版本创建的备份没有）
+    pub sha256: Option<String>,
+    /// 文件所在的备份（增量/差异备份中未变化的文件在之前的备份中）
+    pub backup_id: i64,
+}
+
+/// 两个备份之间的文件差异（归档内路径）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BackupDiff {
+    /// 新增的文件
+    pub added: Vec<String>,
+    /// 删除的文件
+    pub removed: Vec<String>,
+    /// 内容变化的文件
+    pub changed: Vec<String>,
+}
+
+impl BackupDiff {
+    /// 两个备份的文件是否完全相同
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// 读取文件的同时计算SHA-256
+struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+}
+
+impl<R: std::io::Read> std::io::Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let bytes_read = self.inner.read(buf)?;
+        self.hasher.update(&buf[..bytes_read]);
+        Ok(bytes_read)
+    }
+}
+
+/// 写入的同时计算SHA-256和字节数
+struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256,
+    bytes_written: u64,
+}
+
+impl<W: std::io::Write> std::io::Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let bytes_written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..bytes_written]);
+        self.bytes_written += bytes_written as u64;
+        Ok(bytes_written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// 计算文件内容的SHA-256
+fn calculate_file_hash(path: &Path) -> Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// 把文件添加到归档，同时计算内容哈希，返回备份清单项
+fn append_file_to_archive<W: std::io::Write>(
+    archive: &mut tar::Builder<W>,
+    path: &Path,
+    archive_path: &str,
+    metadata: &std::fs::Metadata,
+) -> Result<ManifestEntry> {
+    // 打包的同时计算哈希，不需要额外读取文件
+    let mut header = tar::Header::new_gnu();
+    header.set_metadata(metadata);
+    let mut reader = HashingReader {
+        inner: std::fs::File::open(path)?,
+        hasher: Sha256::new(),
+    };
+    archive
+        .append_data(&mut header, archive_path, &mut reader)
+        .map_err(|e| DuckError::Backup(format!("添加文件到归档失败: {e}")))?;
+
+    Ok(ManifestEntry {
+        path: archive_path.to_string(),
+        size: metadata.len(),
+        mode: header.mode().unwrap_or(0o644),
+        sha256: format!("{:x}", reader.hasher.finalize()),
+    })
+}
+
+/// 读取归档中每个文件的大小和SHA-256，以及备份清单
+///
+/// 读取出错时已读取的条目仍然保留在结果中。
+fn read_archive_entries(
+    encryption: &BackupEncryption,
+    backup_path: &Path,
+    archived: &mut BTreeMap<String, (u64, String)>,
+    manifest: &mut Option<BackupManifest>,
+) -> Result<()> {
+    use std::io::Read;
+
+    let mut archive = tar::Archive::new(encryption.open_archive(backup_path)?);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path()?.to_string_lossy().to_string();
+        if path == backup::MANIFEST_FILE_NAME {
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content)?;
+            *manifest = Some(serde_json::from_slice(&content)?);
+            continue;
+        }
+        let mut reader = HashingReader {
+            inner: &mut entry,
+            hasher: Sha256::new(),
+        };
+        let size = std::io::copy(&mut reader, &mut std::io::sink())?;
+        archived.insert(path, (size, format!("{:x}", reader.hasher.finalize())));
+    }
+    Ok(())
+}
+
+/// 列出归档中的文件（只读取文件头，不解压文件内容），哈希从备份清单中读取
+fn list_archive_entries(
+    encryption: &BackupEncryption,
+    backup_path: &Path,
+    backup_id: i64,
+) -> Result<Vec<BackupEntry>> {
+    let mut archive = tar::Archive::new(encryption.open_archive(backup_path)?);
+    let mut entries = Vec::new();
+    let mut manifest: Option<BackupManifest> = None;
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path()?.to_string_lossy().replace('\\', "/");
+        if path == backup::MANIFEST_FILE_NAME {
+            manifest = Some(serde_json::from_reader(&mut entry)?);
+            continue;
+        }
+        entries.push(BackupEntry {
+            path,
+            size: entry.header().size()?,
+            modified: entry
+                .header()
+                .mtime()
+                .ok()
+                .and_then(|mtime| DateTime::from_timestamp(mtime as i64, 0)),
+            sha256: None,
+            backup_id,
+        });
+    }
+
+    if let Some(manifest) = manifest {
+        let hashes: BTreeMap<String, String> = manifest
+            .files
+            .into_iter()
+            .map(|file| (file.path, file.sha256))
+            .collect();
+        for entry in &mut entries {
+            entry.sha256 = hashes.get(&entry.path).cloned();
+        }
+    }
+    Ok(entries)
+}
+
+/// 备份中的文件是否变化：两边都有哈希时比较哈希，否则比较大小和修改时间
+fn entry_changed(old: &BackupEntry, new: &BackupEntry) -> bool {
+    match (&old.sha256, &new.sha256) {
+        (Some(old_hash), Some(new_hash)) => old_hash != new_hash,
+        _ => old.size != new.size || old.modified != new.modified,
+    }
 }
 
 impl BackupManager {
@@ -52,10 +505,27 @@ impl BackupManager {
             storage_dir,
             database,
             docker_manager,
+            encryption: BackupEncryption::default(),
+            remote_storage: None,
         })
     }
 
+    /// 设置备份加密：新建的备份按配置加密，恢复和校验时用于解密
+    pub fn with_encryption(mut self, encryption: BackupEncryption) -> Self {
+        self.encryption = encryption;
+        self
+    }
+
+    /// 设置远程备份存储：新建的备份上传到远程存储，本地文件缺失时从远程下载
+    pub fn with_remote_storage(mut self, storage: Arc<dyn BackupStorage>) -> Self {
+        self.remote_storage = Some(storage);
+        self
+    }
+
     /// 创建备份
+    ///
+    /// 增量备份基于最近一次成功且有快照的备份，差异备份基于最近一次有快照的全量备份，
+    /// 只打包相对基准备份新增或变化的文件；找不到基准备份时自动改为全量备份。
     pub async fn create_backup(&self, options: BackupOptions) -> Result<BackupRecord> {
         // 检查所有源目录是否存在
         for source_dir in &options.source_dirs {
@@ -67,62 +537,146 @@ impl BackupManager {
             }
         }
 
-        // 生成备份文件名（人类易读格式）
-        let timestamp = Utc::now().format("%Y-%m-%d_%H-%M-%S");
-        let backup_type_str = match options.backup_type {
-            BackupType::Manual => "manual",
-            BackupType::PreUpgrade => "pre-upgrade",
+        // 查找增量/差异备份的基准备份
+        let (backup_mode, parent) = match options.backup_mode {
+            BackupMode::Full => (BackupMode::Full, None),
+            mode => match self.find_base_backup(mode).await? {
+                Some(base) => (mode, Some(base)),
+                None => {
+                    tracing::warn!("没有找到可用作基准的备份（需要备份快照），将创建全量备份");
+                    (BackupMode::Full, None)
+                }
+            },
         };
-
-        let backup_filename = format!(
-            "backup_{}_v{}_{}.tar.gz",
-            backup_type_str, options.service_version, timestamp
-        );
+        let parent_id = parent.as_ref().map(|(parent, _)| parent.id);
+
+        let metadata = BackupMetadata {
+            service_version: options.service_version.clone(),
+            backup_type: options.backup_type.clone(),
+            backup_mode,
+            compression_type: options.compression_type,
+            encryption_fingerprint: self.encryption.fingerprint(),
+            created_at: Utc::now(),
+        };
+        let backup_filename = self.backup_file_name(&metadata);
+        let encryption_fingerprint = metadata.encryption_fingerprint.clone();
 
         let backup_path = self.storage_dir.join(&backup_filename);
 
-        tracing::info!("开始创建备份: {}", backup_path.display());
+        match parent_id {
+            Some(parent_id) => tracing::info!(
+                "开始创建备份: {} (基于备份 {})",
+                backup_path.display(),
+                parent_id
+            ),
+            None => tracing::info!("开始创建备份: {}", backup_path.display()),
+        }
+
+        // 热备份：先导出MySQL数据、保存Redis数据
+        let mysql_dump_path = self
+            .storage_dir
+            .join(format!(".{backup_filename}.mysql.sql"));
+        let extras = if options.hot_backup {
+            self.prepare_hot_backup(&mysql_dump_path).await
+        } else {
+            Ok(ArchiveExtras::default())
+        }
+        .map(|extras| ArchiveExtras {
+            metadata: Some(metadata),
+            ..extras
+        });
 
         // 执行备份
-        match self
-            .perform_backup(
-                &options.source_dirs,
-                &backup_path,
-                options.compression_level,
-            )
-            .await
-        {
-            Ok(_) => {
-                tracing::info!("备份创建成功: {}", backup_path.display());
+        let result = match extras {
+            Ok(extras) => match self
+                .perform_backup(
+                    &options.source_dirs,
+                    &backup_path,
+                    options.compression_type,
+                    options.compression_level,
+                    parent.map(|(_, snapshot)| snapshot),
+                    extras,
+                )
+                .await
+            {
+                // 快照与备份文件保存在一起，供之后的增量/差异备份比较
+                Ok((snapshot, checksum)) => Self::save_snapshot(&backup_path, &snapshot)
+                    .await
+                    .map(|_| checksum),
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(e),
+        };
+        if mysql_dump_path.exists() {
+            tokio::fs::remove_file(&mysql_dump_path).await?;
+        }
+
+        match result {
+            Ok(checksum) => {
+                tracing::info!(
+                    "备份创建成功: {} ({} 个文件, SHA-256: {})",
+                    backup_path.display(),
+                    checksum.file_count,
+                    checksum.backup_hash
+                );
+
+                // 上传到远程存储，上传失败时备份仍保存在本地
+                let storage_target = self.upload_to_remote(&backup_path).await;
 
                 // 记录到数据库
                 let record_id = self
                     .database
-                    .create_backup_record(
-                        backup_path.to_string_lossy().to_string(),
-                        options.service_version,
-                        options.backup_type,
-                        BackupStatus::Completed,
-                    )
+                    .create_backup_record(NewBackupRecord {
+                        file_path: backup_path.to_string_lossy().to_string(),
+                        service_version: options.service_version,
+                        backup_type: options.backup_type,
+                        status: BackupStatus::Completed,
+                        backup_mode,
+                        parent_id,
+                        encryption_fingerprint,
+                        backup_hash: Some(checksum.backup_hash),
+                        file_count: Some(checksum.file_count),
+                        backup_size: Some(checksum.backup_size),
+                        storage_target,
+                        compression_type: options.compression_type,
+                        created_at: None,
+                    })
                     .await?;
 
                 // 获取创建的记录
-                self.database
+                let backup_record = self
+                    .database
                     .get_backup_by_id(record_id)
                     .await?
-                    .ok_or_else(|| DuckError::Backup("无法获取刚创建的备份记录".to_string()))
+                    .ok_or_else(|| DuckError::Backup("无法获取刚创建的备份记录".to_string()))?;
+
+                // 按保留策略清理旧备份，清理失败不影响本次备份
+                if let Err(e) = self.apply_retention_policy().await {
+                    tracing::warn!("按保留策略清理旧备份失败: {}", e);
+                }
+
+                Ok(backup_record)
             }
             Err(e) => {
                 tracing::error!("备份创建失败: {}", e);
 
                 // 记录失败到数据库
                 self.database
-                    .create_backup_record(
-                        backup_path.to_string_lossy().to_string(),
-                        options.service_version,
-                        options.backup_type,
-                        BackupStatus::Failed,
-                    )
+                    .create_backup_record(NewBackupRecord {
+                        file_path: backup_path.to_string_lossy().to_string(),
+                        service_version: options.service_version,
+                        backup_type: options.backup_type,
+                        status: BackupStatus::Failed,
+                        backup_mode,
+                        parent_id,
+                        encryption_fingerprint,
+                        backup_hash: None,
+                        file_count: None,
+                        backup_size: None,
+                        storage_target: backup::LOCAL_STORAGE_TARGET.to_string(),
+                        compression_type: options.compression_type,
+                        created_at: None,
+                    })
                     .await?;
 
                 Err(e)
@@ -130,15 +684,248 @@ impl BackupManager {
         }
     }
 
-    /// 执行实际的备份操作
+    /// 准备热备份：在MySQL容器内导出业务数据库，并让Redis把数据保存到磁盘
+    ///
+    /// MySQL数据目录在服务运行时不一致，不打包，改为打包导出文件；
+    /// Redis保存失败只记录警告，数据目录仍按文件打包。
+    async fn prepare_hot_backup(&self, mysql_dump_path: &Path) -> Result<ArchiveExtras> {
+        let mut extras = ArchiveExtras::default();
+
+        if let Some(mysql_service) = self.docker_manager.find_mysql_service().await? {
+            self.docker_manager
+                .dump_mysql_databases(&mysql_service, mysql_dump_path)
+                .await?;
+            extras.excluded_dirs = backup::HOT_BACKUP_EXCLUDED_DIRS
+                .iter()
+                .map(|dir| dir.to_string())
+                .collect();
+            extras.extra_files.push((
+                mysql_dump_path.to_path_buf(),
+                backup::MYSQL_DUMP_ARCHIVE_PATH.to_string(),
+            ));
+        } else {
+            tracing::warn!("没有找到MySQL服务，热备份不导出MySQL数据");
+        }
+
+        if let Some(redis_service) = self.docker_manager.find_redis_service().await? {
+            if let Err(e) = self.docker_manager.redis_bgsave(&redis_service).await {
+                tracing::warn!("Redis后台保存失败，Redis数据可能不是最新的: {}", e);
+            }
+        }
+
+        Ok(extras)
+    }
+
+    /// 导入热备份恢复出的MySQL导出文件（服务启动后调用），导入后删除导出文件
+    async fn import_hot_backup_dump(&self, target_dir: &Path) -> Result<()> {
+        let dump_path = target_dir.join(backup::MYSQL_DUMP_ARCHIVE_PATH);
+        if !dump_path.exists() {
+            return Ok(());
+        }
+
+        let mysql_service = self
+            .docker_manager
+            .find_mysql_service()
+            .await?
+            .ok_or_else(|| {
+                DuckError::Backup("热备份包含MySQL数据，但没有找到MySQL服务".to_string())
+            })?;
+        self.docker_manager
+            .wait_for_mysql_ready(&mysql_service)
+            .await?;
+        self.docker_manager
+            .import_mysql_dump(&mysql_service, &dump_path)
+            .await?;
+
+        if let Some(dump_dir) = dump_path.parent() {
+            tokio::fs::remove_dir_all(dump_dir).await?;
+        }
+        Ok(())
+    }
+
+    /// 上传备份文件和快照到远程存储，返回记录到备份记录中的存储目标
+    ///
+    /// 没有配置远程存储或上传失败时返回 `local`。
+    async fn upload_to_remote(&self, backup_path: &Path) -> String {
+        let Some(storage) = &self.remote_storage else {
+            return backup::LOCAL_STORAGE_TARGET.to_string();
+        };
+
+        let result = async {
+            for path in [backup_path.to_path_buf(), Self::snapshot_path(backup_path)] {
+                storage.upload(&path, &Self::file_name(&path)?).await?;
+            }
+            Ok::<(), DuckError>(())
+        }
+        .await;
+
+        match result {
+            Ok(()) => {
+                tracing::info!("备份已上传到远程存储: {}", storage.target());
+                storage.target()
+            }
+            Err(e) => {
+                tracing::warn!("上传备份到远程存储失败，备份只保存在本地: {}", e);
+                backup::LOCAL_STORAGE_TARGET.to_string()
+            }
+        }
+    }
+
+    /// 获取与备份记录的存储目标一致的远程存储
+    fn remote_storage_for(&self, backup: &BackupRecord) -> Result<&Arc<dyn BackupStorage>> {
+        if backup.storage_target == backup::LOCAL_STORAGE_TARGET {
+            return Err(DuckError::Backup(format!(
+                "备份 {} 只保存在本地，没有远程副本",
+                backup.id
+            )));
+        }
+        self.remote_storage
+            .as_ref()
+            .filter(|storage| storage.target() == backup.storage_target)
+            .ok_or_else(|| {
+                DuckError::Backup(format!(
+                    "备份 {} 保存在远程存储 {}，但当前没有配置该远程存储",
+                    backup.id, backup.storage_target
+                ))
+            })
+    }
+
+    /// 从远程存储下载备份文件和快照到备份记录中的本地路径
+    async fn download_from_remote(&self, backup: &BackupRecord) -> Result<()> {
+        let storage = self.remote_storage_for(backup)?;
+        let backup_path = Path::new(&backup.file_path);
+
+        tracing::info!(
+            "本地备份文件不存在，从远程存储 {} 下载: {}",
+            storage.target(),
+            backup_path.display()
+        );
+        storage
+            .download(&Self::file_name(backup_path)?, backup_path)
+            .await?;
+
+        // 快照只在恢复增量/差异备份链时需要，下载失败不影响恢复全量备份
+        let snapshot_path = Self::snapshot_path(backup_path);
+        if let Err(e) = storage
+            .download(&Self::file_name(&snapshot_path)?, &snapshot_path)
+            .await
+        {
+            tracing::warn!("下载备份快照失败: {}", e);
+        }
+
+        Ok(())
+    }
+
+    /// 从远程存储下载备份到本地（本地文件已存在时不重复下载）
+    pub async fn download_backup(&self, backup_id: i64) -> Result<BackupRecord> {
+        let backup_record = self
+            .database
+            .get_backup_by_id(backup_id)
+            .await?
+            .ok_or_else(|| DuckError::Backup(format!("备份记录不存在: {backup_id}")))?;
+
+        if !Path::new(&backup_record.file_path).exists() {
+            self.download_from_remote(&backup_record).await?;
+        }
+
+        Ok(backup_record)
+    }
+
+    /// 列出远程存储中的文件
+    pub async fn list_remote_backups(&self) -> Result<Vec<StoredObject>> {
+        let storage = self.remote_storage.as_ref().ok_or_else(|| {
+            DuckError::Backup("没有配置远程备份存储（配置文件中的 [backup.remote]）".to_string())
+        })?;
+        storage.list().await
+    }
+
+    /// 远程备份存储的目标名称（没有配置时为None）
+    pub fn remote_storage_target(&self) -> Option<String> {
+        self.remote_storage.as_ref().map(|storage| storage.target())
+    }
+
+    /// 配置的备份压缩格式（`backup.compression`），没有配置或无法识别时使用gzip
+    pub async fn configured_compression(&self) -> Result<CompressionType> {
+        let Some(value) = self.database.get_config("backup.compression").await? else {
+            return Ok(CompressionType::default());
+        };
+        Ok(value.parse().unwrap_or_else(|e| {
+            tracing::warn!("{}，使用gzip压缩", e);
+            CompressionType::default()
+        }))
+    }
+
+    /// 生成备份文件名（人类易读格式）：`backup_{类型}[_{模式}]_v{版本}_{时间}.{扩展名}`
+    fn backup_file_name(&self, metadata: &BackupMetadata) -> String {
+        let backup_type_str = match metadata.backup_type {
+            BackupType::Manual => "manual",
+            BackupType::PreUpgrade => "pre-upgrade",
+        };
+        let backup_mode_str = match metadata.backup_mode {
+            BackupMode::Full => "",
+            BackupMode::Incremental => "_incremental",
+            BackupMode::Differential => "_differential",
+        };
+
+        let mut backup_filename = format!(
+            "backup_{}{}_v{}_{}.{}",
+            backup_type_str,
+            backup_mode_str,
+            metadata.service_version,
+            metadata.created_at.format("%Y-%m-%d_%H-%M-%S"),
+            metadata.compression_type.extension()
+        );
+        if self.encryption.is_enabled() {
+            backup_filename.push_str(backup::ENCRYPTED_SUFFIX);
+        }
+        backup_filename
+    }
+
+    /// 备份文件名（远程存储中使用相同的文件名）
+    fn file_name(path: &Path) -> Result<String> {
+        path.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or_else(|| DuckError::Backup(format!("无效的备份文件路径: {}", path.display())))
+    }
+
+    /// 查找增量/差异备份的基准备份及其快照
+    async fn find_base_backup(
+        &self,
+        backup_mode: BackupMode,
+    ) -> Result<Option<(BackupRecord, BackupSnapshot)>> {
+        // 备份记录按创建时间倒序排列
+        for backup in self.list_backups().await? {
+            if backup.status != BackupStatus::Completed
+                || (backup_mode == BackupMode::Differential
+                    && backup.backup_mode != BackupMode::Full)
+                || !Path::new(&backup.file_path).exists()
+            {
+                continue;
+            }
+
+            if let Some(snapshot) = Self::load_snapshot(Path::new(&backup.file_path)).await? {
+                return Ok(Some((backup, snapshot)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// 执行实际的备份操作，返回源目录的文件快照（打包的文件同时记录内容哈希）和备份文件的校验信息
+    ///
+    /// 指定基准快照时只打包新增或变化的文件：大小和修改时间都相同的文件视为未变化；
+    /// 只有修改时间变化的文件比较内容哈希，内容相同的文件不再打包。
+    /// 打包的文件记录到备份清单中，清单作为归档的最后一个条目写入。
+    /// `extras` 中排除的目录不打包，额外的文件每次都打包。
     async fn perform_backup(
         &self,
         source_dirs: &[PathBuf],
         backup_path: &Path,
+        compression_type: CompressionType,
         compression_level: u32,
-    ) -> Result<()> {
-        use flate2::Compression;
-        use flate2::write::GzEncoder;
+        base_snapshot: Option<BackupSnapshot>,
+        extras: ArchiveExtras,
+    ) -> Result<(BackupSnapshot, BackupChecksum)> {
         use std::fs::File;
         use tar::Builder;
 
@@ -150,12 +937,21 @@ impl BackupManager {
         // 在后台线程中执行压缩操作，避免阻塞异步运行时
         let source_dirs = source_dirs.to_vec();
         let backup_path = backup_path.to_path_buf();
+        let encryption = self.encryption.clone();
 
-        tokio::task::spawn_blocking(move || {
-            let file = File::create(&backup_path)?;
-            let compression = Compression::new(compression_level);
-            let encoder = GzEncoder::new(file, compression);
+        let result = tokio::task::spawn_blocking(move || {
+            // 写入备份文件的同时计算整个文件的哈希
+            let file = HashingWriter {
+                inner: File::create(&backup_path)?,
+                hasher: Sha256::new(),
+                bytes_written: 0,
+            };
+            // 启用加密时在压缩流外层加密
+            let writer = encryption.wrap_output(file)?;
+            let encoder = CompressionWriter::new(writer, compression_type, compression_level)?;
             let mut archive = Builder::new(encoder);
+            let mut snapshot = BackupSnapshot::default();
+            let mut manifest = BackupManifest::default();
 
             // 遍历所有源目录并添加到归档中
             for source_dir in &source_dirs {
@@ -186,133 +982,558 @@ impl BackupManager {
                         } else {
                             format!("{}/{}", dir_name, relative_path.display())
                         };
+                        if extras
+                            .excluded_dirs
+                            .iter()
+                            .any(|dir| archive_path.starts_with(&format!("{dir}/")))
+                        {
+                            continue;
+                        }
+
+                        let metadata = entry
+                            .metadata()
+                            .map_err(|e| DuckError::Backup(format!("读取文件信息失败: {e}")))?;
+                        let mut file_snapshot = FileSnapshot {
+                            size: metadata.len(),
+                            modified: metadata
+                                .modified()
+                                .ok()
+                                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                                .map_or(0, |duration| duration.as_nanos() as u64),
+                            sha256: None,
+                        };
 
-                        archive
-                            .append_path_with_name(path, archive_path)
-                            .map_err(|e| DuckError::Backup(format!("添加文件到归档失败: {e}")))?;
+                        let changed = match base_snapshot
+                            .as_ref()
+                            .and_then(|base| base.files.get(&archive_path))
+                        {
+                            None => true,
+                            Some(base_file) if base_file.size != file_snapshot.size => true,
+                            Some(base_file) if base_file.modified == file_snapshot.modified => {
+                                file_snapshot.sha256 = base_file.sha256.clone();
+                                false
+                            }
+                            // 只有修改时间变化：比较内容哈希
+                            Some(base_file) => match &base_file.sha256 {
+                                Some(base_hash) => {
+                                    let hash = calculate_file_hash(path)?;
+                                    let changed = *base_hash != hash;
+                                    file_snapshot.sha256 = Some(hash);
+                                    changed
+                                }
+                                None => true,
+                            },
+                        };
+
+                        if changed {
+                            let entry = append_file_to_archive(
+                                &mut archive,
+                                path,
+                                &archive_path,
+                                &metadata,
+                            )?;
+                            file_snapshot.sha256 = Some(entry.sha256.clone());
+                            manifest.files.push(entry);
+                        }
+                        snapshot.files.insert(archive_path, file_snapshot);
                     }
                 }
             }
 
+            // 额外的文件（如MySQL导出文件）不参与增量比较，每次都打包
+            for (path, archive_path) in &extras.extra_files {
+                let metadata = std::fs::metadata(path)?;
+                manifest.files.push(append_file_to_archive(
+                    &mut archive,
+                    path,
+                    archive_path,
+                    &metadata,
+                )?);
+            }
+
+            // 备份清单作为最后一个条目写入
+            manifest.metadata = extras.metadata;
+            let manifest_content = serde_json::to_vec_pretty(&manifest)?;
+            let mut header = tar::Header::new_gnu();
+            header.set_size(manifest_content.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(Utc::now().timestamp().max(0) as u64);
             archive
-                .finish()
-                .map_err(|e| DuckError::Backup(format!("完成归档失败: {e}")))?;
+                .append_data(
+                    &mut header,
+                    backup::MANIFEST_FILE_NAME,
+                    manifest_content.as_slice(),
+                )
+                .map_err(|e| DuckError::Backup(format!("写入备份清单失败: {e}")))?;
+
+            let file = archive
+                .into_inner()
+                .and_then(|encoder| encoder.finish())
+                .map_err(|e| DuckError::Backup(format!("完成归档失败: {e}")))?
+                .finish()?;
+
+            if base_snapshot.is_some() {
+                tracing::info!(
+                    "共 {} 个文件，其中 {} 个新增或变化的文件已备份",
+                    snapshot.files.len(),
+                    manifest.files.len()
+                );
+            }
 
-            Ok::<(), DuckError>(())
+            let checksum = BackupChecksum {
+                backup_hash: format!("{:x}", file.hasher.finalize()),
+                file_count: manifest.files.len() as i64,
+                backup_size: file.bytes_written as i64,
+            };
+
+            Ok::<(BackupSnapshot, BackupChecksum), DuckError>((snapshot, checksum))
         })
         .await??;
 
-        Ok(())
+        Ok(result)
     }
 
-    /// 从备份恢复
+    /// 从备份恢复（增量/差异备份会依次恢复整个备份链）
+    ///
+    /// 备份先解压到目标目录旁的暂存目录并校验，再停止服务与目标目录交换；被替换的数据
+    /// 保存为恢复前快照（见 [`Self::pre_restore_dir`]），服务重启后健康检查失败时自动换回。
+    /// 指定了恢复路径时只替换这些路径，目标目录中的其他文件保持不变。
     pub async fn restore_from_backup(&self, backup_id: i64, options: RestoreOptions) -> Result<()> {
-        // 获取备份记录
-        let backup_record = self
-            .database
-            .get_backup_by_id(backup_id)
-            .await?
-            .ok_or_else(|| DuckError::Backup(format!("备份记录不存在: {backup_id}")))?;
-
-        let backup_path = PathBuf::from(&backup_record.file_path);
-        if !backup_path.exists() {
-            return Err(DuckError::Backup(format!(
-                "备份文件不存在: {}",
-                backup_path.display()
-            )));
-        }
+        let chain = self.get_backup_chain(backup_id).await?;
+        let backup_path = PathBuf::from(&chain[chain.len() - 1].file_path);
+        let filter =
+            Self::restrict_restore_filter(&chain[chain.len() - 1], &options.filter).await?;
 
         tracing::info!("开始从备份恢复: {}", backup_path.display());
 
-        // 停止服务，准备恢复
-        tracing::info!("正在停止服务...");
-        self.docker_manager.stop_services().await?;
-
         // 检查目标目录
-        if options.target_dir.exists() {
-            if options.force_overwrite {
-                tracing::warn!(
-                    "目标目录 {} 已存在，将被清空和覆盖。",
-                    options.target_dir.display()
-                );
-                tokio::fs::remove_dir_all(&options.target_dir).await?;
-            } else {
+        let units = if !filter.is_empty() {
+            filter.paths().to_vec()
+        } else if options.target_dir.exists() {
+            if !options.force_overwrite {
                 return Err(DuckError::Backup(
                     "目标目录已存在，请使用 force_overwrite 选项".to_string(),
                 ));
             }
-        }
+            tracing::warn!(
+                "目标目录 {} 已存在，将被整体替换。",
+                options.target_dir.display()
+            );
+            vec![String::new()]
+        } else {
+            vec![String::new()]
+        };
 
-        // 执行恢复
-        self.perform_restore(&backup_path, &options.target_dir)
+        let staging_dir = self
+            .stage_restore(&chain, &options.target_dir, &filter)
+            .await?;
+        self.swap_and_restart(&options.target_dir, &staging_dir, &units)
             .await?;
-
-        // 启动服务
-        tracing::info!("恢复完成，正在启动服务...");
-        self.docker_manager.start_services().await?;
 
         tracing::info!("服务已成功恢复并启动: {}", options.target_dir.display());
         Ok(())
     }
 
-    /// 只恢复数据文件，保留配置文件的智能恢复
-    pub async fn restore_data_only(&self, backup_id: i64, target_dir: &Path) -> Result<()> {
-        // 获取备份记录
-        let backup_record = self
-            .database
-            .get_backup_by_id(backup_id)
-            .await?
-            .ok_or_else(|| DuckError::Backup(format!("备份记录不存在: {backup_id}")))?;
-
-        let backup_path = PathBuf::from(&backup_record.file_path);
-        if !backup_path.exists() {
-            return Err(DuckError::Backup(format!(
-                "备份文件不存在: {}",
-                backup_path.display()
-            )));
-        }
+    /// 只恢复数据文件，保留配置文件的智能恢复（增量/差异备份会依次恢复整个备份链）
+    ///
+    /// 与 [`Self::restore_from_backup`] 一样先解压到暂存目录再交换，只替换数据目录；
+    /// 指定了恢复路径时只替换这些路径（如只恢复 `data/redis`）。
+    pub async fn restore_data_only(
+        &self,
+        backup_id: i64,
+        target_dir: &Path,
+        filter: &RestoreFilter,
+    ) -> Result<()> {
+        let chain = self.get_backup_chain(backup_id).await?;
+        let backup_path = PathBuf::from(&chain[chain.len() - 1].file_path);
+        let filter = Self::restrict_restore_filter(&chain[chain.len() - 1], filter).await?;
 
         tracing::info!("开始智能数据恢复: {}", backup_path.display());
         tracing::info!("目标目录: {}", target_dir.display());
+        if !filter.is_empty() {
+            tracing::info!("只恢复: {}", filter.paths().join(", "));
+        }
+
+        let staging_dir = self.stage_restore(&chain, target_dir, &filter).await?;
+
+        // 替换数据目录和备份中的其他顶层目录（如MySQL导出文件），配置文件保留
+        let units = if filter.is_empty() {
+            let mut units: BTreeSet<String> = backup::DATA_ONLY_RESTORE_DIRS
+                .iter()
+                .map(|dir| dir.to_string())
+                .collect();
+            let mut entries = tokio::fs::read_dir(&staging_dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                units.insert(entry.file_name().to_string_lossy().to_string());
+            }
+            units.into_iter().collect()
+        } else {
+            filter.paths().to_vec()
+        };
+
+        self.swap_and_restart(target_dir, &staging_dir, &units)
+            .await?;
+
+        tracing::info!("数据已成功恢复并启动: {}", target_dir.display());
+        Ok(())
+    }
+
+    /// 恢复前快照目录：最近一次恢复时被替换的数据保存在目标目录旁的 `.{目录名}.pre-restore`
+    pub fn pre_restore_dir(target_dir: &Path) -> Result<PathBuf> {
+        Self::sibling_dir(target_dir, backup::PRE_RESTORE_SUFFIX)
+    }
+
+    /// 目标目录旁的隐藏目录 `.{目录名}.{suffix}`（与目标目录在同一文件系统，重命名是原子的）
+    fn sibling_dir(target_dir: &Path, suffix: &str) -> Result<PathBuf> {
+        let name = target_dir.file_name().ok_or_else(|| {
+            DuckError::Backup(format!("无效的恢复目标目录: {}", target_dir.display()))
+        })?;
+        Ok(target_dir.with_file_name(format!(".{}.{suffix}", name.to_string_lossy())))
+    }
+
+    /// 把备份链恢复到目标目录旁的暂存目录并校验，返回暂存目录（失败时删除暂存目录）
+    async fn stage_restore(
+        &self,
+        chain: &[BackupRecord],
+        target_dir: &Path,
+        filter: &RestoreFilter,
+    ) -> Result<PathBuf> {
+        let staging_dir = Self::sibling_dir(target_dir, backup::RESTORE_STAGING_SUFFIX)?;
+        // 清理上次中断的恢复留下的暂存目录
+        if staging_dir.exists() {
+            tokio::fs::remove_dir_all(&staging_dir).await?;
+        }
+
+        tracing::info!("正在解压到暂存目录: {}", staging_dir.display());
+        let result = async {
+            self.perform_chain_restore(chain, &staging_dir, filter)
+                .await?;
+            let last_backup = Path::new(&chain[chain.len() - 1].file_path);
+            let snapshot = Self::load_snapshot(last_backup).await?;
+            Self::verify_staging(&staging_dir, snapshot, filter).await
+        }
+        .await;
+
+        if let Err(e) = result {
+            tokio::fs::remove_dir_all(&staging_dir).await.ok();
+            return Err(e);
+        }
+        Ok(staging_dir)
+    }
+
+    /// 校验暂存目录：快照中 `filter` 匹配的文件都已恢复且大小一致（没有快照的早期备份不检查）
+    async fn verify_staging(
+        staging_dir: &Path,
+        snapshot: Option<BackupSnapshot>,
+        filter: &RestoreFilter,
+    ) -> Result<()> {
+        let Some(snapshot) = snapshot else {
+            tracing::warn!("备份没有快照，跳过恢复校验");
+            return Ok(());
+        };
+        let staging_dir = staging_dir.to_path_buf();
+        let filter = filter.clone();
+
+        tokio::task::spawn_blocking(move || {
+            for (archive_path, file) in &snapshot.files {
+                if !filter.matches(archive_path) {
+                    continue;
+                }
+                let size = std::fs::metadata(staging_dir.join(archive_path))
+                    .map_err(|e| {
+                        DuckError::Backup(format!("恢复校验失败，文件缺失 {archive_path}: {e}"))
+                    })?
+                    .len();
+                if size != file.size {
+                    return Err(DuckError::Backup(format!(
+                        "恢复校验失败，文件大小不一致 {archive_path}: 应为 {} 字节，实际 {size} 字节",
+                        file.size
+                    )));
+                }
+            }
+            Ok::<(), DuckError>(())
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    /// 停止服务，用暂存目录中的内容替换目标目录中的 `units`，再启动服务并检查健康状况
+    ///
+    /// `units` 为相对目标目录的路径，空字符串表示整个目标目录。健康检查失败时换回恢复前的数据
+    /// 并重启服务；成功时保留恢复前快照，只保留最近一次。
+    async fn swap_and_restart(
+        &self,
+        target_dir: &Path,
+        staging_dir: &Path,
+        units: &[String],
+    ) -> Result<()> {
+        let pre_restore_dir = Self::pre_restore_dir(target_dir)?;
 
-        // 停止服务，准备恢复
         tracing::info!("正在停止服务...");
-        self.docker_manager.stop_services().await?;
+        if let Err(e) = self.docker_manager.stop_services().await {
+            tokio::fs::remove_dir_all(staging_dir).await.ok();
+            return Err(e);
+        }
+
+        if pre_restore_dir.exists() {
+            tokio::fs::remove_dir_all(&pre_restore_dir).await?;
+        }
+        Self::swap_restored_paths(target_dir, staging_dir, &pre_restore_dir, units).await?;
+        if staging_dir.exists() {
+            tokio::fs::remove_dir_all(staging_dir).await?;
+        }
+        tracing::info!("恢复前的数据已保存到: {}", pre_restore_dir.display());
+
+        tracing::info!("恢复完成，正在启动服务...");
+        let result = async {
+            self.docker_manager.start_services().await?;
+            self.import_hot_backup_dump(target_dir).await?;
+            self.docker_manager.check_services_health().await
+        }
+        .await;
 
-        // 清理现有数据目录，但保留配置文件
-        self.clear_data_directories_only(target_dir).await?;
+        if let Err(e) = result {
+            tracing::error!("恢复后服务健康检查失败，正在换回恢复前的数据: {}", e);
+            if let Err(stop_error) = self.docker_manager.stop_services().await {
+                tracing::warn!("停止服务失败: {}", stop_error);
+            }
+            Self::swap_back(target_dir, &pre_restore_dir, units)
+                .await
+                .map_err(|swap_error| {
+                    DuckError::Backup(format!(
+                        "恢复后服务健康检查失败（{e}），换回恢复前的数据也失败: {swap_error}，恢复前的数据保存在 {}",
+                        pre_restore_dir.display()
+                    ))
+                })?;
+            if let Err(start_error) = self.docker_manager.start_services().await {
+                tracing::error!("换回恢复前的数据后启动服务失败: {}", start_error);
+            }
+            return Err(DuckError::Backup(format!(
+                "恢复后服务健康检查失败，已换回恢复前的数据: {e}"
+            )));
+        }
 
-        // 执行恢复
-        self.perform_restore(&backup_path, target_dir).await?;
+        Ok(())
+    }
 
-        // 启动服务
-        tracing::info!("数据恢复完成，正在启动服务...");
-        self.docker_manager.start_services().await?;
+    /// 用暂存目录中的内容替换目标目录中的 `units`，被替换的内容移动到恢复前快照目录
+    ///
+    /// 都在同一文件系统内重命名，每个路径的替换是原子的；中途失败时换回已替换的路径。
+    async fn swap_restored_paths(
+        target_dir: &Path,
+        staging_dir: &Path,
+        pre_restore_dir: &Path,
+        units: &[String],
+    ) -> Result<()> {
+        for (index, unit) in units.iter().enumerate() {
+            let current = unit_path(target_dir, unit);
+            let saved = unit_path(pre_restore_dir, unit);
+            let restored = unit_path(staging_dir, unit);
+
+            let result = async {
+                if current.exists() {
+                    move_path(&current, &saved).await?;
+                }
+                if restored.exists() {
+                    if let Err(e) = move_path(&restored, &current).await {
+                        if saved.exists() {
+                            move_path(&saved, &current).await?;
+                        }
+                        return Err(e);
+                    }
+                }
+                Ok(())
+            }
+            .await;
 
-        tracing::info!("数据已成功恢复并启动: {}", target_dir.display());
+            if let Err(e) = result {
+                Self::swap_back(target_dir, pre_restore_dir, &units[..index]).await?;
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// 换回恢复前的数据：删除恢复的 `units`，把恢复前快照中的内容移回目标目录
+    async fn swap_back(target_dir: &Path, pre_restore_dir: &Path, units: &[String]) -> Result<()> {
+        for unit in units {
+            let current = unit_path(target_dir, unit);
+            let saved = unit_path(pre_restore_dir, unit);
+            remove_path(&current).await?;
+            if saved.exists() {
+                move_path(&saved, &current).await?;
+            }
+        }
+        if pre_restore_dir.exists() {
+            tokio::fs::remove_dir_all(pre_restore_dir).await?;
+        }
         Ok(())
     }
 
-    /// 只清理数据目录，保留配置文件
-    async fn clear_data_directories_only(&self, docker_dir: &Path) -> Result<()> {
-        let data_dirs_to_clear = ["data", "app"];
+    /// 去掉备份中没有数据的恢复路径，避免这些路径被清空后无法恢复
+    ///
+    /// 根据备份快照判断（没有快照的早期备份不检查）；MySQL导出文件不在快照中，始终保留。
+    /// 所有路径在备份中都没有数据时返回错误。
+    async fn restrict_restore_filter(
+        backup: &BackupRecord,
+        filter: &RestoreFilter,
+    ) -> Result<RestoreFilter> {
+        if filter.is_empty() {
+            return Ok(filter.clone());
+        }
+        let Some(snapshot) = Self::load_snapshot(Path::new(&backup.file_path)).await? else {
+            return Ok(filter.clone());
+        };
+
+        let mut restricted = RestoreFilter::default();
+        for path in filter.paths() {
+            let available = is_under_path(backup::MYSQL_DUMP_ARCHIVE_PATH, path)
+                || snapshot
+                    .files
+                    .keys()
+                    .any(|archive_path| is_under_path(archive_path, path));
+            if available {
+                restricted.paths.push(path.clone());
+            } else {
+                tracing::warn!("备份 {} 中没有 {} 的数据，跳过", backup.id, path);
+            }
+        }
+
+        if restricted.is_empty() {
+            return Err(DuckError::Backup(format!(
+                "备份 {} 中没有要恢复的路径: {}",
+                backup.id,
+                filter.paths().join(", ")
+            )));
+        }
+        Ok(restricted)
+    }
+
+    /// 获取恢复指定备份所需的备份链（从全量备份开始，到指定备份结束）
+    ///
+    /// 链中每个备份的记录和文件都必须存在，本地文件缺失时从远程存储下载。
+    pub async fn get_backup_chain(&self, backup_id: i64) -> Result<Vec<BackupRecord>> {
+        let mut chain = Vec::new();
+        let mut next_id = Some(backup_id);
+
+        while let Some(id) = next_id {
+            let backup_record = self
+                .database
+                .get_backup_by_id(id)
+                .await?
+                .ok_or_else(|| DuckError::Backup(format!("备份记录不存在: {id}")))?;
+
+            if backup_record.status != BackupStatus::Completed {
+                return Err(DuckError::Backup(format!(
+                    "备份 {id} 未成功完成，无法用于恢复"
+                )));
+            }
+            let backup_path = Path::new(&backup_record.file_path);
+            if !backup_path.exists() {
+                if backup_record.storage_target == backup::LOCAL_STORAGE_TARGET {
+                    return Err(DuckError::Backup(format!(
+                        "备份文件不存在: {}",
+                        backup_path.display()
+                    )));
+                }
+                self.download_from_remote(&backup_record).await?;
+            }
+            if chain.iter().any(|backup: &BackupRecord| backup.id == id) {
+                return Err(DuckError::Backup(format!("备份链存在循环引用: {id}")));
+            }
+
+            next_id = backup_record.parent_id;
+            chain.push(backup_record);
+        }
+
+        chain.reverse();
+        Ok(chain)
+    }
 
-        for dir_name in data_dirs_to_clear.iter() {
-            let dir_path = docker_dir.join(dir_name);
-            if dir_path.exists() {
-                tracing::info!("清理数据目录: {}", dir_path.display());
-                tokio::fs::remove_dir_all(&dir_path).await?;
+    /// 依次恢复备份链中的每个备份，并删除最后一个备份时已不存在的文件
+    ///
+    /// 只恢复和删除 `filter` 匹配的文件。
+    async fn perform_chain_restore(
+        &self,
+        chain: &[BackupRecord],
+        target_dir: &Path,
+        filter: &RestoreFilter,
+    ) -> Result<()> {
+        // 只保留链中最后一个热备份的MySQL导出文件
+        let dump_path = target_dir.join(backup::MYSQL_DUMP_ARCHIVE_PATH);
+        for backup in chain {
+            if dump_path.exists() {
+                tokio::fs::remove_file(&dump_path).await?;
+            }
+            if chain.len() > 1 {
+                tracing::info!("恢复备份 {}: {}", backup.id, backup.file_path);
             }
+            self.perform_restore(Path::new(&backup.file_path), target_dir, filter)
+                .await?;
+        }
+
+        if chain.len() > 1 {
+            let last_backup = Path::new(&chain[chain.len() - 1].file_path);
+            let snapshot = Self::load_snapshot(last_backup).await?.ok_or_else(|| {
+                DuckError::Backup(format!("备份快照不存在: {}", last_backup.display()))
+            })?;
+            Self::remove_files_not_in_snapshot(target_dir, snapshot, filter.clone()).await?;
         }
 
-        tracing::info!("数据目录清理完成，配置文件已保留");
         Ok(())
     }
 
-    /// 执行实际的恢复操作
-    async fn perform_restore(&self, backup_path: &Path, target_dir: &Path) -> Result<()> {
-        use flate2::read::GzDecoder;
-        use std::fs::File;
+    /// 删除快照中不存在的文件（在基准备份之后被删除的文件）
+    ///
+    /// 只处理快照中出现的顶层目录（即备份的源目录）中 `filter` 匹配的文件。
+    async fn remove_files_not_in_snapshot(
+        target_dir: &Path,
+        snapshot: BackupSnapshot,
+        filter: RestoreFilter,
+    ) -> Result<()> {
+        let target_dir = target_dir.to_path_buf();
+
+        tokio::task::spawn_blocking(move || {
+            let top_dirs: BTreeSet<&str> = snapshot
+                .files
+                .keys()
+                .filter_map(|path| path.split('/').next())
+                .collect();
+
+            for top_dir in top_dirs {
+                let source_dir = target_dir.join(top_dir);
+                for entry in WalkDir::new(&source_dir).into_iter().flatten() {
+                    let path = entry.path();
+                    if !path.is_file() {
+                        continue;
+                    }
+                    let Ok(relative_path) = path.strip_prefix(&target_dir) else {
+                        continue;
+                    };
+                    let archive_path = relative_path.to_string_lossy().replace('\\', "/");
+                    if filter.matches(&archive_path) && !snapshot.files.contains_key(&archive_path)
+                    {
+                        tracing::debug!("删除备份后已不存在的文件: {}", path.display());
+                        std::fs::remove_file(path)?;
+                    }
+                }
+            }
+
+            Ok::<(), DuckError>(())
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    /// 执行实际的恢复操作（加密的备份自动解密），只解压 `filter` 匹配的文件
+    async fn perform_restore(
+        &self,
+        backup_path: &Path,
+        target_dir: &Path,
+        filter: &RestoreFilter,
+    ) -> Result<()> {
         use tar::Archive;
 
         // 确保目标目录存在
@@ -320,16 +1541,28 @@ impl BackupManager {
 
         let backup_path = backup_path.to_path_buf();
         let target_dir = target_dir.to_path_buf();
+        let encryption = self.encryption.clone();
+        let filter = filter.clone();
 
         // 在后台线程中执行解压操作
         tokio::task::spawn_blocking(move || {
-            let file = File::open(&backup_path)?;
-            let decoder = GzDecoder::new(file);
-            let mut archive = Archive::new(decoder);
-
-            archive
-                .unpack(&target_dir)
-                .map_err(|e| DuckError::Backup(format!("解压归档失败: {e}")))?;
+            let mut archive = Archive::new(encryption.open_archive(&backup_path)?);
+
+            for entry in archive
+                .entries()
+                .map_err(|e| DuckError::Backup(format!("读取归档失败: {e}")))?
+            {
+                let mut entry =
+                    entry.map_err(|e| DuckError::Backup(format!("读取归档失败: {e}")))?;
+                // 备份清单只用于校验，不恢复到目标目录
+                let entry_path = entry.path()?.to_string_lossy().replace('\\', "/");
+                if entry_path == backup::MANIFEST_FILE_NAME || !filter.matches(&entry_path) {
+                    continue;
+                }
+                entry
+                    .unpack_in(&target_dir)
+                    .map_err(|e| DuckError::Backup(format!("解压归档失败: {e}")))?;
+            }
 
             Ok::<(), DuckError>(())
         })
@@ -338,6 +1571,25 @@ impl BackupManager {
         Ok(())
     }
 
+    /// 查找指定服务版本最近一次成功的升级前备份（降级到该版本时用于恢复数据）
+    pub async fn find_pre_upgrade_backup(
+        &self,
+        service_version: &str,
+    ) -> Result<Option<BackupRecord>> {
+        let service_version = service_version.trim_start_matches('v');
+        Ok(self
+            .database
+            .get_all_backups()
+            .await?
+            .into_iter()
+            .filter(|backup| {
+                backup.backup_type == BackupType::PreUpgrade
+                    && backup.status == BackupStatus::Completed
+                    && backup.service_version.trim_start_matches('v') == service_version
+            })
+            .max_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id))))
+    }
+
     /// 获取所有备份记录
     pub async fn list_backups(&self) -> Result<Vec<BackupRecord>> {
         self.database.get_all_backups().await
@@ -352,6 +1604,21 @@ impl BackupManager {
             .await?
             .ok_or_else(|| DuckError::Backup(format!("备份记录不存在: {backup_id}")))?;
 
+        // 增量/差异备份依赖的备份不能单独删除
+        let dependents: Vec<String> = self
+            .list_backups()
+            .await?
+            .iter()
+            .filter(|backup| backup.parent_id == Some(backup_id))
+            .map(|backup| backup.id.to_string())
+            .collect();
+        if !dependents.is_empty() {
+            return Err(DuckError::Backup(format!(
+                "备份 {backup_id} 被备份 {} 依赖，请先删除依赖它的备份",
+                dependents.join(", ")
+            )));
+        }
+
         let backup_path = PathBuf::from(&backup_record.file_path);
 
         // 删除文件
@@ -360,12 +1627,79 @@ impl BackupManager {
             tracing::info!("删除备份文件: {}", backup_path.display());
         }
 
+        // 删除随备份保存的数据库架构回滚脚本
+        let rollback_path = Self::schema_rollback_path(&backup_path);
+        if rollback_path.exists() {
+            tokio::fs::remove_file(&rollback_path).await?;
+            tracing::info!("删除数据库架构回滚脚本: {}", rollback_path.display());
+        }
+
+        // 删除备份快照
+        let snapshot_path = Self::snapshot_path(&backup_path);
+        if snapshot_path.exists() {
+            tokio::fs::remove_file(&snapshot_path).await?;
+        }
+
+        // 删除远程副本，删除失败不影响删除本地备份
+        if backup_record.storage_target != backup::LOCAL_STORAGE_TARGET {
+            let result = async {
+                let storage = self.remote_storage_for(&backup_record)?;
+                for path in [&backup_path, &snapshot_path] {
+                    storage.delete(&Self::file_name(path)?).await?;
+                }
+                Ok::<(), DuckError>(())
+            }
+            .await;
+            if let Err(e) = result {
+                tracing::warn!("删除备份 {} 的远程副本失败: {}", backup_id, e);
+            }
+        }
+
         // 从数据库中删除记录
         self.database.delete_backup_record(backup_id).await?;
 
         Ok(())
     }
 
+    /// 按保留策略清理备份，返回需要清理（或已清理）的备份
+    ///
+    /// `dry_run` 为true时只计算需要清理的备份，不删除任何文件。
+    pub async fn prune_backups(
+        &self,
+        policy: &RetentionPolicy,
+        dry_run: bool,
+    ) -> Result<Vec<BackupRecord>> {
+        let backups = self.list_backups().await?;
+        let to_prune = policy.plan_prune(&backups, Utc::now());
+
+        if dry_run {
+            return Ok(to_prune);
+        }
+
+        let mut pruned = Vec::new();
+        for backup in to_prune {
+            match self.delete_backup(backup.id).await {
+                Ok(()) => {
+                    tracing::info!("按保留策略清理备份 {}: {}", backup.id, backup.file_path);
+                    pruned.push(backup);
+                }
+                Err(e) => tracing::warn!("清理备份 {} 失败: {}", backup.id, e),
+            }
+        }
+
+        Ok(pruned)
+    }
+
+    /// 启用自动清理（`backup.auto_cleanup`）时，按配置的保留策略清理旧备份
+    pub async fn apply_retention_policy(&self) -> Result<Vec<BackupRecord>> {
+        if !RetentionPolicy::auto_cleanup_enabled(&self.database).await? {
+            return Ok(Vec::new());
+        }
+
+        let policy = RetentionPolicy::from_database(&self.database).await?;
+        self.prune_backups(&policy, false).await
+    }
+
     /// 获取备份文件大小
     pub async fn get_backup_size(&self, backup_id: i64) -> Result<u64> {
         let backup_record = self
@@ -386,7 +1720,7 @@ impl BackupManager {
         Ok(metadata.len())
     }
 
-    /// 验证备份文件完整性
+    /// 验证备份文件完整性（加密的备份自动解密）
     pub async fn verify_backup(&self, backup_id: i64) -> Result<bool> {
         let backup_record = self
             .database
@@ -401,14 +1735,11 @@ impl BackupManager {
 
         // 尝试打开并验证归档文件
         let backup_path = backup_path.clone();
+        let encryption = self.encryption.clone();
         let result = tokio::task::spawn_blocking(move || {
-            use flate2::read::GzDecoder;
-            use std::fs::File;
             use tar::Archive;
 
-            let file = File::open(&backup_path)?;
-            let decoder = GzDecoder::new(file);
-            let mut archive = Archive::new(decoder);
+            let mut archive = Archive::new(encryption.open_archive(&backup_path)?);
 
             // 尝试列出所有条目来验证归档完整性
             for entry in archive.entries()? {
@@ -423,6 +1754,390 @@ impl BackupManager {
         Ok(result)
     }
 
+    /// 深度校验备份：重新计算备份文件和归档中每个文件的SHA-256，与备份记录和备份清单比较
+    pub async fn verify_backup_deep(&self, backup_id: i64) -> Result<BackupVerification> {
+        let backup_record = self
+            .database
+            .get_backup_by_id(backup_id)
+            .await?
+            .ok_or_else(|| DuckError::Backup(format!("备份记录不存在: {backup_id}")))?;
+
+        let backup_path = PathBuf::from(&backup_record.file_path);
+        if !backup_path.exists() {
+            return Err(DuckError::Backup(format!(
+                "备份文件不存在: {}",
+                backup_path.display()
+            )));
+        }
+
+        let expected_hash = backup_record.backup_hash;
+        let encryption = self.encryption.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut verification = BackupVerification::default();
+            if let Some(expected_hash) = expected_hash {
+                verification.backup_hash_matches =
+                    Some(calculate_file_hash(&backup_path)? == expected_hash);
+            }
+
+            // 归档中每个文件的大小和哈希
+            let mut archived = BTreeMap::new();
+            let mut manifest = None;
+            if let Err(e) =
+                read_archive_entries(&encryption, &backup_path, &mut archived, &mut manifest)
+            {
+                verification.archive_error = Some(e.to_string());
+            }
+
+            verification.checked_files = archived.len();
+            if let Some(manifest) = manifest {
+                verification.manifest_found = true;
+                for entry in manifest.files {
+                    match archived.remove(&entry.path) {
+                        None => verification.missing_files.push(entry.path),
+                        Some((size, sha256)) if size != entry.size || sha256 != entry.sha256 => {
+                            verification.corrupt_files.push(entry.path)
+                        }
+                        Some(_) => {}
+                    }
+                }
+                verification.unexpected_files = archived.into_keys().collect();
+            }
+
+            Ok::<BackupVerification, DuckError>(verification)
+        })
+        .await?
+    }
+
+    /// 列出备份中的文件（不解压），只列出 `prefix` 路径下的文件
+    ///
+    /// 增量/差异备份列出恢复整个备份链后的内容。
+    pub async fn list_backup_entries(
+        &self,
+        backup_id: i64,
+        prefix: Option<&str>,
+    ) -> Result<Vec<BackupEntry>> {
+        let chain = self.get_backup_chain(backup_id).await?;
+        let filter = match prefix {
+            Some(prefix) => RestoreFilter::default().with_path(prefix)?,
+            None => RestoreFilter::default(),
+        };
+        // 最后一个备份的快照，用于去掉在之前备份之后被删除的文件
+        let snapshot = if chain.len() > 1 {
+            Self::load_snapshot(Path::new(&chain[chain.len() - 1].file_path)).await?
+        } else {
+            None
+        };
+        let encryption = self.encryption.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let mut entries: BTreeMap<String, BackupEntry> = BTreeMap::new();
+            for backup in &chain {
+                // 与恢复时一致：只保留最后一个热备份的MySQL导出文件
+                entries.remove(backup::MYSQL_DUMP_ARCHIVE_PATH);
+                for entry in
+                    list_archive_entries(&encryption, Path::new(&backup.file_path), backup.id)?
+                {
+                    if filter.matches(&entry.path) {
+                        entries.insert(entry.path.clone(), entry);
+                    }
+                }
+            }
+
+            if let Some(snapshot) = snapshot {
+                entries.retain(|path, _| {
+                    path == backup::MYSQL_DUMP_ARCHIVE_PATH || snapshot.files.contains_key(path)
+                });
+            }
+            Ok::<Vec<BackupEntry>, DuckError>(entries.into_values().collect())
+        })
+        .await?
+    }
+
+    /// 把备份中单个文件的内容写入 `output`（不解压整个备份），返回写入的字节数
+    pub async fn read_backup_file<W: std::io::Write + Send + 'static>(
+        &self,
+        backup_id: i64,
+        path: &str,
+        mut output: W,
+    ) -> Result<u64> {
+        let path = normalize_archive_path(path)?;
+        let entry = self
+            .list_backup_entries(backup_id, Some(&path))
+            .await?
+            .into_iter()
+            .find(|entry| entry.path == path)
+            .ok_or_else(|| DuckError::Backup(format!("备份 {backup_id} 中没有文件: {path}")))?;
+        let backup_record = self
+            .database
+            .get_backup_by_id(entry.backup_id)
+            .await?
+            .ok_or_else(|| DuckError::Backup(format!("备份记录不存在: {}", entry.backup_id)))?;
+        let encryption = self.encryption.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let mut archive =
+                tar::Archive::new(encryption.open_archive(Path::new(&backup_record.file_path))?);
+            for archive_entry in archive.entries()? {
+                let mut archive_entry = archive_entry?;
+                if archive_entry.path()?.to_string_lossy().replace('\\', "/") == entry.path {
+                    let size = std::io::copy(&mut archive_entry, &mut output)?;
+                    output.flush()?;
+                    return Ok(size);
+                }
+            }
+            Err(DuckError::Backup(format!(
+                "备份 {} 中没有文件: {}",
+                backup_record.id, entry.path
+            )))
+        })
+        .await?
+    }
+
+    /// 比较两个备份的文件差异（增量/差异备份按恢复后的完整内容比较）
+    pub async fn diff_backups(&self, from_id: i64, to_id: i64) -> Result<BackupDiff> {
+        let from: BTreeMap<String, BackupEntry> = self
+            .list_backup_entries(from_id, None)
+            .await?
+            .into_iter()
+            .map(|entry| (entry.path.clone(), entry))
+            .collect();
+        let to = self.list_backup_entries(to_id, None).await?;
+
+        let mut diff = BackupDiff::default();
+        for entry in &to {
+            match from.get(&entry.path) {
+                None => diff.added.push(entry.path.clone()),
+                Some(old) if entry_changed(old, entry) => diff.changed.push(entry.path.clone()),
+                Some(_) => {}
+            }
+        }
+        let to_paths: BTreeSet<&str> = to.iter().map(|entry| entry.path.as_str()).collect();
+        diff.removed = from
+            .into_keys()
+            .filter(|path| !to_paths.contains(path.as_str()))
+            .collect();
+        Ok(diff)
+    }
+
+    /// 导入其他机器上创建（或通过 `export_backup` 导出）的备份
+    ///
+    /// 校验归档结构和文件完整性，根据备份清单中的元数据创建备份记录。
+    /// 备份文件复制到备份目录，并根据清单生成快照，可作为之后增量/差异备份的基准。
+    pub async fn import_backup(&self, backup_path: &Path) -> Result<BackupRecord> {
+        if !backup_path.is_file() {
+            return Err(DuckError::Backup(format!(
+                "备份文件不存在: {}",
+                backup_path.display()
+            )));
+        }
+        let file_name = Self::file_name(backup_path)?;
+        let target_path = self.storage_dir.join(&file_name);
+        let target_path_str = target_path.to_string_lossy().to_string();
+        if self
+            .list_backups()
+            .await?
+            .iter()
+            .any(|backup| backup.file_path == target_path_str)
+        {
+            return Err(DuckError::Backup(format!("备份已导入: {file_name}")));
+        }
+
+        // 读取并校验归档
+        let source_path = backup_path.to_path_buf();
+        let encryption = self.encryption.clone();
+        let (manifest, backup_hash) = tokio::task::spawn_blocking(move || {
+            let mut archived = BTreeMap::new();
+            let mut manifest = None;
+            read_archive_entries(&encryption, &source_path, &mut archived, &mut manifest)
+                .map_err(|e| DuckError::Backup(format!("读取备份归档失败: {e}")))?;
+            let manifest: BackupManifest = manifest.ok_or_else(|| {
+                DuckError::Backup(
+                    "备份中没有文件清单，无法导入（请在原机器上使用 backup export 导出）"
+                        .to_string(),
+                )
+            })?;
+
+            for entry in &manifest.files {
+                // 归档内路径必须是 `{目录名}/{相对路径}` 格式
+                if !entry.path.contains('/')
+                    || normalize_archive_path(&entry.path).ok().as_ref() != Some(&entry.path)
+                {
+                    return Err(DuckError::Backup(format!(
+                        "备份中有无效的文件路径: {}",
+                        entry.path
+                    )));
+                }
+                match archived.remove(&entry.path) {
+                    Some((size, sha256)) if size == entry.size && sha256 == entry.sha256 => {}
+                    Some(_) => {
+                        return Err(DuckError::Backup(format!("备份文件已损坏: {}", entry.path)));
+                    }
+                    None => {
+                        return Err(DuckError::Backup(format!("备份文件缺失: {}", entry.path)));
+                    }
+                }
+            }
+            if let Some(path) = archived.keys().next() {
+                return Err(DuckError::Backup(format!("备份中有清单外的文件: {path}")));
+            }
+
+            let backup_hash = calculate_file_hash(&source_path)?;
+            Ok::<(BackupManifest, String), DuckError>((manifest, backup_hash))
+        })
+        .await??;
+
+        let metadata = manifest.metadata.clone().ok_or_else(|| {
+            DuckError::Backup(
+                "备份中没有元数据，无法导入（请在原机器上使用 backup export 导出）".to_string(),
+            )
+        })?;
+        if metadata.backup_mode != BackupMode::Full {
+            return Err(DuckError::Backup(
+                "只能导入全量备份，增量/差异备份请在原机器上使用 backup export 导出".to_string(),
+            ));
+        }
+
+        // 复制到备份目录（文件已在备份目录中时不复制）
+        let already_in_storage = target_path.exists()
+            && tokio::fs::canonicalize(&target_path).await?
+                == tokio::fs::canonicalize(backup_path).await?;
+        if !already_in_storage {
+            if target_path.exists() {
+                return Err(DuckError::Backup(format!(
+                    "备份目录中已存在同名文件: {}",
+                    target_path.display()
+                )));
+            }
+            tokio::fs::create_dir_all(&self.storage_dir).await?;
+            tokio::fs::copy(backup_path, &target_path).await?;
+        }
+
+        // 清单中没有修改时间，之后的增量备份会按内容哈希比较
+        let snapshot = BackupSnapshot {
+            files: manifest
+                .files
+                .iter()
+                .filter(|entry| entry.path != backup::MYSQL_DUMP_ARCHIVE_PATH)
+                .map(|entry| {
+                    (
+                        entry.path.clone(),
+                        FileSnapshot {
+                            size: entry.size,
+                            modified: 0,
+                            sha256: Some(entry.sha256.clone()),
+                        },
+                    )
+                })
+                .collect(),
+        };
+        Self::save_snapshot(&target_path, &snapshot).await?;
+
+        let backup_size = tokio::fs::metadata(&target_path).await?.len();
+        let record_id = self
+            .database
+            .create_backup_record(NewBackupRecord {
+                file_path: target_path_str,
+                service_version: metadata.service_version,
+                backup_type: metadata.backup_type,
+                status: BackupStatus::Completed,
+                backup_mode: BackupMode::Full,
+                parent_id: None,
+                encryption_fingerprint: metadata.encryption_fingerprint,
+                backup_hash: Some(backup_hash),
+                file_count: Some(manifest.files.len() as i64),
+                backup_size: Some(backup_size as i64),
+                storage_target: backup::LOCAL_STORAGE_TARGET.to_string(),
+                compression_type: metadata.compression_type,
+                created_at: Some(metadata.created_at),
+            })
+            .await?;
+
+        tracing::info!("备份已导入: {} (ID: {})", target_path.display(), record_id);
+        self.database
+            .get_backup_by_id(record_id)
+            .await?
+            .ok_or_else(|| DuckError::Backup("无法获取刚导入的备份记录".to_string()))
+    }
+
+    /// 导出备份为可在其他机器上导入的独立归档，返回导出的文件路径
+    ///
+    /// 增量/差异备份与其依赖的备份合并为一个全量备份；归档使用当前的加密配置，
+    /// 备份清单中包含版本、类型和创建时间等元数据。`destination` 是目录时使用默认文件名。
+    pub async fn export_backup(&self, backup_id: i64, destination: &Path) -> Result<PathBuf> {
+        let chain = self.get_backup_chain(backup_id).await?;
+        let backup_record = &chain[chain.len() - 1];
+        let metadata = BackupMetadata {
+            service_version: backup_record.service_version.clone(),
+            backup_type: backup_record.backup_type.clone(),
+            backup_mode: BackupMode::Full,
+            compression_type: backup_record.compression_type,
+            encryption_fingerprint: self.encryption.fingerprint(),
+            created_at: backup_record.created_at,
+        };
+
+        let export_path = if destination.is_dir() {
+            destination.join(self.backup_file_name(&metadata))
+        } else {
+            destination.to_path_buf()
+        };
+        if export_path.exists() {
+            return Err(DuckError::Backup(format!(
+                "导出文件已存在: {}",
+                export_path.display()
+            )));
+        }
+
+        // 先恢复整个备份链到临时目录，再重新打包
+        tokio::fs::create_dir_all(&self.storage_dir).await?;
+        let temp_dir = tempfile::Builder::new()
+            .prefix(".export_")
+            .tempdir_in(&self.storage_dir)?;
+        self.perform_chain_restore(&chain, temp_dir.path(), &RestoreFilter::default())
+            .await?;
+
+        let mut source_dirs = Vec::new();
+        let mut entries = tokio::fs::read_dir(temp_dir.path()).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.file_type().await?.is_dir() {
+                source_dirs.push(entry.path());
+            }
+        }
+        source_dirs.sort();
+
+        let compression_type = metadata.compression_type;
+        let result = self
+            .perform_backup(
+                &source_dirs,
+                &export_path,
+                compression_type,
+                6,
+                None,
+                ArchiveExtras {
+                    metadata: Some(metadata),
+                    ..Default::default()
+                },
+            )
+            .await;
+        let (_, checksum) = match result {
+            Ok(result) => result,
+            Err(e) => {
+                // 不保留不完整的导出文件
+                if export_path.exists() {
+                    tokio::fs::remove_file(&export_path).await?;
+                }
+                return Err(e);
+            }
+        };
+
+        tracing::info!(
+            "备份 {} 已导出: {} ({} 个文件)",
+            backup_id,
+            export_path.display(),
+            checksum.file_count
+        );
+        Ok(export_path)
+    }
+
     /// 检查并迁移备份存储目录
     pub async fn migrate_storage_directory(&self, new_storage_dir: &Path) -> Result<()> {
         if new_storage_dir == self.storage_dir {
@@ -457,6 +2172,19 @@ impl BackupManager {
                     new_path.display()
                 );
 
+                // 数据库架构回滚脚本跟随备份文件迁移
+                let old_rollback_path = Self::schema_rollback_path(&old_path);
+                if old_rollback_path.exists() {
+                    tokio::fs::rename(&old_rollback_path, Self::schema_rollback_path(&new_path))
+                        .await?;
+                }
+
+                // 备份快照跟随备份文件迁移
+                let old_snapshot_path = Self::snapshot_path(&old_path);
+                if old_snapshot_path.exists() {
+                    tokio::fs::rename(&old_snapshot_path, Self::snapshot_path(&new_path)).await?;
+                }
+
                 // 更新数据库中的路径
                 self.database
                     .update_backup_file_path(backup.id, new_path.to_string_lossy().to_string())
@@ -468,6 +2196,107 @@ impl BackupManager {
         Ok(())
     }
 
+    /// 获取备份对应的数据库架构回滚脚本路径
+    pub fn schema_rollback_path(backup_path: &Path) -> PathBuf {
+        let mut path = backup_path.as_os_str().to_owned();
+        path.push(backup::SCHEMA_ROLLBACK_SUFFIX);
+        PathBuf::from(path)
+    }
+
+    /// 获取备份对应的快照文件路径
+    pub fn snapshot_path(backup_path: &Path) -> PathBuf {
+        let mut path = backup_path.as_os_str().to_owned();
+        path.push(backup::SNAPSHOT_SUFFIX);
+        PathBuf::from(path)
+    }
+
+    /// 把备份快照与备份文件保存在一起
+    async fn save_snapshot(backup_path: &Path, snapshot: &BackupSnapshot) -> Result<()> {
+        let content = serde_json::to_string(snapshot)?;
+        tokio::fs::write(Self::snapshot_path(backup_path), content).await?;
+        Ok(())
+    }
+
+    /// 读取备份快照，没有时返回None（早期版本创建的备份没有快照）
+    pub async fn load_snapshot(backup_path: &Path) -> Result<Option<BackupSnapshot>> {
+        let snapshot_path = Self::snapshot_path(backup_path);
+        if !snapshot_path.exists() {
+            return Ok(None);
+        }
+
+        let content = tokio::fs::read_to_string(&snapshot_path).await?;
+        let snapshot = serde_json::from_str(&content).map_err(|e| {
+            DuckError::Backup(format!("解析备份快照失败 {}: {e}", snapshot_path.display()))
+        })?;
+        Ok(Some(snapshot))
+    }
+
+    /// 把数据库架构回滚脚本与升级前备份保存在一起
+    pub async fn save_schema_rollback(
+        &self,
+        backup_id: i64,
+        rollback_script: &str,
+    ) -> Result<PathBuf> {
+        let backup_record = self
+            .database
+            .get_backup_by_id(backup_id)
+            .await?
+            .ok_or_else(|| DuckError::Backup(format!("备份记录不存在: {backup_id}")))?;
+
+        let rollback_path = Self::schema_rollback_path(Path::new(&backup_record.file_path));
+        tokio::fs::write(&rollback_path, rollback_script).await?;
+        tracing::info!("数据库架构回滚脚本已保存: {}", rollback_path.display());
+
+        Ok(rollback_path)
+    }
+
+    /// 读取随备份保存的数据库架构回滚脚本，没有时返回None
+    pub async fn load_schema_rollback(&self, backup_id: i64) -> Result<Option<String>> {
+        let backup_record = self
+            .database
+            .get_backup_by_id(backup_id)
+            .await?
+            .ok_or_else(|| DuckError::Backup(format!("备份记录不存在: {backup_id}")))?;
+
+        let rollback_path = Self::schema_rollback_path(Path::new(&backup_record.file_path));
+        if !rollback_path.exists() {
+            return Ok(None);
+        }
+
+        Ok(Some(tokio::fs::read_to_string(&rollback_path).await?))
+    }
+
+    /// 从备份恢复 `filter` 匹配的路径时是否会替换MySQL数据
+    ///
+    /// 根据备份链恢复后的实际内容判断：包含 `data/mysql` 下的文件或热备份的MySQL导出文件，
+    /// 且它们在恢复路径内时返回真。
+    pub async fn restores_mysql_data(
+        &self,
+        backup_id: i64,
+        filter: &RestoreFilter,
+    ) -> Result<bool> {
+        let entries = self.list_backup_entries(backup_id, None).await?;
+        Ok(entries
+            .iter()
+            .any(|entry| is_mysql_data_path(&entry.path) && filter.matches(&entry.path)))
+    }
+
+    /// 从备份恢复 `filter` 匹配的路径后需要执行的数据库架构回滚脚本
+    ///
+    /// MySQL数据随备份恢复时数据库架构已经是备份时的状态，返回None；只恢复了其他数据
+    /// （如 `app` 或 `data/redis`）时MySQL仍是升级后的架构，返回随备份保存的回滚脚本。
+    pub async fn schema_rollback_for_restore(
+        &self,
+        backup_id: i64,
+        filter: &RestoreFilter,
+    ) -> Result<Option<String>> {
+        if self.restores_mysql_data(backup_id, filter).await? {
+            tracing::info!("MySQL数据随备份恢复，无需回滚数据库架构");
+            return Ok(None);
+        }
+        self.load_schema_rollback(backup_id).await
+    }
+
     /// 获取存储目录
     pub fn get_storage_dir(&self) -> &Path {
         &self.storage_dir
@@ -496,3 +2325,745 @@ impl BackupManager {
         Ok(total_size / 2)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+    use tempfile::tempdir;
+
+    /// 在 `dir` 下创建测试用的备份管理器（数据库 `test.db`，备份目录 `backups`）
+    async fn test_manager(dir: &Path) -> BackupManager {
+        let database = Database::connect(dir.join("test.db")).await.unwrap();
+        let docker_manager = DockerManager::new(dir.join("docker-compose.yml")).unwrap();
+        BackupManager::new(dir.join("backups"), database, docker_manager).unwrap()
+    }
+
+    /// 备份 `data_dir` 的手动冷备份选项（gzip压缩）
+    fn backup_options(data_dir: &Path, backup_mode: BackupMode) -> BackupOptions {
+        BackupOptions {
+            backup_type: BackupType::Manual,
+            backup_mode,
+            service_version: "1.0.0".to_string(),
+            source_dirs: vec![data_dir.to_path_buf()],
+            compression_type: CompressionType::Gzip,
+            compression_level: 6,
+            hot_backup: false,
+        }
+    }
+
+    /// 列出备份归档中的文件
+    fn archived_files(backup_path: &Path) -> Vec<String> {
+        let file = std::fs::File::open(backup_path).unwrap();
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+        let mut files: Vec<String> = archive
+            .entries()
+            .unwrap()
+            .map(|entry| entry.unwrap().path().unwrap().to_string_lossy().to_string())
+            .filter(|path| path != backup::MANIFEST_FILE_NAME)
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[tokio::test]
+    async fn test_incremental_backup_chain() {
+        let temp_dir = tempdir().unwrap();
+        let data_dir = temp_dir.path().join("docker").join("data");
+        std::fs::create_dir_all(data_dir.join("mysql")).unwrap();
+        std::fs::write(data_dir.join("mysql/ibdata1"), "base").unwrap();
+        std::fs::write(data_dir.join("mysql/touched.ibd"), "same").unwrap();
+        std::fs::write(data_dir.join("removed.log"), "old").unwrap();
+
+        let manager = test_manager(temp_dir.path()).await;
+        let options = |backup_mode| backup_options(&data_dir, backup_mode);
+
+        let full = manager
+            .create_backup(options(BackupMode::Full))
+            .await
+            .unwrap();
+        assert_eq!(full.backup_mode, BackupMode::Full);
+
+        // 修改、新增、删除文件，并只更新一个文件的修改时间
+        std::fs::write(data_dir.join("mysql/ibdata1"), "changed").unwrap();
+        std::fs::write(data_dir.join("mysql/new.ibd"), "new").unwrap();
+        std::fs::remove_file(data_dir.join("removed.log")).unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(data_dir.join("mysql/touched.ibd"))
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+
+        // 同一秒内创建的备份文件名相同，等待时间戳变化
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        let incremental = manager
+            .create_backup(options(BackupMode::Incremental))
+            .await
+            .unwrap();
+        assert_eq!(incremental.backup_mode, BackupMode::Incremental);
+        assert_eq!(incremental.parent_id, Some(full.id));
+        assert_eq!(
+            archived_files(Path::new(&incremental.file_path)),
+            vec!["data/mysql/ibdata1", "data/mysql/new.ibd"]
+        );
+
+        // 被增量备份依赖的备份不能删除
+        assert!(manager.delete_backup(full.id).await.is_err());
+
+        // 按备份链恢复：全量 + 增量，并删除增量备份时已不存在的文件
+        let chain = manager.get_backup_chain(incremental.id).await.unwrap();
+        assert_eq!(
+            chain.iter().map(|backup| backup.id).collect::<Vec<_>>(),
+            vec![full.id, incremental.id]
+        );
+        let restore_dir = temp_dir.path().join("restore");
+        manager
+            .perform_chain_restore(&chain, &restore_dir, &RestoreFilter::default())
+            .await
+            .unwrap();
+        let read = |path: &str| std::fs::read_to_string(restore_dir.join(path)).unwrap();
+        assert_eq!(read("data/mysql/ibdata1"), "changed");
+        assert_eq!(read("data/mysql/new.ibd"), "new");
+        assert_eq!(read("data/mysql/touched.ibd"), "same");
+        assert!(!restore_dir.join("data/removed.log").exists());
+    }
+
+    #[test]
+    fn test_restore_filter() {
+        let filter = RestoreFilter::default()
+            .with_path("./upload/")
+            .unwrap()
+            .with_service("redis")
+            .unwrap()
+            .with_path("data/redis")
+            .unwrap();
+        assert_eq!(filter.paths(), ["upload", "data/redis"]);
+        assert!(filter.matches("upload/a.png"));
+        assert!(filter.matches("data/redis/dump.rdb"));
+        assert!(!filter.matches("data/redis-old/dump.rdb"));
+        assert!(!filter.matches("data/mysql/ibdata1"));
+        assert!(RestoreFilter::default().matches("data/mysql/ibdata1"));
+
+        let mysql = RestoreFilter::default().with_service("MySQL").unwrap();
+        assert!(mysql.matches(backup::MYSQL_DUMP_ARCHIVE_PATH));
+
+        assert!(RestoreFilter::default().with_path("../etc").is_err());
+        assert!(RestoreFilter::default().with_path("/data").is_err());
+        assert!(RestoreFilter::default().with_service("nginx").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_selective_restore() {
+        let temp_dir = tempdir().unwrap();
+        let data_dir = temp_dir.path().join("docker").join("data");
+        std::fs::create_dir_all(data_dir.join("mysql")).unwrap();
+        std::fs::create_dir_all(data_dir.join("redis")).unwrap();
+        std::fs::write(data_dir.join("mysql/ibdata1"), "backup").unwrap();
+        std::fs::write(data_dir.join("redis/dump.rdb"), "backup").unwrap();
+
+        let manager = test_manager(temp_dir.path()).await;
+        let record = manager
+            .create_backup(backup_options(&data_dir, BackupMode::Full))
+            .await
+            .unwrap();
+
+        // 备份之后两个服务的数据都有变化，只恢复Redis
+        std::fs::write(data_dir.join("mysql/ibdata1"), "current").unwrap();
+        std::fs::write(data_dir.join("redis/dump.rdb"), "broken").unwrap();
+        std::fs::write(data_dir.join("redis/temp.rdb"), "temp").unwrap();
+
+        let filter = RestoreFilter::default()
+            .with_service("redis")
+            .unwrap()
+            .with_service("upload")
+            .unwrap();
+        // 备份中没有上传目录，不清理
+        let filter = BackupManager::restrict_restore_filter(&record, &filter)
+            .await
+            .unwrap();
+        assert_eq!(filter.paths(), ["data/redis"]);
+
+        let docker_dir = temp_dir.path().join("docker");
+        let chain = manager.get_backup_chain(record.id).await.unwrap();
+        let staging_dir = manager
+            .stage_restore(&chain, &docker_dir, &filter)
+            .await
+            .unwrap();
+        let pre_restore_dir = BackupManager::pre_restore_dir(&docker_dir).unwrap();
+        BackupManager::swap_restored_paths(
+            &docker_dir,
+            &staging_dir,
+            &pre_restore_dir,
+            filter.paths(),
+        )
+        .await
+        .unwrap();
+
+        let read = |path: &str| std::fs::read_to_string(data_dir.join(path)).unwrap();
+        assert_eq!(read("redis/dump.rdb"), "backup");
+        assert!(!data_dir.join("redis/temp.rdb").exists());
+        assert_eq!(read("mysql/ibdata1"), "current");
+        // 被替换的数据保存在恢复前快照中
+        assert!(pre_restore_dir.join("data/redis/temp.rdb").exists());
+        assert!(!pre_restore_dir.join("data/mysql").exists());
+
+        // 所有路径在备份中都没有数据时报错
+        let upload_only = RestoreFilter::default().with_service("upload").unwrap();
+        assert!(
+            BackupManager::restrict_restore_filter(&record, &upload_only)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_staged_restore_swap_back() {
+        let temp_dir = tempdir().unwrap();
+        let docker_dir = temp_dir.path().join("docker");
+        std::fs::create_dir_all(docker_dir.join("data/redis")).unwrap();
+        std::fs::create_dir_all(docker_dir.join("app")).unwrap();
+        std::fs::write(docker_dir.join("data/redis/dump.rdb"), "backup").unwrap();
+        std::fs::write(docker_dir.join("app/app.jar"), "backup").unwrap();
+
+        let manager = test_manager(temp_dir.path()).await;
+        let record = manager
+            .create_backup(BackupOptions {
+                source_dirs: vec![docker_dir.join("data"), docker_dir.join("app")],
+                ..backup_options(&docker_dir.join("data"), BackupMode::Full)
+            })
+            .await
+            .unwrap();
+
+        std::fs::write(docker_dir.join("data/redis/dump.rdb"), "current").unwrap();
+        std::fs::write(docker_dir.join("data/new.log"), "current").unwrap();
+        std::fs::write(docker_dir.join(".env"), "config").unwrap();
+
+        // 暂存目录中的文件大小与快照不一致时校验失败
+        let chain = manager.get_backup_chain(record.id).await.unwrap();
+        let filter = RestoreFilter::default();
+        let staging_dir = manager
+            .stage_restore(&chain, &docker_dir, &filter)
+            .await
+            .unwrap();
+        assert_eq!(staging_dir, temp_dir.path().join(".docker.restore-staging"));
+        let snapshot = BackupManager::load_snapshot(Path::new(&record.file_path))
+            .await
+            .unwrap();
+        std::fs::write(staging_dir.join("app/app.jar"), "truncated backup").unwrap();
+        assert!(
+            BackupManager::verify_staging(&staging_dir, snapshot, &filter)
+                .await
+                .is_err()
+        );
+        std::fs::write(staging_dir.join("app/app.jar"), "backup").unwrap();
+
+        // 替换数据目录，配置文件保留
+        let units = vec!["app".to_string(), "data".to_string()];
+        let pre_restore_dir = BackupManager::pre_restore_dir(&docker_dir).unwrap();
+        BackupManager::swap_restored_paths(&docker_dir, &staging_dir, &pre_restore_dir, &units)
+            .await
+            .unwrap();
+        let read = |path: &str| std::fs::read_to_string(docker_dir.join(path)).unwrap();
+        assert_eq!(read("data/redis/dump.rdb"), "backup");
+        assert!(!docker_dir.join("data/new.log").exists());
+        assert_eq!(read(".env"), "config");
+        assert_eq!(
+            std::fs::read_to_string(pre_restore_dir.join("data/redis/dump.rdb")).unwrap(),
+            "current"
+        );
+
+        // 健康检查失败时换回恢复前的数据
+        BackupManager::swap_back(&docker_dir, &pre_restore_dir, &units)
+            .await
+            .unwrap();
+        assert_eq!(read("data/redis/dump.rdb"), "current");
+        assert_eq!(read("data/new.log"), "current");
+        assert_eq!(read("app/app.jar"), "backup");
+        assert_eq!(read(".env"), "config");
+        assert!(!pre_restore_dir.exists());
+    }
+
+    #[tokio::test]
+    async fn test_browse_backup_contents() {
+        let temp_dir = tempdir().unwrap();
+        let data_dir = temp_dir.path().join("docker").join("data");
+        std::fs::create_dir_all(data_dir.join("redis")).unwrap();
+        std::fs::write(data_dir.join("redis/dump.rdb"), "redis").unwrap();
+        std::fs::write(data_dir.join("changed.txt"), "old").unwrap();
+        std::fs::write(data_dir.join("removed.txt"), "removed").unwrap();
+
+        let manager = test_manager(temp_dir.path()).await;
+        let options = |backup_mode| backup_options(&data_dir, backup_mode);
+
+        let full = manager
+            .create_backup(options(BackupMode::Full))
+            .await
+            .unwrap();
+        std::fs::write(data_dir.join("changed.txt"), "new content").unwrap();
+        std::fs::write(data_dir.join("added.txt"), "added").unwrap();
+        std::fs::remove_file(data_dir.join("removed.txt")).unwrap();
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        let incremental = manager
+            .create_backup(options(BackupMode::Incremental))
+            .await
+            .unwrap();
+
+        // 增量备份列出整个备份链恢复后的内容
+        let entries = manager
+            .list_backup_entries(incremental.id, None)
+            .await
+            .unwrap();
+        let listed: Vec<(&str, u64, i64)> = entries
+            .iter()
+            .map(|entry| (entry.path.as_str(), entry.size, entry.backup_id))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                ("data/added.txt", 5, incremental.id),
+                ("data/changed.txt", 11, incremental.id),
+                ("data/redis/dump.rdb", 5, full.id),
+            ]
+        );
+        assert!(entries.iter().all(|entry| entry.sha256.is_some()));
+
+        let redis_entries = manager
+            .list_backup_entries(incremental.id, Some("data/redis/"))
+            .await
+            .unwrap();
+        assert_eq!(redis_entries.len(), 1);
+
+        // 未变化的文件从全量备份中读取
+        let output_path = temp_dir.path().join("dump.rdb");
+        let size = manager
+            .read_backup_file(
+                incremental.id,
+                "./data/redis/dump.rdb",
+                std::fs::File::create(&output_path).unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(size, 5);
+        assert_eq!(std::fs::read_to_string(&output_path).unwrap(), "redis");
+        assert!(
+            manager
+                .read_backup_file(incremental.id, "data/removed.txt", std::io::sink())
+                .await
+                .is_err()
+        );
+
+        let diff = manager.diff_backups(full.id, incremental.id).await.unwrap();
+        assert_eq!(diff.added, vec!["data/added.txt"]);
+        assert_eq!(diff.removed, vec!["data/removed.txt"]);
+        assert_eq!(diff.changed, vec!["data/changed.txt"]);
+        assert!(
+            manager
+                .diff_backups(full.id, full.id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_zstd_and_uncompressed_backups() {
+        let temp_dir = tempdir().unwrap();
+        let data_dir = temp_dir.path().join("docker").join("data");
+        std::fs::create_dir_all(&data_dir).unwrap();
+        std::fs::write(data_dir.join("a.txt"), "a".repeat(10_000)).unwrap();
+
+        let manager = test_manager(temp_dir.path()).await;
+
+        for (compression_type, extension) in [
+            (CompressionType::Zstd, ".tar.zst"),
+            (CompressionType::None, ".tar"),
+        ] {
+            let record = manager
+                .create_backup(BackupOptions {
+                    compression_type,
+                    compression_level: 3,
+                    ..backup_options(&data_dir, BackupMode::Full)
+                })
+                .await
+                .unwrap();
+            assert!(record.file_path.ends_with(extension));
+            assert_eq!(record.compression_type, compression_type);
+            assert!(
+                manager
+                    .verify_backup_deep(record.id)
+                    .await
+                    .unwrap()
+                    .is_valid()
+            );
+
+            // 恢复时根据文件头识别压缩格式
+            let restore_dir = temp_dir.path().join(format!("restore_{}", record.id));
+            let chain = manager.get_backup_chain(record.id).await.unwrap();
+            manager
+                .perform_chain_restore(&chain, &restore_dir, &RestoreFilter::default())
+                .await
+                .unwrap();
+            assert_eq!(
+                std::fs::read_to_string(restore_dir.join("data/a.txt")).unwrap(),
+                "a".repeat(10_000)
+            );
+
+            tokio::time::sleep(Duration::from_millis(1100)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_and_import_backup() {
+        let temp_dir = tempdir().unwrap();
+        let data_dir = temp_dir.path().join("docker").join("data");
+        std::fs::create_dir_all(data_dir.join("redis")).unwrap();
+        std::fs::write(data_dir.join("redis/dump.rdb"), "redis").unwrap();
+        std::fs::write(data_dir.join("removed.txt"), "removed").unwrap();
+
+        let manager = test_manager(temp_dir.path()).await;
+        let options = |backup_mode| BackupOptions {
+            service_version: "1.2.0".to_string(),
+            ..backup_options(&data_dir, backup_mode)
+        };
+        manager
+            .create_backup(options(BackupMode::Full))
+            .await
+            .unwrap();
+        std::fs::write(data_dir.join("added.txt"), "added").unwrap();
+        std::fs::remove_file(data_dir.join("removed.txt")).unwrap();
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        let incremental = manager
+            .create_backup(options(BackupMode::Incremental))
+            .await
+            .unwrap();
+
+        // 增量备份导出为包含整个备份链内容的全量备份
+        let export_dir = temp_dir.path().join("export");
+        std::fs::create_dir_all(&export_dir).unwrap();
+        let export_path = manager
+            .export_backup(incremental.id, &export_dir)
+            .await
+            .unwrap();
+        assert_eq!(
+            archived_files(&export_path),
+            vec!["data/added.txt", "data/redis/dump.rdb"]
+        );
+        assert!(
+            manager
+                .export_backup(incremental.id, &export_path)
+                .await
+                .is_err()
+        );
+
+        // 在另一台机器上导入
+        let other_dir = temp_dir.path().join("other");
+        std::fs::create_dir_all(&other_dir).unwrap();
+        let other_manager = test_manager(&other_dir).await;
+        let imported = other_manager.import_backup(&export_path).await.unwrap();
+        assert_eq!(imported.service_version, "1.2.0");
+        assert_eq!(imported.backup_type, BackupType::Manual);
+        assert_eq!(imported.backup_mode, BackupMode::Full);
+        assert_eq!(imported.created_at, incremental.created_at);
+        assert_eq!(imported.file_count, Some(2));
+        assert!(
+            imported
+                .file_path
+                .starts_with(&*temp_dir.path().join("other_backups").to_string_lossy())
+        );
+        assert!(
+            other_manager
+                .verify_backup_deep(imported.id)
+                .await
+                .unwrap()
+                .is_valid()
+        );
+        assert!(other_manager.import_backup(&export_path).await.is_err());
+
+        // 导入的备份可以作为增量备份的基准
+        let next = other_manager
+            .create_backup(options(BackupMode::Incremental))
+            .await
+            .unwrap();
+        assert_eq!(next.parent_id, Some(imported.id));
+        assert!(archived_files(Path::new(&next.file_path)).is_empty());
+
+        // 增量备份依赖其他备份，不能直接导入
+        assert!(
+            other_manager
+                .import_backup(Path::new(&incremental.file_path))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_backup_round_trip() {
+        let temp_dir = tempdir().unwrap();
+        let data_dir = temp_dir.path().join("docker").join("data");
+        std::fs::create_dir_all(&data_dir).unwrap();
+        std::fs::write(data_dir.join(".env"), "MYSQL_PASSWORD=secret").unwrap();
+
+        let identity = age::x25519::Identity::generate();
+        let secret_key = {
+            use age::secrecy::ExposeSecret;
+            identity.to_string().expose_secret().to_string()
+        };
+        let encryption = BackupEncryption::default()
+            .with_recipients(&[identity.to_public().to_string()])
+            .unwrap()
+            .with_identities(&secret_key)
+            .unwrap();
+
+        let manager = test_manager(temp_dir.path())
+            .await
+            .with_encryption(encryption.clone());
+
+        let record = manager
+            .create_backup(backup_options(&data_dir, BackupMode::Full))
+            .await
+            .unwrap();
+        let backup_path = Path::new(&record.file_path);
+        assert!(record.file_path.ends_with(".tar.gz.age"));
+        assert_eq!(record.encryption_fingerprint, encryption.fingerprint());
+        assert!(BackupEncryption::is_encrypted(backup_path).unwrap());
+
+        // 备份文件中不包含明文
+        let content = std::fs::read(backup_path).unwrap();
+        assert!(!content.windows(6).any(|window| window == b"secret"));
+
+        assert!(manager.verify_backup(record.id).await.unwrap());
+        let restore_dir = temp_dir.path().join("restore");
+        manager
+            .perform_restore(backup_path, &restore_dir)
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(restore_dir.join("data/.env")).unwrap(),
+            "MYSQL_PASSWORD=secret"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deep_verify_detects_tampered_files() {
+        use std::io::Read;
+
+        let temp_dir = tempdir().unwrap();
+        let data_dir = temp_dir.path().join("docker").join("data");
+        std::fs::create_dir_all(&data_dir).unwrap();
+        std::fs::write(data_dir.join("a.txt"), "aaa").unwrap();
+        std::fs::write(data_dir.join("b.txt"), "bbb").unwrap();
+        std::fs::write(data_dir.join("c.txt"), "ccc").unwrap();
+
+        let manager = test_manager(temp_dir.path()).await;
+
+        let record = manager
+            .create_backup(backup_options(&data_dir, BackupMode::Full))
+            .await
+            .unwrap();
+        let backup_path = PathBuf::from(&record.file_path);
+        assert_eq!(record.file_count, Some(3));
+        assert_eq!(
+            record.backup_size,
+            Some(std::fs::metadata(&backup_path).unwrap().len() as i64)
+        );
+        assert_eq!(
+            record.backup_hash,
+            Some(calculate_file_hash(&backup_path).unwrap())
+        );
+
+        let verification = manager.verify_backup_deep(record.id).await.unwrap();
+        assert!(verification.is_valid());
+        assert_eq!(verification.backup_hash_matches, Some(true));
+        assert_eq!(verification.checked_files, 3);
+
+        // 重新打包：篡改 a.txt，删除 b.txt，保留原来的清单
+        let mut entries = Vec::new();
+        let file = std::fs::File::open(&backup_path).unwrap();
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let path = entry.path().unwrap().to_string_lossy().to_string();
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content).unwrap();
+            entries.push((path, content));
+        }
+        let file = std::fs::File::create(&backup_path).unwrap();
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            file,
+            flate2::Compression::default(),
+        ));
+        for (path, mut content) in entries {
+            match path.as_str() {
+                "data/a.txt" => content = b"tampered".to_vec(),
+                "data/b.txt" => continue,
+                _ => {}
+            }
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            builder
+                .append_data(&mut header, &path, content.as_slice())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let verification = manager.verify_backup_deep(record.id).await.unwrap();
+        assert!(!verification.is_valid());
+        assert_eq!(verification.backup_hash_matches, Some(false));
+        assert!(verification.manifest_found);
+        assert_eq!(verification.corrupt_files, vec!["data/a.txt"]);
+        assert_eq!(verification.missing_files, vec!["data/b.txt"]);
+        assert!(verification.unexpected_files.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_remote_storage_upload_and_restore() {
+        use crate::backup_storage::LocalStorage;
+
+        let temp_dir = tempdir().unwrap();
+        let data_dir = temp_dir.path().join("docker").join("data");
+        std::fs::create_dir_all(&data_dir).unwrap();
+        std::fs::write(data_dir.join("a.txt"), "aaa").unwrap();
+
+        let storage = Arc::new(LocalStorage::new(temp_dir.path().join("remote")));
+        let manager = test_manager(temp_dir.path())
+            .await
+            .with_remote_storage(storage.clone());
+
+        let record = manager
+            .create_backup(backup_options(&data_dir, BackupMode::Full))
+            .await
+            .unwrap();
+        assert_eq!(record.storage_target, storage.target());
+        let remote_files: Vec<String> = manager
+            .list_remote_backups()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|object| object.name)
+            .collect();
+        assert_eq!(remote_files.len(), 2);
+
+        // 本地备份文件丢失后从远程存储下载并恢复
+        let backup_path = PathBuf::from(&record.file_path);
+        std::fs::remove_file(&backup_path).unwrap();
+        let chain = manager.get_backup_chain(record.id).await.unwrap();
+        assert!(backup_path.exists());
+        let restore_dir = temp_dir.path().join("restore");
+        manager
+            .perform_chain_restore(&chain, &restore_dir, &RestoreFilter::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(restore_dir.join("data/a.txt")).unwrap(),
+            "aaa"
+        );
+
+        // 删除备份时同时删除远程副本
+        manager.delete_backup(record.id).await.unwrap();
+        assert!(manager.list_remote_backups().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_schema_rollback_for_restore() {
+        let temp_dir = tempdir().unwrap();
+        let data_dir = temp_dir.path().join("docker").join("data");
+        std::fs::create_dir_all(data_dir.join("mysql")).unwrap();
+        std::fs::create_dir_all(data_dir.join("redis")).unwrap();
+        std::fs::write(data_dir.join("mysql/ibdata1"), "mysql").unwrap();
+        std::fs::write(data_dir.join("redis/dump.rdb"), "redis").unwrap();
+
+        let manager = test_manager(temp_dir.path()).await;
+        let record = manager
+            .create_backup(backup_options(&data_dir, BackupMode::Full))
+            .await
+            .unwrap();
+        let script = "USE `app`;\nALTER TABLE `users` DROP COLUMN `email`;\n";
+        manager
+            .save_schema_rollback(record.id, script)
+            .await
+            .unwrap();
+
+        // 恢复全部数据或MySQL数据时，数据库架构随数据恢复
+        let all = RestoreFilter::default();
+        let mysql = RestoreFilter::default().with_service("mysql").unwrap();
+        assert!(manager.restores_mysql_data(record.id, &all).await.unwrap());
+        for filter in [&all, &mysql] {
+            assert_eq!(
+                manager
+                    .schema_rollback_for_restore(record.id, filter)
+                    .await
+                    .unwrap(),
+                None
+            );
+        }
+
+        // 只恢复其他数据时，MySQL保留升级后的架构，需要执行回滚脚本
+        let redis = RestoreFilter::default().with_service("redis").unwrap();
+        assert!(
+            !manager
+                .restores_mysql_data(record.id, &redis)
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            manager
+                .schema_rollback_for_restore(record.id, &redis)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some(script)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hot_backup_archive_content() {
+        let temp_dir = tempdir().unwrap();
+        let data_dir = temp_dir.path().join("docker").join("data");
+        std::fs::create_dir_all(data_dir.join("mysql")).unwrap();
+        std::fs::create_dir_all(data_dir.join("redis")).unwrap();
+        std::fs::write(data_dir.join("mysql/ibdata1"), "inconsistent").unwrap();
+        std::fs::write(data_dir.join("redis/dump.rdb"), "rdb").unwrap();
+        let dump_path = temp_dir.path().join("databases.sql");
+        std::fs::write(&dump_path, "CREATE DATABASE app;").unwrap();
+
+        let manager = test_manager(temp_dir.path()).await;
+
+        // 热备份不打包MySQL数据目录，改为打包导出文件
+        let backup_path = temp_dir.path().join("backups/backup_hot.tar.gz");
+        let (snapshot, checksum) = manager
+            .perform_backup(
+                std::slice::from_ref(&data_dir),
+                &backup_path,
+                CompressionType::Gzip,
+                6,
+                None,
+                ArchiveExtras {
+                    excluded_dirs: vec!["data/mysql".to_string()],
+                    extra_files: vec![(dump_path, backup::MYSQL_DUMP_ARCHIVE_PATH.to_string())],
+                    metadata: None,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            archived_files(&backup_path),
+            vec!["data/redis/dump.rdb", backup::MYSQL_DUMP_ARCHIVE_PATH]
+        );
+        assert_eq!(checksum.file_count, 2);
+        assert!(!snapshot.files.contains_key("data/mysql/ibdata1"));
+        assert!(!snapshot.files.contains_key(backup::MYSQL_DUMP_ARCHIVE_PATH));
+
+        let restore_dir = temp_dir.path().join("restore");
+        manager
+            .perform_restore(&backup_path, &restore_dir)
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(restore_dir.join(backup::MYSQL_DUMP_ARCHIVE_PATH)).unwrap(),
+            "CREATE DATABASE app;"
+        );
+        assert!(!restore_dir.join("data/mysql").exists());
+    }
+}
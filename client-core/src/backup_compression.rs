@@ -0,0 +1,114 @@
+//! 备份压缩
+//!
+//! 备份归档（tar）支持 gzip、zstd 和不压缩三种格式，zstd 使用多线程压缩。
+//! 恢复时根据归档的文件头自动识别压缩格式，不依赖备份记录和文件名。
+
+use crate::{Result, database::CompressionType};
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use std::io::{BufRead, BufReader, Read, Write};
+
+/// gzip 文件头
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+/// zstd 文件头
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// 备份归档的压缩写入流
+pub enum CompressionWriter<W: Write> {
+    Gzip(GzEncoder<W>),
+    Zstd(zstd::Encoder<'static, W>),
+    None(W),
+}
+
+impl<W: Write> CompressionWriter<W> {
+    /// 按指定格式压缩写入 `output`
+    ///
+    /// `level` 为压缩级别：gzip 为 0-9（超出按9处理），zstd 为 1-22（0表示zstd默认级别）。
+    pub fn new(output: W, compression_type: CompressionType, level: u32) -> Result<Self> {
+        Ok(match compression_type {
+            CompressionType::Gzip => {
+                CompressionWriter::Gzip(GzEncoder::new(output, Compression::new(level.min(9))))
+            }
+            CompressionType::Zstd => {
+                let mut encoder = zstd::Encoder::new(output, level as i32)?;
+                let workers = std::thread::available_parallelism().map_or(1, |n| n.get() as u32);
+                encoder.multithread(workers)?;
+                CompressionWriter::Zstd(encoder)
+            }
+            CompressionType::None => CompressionWriter::None(output),
+        })
+    }
+
+    /// 完成压缩并返回底层写入流
+    pub fn finish(self) -> std::io::Result<W> {
+        match self {
+            CompressionWriter::Gzip(encoder) => encoder.finish(),
+            CompressionWriter::Zstd(encoder) => encoder.finish(),
+            CompressionWriter::None(output) => Ok(output),
+        }
+    }
+}
+
+impl<W: Write> Write for CompressionWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            CompressionWriter::Gzip(encoder) => encoder.write(buf),
+            CompressionWriter::Zstd(encoder) => encoder.write(buf),
+            CompressionWriter::None(output) => output.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            CompressionWriter::Gzip(encoder) => encoder.flush(),
+            CompressionWriter::Zstd(encoder) => encoder.flush(),
+            CompressionWriter::None(output) => output.flush(),
+        }
+    }
+}
+
+/// 根据文件头识别压缩格式，返回解压后的tar数据流
+pub fn decompress<R: Read + 'static>(reader: R) -> Result<Box<dyn Read>> {
+    let mut reader = BufReader::new(reader);
+    let header = reader.fill_buf()?;
+    let is_gzip = header.starts_with(GZIP_MAGIC);
+    let is_zstd = header.starts_with(ZSTD_MAGIC);
+
+    Ok(if is_gzip {
+        Box::new(GzDecoder::new(reader))
+    } else if is_zstd {
+        Box::new(zstd::Decoder::with_buffer(reader)?)
+    } else {
+        Box::new(reader)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_detects_format() {
+        let data = b"tar archive content ".repeat(1000);
+
+        for compression_type in [
+            CompressionType::Gzip,
+            CompressionType::Zstd,
+            CompressionType::None,
+        ] {
+            let mut writer = CompressionWriter::new(Vec::new(), compression_type, 3).unwrap();
+            writer.write_all(&data).unwrap();
+            let compressed = writer.finish().unwrap();
+            if compression_type != CompressionType::None {
+                assert!(compressed.len() < data.len());
+            }
+
+            let mut decompressed = Vec::new();
+            decompress(std::io::Cursor::new(compressed))
+                .unwrap()
+                .read_to_end(&mut decompressed)
+                .unwrap();
+            assert_eq!(decompressed, data);
+        }
+    }
+}
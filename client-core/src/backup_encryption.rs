@@ -0,0 +1,294 @@
+//! 备份加密
+//!
+//! 备份归档（压缩后的tar）外层使用 age 格式加密：配置了公钥接收者时用公钥加密，
+//! 否则使用环境变量中的口令加密。恢复和校验时根据文件头自动识别加密的备份并解密。
+
+use crate::{DuckError, Result, backup_compression, config::BackupConfig, constants::backup};
+use age::secrecy::SecretString;
+use age::stream::StreamWriter;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// age 加密文件的文件头
+const AGE_MAGIC: &[u8] = b"age-encryption.org/v1";
+
+/// 备份加密配置：加密时使用的口令或公钥接收者，以及解密时使用的私钥
+#[derive(Clone, Default)]
+pub struct BackupEncryption {
+    passphrase: Option<SecretString>,
+    recipients: Vec<age::x25519::Recipient>,
+    identities: Vec<age::x25519::Identity>,
+}
+
+impl std::fmt::Debug for BackupEncryption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BackupEncryption")
+            .field("passphrase", &self.passphrase.is_some())
+            .field("recipients", &self.recipients.len())
+            .field("identities", &self.identities.len())
+            .finish()
+    }
+}
+
+impl BackupEncryption {
+    /// 根据配置文件和环境变量创建加密配置
+    ///
+    /// 口令从环境变量 `DUCK_BACKUP_PASSPHRASE` 读取，不保存在配置文件中。
+    pub fn from_config(config: &BackupConfig) -> Result<Self> {
+        let mut encryption = Self::default().with_recipients(&config.encryption_recipients)?;
+
+        if let Some(passphrase) = std::env::var(backup::ENCRYPTION_PASSPHRASE_ENV)
+            .ok()
+            .filter(|passphrase| !passphrase.is_empty())
+        {
+            encryption = encryption.with_passphrase(passphrase);
+        }
+
+        if let Some(identity_file) = &config.encryption_identity_file {
+            let content = std::fs::read_to_string(identity_file).map_err(|e| {
+                DuckError::Backup(format!("读取备份解密私钥文件失败 {identity_file}: {e}"))
+            })?;
+            encryption = encryption.with_identities(&content)?;
+        }
+
+        Ok(encryption)
+    }
+
+    /// 使用口令加密和解密
+    pub fn with_passphrase(mut self, passphrase: impl Into<String>) -> Self {
+        self.passphrase = Some(SecretString::from(passphrase.into()));
+        self
+    }
+
+    /// 使用公钥（`age1...`）加密
+    pub fn with_recipients(mut self, recipients: &[String]) -> Result<Self> {
+        for recipient in recipients {
+            let parsed = recipient
+                .trim()
+                .parse()
+                .map_err(|e| DuckError::Backup(format!("无效的备份加密公钥 {recipient}: {e}")))?;
+            self.recipients.push(parsed);
+        }
+        Ok(self)
+    }
+
+    /// 使用私钥（`AGE-SECRET-KEY-...`，每行一个，支持 `#` 注释）解密
+    pub fn with_identities(mut self, identities: &str) -> Result<Self> {
+        for line in identities.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let identity = line
+                .parse()
+                .map_err(|e| DuckError::Backup(format!("无效的备份解密私钥: {e}")))?;
+            self.identities.push(identity);
+        }
+        Ok(self)
+    }
+
+    /// 新建的备份是否加密
+    pub fn is_enabled(&self) -> bool {
+        !self.recipients.is_empty() || self.passphrase.is_some()
+    }
+
+    /// 加密密钥的指纹，记录到备份记录中用于确认解密所需的密钥
+    ///
+    /// 公钥加密时为公钥列表的SHA-256前16位；口令加密时不记录口令相关信息，只标记为 `passphrase`。
+    pub fn fingerprint(&self) -> Option<String> {
+        if !self.recipients.is_empty() {
+            let mut recipients: Vec<String> =
+                self.recipients.iter().map(|r| r.to_string()).collect();
+            recipients.sort();
+            let hash = Sha256::digest(recipients.join("\n").as_bytes());
+            Some(format!("age:{}", &format!("{hash:x}")[..16]))
+        } else if self.passphrase.is_some() {
+            Some("passphrase".to_string())
+        } else {
+            None
+        }
+    }
+
+    /// 包装备份文件的写入流：启用加密时写入的数据会被加密
+    pub fn wrap_output<W: Write>(&self, output: W) -> Result<BackupWriter<W>> {
+        let encryptor = if !self.recipients.is_empty() {
+            age::Encryptor::with_recipients(
+                self.recipients
+                    .iter()
+                    .map(|recipient| recipient as &dyn age::Recipient),
+            )
+            .map_err(|e| DuckError::Backup(format!("创建备份加密器失败: {e}")))?
+        } else if let Some(passphrase) = &self.passphrase {
+            age::Encryptor::with_user_passphrase(passphrase.clone())
+        } else {
+            return Ok(BackupWriter::Plain(output));
+        };
+
+        Ok(BackupWriter::Encrypted(encryptor.wrap_output(output)?))
+    }
+
+    /// 检查备份文件是否加密
+    pub fn is_encrypted(backup_path: &Path) -> Result<bool> {
+        let mut header = [0u8; AGE_MAGIC.len()];
+        let mut file = File::open(backup_path)?;
+        match file.read_exact(&mut header) {
+            Ok(()) => Ok(header == AGE_MAGIC),
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// 打开备份文件，返回解密并解压后的tar数据流（未加密的备份直接解压，压缩格式自动识别）
+    pub fn open_archive(&self, backup_path: &Path) -> Result<Box<dyn Read>> {
+        let encrypted = Self::is_encrypted(backup_path)?;
+        let file = File::open(backup_path)?;
+        if !encrypted {
+            return backup_compression::decompress(file);
+        }
+
+        let decryptor = age::Decryptor::new(file)
+            .map_err(|e| DuckError::Backup(format!("读取加密备份失败: {e}")))?;
+
+        let result = if decryptor.is_scrypt() {
+            let passphrase = self.passphrase.clone().ok_or_else(|| {
+                DuckError::Backup(format!(
+                    "备份使用口令加密，请通过环境变量 {} 提供口令",
+                    backup::ENCRYPTION_PASSPHRASE_ENV
+                ))
+            })?;
+            let identity = age::scrypt::Identity::new(passphrase);
+            decryptor.decrypt(std::iter::once(&identity as &dyn age::Identity))
+        } else {
+            if self.identities.is_empty() {
+                return Err(DuckError::Backup(
+                    "备份使用公钥加密，请在配置文件中设置 backup.encryption_identity_file"
+                        .to_string(),
+                ));
+            }
+            decryptor.decrypt(
+                self.identities
+                    .iter()
+                    .map(|identity| identity as &dyn age::Identity),
+            )
+        };
+        let reader = result.map_err(|e| DuckError::Backup(format!("解密备份失败: {e}")))?;
+
+        backup_compression::decompress(reader)
+    }
+}
+
+/// 备份文件写入流（明文或加密）
+pub enum BackupWriter<W: Write> {
+    Plain(W),
+    Encrypted(StreamWriter<W>),
+}
+
+impl<W: Write> BackupWriter<W> {
+    /// 完成写入并返回底层写入流；加密时必须调用，否则备份文件不完整
+    pub fn finish(self) -> Result<W> {
+        let mut output = match self {
+            BackupWriter::Plain(output) => output,
+            BackupWriter::Encrypted(writer) => writer.finish()?,
+        };
+        output.flush()?;
+        Ok(output)
+    }
+}
+
+impl<W: Write> Write for BackupWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            BackupWriter::Plain(output) => output.write(buf),
+            BackupWriter::Encrypted(writer) => writer.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            BackupWriter::Plain(output) => output.flush(),
+            BackupWriter::Encrypted(writer) => writer.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn write_and_read(
+        encryption: &BackupEncryption,
+        decryption: &BackupEncryption,
+    ) -> Result<String> {
+        use flate2::{Compression, write::GzEncoder};
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("backup.tar.gz.age");
+        let writer = encryption.wrap_output(File::create(&path)?)?;
+        let mut encoder = GzEncoder::new(writer, Compression::default());
+        encoder.write_all(b"secret data")?;
+        encoder.finish()?.finish()?;
+
+        assert_eq!(
+            BackupEncryption::is_encrypted(&path)?,
+            encryption.is_enabled()
+        );
+
+        let mut content = String::new();
+        decryption
+            .open_archive(&path)?
+            .read_to_string(&mut content)?;
+        Ok(content)
+    }
+
+    #[test]
+    fn test_plain_backup_is_not_encrypted() {
+        let encryption = BackupEncryption::default();
+        assert!(!encryption.is_enabled());
+        assert_eq!(encryption.fingerprint(), None);
+        assert_eq!(
+            write_and_read(&encryption, &encryption).unwrap(),
+            "secret data"
+        );
+    }
+
+    #[test]
+    fn test_recipient_encryption_round_trip() {
+        let identity = age::x25519::Identity::generate();
+        let recipient = identity.to_public().to_string();
+        let secret_key = {
+            use age::secrecy::ExposeSecret;
+            identity.to_string().expose_secret().to_string()
+        };
+
+        let encryption = BackupEncryption::default()
+            .with_recipients(&[recipient])
+            .unwrap();
+        assert!(encryption.fingerprint().unwrap().starts_with("age:"));
+
+        let decryption = BackupEncryption::default()
+            .with_identities(&format!("# 备份私钥\n{secret_key}\n"))
+            .unwrap();
+        assert_eq!(
+            write_and_read(&encryption, &decryption).unwrap(),
+            "secret data"
+        );
+
+        // 没有私钥时无法解密
+        assert!(write_and_read(&encryption, &BackupEncryption::default()).is_err());
+    }
+
+    #[test]
+    fn test_passphrase_encryption_round_trip() {
+        let encryption = BackupEncryption::default().with_passphrase("correct horse");
+        assert_eq!(encryption.fingerprint().as_deref(), Some("passphrase"));
+        assert_eq!(
+            write_and_read(&encryption, &encryption).unwrap(),
+            "secret data"
+        );
+
+        let wrong = BackupEncryption::default().with_passphrase("wrong");
+        assert!(write_and_read(&encryption, &wrong).is_err());
+    }
+}
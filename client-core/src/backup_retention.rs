@@ -0,0 +1,294 @@
+//! 备份保留策略
+//!
+//! 根据 `app_config` 中的 `backup.*` 配置决定哪些备份需要清理：
+//! 保留最近N个、保留最近N天内的备份，以及按天/周/月各保留一个（祖父-父-子）。
+//! 任一规则保留的备份都不会被清理；增量/差异备份所依赖的备份随之保留，
+//! 最近一次成功的升级前备份始终保留。
+
+use crate::{
+    Result,
+    database::{BackupRecord, BackupStatus, BackupType, Database},
+};
+use chrono::{DateTime, Datelike, Duration, Utc};
+use std::collections::{HashMap, HashSet};
+
+/// 备份保留策略（数量为0表示不启用该规则）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RetentionPolicy {
+    /// 保留最近的N个成功备份
+    pub keep_last: usize,
+    /// 保留最近N天内创建的备份
+    pub max_age_days: u32,
+    /// 最近N天每天保留最新的一个备份
+    pub keep_daily: usize,
+    /// 最近N周每周保留最新的一个备份
+    pub keep_weekly: usize,
+    /// 最近N个月每月保留最新的一个备份
+    pub keep_monthly: usize,
+}
+
+impl RetentionPolicy {
+    /// 从 `app_config` 读取保留策略
+    pub async fn from_database(database: &Database) -> Result<Self> {
+        async fn get_count(database: &Database, key: &str) -> Result<u32> {
+            Ok(database
+                .get_config(key)
+                .await?
+                .and_then(|value| value.trim().parse().ok())
+                .unwrap_or(0))
+        }
+
+        Ok(Self {
+            keep_last: get_count(database, "backup.keep_last").await? as usize,
+            max_age_days: get_count(database, "backup.retention_days").await?,
+            keep_daily: get_count(database, "backup.keep_daily").await? as usize,
+            keep_weekly: get_count(database, "backup.keep_weekly").await? as usize,
+            keep_monthly: get_count(database, "backup.keep_monthly").await? as usize,
+        })
+    }
+
+    /// 创建备份后是否自动清理（`backup.auto_cleanup`）
+    pub async fn auto_cleanup_enabled(database: &Database) -> Result<bool> {
+        Ok(database
+            .get_config("backup.auto_cleanup")
+            .await?
+            .is_some_and(|value| value.trim() == "true"))
+    }
+
+    /// 是否没有启用任何保留规则（此时不清理任何备份）
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// 计算需要清理的备份，按删除顺序返回（新的在前，保证先删除依赖其他备份的增量/差异备份）
+    ///
+    /// 失败的备份不计入保留数量，只按保留天数保留。
+    pub fn plan_prune(&self, backups: &[BackupRecord], now: DateTime<Utc>) -> Vec<BackupRecord> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+
+        let mut sorted: Vec<&BackupRecord> = backups.iter().collect();
+        sorted.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id)));
+        let completed: Vec<&BackupRecord> = sorted
+            .iter()
+            .copied()
+            .filter(|backup| backup.status == BackupStatus::Completed)
+            .collect();
+
+        let mut keep: HashSet<i64> = HashSet::new();
+
+        keep.extend(
+            completed
+                .iter()
+                .take(self.keep_last)
+                .map(|backup| backup.id),
+        );
+
+        if self.max_age_days > 0 {
+            let cutoff = now - Duration::days(i64::from(self.max_age_days));
+            keep.extend(
+                sorted
+                    .iter()
+                    .filter(|backup| backup.created_at >= cutoff)
+                    .map(|backup| backup.id),
+            );
+        }
+
+        keep.extend(keep_per_period(&completed, self.keep_daily, |time| {
+            (time.year(), time.ordinal())
+        }));
+        keep.extend(keep_per_period(&completed, self.keep_weekly, |time| {
+            let week = time.iso_week();
+            (week.year(), week.week())
+        }));
+        keep.extend(keep_per_period(&completed, self.keep_monthly, |time| {
+            (time.year(), time.month())
+        }));
+
+        // 最近一次成功的升级前备份用于升级回滚，始终保留
+        if let Some(backup) = completed
+            .iter()
+            .find(|backup| matches!(backup.backup_type, BackupType::PreUpgrade))
+        {
+            keep.insert(backup.id);
+        }
+
+        // 保留的增量/差异备份所依赖的备份链
+        let parents: HashMap<i64, Option<i64>> = backups
+            .iter()
+            .map(|backup| (backup.id, backup.parent_id))
+            .collect();
+        for id in keep.clone() {
+            let mut parent_id = parents.get(&id).copied().flatten();
+            while let Some(id) = parent_id {
+                if !keep.insert(id) {
+                    break;
+                }
+                parent_id = parents.get(&id).copied().flatten();
+            }
+        }
+
+        sorted
+            .into_iter()
+            .filter(|backup| !keep.contains(&backup.id))
+            .cloned()
+            .collect()
+    }
+}
+
+/// 按时间段分组（备份按时间倒序），保留最近 `count` 个时间段中各自最新的备份
+fn keep_per_period<K: PartialEq>(
+    completed: &[&BackupRecord],
+    count: usize,
+    period: impl Fn(DateTime<Utc>) -> K,
+) -> Vec<i64> {
+    let mut kept = Vec::new();
+    let mut last_period = None;
+
+    for backup in completed {
+        if kept.len() >= count {
+            break;
+        }
+        let current = period(backup.created_at);
+        if last_period.as_ref() != Some(&current) {
+            kept.push(backup.id);
+            last_period = Some(current);
+        }
+    }
+
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{BackupMode, CompressionType};
+    use chrono::TimeZone;
+
+    fn backup(id: i64, created_at: DateTime<Utc>) -> BackupRecord {
+        BackupRecord {
+            id,
+            file_path: format!("/backups/backup_{id}.tar.gz"),
+            service_version: "1.0.0".to_string(),
+            backup_type: BackupType::Manual,
+            status: BackupStatus::Completed,
+            backup_mode: BackupMode::Full,
+            parent_id: None,
+            encryption_fingerprint: None,
+            backup_hash: None,
+            file_count: None,
+            backup_size: None,
+            storage_target: "local".to_string(),
+            compression_type: CompressionType::Gzip,
+            created_at,
+        }
+    }
+
+    fn pruned_ids(
+        policy: &RetentionPolicy,
+        backups: &[BackupRecord],
+        now: DateTime<Utc>,
+    ) -> Vec<i64> {
+        policy
+            .plan_prune(backups, now)
+            .iter()
+            .map(|backup| backup.id)
+            .collect()
+    }
+
+    #[test]
+    fn test_empty_policy_keeps_everything() {
+        let now = Utc.with_ymd_and_hms(2025, 6, 30, 12, 0, 0).unwrap();
+        let backups: Vec<_> = (1..=5)
+            .map(|id| backup(id, now - Duration::days(100 * id)))
+            .collect();
+
+        assert!(
+            RetentionPolicy::default()
+                .plan_prune(&backups, now)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_keep_last_and_max_age() {
+        let now = Utc.with_ymd_and_hms(2025, 6, 30, 12, 0, 0).unwrap();
+        // 每天一个备份，ID越大越旧
+        let mut backups: Vec<_> = (1..=6)
+            .map(|id| backup(id, now - Duration::days(id)))
+            .collect();
+        backups[1].status = BackupStatus::Failed;
+
+        let keep_last = RetentionPolicy {
+            keep_last: 2,
+            ..Default::default()
+        };
+        // 失败的备份不计入保留数量
+        assert_eq!(pruned_ids(&keep_last, &backups, now), vec![2, 4, 5, 6]);
+
+        let max_age = RetentionPolicy {
+            max_age_days: 3,
+            ..Default::default()
+        };
+        assert_eq!(pruned_ids(&max_age, &backups, now), vec![4, 5, 6]);
+    }
+
+    #[test]
+    fn test_grandfather_father_son() {
+        let now = Utc.with_ymd_and_hms(2025, 6, 30, 12, 0, 0).unwrap();
+        // 最近90天每天两个备份
+        let backups: Vec<_> = (0..180)
+            .map(|i| backup(i + 1, now - Duration::hours(12 * i)))
+            .collect();
+        let policy = RetentionPolicy {
+            keep_daily: 3,
+            keep_weekly: 2,
+            keep_monthly: 3,
+            ..Default::default()
+        };
+
+        let pruned: HashSet<i64> = pruned_ids(&policy, &backups, now).into_iter().collect();
+        let kept: Vec<DateTime<Utc>> = backups
+            .iter()
+            .filter(|backup| !pruned.contains(&backup.id))
+            .map(|backup| backup.created_at)
+            .collect();
+
+        let expected = vec![
+            // 每天：6月30日、29日、28日
+            Utc.with_ymd_and_hms(2025, 6, 30, 12, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2025, 6, 29, 12, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2025, 6, 28, 12, 0, 0).unwrap(),
+            // 每周：上一周的最后一个备份（6月29日是周日，已由每天规则保留）
+            // 每月：5月和4月的最后一个备份
+            Utc.with_ymd_and_hms(2025, 5, 31, 12, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2025, 4, 30, 12, 0, 0).unwrap(),
+        ];
+        assert_eq!(kept, expected);
+    }
+
+    #[test]
+    fn test_keeps_chain_and_latest_pre_upgrade() {
+        let now = Utc.with_ymd_and_hms(2025, 6, 30, 12, 0, 0).unwrap();
+        let mut base = backup(1, now - Duration::days(10));
+        base.backup_type = BackupType::PreUpgrade;
+        let mut older_pre_upgrade = backup(2, now - Duration::days(20));
+        older_pre_upgrade.backup_type = BackupType::PreUpgrade;
+        let full = backup(3, now - Duration::days(3));
+        let mut incremental = backup(4, now - Duration::days(2));
+        incremental.backup_mode = BackupMode::Incremental;
+        incremental.parent_id = Some(3);
+        let mut latest = backup(5, now - Duration::days(1));
+        latest.backup_mode = BackupMode::Incremental;
+        latest.parent_id = Some(4);
+        let backups = vec![base, older_pre_upgrade, full, incremental, latest];
+
+        let policy = RetentionPolicy {
+            keep_last: 1,
+            ..Default::default()
+        };
+        // 最新的增量备份依赖 4 和 3；1 是最近一次升级前备份
+        assert_eq!(pruned_ids(&policy, &backups, now), vec![2]);
+    }
+}
@@ -0,0 +1,368 @@
+//! 远程备份存储
+//!
+//! 备份创建后上传到远程存储（本地文件系统中的其他目录或S3兼容的对象存储），
+//! 本地备份文件丢失时从远程存储下载后再恢复。远程存储中的文件名与本地备份文件名相同。
+
+use crate::{
+    DuckError, Result,
+    config::{RemoteStorageConfig, S3StorageConfig},
+    constants::backup,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+
+/// 下载或复制过程中的临时文件后缀，完成后重命名，避免留下不完整的备份文件
+const PARTIAL_SUFFIX: &str = ".part";
+
+/// 远程存储中的文件
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredObject {
+    /// 文件名（不含路径前缀）
+    pub name: String,
+    /// 文件大小（字节）
+    pub size: u64,
+    /// 最后修改时间
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+/// 备份存储
+#[async_trait]
+pub trait BackupStorage: std::fmt::Debug + Send + Sync {
+    /// 存储目标名称，记录到备份记录中，如 `s3://bucket/prefix`
+    fn target(&self) -> String;
+
+    /// 上传本地文件
+    async fn upload(&self, local_path: &Path, name: &str) -> Result<()>;
+
+    /// 下载文件到本地路径
+    async fn download(&self, name: &str, local_path: &Path) -> Result<()>;
+
+    /// 列出存储中的文件
+    async fn list(&self) -> Result<Vec<StoredObject>>;
+
+    /// 删除文件（文件不存在时不报错）
+    async fn delete(&self, name: &str) -> Result<()>;
+}
+
+/// 根据配置创建远程备份存储
+///
+/// S3访问密钥从环境变量 `DUCK_BACKUP_S3_ACCESS_KEY` 和 `DUCK_BACKUP_S3_SECRET_KEY` 读取。
+pub fn from_config(config: &RemoteStorageConfig) -> Result<Arc<dyn BackupStorage>> {
+    match config {
+        RemoteStorageConfig::Local { path } => Ok(Arc::new(LocalStorage::new(path))),
+        RemoteStorageConfig::S3(config) => {
+            let read_env = |name: &str| {
+                std::env::var(name)
+                    .ok()
+                    .filter(|value| !value.is_empty())
+                    .ok_or_else(|| {
+                        DuckError::Backup(format!("S3备份存储需要通过环境变量 {name} 提供访问密钥"))
+                    })
+            };
+            let access_key = read_env(backup::S3_ACCESS_KEY_ENV)?;
+            let secret_key = read_env(backup::S3_SECRET_KEY_ENV)?;
+            Ok(Arc::new(S3Storage::new(config, &access_key, &secret_key)?))
+        }
+    }
+}
+
+/// 下载完成后把临时文件重命名为目标文件
+async fn finish_partial(partial_path: &Path, local_path: &Path) -> Result<()> {
+    tokio::fs::rename(partial_path, local_path).await?;
+    Ok(())
+}
+
+/// 目标文件对应的临时文件路径
+fn partial_path(local_path: &Path) -> PathBuf {
+    let mut path = local_path.as_os_str().to_owned();
+    path.push(PARTIAL_SUFFIX);
+    PathBuf::from(path)
+}
+
+/// 本地文件系统中的备份存储（如挂载的NAS或另一块磁盘）
+#[derive(Debug, Clone)]
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+#[async_trait]
+impl BackupStorage for LocalStorage {
+    fn target(&self) -> String {
+        format!("file://{}", self.root.display())
+    }
+
+    async fn upload(&self, local_path: &Path, name: &str) -> Result<()> {
+        tokio::fs::create_dir_all(&self.root).await?;
+        let remote_path = self.root.join(name);
+        let partial_path = partial_path(&remote_path);
+        tokio::fs::copy(local_path, &partial_path).await?;
+        finish_partial(&partial_path, &remote_path).await
+    }
+
+    async fn download(&self, name: &str, local_path: &Path) -> Result<()> {
+        let remote_path = self.root.join(name);
+        if !remote_path.exists() {
+            return Err(DuckError::Backup(format!(
+                "远程备份文件不存在: {}",
+                remote_path.display()
+            )));
+        }
+        if let Some(parent) = local_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let partial_path = partial_path(local_path);
+        tokio::fs::copy(&remote_path, &partial_path).await?;
+        finish_partial(&partial_path, local_path).await
+    }
+
+    async fn list(&self) -> Result<Vec<StoredObject>> {
+        let mut objects = Vec::new();
+        if !self.root.exists() {
+            return Ok(objects);
+        }
+
+        let mut entries = tokio::fs::read_dir(&self.root).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let metadata = entry.metadata().await?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !metadata.is_file() || name.ends_with(PARTIAL_SUFFIX) {
+                continue;
+            }
+            objects.push(StoredObject {
+                name,
+                size: metadata.len(),
+                last_modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+            });
+        }
+        objects.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(objects)
+    }
+
+    async fn delete(&self, name: &str) -> Result<()> {
+        let remote_path = self.root.join(name);
+        if remote_path.exists() {
+            tokio::fs::remove_file(&remote_path).await?;
+        }
+        Ok(())
+    }
+}
+
+/// S3兼容对象存储中的备份存储，大文件自动分片上传
+pub struct S3Storage {
+    bucket: Box<s3::Bucket>,
+    bucket_name: String,
+    prefix: String,
+}
+
+impl std::fmt::Debug for S3Storage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // 不输出访问密钥
+        f.debug_struct("S3Storage")
+            .field("bucket", &self.bucket_name)
+            .field("prefix", &self.prefix)
+            .finish()
+    }
+}
+
+impl S3Storage {
+    pub fn new(config: &S3StorageConfig, access_key: &str, secret_key: &str) -> Result<Self> {
+        let region = s3::Region::Custom {
+            region: config.region.clone(),
+            endpoint: config.endpoint.trim_end_matches('/').to_string(),
+        };
+        let credentials =
+            s3::creds::Credentials::new(Some(access_key), Some(secret_key), None, None, None)
+                .map_err(|e| DuckError::Backup(format!("无效的S3访问密钥: {e}")))?;
+        let mut bucket = s3::Bucket::new(&config.bucket, region, credentials)
+            .map_err(|e| DuckError::Backup(format!("创建S3存储桶客户端失败: {e}")))?;
+        if config.path_style {
+            bucket = bucket.with_path_style();
+        }
+
+        Ok(Self {
+            bucket,
+            bucket_name: config.bucket.clone(),
+            prefix: config.prefix.trim_matches('/').to_string(),
+        })
+    }
+
+    /// 文件在存储桶中的对象键
+    fn object_key(&self, name: &str) -> String {
+        if self.prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", self.prefix, name)
+        }
+    }
+}
+
+#[async_trait]
+impl BackupStorage for S3Storage {
+    fn target(&self) -> String {
+        if self.prefix.is_empty() {
+            format!("s3://{}", self.bucket_name)
+        } else {
+            format!("s3://{}/{}", self.bucket_name, self.prefix)
+        }
+    }
+
+    async fn upload(&self, local_path: &Path, name: &str) -> Result<()> {
+        let key = self.object_key(name);
+        let mut file = tokio::fs::File::open(local_path).await?;
+        // 超过分片大小的文件自动使用分片上传
+        self.bucket
+            .put_object_stream(&mut file, &key)
+            .await
+            .map_err(|e| DuckError::Backup(format!("上传备份到S3失败 {key}: {e}")))?;
+        Ok(())
+    }
+
+    async fn download(&self, name: &str, local_path: &Path) -> Result<()> {
+        let key = self.object_key(name);
+        if let Some(parent) = local_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let partial_path = partial_path(local_path);
+        let mut file = tokio::fs::File::create(&partial_path).await?;
+        let result = self.bucket.get_object_to_writer(&key, &mut file).await;
+        file.flush().await?;
+        drop(file);
+
+        if let Err(e) = result {
+            let _ = tokio::fs::remove_file(&partial_path).await;
+            return Err(DuckError::Backup(format!("从S3下载备份失败 {key}: {e}")));
+        }
+        finish_partial(&partial_path, local_path).await
+    }
+
+    async fn list(&self) -> Result<Vec<StoredObject>> {
+        let prefix = if self.prefix.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", self.prefix)
+        };
+        let results = self
+            .bucket
+            .list(prefix.clone(), Some("/".to_string()))
+            .await
+            .map_err(|e| DuckError::Backup(format!("列出S3备份失败: {e}")))?;
+
+        let mut objects: Vec<StoredObject> = results
+            .into_iter()
+            .flat_map(|result| result.contents)
+            .filter_map(|object| {
+                let name = object.key.strip_prefix(&prefix)?.to_string();
+                (!name.is_empty()).then(|| StoredObject {
+                    name,
+                    size: object.size,
+                    last_modified: DateTime::parse_from_rfc3339(&object.last_modified)
+                        .ok()
+                        .map(|time| time.with_timezone(&Utc)),
+                })
+            })
+            .collect();
+        objects.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(objects)
+    }
+
+    async fn delete(&self, name: &str) -> Result<()> {
+        let key = self.object_key(name);
+        self.bucket
+            .delete_object(&key)
+            .await
+            .map_err(|e| DuckError::Backup(format!("删除S3备份失败 {key}: {e}")))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    async fn round_trip(storage: &dyn BackupStorage) {
+        let temp_dir = tempdir().unwrap();
+        let local_path = temp_dir.path().join("backup_manual_v1.0.0.tar.gz");
+        std::fs::write(&local_path, "backup data").unwrap();
+
+        storage
+            .upload(&local_path, "backup_manual_v1.0.0.tar.gz")
+            .await
+            .unwrap();
+        let objects = storage.list().await.unwrap();
+        assert!(
+            objects
+                .iter()
+                .any(|object| object.name == "backup_manual_v1.0.0.tar.gz" && object.size == 11)
+        );
+
+        let download_path = temp_dir.path().join("restore").join("backup.tar.gz");
+        storage
+            .download("backup_manual_v1.0.0.tar.gz", &download_path)
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&download_path).unwrap(),
+            "backup data"
+        );
+        assert!(!partial_path(&download_path).exists());
+
+        storage.delete("backup_manual_v1.0.0.tar.gz").await.unwrap();
+        assert!(
+            !storage
+                .list()
+                .await
+                .unwrap()
+                .iter()
+                .any(|object| object.name == "backup_manual_v1.0.0.tar.gz")
+        );
+        assert!(
+            storage
+                .download("backup_manual_v1.0.0.tar.gz", &download_path)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_local_storage_round_trip() {
+        let temp_dir = tempdir().unwrap();
+        let storage = LocalStorage::new(temp_dir.path().join("remote"));
+        assert!(storage.target().starts_with("file://"));
+        assert!(storage.list().await.unwrap().is_empty());
+
+        round_trip(&storage).await;
+    }
+
+    /// 启动本地MinIO后运行：
+    /// `docker run -d -p 9000:9000 minio/minio server /data`，
+    /// 创建存储桶 `duck-backups-test` 后执行 `cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore = "需要本地MinIO容器"]
+    async fn test_s3_storage_round_trip_with_minio() {
+        let config = S3StorageConfig {
+            endpoint: std::env::var("DUCK_TEST_S3_ENDPOINT")
+                .unwrap_or_else(|_| "http://127.0.0.1:9000".to_string()),
+            region: "us-east-1".to_string(),
+            bucket: "duck-backups-test".to_string(),
+            prefix: "client-test".to_string(),
+            path_style: true,
+        };
+        let storage = S3Storage::new(&config, "minioadmin", "minioadmin").unwrap();
+        assert_eq!(storage.target(), "s3://duck-backups-test/client-test");
+
+        round_trip(&storage).await;
+    }
+}
@@ -31,6 +31,44 @@ pub struct DockerConfig {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct BackupConfig {
     pub storage_dir: String,
+    /// 备份加密公钥（age格式，`age1...`），设置后新建的备份使用公钥加密
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub encryption_recipients: Vec<String>,
+    /// 解密备份使用的私钥文件（每行一个 `AGE-SECRET-KEY-...`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption_identity_file: Option<String>,
+    /// 远程备份存储，设置后新建的备份会上传到远程存储
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote: Option<RemoteStorageConfig>,
+}
+
+/// 远程备份存储配置
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum RemoteStorageConfig {
+    /// 本地文件系统中的其他目录（如挂载的NAS或另一块磁盘）
+    Local { path: String },
+    /// S3兼容的对象存储（AWS S3、MinIO等）
+    S3(S3StorageConfig),
+}
+
+/// S3兼容对象存储配置，访问密钥从环境变量读取
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct S3StorageConfig {
+    pub endpoint: String,
+    #[serde(default = "default_s3_region")]
+    pub region: String,
+    pub bucket: String,
+    /// 备份文件在存储桶中的路径前缀
+    #[serde(default)]
+    pub prefix: String,
+    /// 使用路径风格访问（MinIO等自建存储通常需要开启）
+    #[serde(default)]
+    pub path_style: bool,
+}
+
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
 }
 
 /// 缓存相关配置
@@ -59,6 +97,9 @@ impl Default for AppConfig {
                 storage_dir: backup::get_default_storage_dir()
                     .to_string_lossy()
                     .to_string(),
+                encryption_recipients: Vec::new(),
+                encryption_identity_file: None,
+                remote: None,
             },
             cache: CacheConfig {
                 cache_dir: config::get_default_cache_dir()
@@ -118,11 +159,55 @@ impl AppConfig {
             .replace("{docker_service_version}", &self.versions.docker_service)
             .replace("{compose_file}", &self.docker.compose_file)
             .replace("{backup_storage_dir}", &self.backup.storage_dir)
+            .replace("{backup_encryption}", &self.backup_encryption_toml())
+            .replace("{backup_remote}", &self.backup_remote_toml())
             .replace("{cache_dir}", &self.cache.cache_dir)
             .replace("{download_dir}", &self.cache.download_dir)
             .replace("{check_frequency}", &self.updates.check_frequency)
     }
 
+    /// 生成备份加密配置项，未配置时生成注释掉的示例
+    fn backup_encryption_toml(&self) -> String {
+        let recipients = if self.backup.encryption_recipients.is_empty() {
+            "# encryption_recipients = [\"age1...\"]".to_string()
+        } else {
+            let recipients: Vec<String> = self
+                .backup
+                .encryption_recipients
+                .iter()
+                .map(|recipient| format!("{recipient:?}"))
+                .collect();
+            format!("encryption_recipients = [{}]", recipients.join(", "))
+        };
+        let identity_file = match &self.backup.encryption_identity_file {
+            Some(path) => format!("encryption_identity_file = {path:?}"),
+            None => "# encryption_identity_file = \"./backup-key.txt\"".to_string(),
+        };
+        format!("{recipients}\n{identity_file}")
+    }
+
+    /// 生成远程备份存储配置，未配置时生成注释掉的示例
+    fn backup_remote_toml(&self) -> String {
+        match self
+            .backup
+            .remote
+            .as_ref()
+            .and_then(|remote| toml::to_string(remote).ok())
+        {
+            Some(remote) => format!("[backup.remote]\n{}", remote.trim_end()),
+            None => [
+                "# [backup.remote]",
+                "# type = \"s3\"",
+                "# endpoint = \"http://127.0.0.1:9000\"",
+                "# region = \"us-east-1\"",
+                "# bucket = \"duck-backups\"",
+                "# prefix = \"client-01\"",
+                "# path_style = true",
+            ]
+            .join("\n"),
+        }
+    }
+
     /// 确保缓存目录存在
     pub fn ensure_cache_dirs(&self) -> Result<()> {
         fs::create_dir_all(&self.cache.cache_dir)?;
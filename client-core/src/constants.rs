@@ -29,6 +29,29 @@ pub mod docker {
     /// 日志目录名
     pub const LOGS_DIR_NAME: &str = "logs";
 
+    /// MySQL服务名（docker-compose.yml中的服务名）
+    pub const MYSQL_SERVICE_NAME: &str = "mysql";
+
+    /// Redis服务名（docker-compose.yml中的服务名）
+    pub const REDIS_SERVICE_NAME: &str = "redis";
+
+    /// 查找MySQL初始化SQL时需要跳过的目录（运行时数据，不属于服务包）
+    pub const INIT_SQL_SKIP_DIRS: [&str; 5] = [
+        DATA_DIR_NAME,
+        LOGS_DIR_NAME,
+        UPLOAD_DIR_NAME,
+        BACKUPS_DIR_NAME,
+        IMAGES_DIR_NAME,
+    ];
+
+    /// 运行时数据目录（不属于服务包，升级回滚时保留在原处）
+    pub const RUNTIME_DIRS: [&str; 4] = [
+        DATA_DIR_NAME,
+        LOGS_DIR_NAME,
+        UPLOAD_DIR_NAME,
+        BACKUPS_DIR_NAME,
+    ];
+
     /// 服务数据目录结构
     pub mod data_dirs {
         /// MySQL数据目录
@@ -243,6 +266,9 @@ pub mod api {
         /// Docker版本列表更新端点
         pub const DOCKER_UPDATE_VERSION_LIST: &str = "/api/v1/docker/updateVersionList";
 
+        /// 指定版本的Docker服务清单端点（包含占位符）
+        pub const DOCKER_VERSION_MANIFEST: &str = "/api/v1/docker/versions/{version}/manifest";
+
         /// Docker完整服务包下载端点
         pub const DOCKER_DOWNLOAD_FULL: &str =
             "/api/v1/clients/downloads/docker/services/full/latest";
@@ -293,6 +319,45 @@ pub mod backup {
     /// 最小有效ZIP文件大小（字节）
     pub const MIN_ZIP_FILE_SIZE: u64 = 100;
 
+    /// 数据库架构回滚脚本后缀（与升级前备份文件保存在同一目录）
+    pub const SCHEMA_ROLLBACK_SUFFIX: &str = ".schema_down.sql";
+
+    /// 备份文件快照后缀（记录备份时每个文件的大小、修改时间和哈希，用于增量备份）
+    pub const SNAPSHOT_SUFFIX: &str = ".snapshot.json";
+
+    /// 加密备份文件的附加后缀（age格式）
+    pub const ENCRYPTED_SUFFIX: &str = ".age";
+
+    /// 备份清单在归档中的文件名（记录每个文件的路径、大小、权限和SHA-256，作为归档的最后一个条目）
+    pub const MANIFEST_FILE_NAME: &str = ".duck_backup_manifest.json";
+
+    /// 热备份时MySQL导出文件在归档中的路径（恢复后导入MySQL）
+    pub const MYSQL_DUMP_ARCHIVE_PATH: &str = "mysql-dump/databases.sql";
+
+    /// 热备份时不打包的目录（MySQL数据改为通过mysqldump导出）
+    pub const HOT_BACKUP_EXCLUDED_DIRS: [&str; 1] = ["data/mysql"];
+
+    /// 恢复暂存目录后缀：恢复内容先解压到目标目录旁的 `.{目录名}.restore-staging`
+    pub const RESTORE_STAGING_SUFFIX: &str = "restore-staging";
+
+    /// 恢复前快照目录后缀：恢复时被替换的数据移动到目标目录旁的 `.{目录名}.pre-restore`
+    pub const PRE_RESTORE_SUFFIX: &str = "pre-restore";
+
+    /// 只恢复数据时替换的目录（配置文件保留）
+    pub const DATA_ONLY_RESTORE_DIRS: [&str; 2] = ["data", "app"];
+
+    /// 备份加密口令的环境变量名（口令不保存在配置文件中）
+    pub const ENCRYPTION_PASSPHRASE_ENV: &str = "DUCK_BACKUP_PASSPHRASE";
+
+    /// 远程备份存储的本地存储目标名称（只保存在本地的备份）
+    pub const LOCAL_STORAGE_TARGET: &str = "local";
+
+    /// S3访问密钥ID的环境变量名（密钥不保存在配置文件中）
+    pub const S3_ACCESS_KEY_ENV: &str = "DUCK_BACKUP_S3_ACCESS_KEY";
+
+    /// S3访问密钥的环境变量名
+    pub const S3_SECRET_KEY_ENV: &str = "DUCK_BACKUP_S3_SECRET_KEY";
+
     /// 获取默认备份目录路径（跨平台）
     pub fn get_backup_dir() -> PathBuf {
         Path::new(".").join(DATA_DIR_NAME).join(BACKUP_DIR_NAME)
@@ -320,6 +385,27 @@ pub mod upgrade {
     /// 下载的docker服务包文件名
     pub const DOCKER_SERVICE_PACKAGE: &str = "docker.zip";
 
+    /// 下载的docker服务补丁包文件名
+    pub const DOCKER_PATCH_PACKAGE: &str = "docker-patch.zip";
+
+    /// 补丁包中的补丁描述文件名
+    pub const PATCH_MANIFEST_FILE_NAME: &str = "patch.json";
+
+    /// 应用补丁时的暂存目录后缀（`.docker.patch-staging`）
+    pub const PATCH_STAGING_SUFFIX: &str = "patch-staging";
+
+    /// 升级前服务包文件的保存目录后缀（`.docker.pre-upgrade`），升级失败时从这里回滚
+    pub const PRE_UPGRADE_SUFFIX: &str = "pre-upgrade";
+
+    /// 服务包签名文件扩展名（`docker.zip.minisig`）
+    pub const SIGNATURE_FILE_EXTENSION: &str = "minisig";
+
+    /// 受信任的服务包签名公钥（minisign，与客户端更新使用同一把发布密钥）
+    ///
+    /// 轮换密钥时先在客户端中加入新公钥，待旧客户端升级后再停用旧密钥。
+    pub const TRUSTED_PUBLIC_KEYS: &[&str] =
+        &["RWQiUtptLqjGi7N8nU9cBq8ru0kj/F19EDhOXtLcGdZ9/AHvlXFe1E0x"];
+
     /// 默认更新包文件名
     pub const DEFAULT_UPDATE_PACKAGE: &str = "update.zip";
 
@@ -383,6 +469,12 @@ pub mod timeout {
 
     /// 服务验证前等待时间（让服务稳定）
     pub const SERVICE_VERIFY_WAIT: u64 = 5;
+
+    /// 等待MySQL可以接受连接的超时时间（用于执行架构迁移前）
+    pub const MYSQL_READY_TIMEOUT: u64 = 120;
+
+    /// 等待Redis后台保存（BGSAVE）完成的超时时间
+    pub const REDIS_BGSAVE_TIMEOUT: u64 = 300;
 }
 
 /// 网络相关常量
@@ -1,6 +1,8 @@
 use super::types::DockerManager;
 use crate::{DuckError, Result};
+use std::path::Path;
 use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
 use tokio::process::Command;
 
 impl DockerManager {
@@ -86,6 +88,136 @@ impl DockerManager {
         Ok(output)
     }
 
+    /// 执行 docker-compose 命令，并通过标准输入传入数据
+    pub(crate) async fn run_compose_command_with_stdin(
+        &self,
+        args: &[&str],
+        stdin: &str,
+    ) -> Result<std::process::Output> {
+        let compose_path = self.compose_file.to_string_lossy().to_string();
+
+        // 尝试使用 docker compose（新语法）
+        let mut cmd_args = vec!["compose", "-f", &compose_path];
+        cmd_args.extend(args);
+        if let Ok(output) = Self::run_command_with_stdin("docker", &cmd_args, stdin).await {
+            return Ok(output);
+        }
+
+        // 回退到 docker-compose（旧语法）
+        let mut cmd_args = vec!["-f", &compose_path];
+        cmd_args.extend(args);
+        Self::run_command_with_stdin("docker-compose", &cmd_args, stdin).await
+    }
+
+    /// 执行命令并写入标准输入
+    async fn run_command_with_stdin(
+        program: &str,
+        args: &[&str],
+        stdin: &str,
+    ) -> Result<std::process::Output> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        if let Some(mut child_stdin) = child.stdin.take() {
+            child_stdin.write_all(stdin.as_bytes()).await?;
+            // 关闭标准输入，通知子进程输入结束
+            drop(child_stdin);
+        }
+
+        let output = child.wait_with_output().await?;
+        Ok(output)
+    }
+
+    /// 执行 docker-compose 命令，标准输出直接写入文件（用于导出大量数据）
+    pub(crate) async fn run_compose_command_to_file(
+        &self,
+        args: &[&str],
+        output_path: &Path,
+    ) -> Result<std::process::Output> {
+        let compose_path = self.compose_file.to_string_lossy().to_string();
+
+        // 尝试使用 docker compose（新语法）
+        let mut cmd_args = vec!["compose", "-f", &compose_path];
+        cmd_args.extend(args);
+        if let Ok(output) = Self::run_command_with_stdio(
+            "docker",
+            &cmd_args,
+            Stdio::null(),
+            Stdio::from(std::fs::File::create(output_path)?),
+        )
+        .await
+        {
+            return Ok(output);
+        }
+
+        // 回退到 docker-compose（旧语法）
+        let mut cmd_args = vec!["-f", &compose_path];
+        cmd_args.extend(args);
+        Self::run_command_with_stdio(
+            "docker-compose",
+            &cmd_args,
+            Stdio::null(),
+            Stdio::from(std::fs::File::create(output_path)?),
+        )
+        .await
+    }
+
+    /// 执行 docker-compose 命令，标准输入从文件读取（用于导入大量数据）
+    pub(crate) async fn run_compose_command_with_stdin_file(
+        &self,
+        args: &[&str],
+        input_path: &Path,
+    ) -> Result<std::process::Output> {
+        let compose_path = self.compose_file.to_string_lossy().to_string();
+
+        // 尝试使用 docker compose（新语法）
+        let mut cmd_args = vec!["compose", "-f", &compose_path];
+        cmd_args.extend(args);
+        if let Ok(output) = Self::run_command_with_stdio(
+            "docker",
+            &cmd_args,
+            Stdio::from(std::fs::File::open(input_path)?),
+            Stdio::piped(),
+        )
+        .await
+        {
+            return Ok(output);
+        }
+
+        // 回退到 docker-compose（旧语法）
+        let mut cmd_args = vec!["-f", &compose_path];
+        cmd_args.extend(args);
+        Self::run_command_with_stdio(
+            "docker-compose",
+            &cmd_args,
+            Stdio::from(std::fs::File::open(input_path)?),
+            Stdio::piped(),
+        )
+        .await
+    }
+
+    /// 使用指定的标准输入和标准输出执行命令，标准错误始终被捕获
+    async fn run_command_with_stdio(
+        program: &str,
+        args: &[&str],
+        stdin: Stdio,
+        stdout: Stdio,
+    ) -> Result<std::process::Output> {
+        let output = Command::new(program)
+            .args(args)
+            .stdin(stdin)
+            .stdout(stdout)
+            .stderr(Stdio::piped())
+            .output()
+            .await?;
+
+        Ok(output)
+    }
+
     /// 执行 docker 命令
     pub(crate) async fn run_docker_command(&self, args: &[&str]) -> Result<std::process::Output> {
         let output = Command::new("docker")
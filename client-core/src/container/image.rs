@@ -61,6 +61,56 @@ impl DockerManager {
         )))
     }
 
+    /// 获取 docker-compose.yml 中各服务使用的镜像（已替换环境变量）
+    pub async fn get_compose_images(&self) -> Result<Vec<String>> {
+        let output = self.run_compose_command(&["config", "--images"]).await?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(DuckError::Docker(format!(
+                "获取compose镜像列表失败: {stderr}"
+            )));
+        }
+
+        let mut images: Vec<String> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect();
+        images.sort();
+        images.dedup();
+        Ok(images)
+    }
+
+    /// 获取镜像ID，镜像不存在时返回 `None`
+    pub async fn get_image_id(&self, image: &str) -> Result<Option<String>> {
+        let output = self
+            .run_docker_command(&["image", "inspect", "--format", "{{.Id}}", image])
+            .await?;
+
+        if !output.status.success() {
+            return Ok(None);
+        }
+
+        let id = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        Ok((!id.is_empty()).then_some(id))
+    }
+
+    /// 给镜像打标签（`docker tag`）
+    pub async fn tag_image(&self, source: &str, target: &str) -> Result<()> {
+        let output = self.run_docker_command(&["tag", source, target]).await?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(DuckError::Docker(format!(
+                "设置镜像标签 {target} 失败: {stderr}"
+            )));
+        }
+
+        Ok(())
+    }
+
     /// 拉取最新镜像
     pub async fn pull_images(&self) -> Result<()> {
         self.check_prerequisites().await?;
@@ -3,6 +3,8 @@ mod command;
 mod config;
 mod image;
 mod manager;
+mod mysql;
+mod redis;
 mod service;
 mod types;
 
@@ -0,0 +1,218 @@
+use super::types::DockerManager;
+use crate::constants::{docker, timeout};
+use crate::{DuckError, Result};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+/// 在MySQL容器内执行命令时使用的客户端参数（密码取自容器自身的环境变量）
+const MYSQL_CLIENT_ARGS: &str = r#"-uroot -p"$MYSQL_ROOT_PASSWORD""#;
+
+/// 热备份时不导出的MySQL系统库
+const MYSQL_SYSTEM_DATABASES: &str = "information_schema|performance_schema|mysql|sys";
+
+impl DockerManager {
+    /// 查找 docker-compose.yml 中的MySQL服务名
+    pub async fn find_mysql_service(&self) -> Result<Option<String>> {
+        let service_names = self.get_compose_service_names().await?;
+
+        if service_names.contains(docker::MYSQL_SERVICE_NAME) {
+            return Ok(Some(docker::MYSQL_SERVICE_NAME.to_string()));
+        }
+
+        // 回退到名称匹配（如 db-mysql、mysql8 等）
+        let mut candidates: Vec<String> = service_names
+            .into_iter()
+            .filter(|name| name.to_lowercase().contains(docker::MYSQL_SERVICE_NAME))
+            .collect();
+        candidates.sort();
+
+        Ok(candidates.into_iter().next())
+    }
+
+    /// 等待MySQL服务可以接受连接
+    pub async fn wait_for_mysql_ready(&self, service_name: &str) -> Result<()> {
+        let timeout_duration = Duration::from_secs(timeout::MYSQL_READY_TIMEOUT);
+        let check_interval = Duration::from_secs(timeout::HEALTH_CHECK_INTERVAL);
+        let start_time = Instant::now();
+        let ping_command = format!("mysqladmin ping {MYSQL_CLIENT_ARGS} --silent");
+
+        info!("⏳ 等待MySQL服务 {} 就绪...", service_name);
+
+        while start_time.elapsed() < timeout_duration {
+            let output = self
+                .run_compose_command(&["exec", "-T", service_name, "sh", "-c", &ping_command])
+                .await?;
+
+            if output.status.success() {
+                info!("✅ MySQL服务已就绪");
+                return Ok(());
+            }
+
+            debug!(
+                "MySQL尚未就绪: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            tokio::time::sleep(check_interval).await;
+        }
+
+        Err(DuckError::Docker(format!(
+            "等待MySQL服务 {service_name} 就绪超时 ({} 秒)",
+            timeout::MYSQL_READY_TIMEOUT
+        )))
+    }
+
+    /// 读取MySQL容器环境变量中的默认数据库（`MYSQL_DATABASE`），未设置时返回 `None`
+    pub async fn get_mysql_default_database(&self, service_name: &str) -> Result<Option<String>> {
+        let output = self
+            .run_compose_command(&[
+                "exec",
+                "-T",
+                service_name,
+                "sh",
+                "-c",
+                r#"printf %s "$MYSQL_DATABASE""#,
+            ])
+            .await?;
+
+        if !output.status.success() {
+            return Err(DuckError::Docker(format!(
+                "读取MySQL默认数据库失败: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        let database = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        Ok((!database.is_empty()).then_some(database))
+    }
+
+    /// 在MySQL容器内执行SQL脚本，返回mysql客户端的输出
+    pub async fn execute_mysql_sql(
+        &self,
+        service_name: &str,
+        database: Option<&str>,
+        sql: &str,
+    ) -> Result<String> {
+        let mysql_command = mysql_sql_command(database)?;
+
+        let output = self
+            .run_compose_command_with_stdin(
+                &["exec", "-T", service_name, "sh", "-c", &mysql_command],
+                sql,
+            )
+            .await?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+
+        if !output.status.success() {
+            return Err(DuckError::Docker(format!(
+                "在MySQL容器中执行SQL失败: {}",
+                stderr.trim()
+            )));
+        }
+
+        if !stderr.trim().is_empty() {
+            // mysql客户端会把命令行密码警告输出到stderr，不视为失败
+            debug!("MySQL输出: {}", stderr.trim());
+        }
+
+        Ok(stdout)
+    }
+
+    /// 在MySQL容器内用 mysqldump 导出所有业务数据库到本地文件（热备份，不需要停止服务）
+    ///
+    /// 使用 `--single-transaction` 在一致性快照中导出InnoDB表，导出期间不锁表；
+    /// 导出内容直接写入文件，不经过内存。
+    pub async fn dump_mysql_databases(&self, service_name: &str, output_path: &Path) -> Result<()> {
+        let dump_command = format!(
+            "DATABASES=$(mysql {MYSQL_CLIENT_ARGS} -N -e 'SHOW DATABASES' | grep -Ev '^({MYSQL_SYSTEM_DATABASES})$') && \
+             mysqldump {MYSQL_CLIENT_ARGS} --single-transaction --quick --routines --triggers --events --databases $DATABASES"
+        );
+
+        info!("📤 导出MySQL数据（mysqldump --single-transaction）...");
+        let output = self
+            .run_compose_command_to_file(
+                &["exec", "-T", service_name, "sh", "-c", &dump_command],
+                output_path,
+            )
+            .await?;
+
+        if !output.status.success() {
+            let _ = tokio::fs::remove_file(output_path).await;
+            return Err(DuckError::Docker(format!(
+                "导出MySQL数据失败: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        let size = tokio::fs::metadata(output_path).await?.len();
+        info!("✅ MySQL数据导出完成 ({} 字节)", size);
+        Ok(())
+    }
+
+    /// 把 mysqldump 导出的SQL文件导入MySQL容器（恢复热备份）
+    pub async fn import_mysql_dump(&self, service_name: &str, dump_path: &Path) -> Result<()> {
+        let mysql_command = format!("mysql {MYSQL_CLIENT_ARGS}");
+
+        info!("📥 导入MySQL数据: {}", dump_path.display());
+        let output = self
+            .run_compose_command_with_stdin_file(
+                &["exec", "-T", service_name, "sh", "-c", &mysql_command],
+                dump_path,
+            )
+            .await?;
+
+        if !output.status.success() {
+            return Err(DuckError::Docker(format!(
+                "导入MySQL数据失败: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        info!("✅ MySQL数据导入完成");
+        Ok(())
+    }
+}
+
+/// 生成在MySQL容器内执行SQL的shell命令
+///
+/// 数据库名放在单引号中传给 `--database`，因此只拒绝空名称和包含单引号或NUL的名称；
+/// 未指定数据库时使用容器环境变量中的默认数据库，环境变量为空时不选择数据库。
+fn mysql_sql_command(database: Option<&str>) -> Result<String> {
+    match database {
+        Some(name) => {
+            if name.is_empty() || name.contains(['\'', '\0']) {
+                return Err(DuckError::Docker(format!("无效的数据库名: {name:?}")));
+            }
+            Ok(format!("mysql {MYSQL_CLIENT_ARGS} --database='{name}'"))
+        }
+        None => Ok(format!(
+            r#"mysql {MYSQL_CLIENT_ARGS} ${{MYSQL_DATABASE:+--database="$MYSQL_DATABASE"}}"#
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mysql_sql_command() {
+        for name in ["app_db", "duck-db", "app db", "$(id)"] {
+            assert!(
+                mysql_sql_command(Some(name))
+                    .unwrap()
+                    .ends_with(&format!("--database='{name}'"))
+            );
+        }
+        assert!(
+            mysql_sql_command(None)
+                .unwrap()
+                .ends_with(r#"${MYSQL_DATABASE:+--database="$MYSQL_DATABASE"}"#)
+        );
+        for name in ["", "app'; rm -rf /; '", "app\0db"] {
+            assert!(mysql_sql_command(Some(name)).is_err(), "{name:?}");
+        }
+    }
+}
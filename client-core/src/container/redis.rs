@@ -0,0 +1,108 @@
+use super::types::DockerManager;
+use crate::constants::{docker, timeout};
+use crate::{DuckError, Result};
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+/// 在Redis容器内执行的 redis-cli 命令（设置了 REDIS_PASSWORD 环境变量时带上密码）
+const REDIS_CLI: &str = r#"redis-cli ${REDIS_PASSWORD:+-a "$REDIS_PASSWORD" --no-auth-warning}"#;
+
+/// 从 `INFO persistence` 的输出中读取指定字段
+fn parse_redis_info<'a>(info: &'a str, key: &str) -> Option<&'a str> {
+    info.lines()
+        .filter_map(|line| line.trim().split_once(':'))
+        .find(|(name, _)| *name == key)
+        .map(|(_, value)| value)
+}
+
+impl DockerManager {
+    /// 查找 docker-compose.yml 中的Redis服务名
+    pub async fn find_redis_service(&self) -> Result<Option<String>> {
+        let service_names = self.get_compose_service_names().await?;
+
+        if service_names.contains(docker::REDIS_SERVICE_NAME) {
+            return Ok(Some(docker::REDIS_SERVICE_NAME.to_string()));
+        }
+
+        let mut candidates: Vec<String> = service_names
+            .into_iter()
+            .filter(|name| name.to_lowercase().contains(docker::REDIS_SERVICE_NAME))
+            .collect();
+        candidates.sort();
+
+        Ok(candidates.into_iter().next())
+    }
+
+    /// 在Redis容器内执行 redis-cli 命令，返回命令输出
+    async fn execute_redis_command(&self, service_name: &str, command: &str) -> Result<String> {
+        let redis_command = format!("{REDIS_CLI} {command}");
+        let output = self
+            .run_compose_command(&["exec", "-T", service_name, "sh", "-c", &redis_command])
+            .await?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if !output.status.success() || stdout.starts_with("ERR") {
+            return Err(DuckError::Docker(format!(
+                "在Redis容器中执行 {command} 失败: {} {}",
+                stdout,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Ok(stdout)
+    }
+
+    /// 触发Redis后台保存（BGSAVE），等待RDB文件写入完成
+    ///
+    /// 已有后台保存在进行时直接等待其完成。
+    pub async fn redis_bgsave(&self, service_name: &str) -> Result<()> {
+        info!("💾 触发Redis后台保存（BGSAVE）...");
+        if let Err(e) = self.execute_redis_command(service_name, "BGSAVE").await {
+            if !e.to_string().contains("in progress") {
+                return Err(e);
+            }
+            debug!("Redis后台保存已在进行中");
+        }
+
+        let timeout_duration = Duration::from_secs(timeout::REDIS_BGSAVE_TIMEOUT);
+        let check_interval = Duration::from_secs(1);
+        let start_time = Instant::now();
+
+        while start_time.elapsed() < timeout_duration {
+            let info = self
+                .execute_redis_command(service_name, "INFO persistence")
+                .await?;
+            if parse_redis_info(&info, "rdb_bgsave_in_progress") == Some("0") {
+                return match parse_redis_info(&info, "rdb_last_bgsave_status") {
+                    Some("ok") => {
+                        info!("✅ Redis后台保存完成");
+                        Ok(())
+                    }
+                    status => Err(DuckError::Docker(format!(
+                        "Redis后台保存失败: {}",
+                        status.unwrap_or("未知状态")
+                    ))),
+                };
+            }
+            tokio::time::sleep(check_interval).await;
+        }
+
+        Err(DuckError::Docker(format!(
+            "等待Redis后台保存完成超时 ({} 秒)",
+            timeout::REDIS_BGSAVE_TIMEOUT
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_redis_info() {
+        let info = "# Persistence\r\nloading:0\r\nrdb_bgsave_in_progress:1\r\nrdb_last_bgsave_status:ok\r\n";
+        assert_eq!(parse_redis_info(info, "rdb_bgsave_in_progress"), Some("1"));
+        assert_eq!(parse_redis_info(info, "rdb_last_bgsave_status"), Some("ok"));
+        assert_eq!(parse_redis_info(info, "aof_enabled"), None);
+    }
+}
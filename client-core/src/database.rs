@@ -1,4 +1,4 @@
-use crate::{Result, db::DuckDbManager};
+use crate::{DuckError, Result, db::DuckDbManager};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
@@ -26,16 +26,115 @@ pub struct BackupRecord {
     pub service_version: String,
     pub backup_type: BackupType,
     pub status: BackupStatus,
+    pub backup_mode: BackupMode,
+    /// 增量/差异备份所基于的备份ID（全量备份为None）
+    pub parent_id: Option<i64>,
+    /// 加密密钥指纹（未加密的备份为None）
+    pub encryption_fingerprint: Option<String>,
+    /// 备份文件的SHA-256（失败的备份和早期版本创建的备份为None）
+    pub backup_hash: Option<String>,
+    /// 归档中的文件数量
+    pub file_count: Option<i64>,
+    /// 备份文件大小（字节）
+    pub backup_size: Option<i64>,
+    /// 存储目标：`local` 表示只保存在本地，否则为备份上传到的远程存储（如 `s3://bucket/prefix`）
+    pub storage_target: String,
+    /// 归档的压缩格式
+    pub compression_type: CompressionType,
     pub created_at: DateTime<Utc>,
 }
 
+/// 新建备份记录（ID和创建时间由数据库生成）
+#[derive(Debug, Clone)]
+pub struct NewBackupRecord {
+    pub file_path: String,
+    pub service_version: String,
+    pub backup_type: BackupType,
+    pub status: BackupStatus,
+    pub backup_mode: BackupMode,
+    /// 增量/差异备份所基于的备份ID（全量备份为None）
+    pub parent_id: Option<i64>,
+    /// 加密密钥指纹（未加密的备份为None）
+    pub encryption_fingerprint: Option<String>,
+    /// 备份文件的SHA-256（失败的备份和早期版本创建的备份为None）
+    pub backup_hash: Option<String>,
+    /// 归档中的文件数量
+    pub file_count: Option<i64>,
+    /// 备份文件大小（字节）
+    pub backup_size: Option<i64>,
+    /// 存储目标：`local` 表示只保存在本地，否则为备份上传到的远程存储（如 `s3://bucket/prefix`）
+    pub storage_target: String,
+    /// 归档的压缩格式
+    pub compression_type: CompressionType,
+    /// 创建时间（None表示使用当前时间；导入的备份使用备份中记录的时间）
+    pub created_at: Option<DateTime<Utc>>,
+}
+
 /// 备份类型
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum BackupType {
     Manual,
     PreUpgrade,
 }
 
+/// 备份模式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum BackupMode {
+    /// 全量备份：包含所有文件
+    Full,
+    /// 增量备份：只包含相对上一个备份变化的文件
+    Incremental,
+    /// 差异备份：只包含相对最近一次全量备份变化的文件
+    Differential,
+}
+
+/// 备份归档的压缩格式
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum CompressionType {
+    /// gzip（单线程）
+    #[default]
+    Gzip,
+    /// zstd（多线程）
+    Zstd,
+    /// 不压缩
+    None,
+}
+
+impl CompressionType {
+    /// 数据库和配置中使用的名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CompressionType::Gzip => "gzip",
+            CompressionType::Zstd => "zstd",
+            CompressionType::None => "none",
+        }
+    }
+
+    /// 备份文件扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            CompressionType::Gzip => "tar.gz",
+            CompressionType::Zstd => "tar.zst",
+            CompressionType::None => "tar",
+        }
+    }
+}
+
+impl std::str::FromStr for CompressionType {
+    type Err = DuckError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "gzip" | "gz" => Ok(CompressionType::Gzip),
+            "zstd" | "zst" => Ok(CompressionType::Zstd),
+            "none" => Ok(CompressionType::None),
+            _ => Err(DuckError::Backup(format!(
+                "未知的压缩格式: {s}，可选: gzip, zstd, none"
+            ))),
+        }
+    }
+}
+
 /// 备份状态
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum BackupStatus {
@@ -72,6 +171,29 @@ pub enum TaskStatus {
     Cancelled,
 }
 
+/// 升级状态
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum UpgradeStatus {
+    Pending,
+    Running,
+    Success,
+    Failed,
+    RolledBack,
+}
+
+impl UpgradeStatus {
+    /// 数据库中存储的状态字符串
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UpgradeStatus::Pending => "PENDING",
+            UpgradeStatus::Running => "RUNNING",
+            UpgradeStatus::Success => "SUCCESS",
+            UpgradeStatus::Failed => "FAILED",
+            UpgradeStatus::RolledBack => "ROLLED_BACK",
+        }
+    }
+}
+
 impl Database {
     /// 连接到数据库
     pub async fn connect<P: AsRef<Path>>(db_path: P) -> Result<Self> {
@@ -168,25 +290,39 @@ impl Database {
     }
 
     /// 创建备份记录
-    pub async fn create_backup_record(
-        &self,
-        file_path: String,
-        service_version: String,
-        backup_type: BackupType,
-        status: BackupStatus,
-    ) -> Result<i64> {
-        let backup_type_str = match backup_type {
+    pub async fn create_backup_record(&self, record: NewBackupRecord) -> Result<i64> {
+        let backup_type_str = match record.backup_type {
             BackupType::Manual => "manual",
             BackupType::PreUpgrade => "pre-upgrade",
         };
 
-        let status_str = match status {
+        let status_str = match record.status {
             BackupStatus::Completed => "completed",
             BackupStatus::Failed => "failed",
         };
 
+        let backup_mode_str = match record.backup_mode {
+            BackupMode::Full => "FULL",
+            BackupMode::Incremental => "INCREMENTAL",
+            BackupMode::Differential => "DIFFERENTIAL",
+        };
+
         self.manager
-            .create_backup_record(file_path, service_version, backup_type_str, status_str)
+            .create_backup_record(crate::db::NewBackupRecord {
+                file_path: record.file_path,
+                service_version: record.service_version,
+                backup_type: backup_type_str.to_string(),
+                status: status_str.to_string(),
+                backup_mode: backup_mode_str.to_string(),
+                parent_id: record.parent_id,
+                encryption_fingerprint: record.encryption_fingerprint,
+                backup_hash: record.backup_hash,
+                file_count: record.file_count,
+                backup_size: record.backup_size,
+                storage_target: record.storage_target,
+                compression_type: record.compression_type.as_str().to_string(),
+                created_at: record.created_at,
+            })
             .await
     }
 
@@ -208,12 +344,27 @@ impl Database {
                 _ => BackupStatus::Failed,
             };
 
+            let backup_mode = match backup.backup_mode.as_str() {
+                "INCREMENTAL" => BackupMode::Incremental,
+                "DIFFERENTIAL" => BackupMode::Differential,
+                _ => BackupMode::Full,
+            };
+
             backups.push(BackupRecord {
                 id: backup.id,
                 file_path: backup.file_path,
                 service_version: backup.service_version,
                 backup_type,
                 status,
+                backup_mode,
+                parent_id: backup.parent_id,
+                encryption_fingerprint: backup.encryption_fingerprint,
+                backup_hash: backup.backup_hash,
+                file_count: backup.file_count,
+                backup_size: backup.backup_size,
+                storage_target: backup.storage_target,
+                // 无法识别的值按gzip处理，恢复时以归档文件头为准
+                compression_type: backup.compression_type.parse().unwrap_or_default(),
                 created_at: backup.created_at,
             });
         }
@@ -236,12 +387,27 @@ impl Database {
                 _ => BackupStatus::Failed,
             };
 
+            let backup_mode = match backup.backup_mode.as_str() {
+                "INCREMENTAL" => BackupMode::Incremental,
+                "DIFFERENTIAL" => BackupMode::Differential,
+                _ => BackupMode::Full,
+            };
+
             Ok(Some(BackupRecord {
                 id: backup.id,
                 file_path: backup.file_path,
                 service_version: backup.service_version,
                 backup_type,
                 status,
+                backup_mode,
+                parent_id: backup.parent_id,
+                encryption_fingerprint: backup.encryption_fingerprint,
+                backup_hash: backup.backup_hash,
+                file_count: backup.file_count,
+                backup_size: backup.backup_size,
+                storage_target: backup.storage_target,
+                // 无法识别的值按gzip处理，恢复时以归档文件头为准
+                compression_type: backup.compression_type.parse().unwrap_or_default(),
                 created_at: backup.created_at,
             }))
         } else {
@@ -335,6 +501,58 @@ impl Database {
             .await
     }
 
+    /// 创建升级历史记录（状态为RUNNING），返回升级ID
+    pub async fn create_upgrade_history(
+        &self,
+        from_version: &str,
+        to_version: &str,
+        upgrade_type: &str,
+    ) -> Result<String> {
+        let upgrade_id = Uuid::new_v4().to_string();
+        self.manager
+            .create_upgrade_history(
+                &upgrade_id,
+                from_version,
+                to_version,
+                upgrade_type,
+                UpgradeStatus::Running.as_str(),
+            )
+            .await?;
+        Ok(upgrade_id)
+    }
+
+    /// 完成升级历史记录
+    pub async fn complete_upgrade_history(
+        &self,
+        upgrade_id: &str,
+        status: UpgradeStatus,
+        error_message: Option<String>,
+        upgrade_log: Option<String>,
+    ) -> Result<()> {
+        self.manager
+            .complete_upgrade_history(upgrade_id, status.as_str(), error_message, upgrade_log)
+            .await
+    }
+
+    /// 关联升级前备份，回滚时从该备份恢复数据
+    pub async fn set_upgrade_history_backup(&self, upgrade_id: &str, backup_id: i64) -> Result<()> {
+        self.manager
+            .set_upgrade_history_backup(upgrade_id, backup_id)
+            .await
+    }
+
+    /// 记录升级已回滚（状态为 `ROLLED_BACK`）
+    pub async fn roll_back_upgrade_history(
+        &self,
+        upgrade_id: &str,
+        error_message: Option<String>,
+        rollback_reason: &str,
+    ) -> Result<()> {
+        self.manager
+            .roll_back_upgrade_history(upgrade_id, error_message, rollback_reason)
+            .await
+    }
+
     /// 批量更新备份文件路径（用于存储目录迁移）
     pub async fn update_all_backup_paths(&self, old_prefix: &str, new_prefix: &str) -> Result<()> {
         let backups = self.get_all_backups().await?;
@@ -353,6 +571,7 @@ impl Database {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::TimeZone;
     use tempfile::tempdir;
 
     #[tokio::test]
@@ -401,12 +620,21 @@ mod tests {
 
         // 创建备份记录
         let backup_id = db
-            .create_backup_record(
-                "/test/backup.zip".to_string(),
-                "1.0.0".to_string(),
-                BackupType::Manual,
-                BackupStatus::Completed,
-            )
+            .create_backup_record(NewBackupRecord {
+                file_path: "/test/backup.zip".to_string(),
+                service_version: "1.0.0".to_string(),
+                backup_type: BackupType::Manual,
+                status: BackupStatus::Completed,
+                backup_mode: BackupMode::Full,
+                parent_id: None,
+                encryption_fingerprint: None,
+                backup_hash: Some("abc123".to_string()),
+                file_count: Some(42),
+                backup_size: Some(1024),
+                storage_target: "local".to_string(),
+                compression_type: CompressionType::Gzip,
+                created_at: None,
+            })
             .await
             .unwrap();
 
@@ -416,5 +644,80 @@ mod tests {
         let backup = backup.unwrap();
         assert_eq!(backup.file_path, "/test/backup.zip");
         assert_eq!(backup.service_version, "1.0.0");
+        assert_eq!(backup.backup_mode, BackupMode::Full);
+        assert_eq!(backup.parent_id, None);
+        assert_eq!(backup.encryption_fingerprint, None);
+        assert_eq!(backup.backup_hash.as_deref(), Some("abc123"));
+        assert_eq!(backup.file_count, Some(42));
+        assert_eq!(backup.backup_size, Some(1024));
+        assert_eq!(backup.storage_target, "local");
+        assert_eq!(backup.compression_type, CompressionType::Gzip);
+
+        // 增量备份记录所基于的备份
+        let incremental_id = db
+            .create_backup_record(NewBackupRecord {
+                file_path: "/test/backup_incremental.tar.gz.age".to_string(),
+                service_version: "1.0.0".to_string(),
+                backup_type: BackupType::Manual,
+                status: BackupStatus::Completed,
+                backup_mode: BackupMode::Incremental,
+                parent_id: Some(backup_id),
+                encryption_fingerprint: Some("age:0123456789abcdef".to_string()),
+                backup_hash: None,
+                file_count: None,
+                backup_size: None,
+                storage_target: "s3://duck-backups/client-01".to_string(),
+                compression_type: CompressionType::Zstd,
+                created_at: Some(Utc.with_ymd_and_hms(2025, 6, 1, 8, 0, 0).unwrap()),
+            })
+            .await
+            .unwrap();
+        let incremental = db.get_backup_by_id(incremental_id).await.unwrap().unwrap();
+        assert_eq!(incremental.backup_mode, BackupMode::Incremental);
+        assert_eq!(incremental.parent_id, Some(backup_id));
+        assert_eq!(incremental.compression_type, CompressionType::Zstd);
+        assert_eq!(
+            incremental.created_at,
+            Utc.with_ymd_and_hms(2025, 6, 1, 8, 0, 0).unwrap()
+        );
+        assert_eq!(
+            incremental.encryption_fingerprint.as_deref(),
+            Some("age:0123456789abcdef")
+        );
+        assert_eq!(incremental.storage_target, "s3://duck-backups/client-01");
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_history() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let db = Database::connect(&db_path).await.unwrap();
+
+        let upgrade_id = db
+            .create_upgrade_history("1.0.0", "1.1.0", "FULL")
+            .await
+            .unwrap();
+        assert!(Uuid::parse_str(&upgrade_id).is_ok());
+
+        db.complete_upgrade_history(
+            &upgrade_id,
+            UpgradeStatus::Success,
+            None,
+            Some("ALTER TABLE `users` ADD COLUMN `email` VARCHAR(255);".to_string()),
+        )
+        .await
+        .unwrap();
+
+        let rolled_back_id = db
+            .create_upgrade_history("1.1.0", "1.2.0", "FULL")
+            .await
+            .unwrap();
+        db.roll_back_upgrade_history(
+            &rolled_back_id,
+            Some("等待服务启动超时".to_string()),
+            "新版本服务未能在180秒内健康运行",
+        )
+        .await
+        .unwrap();
     }
 }
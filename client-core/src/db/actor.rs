@@ -7,7 +7,7 @@ use tokio::sync::mpsc;
 use tracing::{debug, info};
 
 use super::messages::{AppStateRecord, DbMessage, DownloadTaskRecord, UserActionRecord};
-use super::models::{BackupRecord, ScheduledTask};
+use super::models::{BackupRecord, NewBackupRecord, ScheduledTask};
 
 /// DuckDB Actor - 确保单线程访问DuckDB
 pub struct DuckDbActor {
@@ -57,15 +57,8 @@ impl DuckDbActor {
                 let result = self.set_config(&key, &value);
                 let _ = respond_to.send(result);
             }
-            DbMessage::CreateBackupRecord {
-                file_path,
-                service_version,
-                backup_type,
-                status,
-                respond_to,
-            } => {
-                let result =
-                    self.create_backup_record(&file_path, &service_version, &backup_type, &status);
+            DbMessage::CreateBackupRecord { record, respond_to } => {
+                let result = self.create_backup_record(&record);
                 let _ = respond_to.send(result);
             }
             DbMessage::GetAllBackups { respond_to } => {
@@ -229,6 +222,61 @@ impl DuckDbActor {
                 let result = self.get_user_actions(limit);
                 let _ = respond_to.send(result);
             }
+
+            // ========== 升级历史 ==========
+            DbMessage::CreateUpgradeHistory {
+                upgrade_id,
+                from_version,
+                to_version,
+                upgrade_type,
+                status,
+                respond_to,
+            } => {
+                let result = self.create_upgrade_history(
+                    &upgrade_id,
+                    &from_version,
+                    &to_version,
+                    &upgrade_type,
+                    &status,
+                );
+                let _ = respond_to.send(result);
+            }
+            DbMessage::CompleteUpgradeHistory {
+                upgrade_id,
+                status,
+                error_message,
+                upgrade_log,
+                respond_to,
+            } => {
+                let result = self.complete_upgrade_history(
+                    &upgrade_id,
+                    &status,
+                    error_message.as_deref(),
+                    upgrade_log.as_deref(),
+                );
+                let _ = respond_to.send(result);
+            }
+            DbMessage::SetUpgradeHistoryBackup {
+                upgrade_id,
+                backup_id,
+                respond_to,
+            } => {
+                let result = self.set_upgrade_history_backup(&upgrade_id, backup_id);
+                let _ = respond_to.send(result);
+            }
+            DbMessage::RollBackUpgradeHistory {
+                upgrade_id,
+                error_message,
+                rollback_reason,
+                respond_to,
+            } => {
+                let result = self.roll_back_upgrade_history(
+                    &upgrade_id,
+                    error_message.as_deref(),
+                    &rollback_reason,
+                );
+                let _ = respond_to.send(result);
+            }
         }
     }
 
@@ -293,18 +341,26 @@ impl DuckDbActor {
     }
 
     /// 创建备份记录
-    fn create_backup_record(
-        &mut self,
-        file_path: &str,
-        service_version: &str,
-        backup_type: &str,
-        status: &str,
-    ) -> Result<i64> {
+    fn create_backup_record(&mut self, record: &NewBackupRecord) -> Result<i64> {
         // 插入记录，让数据库自动生成ID
         self.connection.execute(
-            "INSERT INTO backups (file_path, service_version, backup_type, status) 
-             VALUES (?, ?, ?, ?)",
-            params![file_path, service_version, backup_type, status],
+            "INSERT INTO backups (file_path, service_version, backup_type, status, backup_mode, parent_id, encryption_fingerprint, backup_hash, file_count, backup_size, storage_target, compression_type, created_at) 
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, COALESCE(?, CURRENT_TIMESTAMP))",
+            params![
+                record.file_path,
+                record.service_version,
+                record.backup_type,
+                record.status,
+                record.backup_mode,
+                record.parent_id,
+                record.encryption_fingerprint,
+                record.backup_hash,
+                record.file_count,
+                record.backup_size,
+                record.storage_target,
+                record.compression_type,
+                record.created_at
+            ],
         )?;
 
         // 获取最后插入的ID
@@ -318,7 +374,7 @@ impl DuckDbActor {
     /// 获取所有备份记录
     fn get_all_backups(&mut self) -> Result<Vec<BackupRecord>> {
         let mut stmt = self.connection.prepare(
-            "SELECT id, file_path, service_version, backup_type, status, created_at 
+            "SELECT id, file_path, service_version, backup_type, status, backup_mode, parent_id, encryption_fingerprint, backup_hash, file_count, backup_size, storage_target, compression_type, created_at 
              FROM backups ORDER BY created_at DESC",
         )?;
 
@@ -329,7 +385,15 @@ impl DuckDbActor {
                 service_version: row.get(2)?,
                 backup_type: row.get(3)?,
                 status: row.get(4)?,
-                created_at: row.get(5)?,
+                backup_mode: row.get(5)?,
+                parent_id: row.get(6)?,
+                encryption_fingerprint: row.get(7)?,
+                backup_hash: row.get(8)?,
+                file_count: row.get(9)?,
+                backup_size: row.get(10)?,
+                storage_target: row.get(11)?,
+                compression_type: row.get(12)?,
+                created_at: row.get(13)?,
             })
         })?;
 
@@ -344,7 +408,7 @@ impl DuckDbActor {
     /// 根据ID获取备份记录
     fn get_backup_by_id(&mut self, id: i64) -> Result<Option<BackupRecord>> {
         let mut stmt = self.connection.prepare(
-            "SELECT id, file_path, service_version, backup_type, status, created_at 
+            "SELECT id, file_path, service_version, backup_type, status, backup_mode, parent_id, encryption_fingerprint, backup_hash, file_count, backup_size, storage_target, compression_type, created_at 
              FROM backups WHERE id = ?",
         )?;
 
@@ -357,7 +421,15 @@ impl DuckDbActor {
                 service_version: row.get(2)?,
                 backup_type: row.get(3)?,
                 status: row.get(4)?,
-                created_at: row.get(5)?,
+                backup_mode: row.get(5)?,
+                parent_id: row.get(6)?,
+                encryption_fingerprint: row.get(7)?,
+                backup_hash: row.get(8)?,
+                file_count: row.get(9)?,
+                backup_size: row.get(10)?,
+                storage_target: row.get(11)?,
+                compression_type: row.get(12)?,
+                created_at: row.get(13)?,
             }))
         } else {
             Ok(None)
@@ -727,4 +799,66 @@ impl DuckDbActor {
         }
         Ok(actions)
     }
+
+    // ========== 升级历史方法 ==========
+
+    /// 创建升级历史记录
+    fn create_upgrade_history(
+        &mut self,
+        upgrade_id: &str,
+        from_version: &str,
+        to_version: &str,
+        upgrade_type: &str,
+        status: &str,
+    ) -> Result<()> {
+        self.connection.execute(
+            "INSERT INTO upgrade_history (upgrade_id, from_version, to_version, upgrade_type, status, started_at) 
+             VALUES (?, ?, ?, ?, ?, CURRENT_TIMESTAMP)",
+            params![upgrade_id, from_version, to_version, upgrade_type, status],
+        )?;
+        Ok(())
+    }
+
+    /// 完成升级历史记录
+    fn complete_upgrade_history(
+        &mut self,
+        upgrade_id: &str,
+        status: &str,
+        error_message: Option<&str>,
+        upgrade_log: Option<&str>,
+    ) -> Result<()> {
+        self.connection.execute(
+            "UPDATE upgrade_history SET status = ?, error_message = ?, upgrade_log = ?, 
+             completed_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP 
+             WHERE upgrade_id = ?",
+            params![status, error_message, upgrade_log, upgrade_id],
+        )?;
+        Ok(())
+    }
+
+    /// 关联升级前备份
+    fn set_upgrade_history_backup(&mut self, upgrade_id: &str, backup_id: i64) -> Result<()> {
+        self.connection.execute(
+            "UPDATE upgrade_history SET backup_id = ?, updated_at = CURRENT_TIMESTAMP
+             WHERE upgrade_id = ?",
+            params![backup_id, upgrade_id],
+        )?;
+        Ok(())
+    }
+
+    /// 记录升级已回滚
+    fn roll_back_upgrade_history(
+        &mut self,
+        upgrade_id: &str,
+        error_message: Option<&str>,
+        rollback_reason: &str,
+    ) -> Result<()> {
+        self.connection.execute(
+            "UPDATE upgrade_history SET status = 'ROLLED_BACK', error_message = ?, rollback_reason = ?,
+             completed_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+             WHERE upgrade_id = ?",
+            params![error_message, rollback_reason, upgrade_id],
+        )?;
+        Ok(())
+    }
 }
@@ -6,7 +6,7 @@ use uuid::Uuid;
 
 use super::actor::DuckDbActor;
 use super::messages::{AppStateRecord, DbMessage, DownloadTaskRecord, UserActionRecord};
-use super::models::{BackupRecord, ScheduledTask};
+use super::models::{BackupRecord, NewBackupRecord, ScheduledTask};
 
 /// DuckDB数据库管理器
 #[derive(Debug, Clone)]
@@ -339,24 +339,94 @@ impl DuckDbManager {
             .map_err(|_| DuckError::Custom("等待数据库响应超时".to_string()))?
     }
 
-    // ========== 现有的备份和任务管理 ==========
+    // ========== 升级历史 ==========
 
-    /// 创建备份记录
-    pub async fn create_backup_record(
+    /// 创建升级历史记录
+    pub async fn create_upgrade_history(
         &self,
-        file_path: String,
-        service_version: String,
-        backup_type: &str,
+        upgrade_id: &str,
+        from_version: &str,
+        to_version: &str,
+        upgrade_type: &str,
         status: &str,
-    ) -> Result<i64> {
+    ) -> Result<()> {
+        let (respond_to, receiver) = oneshot::channel();
+
+        self.sender
+            .send(DbMessage::CreateUpgradeHistory {
+                upgrade_id: upgrade_id.to_string(),
+                from_version: from_version.to_string(),
+                to_version: to_version.to_string(),
+                upgrade_type: upgrade_type.to_string(),
+                status: status.to_string(),
+                respond_to,
+            })
+            .await
+            .map_err(|_| DuckError::Custom("数据库Actor已关闭".to_string()))?;
+
+        receiver
+            .await
+            .map_err(|_| DuckError::Custom("等待数据库响应超时".to_string()))?
+    }
+
+    /// 完成升级历史记录
+    pub async fn complete_upgrade_history(
+        &self,
+        upgrade_id: &str,
+        status: &str,
+        error_message: Option<String>,
+        upgrade_log: Option<String>,
+    ) -> Result<()> {
         let (respond_to, receiver) = oneshot::channel();
 
         self.sender
-            .send(DbMessage::CreateBackupRecord {
-                file_path,
-                service_version,
-                backup_type: backup_type.to_string(),
+            .send(DbMessage::CompleteUpgradeHistory {
+                upgrade_id: upgrade_id.to_string(),
                 status: status.to_string(),
+                error_message,
+                upgrade_log,
+                respond_to,
+            })
+            .await
+            .map_err(|_| DuckError::Custom("数据库Actor已关闭".to_string()))?;
+
+        receiver
+            .await
+            .map_err(|_| DuckError::Custom("等待数据库响应超时".to_string()))?
+    }
+
+    /// 关联升级前备份
+    pub async fn set_upgrade_history_backup(&self, upgrade_id: &str, backup_id: i64) -> Result<()> {
+        let (respond_to, receiver) = oneshot::channel();
+
+        self.sender
+            .send(DbMessage::SetUpgradeHistoryBackup {
+                upgrade_id: upgrade_id.to_string(),
+                backup_id,
+                respond_to,
+            })
+            .await
+            .map_err(|_| DuckError::Custom("数据库Actor已关闭".to_string()))?;
+
+        receiver
+            .await
+            .map_err(|_| DuckError::Custom("等待数据库响应超时".to_string()))?
+    }
+
+    /// 记录升级已回滚
+    pub async fn roll_back_upgrade_history(
+        &self,
+        upgrade_id: &str,
+        error_message: Option<String>,
+        rollback_reason: &str,
+    ) -> Result<()> {
+        let (respond_to, receiver) = oneshot::channel();
+
+        self.sender
+            .send(DbMessage::RollBackUpgradeHistory {
+                upgrade_id: upgrade_id.to_string(),
+                error_message,
+                rollback_reason: rollback_reason.to_string(),
                 respond_to,
             })
             .await
@@ -367,6 +437,22 @@ impl DuckDbManager {
             .map_err(|_| DuckError::Custom("等待数据库响应超时".to_string()))?
     }
 
+    // ========== 现有的备份和任务管理 ==========
+
+    /// 创建备份记录
+    pub async fn create_backup_record(&self, record: NewBackupRecord) -> Result<i64> {
+        let (respond_to, receiver) = oneshot::channel();
+
+        self.sender
+            .send(DbMessage::CreateBackupRecord { record, respond_to })
+            .await
+            .map_err(|_| DuckError::Custom("数据库Actor已关闭".to_string()))?;
+
+        receiver
+            .await
+            .map_err(|_| DuckError::Custom("等待数据库响应超时".to_string()))?
+    }
+
     /// 获取所有备份记录
     pub async fn get_all_backups(&self) -> Result<Vec<BackupRecord>> {
         let (respond_to, receiver) = oneshot::channel();
@@ -2,7 +2,7 @@ use crate::Result;
 use chrono::{DateTime, Utc};
 use tokio::sync::oneshot;
 
-use super::models::{BackupRecord, ScheduledTask};
+use super::models::{BackupRecord, NewBackupRecord, ScheduledTask};
 
 /// DuckDB数据库操作消息
 #[derive(Debug)]
@@ -93,13 +93,42 @@ pub enum DbMessage {
         respond_to: oneshot::Sender<Result<Vec<UserActionRecord>>>,
     },
 
+    // ========== 升级历史 ==========
+    /// 创建升级历史记录
+    CreateUpgradeHistory {
+        upgrade_id: String,
+        from_version: String,
+        to_version: String,
+        upgrade_type: String,
+        status: String,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    /// 完成升级历史记录
+    CompleteUpgradeHistory {
+        upgrade_id: String,
+        status: String,
+        error_message: Option<String>,
+        upgrade_log: Option<String>,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    /// 关联升级前备份
+    SetUpgradeHistoryBackup {
+        upgrade_id: String,
+        backup_id: i64,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    /// 记录升级已回滚
+    RollBackUpgradeHistory {
+        upgrade_id: String,
+        error_message: Option<String>,
+        rollback_reason: String,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+
     // ========== 现有的备份和任务管理 ==========
     /// 创建备份记录
     CreateBackupRecord {
-        file_path: String,
-        service_version: String,
-        backup_type: String,
-        status: String,
+        record: NewBackupRecord,
         respond_to: oneshot::Sender<Result<i64>>,
     },
     /// 获取所有备份记录
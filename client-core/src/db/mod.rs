@@ -15,7 +15,7 @@ mod models;
 
 // 公开核心接口
 pub use manager::DuckDbManager;
-pub use models::{BackupRecord, ScheduledTask};
+pub use models::{BackupRecord, NewBackupRecord, ScheduledTask};
 
 // 重新导出常用类型
 pub type DbManager = DuckDbManager;
@@ -9,9 +9,35 @@ pub struct BackupRecord {
     pub service_version: String,
     pub backup_type: String,
     pub status: String,
+    pub backup_mode: String,
+    pub parent_id: Option<i64>,
+    pub encryption_fingerprint: Option<String>,
+    pub backup_hash: Option<String>,
+    pub file_count: Option<i64>,
+    pub backup_size: Option<i64>,
+    pub storage_target: String,
+    pub compression_type: String,
     pub created_at: DateTime<Utc>,
 }
 
+/// 新建备份记录（ID和创建时间由数据库生成）
+#[derive(Debug, Clone)]
+pub struct NewBackupRecord {
+    pub file_path: String,
+    pub service_version: String,
+    pub backup_type: String,
+    pub status: String,
+    pub backup_mode: String,
+    pub parent_id: Option<i64>,
+    pub encryption_fingerprint: Option<String>,
+    pub backup_hash: Option<String>,
+    pub file_count: Option<i64>,
+    pub backup_size: Option<i64>,
+    pub storage_target: String,
+    pub compression_type: String,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
 /// 计划任务
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScheduledTask {
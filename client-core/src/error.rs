@@ -46,6 +46,9 @@ pub enum DuckError {
     #[error("升级操作失败: {0}")]
     Upgrade(String),
 
+    #[error("服务包签名校验失败: {0}")]
+    Signature(String),
+
     #[error("客户端未注册")]
     ClientNotRegistered,
 
@@ -2,6 +2,10 @@ pub mod api;
 pub mod api_config;
 pub mod authenticated_client;
 pub mod backup;
+pub mod backup_compression;
+pub mod backup_encryption;
+pub mod backup_retention;
+pub mod backup_storage;
 pub mod config;
 pub mod config_manager;
 pub mod constants;
@@ -11,8 +15,12 @@ pub mod database_manager;
 pub mod db;
 pub mod downloader;
 pub mod error;
+pub mod package_signature;
+pub mod schema_migration;
+pub mod service_patch;
 pub mod sql_diff;
 pub mod upgrade;
+pub mod upgrade_transaction;
 
 pub use database_manager::DatabaseManager;
 pub use error::*;
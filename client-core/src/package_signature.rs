@@ -0,0 +1,185 @@
+//! 服务包签名校验
+//!
+//! 服务清单中每个包的 `signature` 是 minisign 签名（与客户端更新相同，为签名文件内容的base64编码，
+//! 也接受签名文件原文）。下载时签名保存到服务包旁的 `.minisig` 文件，解压前使用客户端内置的公钥校验，
+//! 防止服务端被篡改后下发与哈希匹配的恶意服务包。
+
+use crate::{DuckError, Result, constants::upgrade};
+use base64::Engine;
+use minisign_verify::{PublicKey, Signature};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// 服务包对应的签名文件路径（`docker.zip` -> `docker.zip.minisig`）
+pub fn signature_path(package_path: &Path) -> PathBuf {
+    let mut file_name = package_path.as_os_str().to_os_string();
+    file_name.push(".");
+    file_name.push(upgrade::SIGNATURE_FILE_EXTENSION);
+    PathBuf::from(file_name)
+}
+
+/// 解析服务清单中的签名（base64编码的签名文件或签名文件原文），返回签名文件内容
+pub fn decode_signature(signature: &str) -> Result<String> {
+    let signature = signature.trim();
+    let text = if signature.starts_with("untrusted comment:") {
+        signature.to_string()
+    } else {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(signature)
+            .map_err(|e| DuckError::Signature(format!("签名不是有效的base64: {e}")))?;
+        String::from_utf8(bytes)
+            .map_err(|e| DuckError::Signature(format!("签名不是有效的文本: {e}")))?
+    };
+
+    Signature::decode(&text)
+        .map_err(|e| DuckError::Signature(format!("无效的minisign签名: {e}")))?;
+    Ok(text)
+}
+
+/// 保存服务清单中的签名到服务包旁的签名文件；清单未提供签名时删除旧的签名文件
+pub async fn save_signature(package_path: &Path, signature: &str) -> Result<()> {
+    let path = signature_path(package_path);
+    if signature.trim().is_empty() {
+        if path.exists() {
+            tokio::fs::remove_file(&path).await?;
+        }
+        return Ok(());
+    }
+
+    let text = decode_signature(signature)?;
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(&path, text).await?;
+    Ok(())
+}
+
+/// 客户端内置的服务包签名公钥
+fn trusted_public_keys() -> Result<Vec<PublicKey>> {
+    upgrade::TRUSTED_PUBLIC_KEYS
+        .iter()
+        .map(|key| {
+            PublicKey::from_base64(key)
+                .map_err(|e| DuckError::Signature(format!("内置公钥无效 {key}: {e}")))
+        })
+        .collect()
+}
+
+/// 使用内置公钥校验服务包与其签名文件
+pub async fn verify_package(package_path: &Path) -> Result<()> {
+    let signature_file = signature_path(package_path);
+    if !signature_file.exists() {
+        return Err(DuckError::Signature(format!(
+            "缺少服务包签名文件: {}",
+            signature_file.display()
+        )));
+    }
+    let signature = tokio::fs::read_to_string(&signature_file).await?;
+    let keys = trusted_public_keys()?;
+
+    let package_path = package_path.to_path_buf();
+    tokio::task::spawn_blocking(move || verify_with_keys(&package_path, &signature, &keys))
+        .await??;
+
+    info!("🔏 服务包签名校验通过");
+    Ok(())
+}
+
+/// 流式校验文件签名，签名必须由 `keys` 中的某个公钥生成
+fn verify_with_keys(package_path: &Path, signature: &str, keys: &[PublicKey]) -> Result<()> {
+    let signature = Signature::decode(signature)
+        .map_err(|e| DuckError::Signature(format!("无效的minisign签名: {e}")))?;
+
+    // 只有签名的密钥ID与公钥一致时才能创建校验器
+    let mut verifier = keys
+        .iter()
+        .find_map(|key| key.verify_stream(&signature).ok())
+        .ok_or_else(|| {
+            DuckError::Signature(
+                "签名不是由受信任的公钥生成（或使用了不支持的旧版minisign签名）".to_string(),
+            )
+        })?;
+
+    let mut file = File::open(package_path)?;
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let bytes_read = file.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        verifier.update(&buffer[..bytes_read]);
+    }
+
+    verifier.finalize().map_err(|e| {
+        DuckError::Signature(format!(
+            "服务包与签名不匹配 {}: {e}",
+            package_path.display()
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    // minisign 项目的测试公钥和对 "test" 的签名
+    const TEST_PUBLIC_KEY: &str = "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3";
+    const TEST_SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1556193335\tfile:test
+y/rUw2y8/hOUYjZU71eHp/Wo1KZ40fGy2VJEDl34XMJM+TX48Ss/17u3IvIfbVR1FkZZSNCisQbuQY+bHwhEBg==";
+
+    #[test]
+    fn test_verify_with_keys() {
+        let dir = tempdir().unwrap();
+        let package = dir.path().join("docker.zip");
+        let test_key = PublicKey::from_base64(TEST_PUBLIC_KEY).unwrap();
+
+        std::fs::write(&package, "test").unwrap();
+        verify_with_keys(&package, TEST_SIGNATURE, std::slice::from_ref(&test_key)).unwrap();
+
+        // 内容被篡改
+        std::fs::write(&package, "Test").unwrap();
+        assert!(verify_with_keys(&package, TEST_SIGNATURE, &[test_key]).is_err());
+
+        // 签名不是由内置公钥生成
+        std::fs::write(&package, "test").unwrap();
+        let trusted = trusted_public_keys().unwrap();
+        assert!(verify_with_keys(&package, TEST_SIGNATURE, &trusted).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_save_signature_accepts_base64_and_text() {
+        let dir = tempdir().unwrap();
+        let package = dir.path().join("docker.zip");
+        let signature_file = dir.path().join("docker.zip.minisig");
+        assert_eq!(signature_path(&package), signature_file);
+
+        let encoded = base64::engine::general_purpose::STANDARD.encode(TEST_SIGNATURE);
+        save_signature(&package, &encoded).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&signature_file).unwrap(),
+            TEST_SIGNATURE
+        );
+
+        save_signature(&package, TEST_SIGNATURE).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&signature_file).unwrap(),
+            TEST_SIGNATURE
+        );
+
+        assert!(save_signature(&package, "not a signature").await.is_err());
+
+        // 清单未提供签名时删除旧的签名文件，解压前校验失败
+        save_signature(&package, "").await.unwrap();
+        assert!(!signature_file.exists());
+        std::fs::write(&package, "test").unwrap();
+        assert!(matches!(
+            verify_package(&package).await,
+            Err(DuckError::Signature(_))
+        ));
+    }
+}
@@ -0,0 +1,625 @@
+//! MySQL架构迁移
+//!
+//! 升级时比较新旧服务包中的MySQL初始化SQL，生成差异SQL，
+//! 并在MySQL容器就绪后执行，执行结果记录到 `upgrade_history` 表。
+//! 同时生成回滚（down）脚本，随升级前备份保存，回滚时用于恢复数据库架构。
+
+use crate::{
+    DuckError, Result,
+    backup::{BackupManager, RestoreFilter},
+    constants::docker,
+    container::DockerManager,
+    database::{Database, UpgradeStatus},
+    sql_diff::{ChangeSafety, DiffStatement, analyze_schema_diff},
+};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tracing::{error, info, warn};
+use walkdir::WalkDir;
+
+/// 架构迁移计划
+#[derive(Debug, Clone)]
+pub struct SchemaMigrationPlan {
+    pub from_version: String,
+    pub to_version: String,
+    /// 差异涉及的数据库（默认数据库不列出）
+    pub databases: Vec<String>,
+    /// 差异SQL中是否有不带 USE 语句、需要在容器默认数据库中执行的部分
+    pub uses_default_database: bool,
+    /// 可执行的差异SQL（按数据库带有 USE 语句，在容器的默认数据库中执行）
+    pub diff_sql: String,
+    /// 差异描述
+    pub description: String,
+    /// 差异SQL中的每条语句及其安全级别
+    pub statements: Vec<DiffStatement>,
+    /// 回滚（down）差异SQL
+    pub down_sql: String,
+}
+
+impl SchemaMigrationPlan {
+    /// 差异SQL中的有效行（去掉注释和空行）
+    pub fn sql_lines(&self) -> Vec<&str> {
+        self.diff_sql
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with("--"))
+            .collect()
+    }
+
+    /// 是否没有需要执行的语句
+    pub fn is_empty(&self) -> bool {
+        self.sql_lines().is_empty()
+    }
+
+    /// 可能导致数据丢失的语句
+    pub fn data_loss_statements(&self) -> Vec<&DiffStatement> {
+        self.statements
+            .iter()
+            .filter(|statement| statement.safety == ChangeSafety::DataLoss)
+            .collect()
+    }
+
+    /// 生成随备份保存的回滚脚本（包含版本说明），没有回滚语句时返回None
+    pub fn rollback_script(&self) -> Option<String> {
+        if self.down_sql.trim().is_empty() {
+            return None;
+        }
+
+        // 回滚SQL按数据库带有 USE 语句
+        let mut script = format!(
+            "-- 数据库架构回滚脚本: {} -> {}\n",
+            self.to_version, self.from_version
+        );
+        script.push_str(&self.down_sql);
+        script.push('\n');
+        Some(script)
+    }
+}
+
+/// 查找服务包目录中的MySQL初始化SQL文件（按路径排序，与MySQL初始化脚本的执行顺序一致）
+pub fn find_init_sql_files(package_dir: &Path) -> Vec<PathBuf> {
+    let sql_files: Vec<PathBuf> = WalkDir::new(package_dir)
+        .into_iter()
+        .filter_entry(|entry| {
+            // 跳过运行时数据目录，只查找服务包自带的文件
+            entry.depth() != 1
+                || !entry.file_type().is_dir()
+                || !docker::INIT_SQL_SKIP_DIRS
+                    .iter()
+                    .any(|dir| entry.file_name() == *dir)
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            entry
+                .path()
+                .strip_prefix(package_dir)
+                .ok()
+                .map(Path::to_path_buf)
+        })
+        .collect();
+
+    select_init_sql_files(sql_files)
+        .into_iter()
+        .map(|path| package_dir.join(path))
+        .collect()
+}
+
+/// 读取服务包目录中的MySQL初始化SQL（多个文件按顺序拼接），没有SQL文件时返回None
+pub fn load_init_sql(package_dir: &Path) -> Result<Option<String>> {
+    let sql_files = find_init_sql_files(package_dir);
+    if sql_files.is_empty() {
+        return Ok(None);
+    }
+
+    let mut content = String::new();
+    for sql_file in &sql_files {
+        info!("📄 读取MySQL初始化SQL: {}", sql_file.display());
+        content.push_str(&std::fs::read_to_string(sql_file)?);
+        content.push('\n');
+    }
+
+    Ok(Some(content))
+}
+
+/// 直接从服务包ZIP文件中读取MySQL初始化SQL（无需解压部署），没有SQL文件时返回None
+///
+/// ZIP中带有顶层目录（如 `docker/`）时，按去掉顶层目录后的路径查找，与解压后的目录结构一致。
+pub fn load_init_sql_from_package(zip_path: &Path) -> Result<Option<String>> {
+    let file = std::fs::File::open(zip_path)?;
+    let mut archive = zip::ZipArchive::new(file)?;
+
+    // docker-compose.yml 所在目录即服务包根目录
+    let root_prefix = archive
+        .file_names()
+        .filter(|name| !name.starts_with("__MACOSX"))
+        .find(|name| name.ends_with(docker::COMPOSE_FILE_NAME))
+        .and_then(|name| Path::new(name).parent().map(Path::to_path_buf))
+        .unwrap_or_default();
+
+    // 相对路径 -> ZIP中的条目名
+    let entries: HashMap<PathBuf, String> = archive
+        .file_names()
+        .filter(|name| !name.ends_with('/') && !name.starts_with("__MACOSX"))
+        .filter_map(|name| {
+            Path::new(name)
+                .strip_prefix(&root_prefix)
+                .ok()
+                .map(|path| (path.to_path_buf(), name.to_string()))
+        })
+        .collect();
+
+    let sql_files = select_init_sql_files(entries.keys().cloned().collect());
+    if sql_files.is_empty() {
+        return Ok(None);
+    }
+
+    let mut content = String::new();
+    for sql_file in &sql_files {
+        let entry_name = &entries[sql_file];
+        info!("📄 读取服务包中的MySQL初始化SQL: {}", entry_name);
+        archive.by_name(entry_name)?.read_to_string(&mut content)?;
+        content.push('\n');
+    }
+
+    Ok(Some(content))
+}
+
+/// 读取应用补丁后的MySQL初始化SQL（无需应用补丁），没有SQL文件时返回None
+///
+/// 按目标版本完整服务包的文件清单（`full_files`）查找初始化SQL：补丁包含的文件从补丁包读取，
+/// 其余文件从已安装的服务包目录读取。
+pub fn load_init_sql_from_patch(
+    patch_path: &Path,
+    package_dir: &Path,
+    full_files: &BTreeMap<String, String>,
+) -> Result<Option<String>> {
+    let sql_files = select_init_sql_files(full_files.keys().map(PathBuf::from).collect());
+    if sql_files.is_empty() {
+        return Ok(None);
+    }
+
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(patch_path)?)?;
+    let mut content = String::new();
+    for sql_file in &sql_files {
+        let entry_name = sql_file.to_string_lossy().replace('\\', "/");
+        match archive.by_name(&entry_name) {
+            Ok(mut entry) => {
+                info!("📄 读取补丁包中的MySQL初始化SQL: {}", entry_name);
+                entry.read_to_string(&mut content)?;
+            }
+            Err(_) => {
+                let path = package_dir.join(sql_file);
+                info!("📄 读取MySQL初始化SQL: {}", path.display());
+                content.push_str(&std::fs::read_to_string(&path)?);
+            }
+        }
+        content.push('\n');
+    }
+
+    Ok(Some(content))
+}
+
+/// 从服务包内的相对路径中挑选MySQL初始化SQL文件
+///
+/// 跳过运行时数据目录，优先使用MySQL相关目录下的SQL文件，并按路径排序。
+fn select_init_sql_files(relative_paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut sql_files: Vec<PathBuf> = relative_paths
+        .into_iter()
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("sql"))
+        })
+        .filter(|path| {
+            // 只查找服务包自带的文件
+            let top_dir = path.components().next().map(|c| c.as_os_str());
+            path.components().count() == 1
+                || !docker::INIT_SQL_SKIP_DIRS
+                    .iter()
+                    .any(|dir| top_dir == Some(std::ffi::OsStr::new(dir)))
+        })
+        .collect();
+
+    let mysql_files: Vec<PathBuf> = sql_files
+        .iter()
+        .filter(|path| {
+            path.to_string_lossy()
+                .to_lowercase()
+                .contains(docker::MYSQL_SERVICE_NAME)
+        })
+        .cloned()
+        .collect();
+    if !mysql_files.is_empty() {
+        sql_files = mysql_files;
+    }
+
+    sql_files.sort();
+    sql_files
+}
+
+/// 根据新旧初始化SQL生成架构迁移计划
+///
+/// 旧版本没有初始化SQL时（首次部署），数据库由MySQL容器的初始化脚本创建，无需迁移。
+pub fn plan_schema_migration(
+    old_sql: Option<&str>,
+    new_sql: Option<&str>,
+    from_version: &str,
+    to_version: &str,
+) -> Result<Option<SchemaMigrationPlan>> {
+    let (Some(old_sql), Some(new_sql)) = (old_sql, new_sql) else {
+        info!("ℹ️ 新旧服务包中缺少MySQL初始化SQL，跳过架构迁移");
+        return Ok(None);
+    };
+
+    let report = analyze_schema_diff(Some(old_sql), new_sql, Some(from_version), to_version)?;
+
+    let plan = SchemaMigrationPlan {
+        from_version: from_version.to_string(),
+        to_version: to_version.to_string(),
+        databases: report.diff.database_names(),
+        uses_default_database: report.diff.uses_default_database(),
+        diff_sql: report.diff_sql,
+        description: report.description,
+        statements: report.statements,
+        down_sql: report.down_sql,
+    };
+
+    if plan.is_empty() {
+        info!("✅ 数据库架构无变化: {}", plan.description);
+        return Ok(None);
+    }
+
+    Ok(Some(plan))
+}
+
+/// 显示架构迁移计划
+pub fn print_migration_plan(plan: &SchemaMigrationPlan) {
+    info!("🗄️ 数据库架构迁移计划: {}", plan.description);
+    if !plan.databases.is_empty() {
+        info!("   涉及数据库: {}", plan.databases.join(", "));
+    }
+    for statement in &plan.statements {
+        let marker = match statement.safety {
+            ChangeSafety::Safe => "✅",
+            ChangeSafety::Locking => "🔒",
+            ChangeSafety::DataLoss => "⚠️",
+        };
+        for (index, line) in statement.sql.lines().enumerate() {
+            if index == 0 {
+                info!(
+                    "   {} [{}] {}",
+                    marker,
+                    statement.safety.description(),
+                    line
+                );
+            } else {
+                info!("        {}", line);
+            }
+        }
+    }
+}
+
+/// 检查迁移计划是否允许执行：包含可能丢失数据的语句时，必须显式允许
+pub fn ensure_migration_allowed(plan: &SchemaMigrationPlan, allow_data_loss: bool) -> Result<()> {
+    let data_loss_statements = plan.data_loss_statements();
+    if data_loss_statements.is_empty() {
+        return Ok(());
+    }
+
+    if allow_data_loss {
+        warn!(
+            "⚠️ 已允许执行 {} 条可能导致数据丢失的迁移语句",
+            data_loss_statements.len()
+        );
+        return Ok(());
+    }
+
+    for statement in &data_loss_statements {
+        error!("   ⚠️ {}", statement.sql);
+    }
+    Err(DuckError::upgrade(format!(
+        "数据库架构迁移包含 {} 条可能导致数据丢失的语句，已拒绝执行；确认无误后请使用 --allow-data-loss 重新执行",
+        data_loss_statements.len()
+    )))
+}
+
+/// 在MySQL容器中执行架构迁移，并记录到升级历史
+pub async fn apply_schema_migration(
+    docker_manager: &DockerManager,
+    database: &Database,
+    plan: &SchemaMigrationPlan,
+) -> Result<()> {
+    let upgrade_id = database
+        .create_upgrade_history(&plan.from_version, &plan.to_version, "FULL")
+        .await?;
+
+    match execute_migration(docker_manager, plan).await {
+        Ok(output) => {
+            let upgrade_log = if output.trim().is_empty() {
+                plan.diff_sql.clone()
+            } else {
+                format!("{}\n-- MySQL输出:\n{}", plan.diff_sql, output.trim())
+            };
+            database
+                .complete_upgrade_history(
+                    &upgrade_id,
+                    UpgradeStatus::Success,
+                    None,
+                    Some(upgrade_log),
+                )
+                .await?;
+            info!("✅ 数据库架构迁移完成");
+            Ok(())
+        }
+        Err(e) => {
+            error!("❌ 数据库架构迁移失败: {}", e);
+            if let Err(record_err) = database
+                .complete_upgrade_history(
+                    &upgrade_id,
+                    UpgradeStatus::Failed,
+                    Some(e.to_string()),
+                    Some(plan.diff_sql.clone()),
+                )
+                .await
+            {
+                warn!("⚠️ 记录升级历史失败: {}", record_err);
+            }
+            Err(e)
+        }
+    }
+}
+
+/// 执行随升级前备份保存的数据库架构回滚脚本
+pub async fn apply_schema_rollback(
+    docker_manager: &DockerManager,
+    rollback_script: &str,
+) -> Result<()> {
+    info!("⏪ 正在回滚数据库架构...");
+    // 回滚脚本自带 USE 语句，容器设置了默认数据库时在默认数据库中执行
+    execute_sql(docker_manager, false, rollback_script).await?;
+    info!("✅ 数据库架构回滚完成");
+    Ok(())
+}
+
+/// 从备份恢复后回滚数据库架构，返回是否执行了回滚脚本
+///
+/// 恢复的内容不包含MySQL数据时（如只恢复了 `app` 或 `data/redis`），MySQL仍是升级后的架构，
+/// 执行随升级前备份保存的回滚脚本；MySQL数据随备份恢复时不执行。
+pub async fn rollback_schema_after_restore(
+    docker_manager: &DockerManager,
+    backup_manager: &BackupManager,
+    backup_id: i64,
+    filter: &RestoreFilter,
+) -> Result<bool> {
+    match backup_manager
+        .schema_rollback_for_restore(backup_id, filter)
+        .await?
+    {
+        Some(rollback_script) => {
+            apply_schema_rollback(docker_manager, &rollback_script).await?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// 等待MySQL就绪后执行差异SQL
+async fn execute_migration(
+    docker_manager: &DockerManager,
+    plan: &SchemaMigrationPlan,
+) -> Result<String> {
+    // 差异SQL按数据库带有 USE 语句，默认数据库的语句在最前
+    execute_sql(docker_manager, plan.uses_default_database, &plan.diff_sql).await
+}
+
+/// 等待MySQL就绪后在MySQL容器中执行SQL
+///
+/// SQL中有不带 USE 语句的部分时要求容器设置了默认数据库（`MYSQL_DATABASE`）。
+async fn execute_sql(
+    docker_manager: &DockerManager,
+    require_default_database: bool,
+    sql: &str,
+) -> Result<String> {
+    let service_name = docker_manager
+        .find_mysql_service()
+        .await?
+        .ok_or_else(|| DuckError::Docker("docker-compose.yml 中未找到MySQL服务".to_string()))?;
+
+    docker_manager.wait_for_mysql_ready(&service_name).await?;
+
+    if require_default_database
+        && docker_manager
+            .get_mysql_default_database(&service_name)
+            .await?
+            .is_none()
+    {
+        return Err(DuckError::Docker(format!(
+            "差异SQL需要在默认数据库中执行，但MySQL服务 {service_name} 没有设置 MYSQL_DATABASE 环境变量"
+        )));
+    }
+
+    info!("🚀 正在MySQL服务 {} 中执行SQL...", service_name);
+    docker_manager
+        .execute_mysql_sql(&service_name, None, sql)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_find_init_sql_files_skips_data_dir() {
+        let dir = tempdir().unwrap();
+        let init_dir = dir.path().join("config").join("mysql");
+        std::fs::create_dir_all(&init_dir).unwrap();
+        std::fs::create_dir_all(dir.path().join("data").join("mysql")).unwrap();
+        std::fs::write(init_dir.join("02_data.sql"), "").unwrap();
+        std::fs::write(init_dir.join("01_schema.sql"), "").unwrap();
+        std::fs::write(dir.path().join("data/mysql/dump.sql"), "").unwrap();
+
+        let files = find_init_sql_files(dir.path());
+        assert_eq!(
+            files,
+            vec![init_dir.join("01_schema.sql"), init_dir.join("02_data.sql")]
+        );
+    }
+
+    #[test]
+    fn test_load_init_sql_from_package() {
+        use std::io::Write;
+        use zip::write::SimpleFileOptions;
+
+        let dir = tempdir().unwrap();
+        let zip_path = dir.path().join("docker.zip");
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&zip_path).unwrap());
+        for (name, content) in [
+            ("docker/docker-compose.yml", "services: {}"),
+            (
+                "docker/config/mysql/02_data.sql",
+                "INSERT INTO users VALUES (1);",
+            ),
+            (
+                "docker/config/mysql/01_schema.sql",
+                "CREATE TABLE users (id INT);",
+            ),
+            ("docker/data/mysql/dump.sql", "DROP TABLE users;"),
+        ] {
+            writer
+                .start_file(name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let sql = load_init_sql_from_package(&zip_path).unwrap().unwrap();
+        assert_eq!(
+            sql,
+            "CREATE TABLE users (id INT);\nINSERT INTO users VALUES (1);\n"
+        );
+    }
+
+    #[test]
+    fn test_load_init_sql_from_patch() {
+        use crate::constants::upgrade;
+        use std::io::Write;
+        use zip::write::SimpleFileOptions;
+
+        let dir = tempdir().unwrap();
+        let docker_dir = dir.path().join("docker");
+        std::fs::create_dir_all(docker_dir.join("config/mysql")).unwrap();
+        std::fs::write(
+            docker_dir.join("config/mysql/01_schema.sql"),
+            "CREATE TABLE users (id INT);",
+        )
+        .unwrap();
+        std::fs::write(
+            docker_dir.join("config/mysql/02_data.sql"),
+            "INSERT INTO users VALUES (1);",
+        )
+        .unwrap();
+
+        // 补丁修改 01_schema.sql，删除 02_data.sql
+        let manifest = crate::service_patch::PatchManifest {
+            base_version: "1.0.0".to_string(),
+            target_version: "1.1.0".to_string(),
+            removed: vec!["config/mysql/02_data.sql".to_string()],
+        };
+        let full_files = BTreeMap::from([
+            ("docker-compose.yml".to_string(), "hash".to_string()),
+            ("config/mysql/01_schema.sql".to_string(), "hash".to_string()),
+        ]);
+        let patch_path = dir.path().join("patch.zip");
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&patch_path).unwrap());
+        writer
+            .start_file(
+                upgrade::PATCH_MANIFEST_FILE_NAME,
+                SimpleFileOptions::default(),
+            )
+            .unwrap();
+        writer
+            .write_all(&serde_json::to_vec(&manifest).unwrap())
+            .unwrap();
+        writer
+            .start_file("config/mysql/01_schema.sql", SimpleFileOptions::default())
+            .unwrap();
+        writer
+            .write_all(b"CREATE TABLE users (id INT, name VARCHAR(64));")
+            .unwrap();
+        writer.finish().unwrap();
+
+        let sql = load_init_sql_from_patch(&patch_path, &docker_dir, &full_files)
+            .unwrap()
+            .unwrap();
+        assert_eq!(sql, "CREATE TABLE users (id INT, name VARCHAR(64));\n");
+        // 已安装的服务包保持不变
+        assert_eq!(
+            std::fs::read_to_string(docker_dir.join("config/mysql/01_schema.sql")).unwrap(),
+            "CREATE TABLE users (id INT);"
+        );
+    }
+
+    #[test]
+    fn test_plan_schema_migration() {
+        let old_sql = "USE app;\nCREATE TABLE users (id INT PRIMARY KEY);";
+        let new_sql =
+            "USE app;\nCREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(64) NOT NULL);";
+
+        let plan = plan_schema_migration(Some(old_sql), Some(new_sql), "1.0.0", "1.1.0")
+            .unwrap()
+            .unwrap();
+        assert_eq!(plan.databases, vec!["app".to_string()]);
+        assert!(!plan.uses_default_database);
+        assert!(plan.diff_sql.contains("USE `app`;"));
+        assert!(
+            plan.sql_lines()
+                .iter()
+                .any(|s| s.contains("ADD COLUMN `name`"))
+        );
+        assert!(ensure_migration_allowed(&plan, false).is_ok());
+
+        // 无变化或首次部署时不需要迁移
+        assert!(
+            plan_schema_migration(Some(old_sql), Some(old_sql), "1.0.0", "1.1.0")
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            plan_schema_migration(None, Some(new_sql), "1.0.0", "1.1.0")
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_ensure_migration_allowed_rejects_data_loss() {
+        let old_sql = "CREATE TABLE users (id INT PRIMARY KEY, phone VARCHAR(20));";
+        let new_sql = "CREATE TABLE users (id INT PRIMARY KEY);";
+
+        let plan = plan_schema_migration(Some(old_sql), Some(new_sql), "1.0.0", "1.1.0")
+            .unwrap()
+            .unwrap();
+        assert!(plan.uses_default_database);
+        assert_eq!(plan.data_loss_statements().len(), 1);
+        assert!(ensure_migration_allowed(&plan, false).is_err());
+        assert!(ensure_migration_allowed(&plan, true).is_ok());
+    }
+
+    #[test]
+    fn test_rollback_script() {
+        let old_sql = "USE app;\nCREATE TABLE users (id INT PRIMARY KEY, phone VARCHAR(20));";
+        let new_sql = "USE app;\nCREATE TABLE users (id INT PRIMARY KEY, email VARCHAR(64));";
+
+        let plan = plan_schema_migration(Some(old_sql), Some(new_sql), "1.0.0", "1.1.0")
+            .unwrap()
+            .unwrap();
+        let script = plan.rollback_script().unwrap();
+
+        assert!(script.starts_with("-- 数据库架构回滚脚本: 1.1.0 -> 1.0.0\n"));
+        assert!(script.contains("USE `app`;"));
+        assert!(script.contains("ALTER TABLE `users` ADD COLUMN `phone` VARCHAR(20) AFTER `id`;"));
+        assert!(script.contains("ALTER TABLE `users` DROP COLUMN `email`;"));
+    }
+}
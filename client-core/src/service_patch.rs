@@ -0,0 +1,391 @@
+//! 服务增量补丁包
+//!
+//! 补丁包是一个zip文件：`patch.json` 描述补丁（基础版本、目标版本、删除的文件），其余条目是
+//! 相对基础版本新增或变化的文件（包括 `images/` 下的镜像tar包），路径为完整服务包中 `docker/`
+//! 目录下的相对路径。
+//!
+//! 应用补丁前先校验：补丁中的文件解压到 `docker/` 旁的暂存目录，补丁之外的文件直接在
+//! `docker/` 中校验，全部与目标版本服务清单中完整服务包的文件清单（`packages.full.files`）
+//! 一致后才替换到 `docker/` 目录。文件清单来自服务清单而不是补丁包本身，补丁包无法为自己担保。
+//! 任何不一致都返回错误，由调用方改用完整服务包。
+
+use crate::{DuckError, Result, constants::upgrade};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::{Component, Path};
+
+/// 补丁包描述（补丁包中的 `patch.json`）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PatchManifest {
+    /// 补丁适用的已安装版本
+    pub base_version: String,
+    /// 应用补丁后的版本
+    pub target_version: String,
+    /// 目标版本中已删除的文件
+    #[serde(default)]
+    pub removed: Vec<String>,
+}
+
+/// 计算文件的SHA-256
+fn file_sha256(path: &Path) -> Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// 是否为不含 `..` 的相对路径
+fn is_relative_path(path: &str) -> bool {
+    !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// 读取补丁包描述
+pub fn read_patch_manifest(patch_path: &Path) -> Result<PatchManifest> {
+    let mut archive = zip::ZipArchive::new(File::open(patch_path)?)?;
+    let entry = archive
+        .by_name(upgrade::PATCH_MANIFEST_FILE_NAME)
+        .map_err(|_| {
+            DuckError::Upgrade(format!(
+                "补丁包中没有 {}: {}",
+                upgrade::PATCH_MANIFEST_FILE_NAME,
+                patch_path.display()
+            ))
+        })?;
+    Ok(serde_json::from_reader(entry)?)
+}
+
+/// 把补丁包应用到服务目录，返回补丁描述
+///
+/// `installed_version` 必须与补丁的基础版本一致，`full_files` 是目标版本服务清单中完整服务包的
+/// 文件清单（相对 `docker/` 的路径 -> SHA-256）。校验失败时服务目录保持不变。
+pub async fn apply_patch(
+    patch_path: &Path,
+    docker_dir: &Path,
+    installed_version: &str,
+    full_files: &BTreeMap<String, String>,
+) -> Result<PatchManifest> {
+    let patch_path = patch_path.to_path_buf();
+    let docker_dir = docker_dir.to_path_buf();
+    let installed_version = installed_version.to_string();
+    let full_files = full_files.clone();
+
+    tokio::task::spawn_blocking(move || {
+        apply_patch_blocking(&patch_path, &docker_dir, &installed_version, &full_files)
+    })
+    .await?
+}
+
+fn apply_patch_blocking(
+    patch_path: &Path,
+    docker_dir: &Path,
+    installed_version: &str,
+    full_files: &BTreeMap<String, String>,
+) -> Result<PatchManifest> {
+    let manifest = read_patch_manifest(patch_path)?;
+    if manifest.base_version.trim_start_matches('v') != installed_version.trim_start_matches('v') {
+        return Err(DuckError::Upgrade(format!(
+            "补丁基础版本 {} 与已安装版本 {} 不一致",
+            manifest.base_version, installed_version
+        )));
+    }
+    if full_files.is_empty() {
+        return Err(DuckError::Upgrade(
+            "服务清单中没有完整服务包的文件清单，无法校验补丁".to_string(),
+        ));
+    }
+    if let Some(path) = manifest.removed.iter().find(|path| !is_relative_path(path)) {
+        return Err(DuckError::Upgrade(format!("补丁描述中的路径无效: {path}")));
+    }
+    if let Some(path) = full_files.keys().find(|path| !is_relative_path(path)) {
+        return Err(DuckError::Upgrade(format!(
+            "完整服务包文件清单中的路径无效: {path}"
+        )));
+    }
+    if let Some(path) = manifest
+        .removed
+        .iter()
+        .find(|path| full_files.contains_key(*path))
+    {
+        return Err(DuckError::Upgrade(format!(
+            "补丁删除的文件在完整服务包中: {path}"
+        )));
+    }
+
+    let dir_name = docker_dir
+        .file_name()
+        .ok_or_else(|| DuckError::Upgrade(format!("无效的服务目录: {}", docker_dir.display())))?
+        .to_string_lossy();
+    let staging_dir =
+        docker_dir.with_file_name(format!(".{dir_name}.{}", upgrade::PATCH_STAGING_SUFFIX));
+    if staging_dir.exists() {
+        std::fs::remove_dir_all(&staging_dir)?;
+    }
+
+    let result = stage_and_verify(patch_path, docker_dir, &staging_dir, full_files).and_then(
+        |patched_files| {
+            for path in &patched_files {
+                let target = docker_dir.join(path);
+                if let Some(parent) = target.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::rename(staging_dir.join(path), &target)?;
+            }
+            for path in &manifest.removed {
+                let target = docker_dir.join(path);
+                if target.is_file() {
+                    std::fs::remove_file(&target)?;
+                }
+            }
+            Ok(())
+        },
+    );
+
+    if staging_dir.exists() {
+        std::fs::remove_dir_all(&staging_dir)?;
+    }
+    result?;
+    Ok(manifest)
+}
+
+/// 把补丁中的文件解压到暂存目录，并按完整服务包的文件清单校验补丁后的服务目录
+///
+/// 返回补丁中包含的文件。
+fn stage_and_verify(
+    patch_path: &Path,
+    docker_dir: &Path,
+    staging_dir: &Path,
+    full_files: &BTreeMap<String, String>,
+) -> Result<Vec<String>> {
+    let mut archive = zip::ZipArchive::new(File::open(patch_path)?)?;
+    let mut patched_files = Vec::new();
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        if entry.is_dir() {
+            continue;
+        }
+        // 拒绝绝对路径和 `..`
+        let path = entry
+            .enclosed_name()
+            .map(|path| path.to_string_lossy().replace('\\', "/"))
+            .ok_or_else(|| DuckError::Upgrade(format!("补丁包中的路径无效: {}", entry.name())))?;
+        if path == upgrade::PATCH_MANIFEST_FILE_NAME {
+            continue;
+        }
+        let Some(expected_hash) = full_files.get(&path) else {
+            return Err(DuckError::Upgrade(format!(
+                "补丁中的文件不在完整服务包清单中: {path}"
+            )));
+        };
+
+        let staged = staging_dir.join(&path);
+        if let Some(parent) = staged.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::io::copy(&mut entry, &mut File::create(&staged)?)?;
+        verify_file(&staged, &path, expected_hash)?;
+        patched_files.push(path);
+    }
+
+    // 补丁之外的文件必须与已安装版本中的一致
+    for (path, expected_hash) in full_files {
+        if !patched_files.contains(path) {
+            verify_file(&docker_dir.join(path), path, expected_hash)?;
+        }
+    }
+
+    Ok(patched_files)
+}
+
+/// 校验文件的SHA-256与清单一致
+fn verify_file(path: &Path, archive_path: &str, expected_hash: &str) -> Result<()> {
+    if !path.is_file() {
+        return Err(DuckError::Upgrade(format!(
+            "补丁后缺少文件: {archive_path}"
+        )));
+    }
+    let actual_hash = file_sha256(path)?;
+    if !actual_hash.eq_ignore_ascii_case(expected_hash) {
+        return Err(DuckError::Upgrade(format!(
+            "补丁后文件与完整服务包不一致: {archive_path}"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+    use zip::write::SimpleFileOptions;
+
+    fn sha256(content: &str) -> String {
+        format!("{:x}", Sha256::digest(content.as_bytes()))
+    }
+
+    fn write_patch(path: &Path, manifest: &PatchManifest, files: &[(&str, &str)]) {
+        let mut writer = zip::ZipWriter::new(File::create(path).unwrap());
+        writer
+            .start_file(
+                upgrade::PATCH_MANIFEST_FILE_NAME,
+                SimpleFileOptions::default(),
+            )
+            .unwrap();
+        writer
+            .write_all(&serde_json::to_vec(manifest).unwrap())
+            .unwrap();
+        for (name, content) in files {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    fn installed_docker_dir(root: &Path) -> PathBuf {
+        let docker_dir = root.join("docker");
+        std::fs::create_dir_all(docker_dir.join("images")).unwrap();
+        std::fs::create_dir_all(docker_dir.join("data/mysql")).unwrap();
+        std::fs::write(docker_dir.join("docker-compose.yml"), "services: v1").unwrap();
+        std::fs::write(docker_dir.join("images/backend.tar"), "backend v1").unwrap();
+        std::fs::write(docker_dir.join("images/old.tar"), "old").unwrap();
+        std::fs::write(docker_dir.join("images/frontend.tar"), "frontend").unwrap();
+        std::fs::write(docker_dir.join("data/mysql/ibdata1"), "data").unwrap();
+        docker_dir
+    }
+
+    fn target_manifest() -> PatchManifest {
+        PatchManifest {
+            base_version: "1.0.0".to_string(),
+            target_version: "1.1.0".to_string(),
+            removed: vec!["images/old.tar".to_string()],
+        }
+    }
+
+    /// 目标版本服务清单中完整服务包的文件清单
+    fn full_files() -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("docker-compose.yml".to_string(), sha256("services: v2")),
+            ("images/backend.tar".to_string(), sha256("backend v2")),
+            ("images/frontend.tar".to_string(), sha256("frontend")),
+            ("config/new.conf".to_string(), sha256("new")),
+        ])
+    }
+
+    #[tokio::test]
+    async fn test_apply_patch() {
+        let dir = tempdir().unwrap();
+        let docker_dir = installed_docker_dir(dir.path());
+        let patch_path = dir.path().join("docker-patch.zip");
+        write_patch(
+            &patch_path,
+            &target_manifest(),
+            &[
+                ("docker-compose.yml", "services: v2"),
+                ("images/backend.tar", "backend v2"),
+                ("config/new.conf", "new"),
+            ],
+        );
+
+        // 基础版本不一致时不应用
+        assert!(
+            apply_patch(&patch_path, &docker_dir, "0.9.0", &full_files())
+                .await
+                .is_err()
+        );
+
+        let manifest = apply_patch(&patch_path, &docker_dir, "1.0.0", &full_files())
+            .await
+            .unwrap();
+        assert_eq!(manifest.target_version, "1.1.0");
+
+        let read = |path: &str| std::fs::read_to_string(docker_dir.join(path)).unwrap();
+        assert_eq!(read("docker-compose.yml"), "services: v2");
+        assert_eq!(read("images/backend.tar"), "backend v2");
+        assert_eq!(read("config/new.conf"), "new");
+        assert_eq!(read("images/frontend.tar"), "frontend");
+        assert!(!docker_dir.join("images/old.tar").exists());
+        // 运行时数据不受影响
+        assert_eq!(read("data/mysql/ibdata1"), "data");
+        assert!(!dir.path().join(".docker.patch-staging").exists());
+    }
+
+    #[tokio::test]
+    async fn test_mismatch_leaves_docker_dir_unchanged() {
+        let dir = tempdir().unwrap();
+        let docker_dir = installed_docker_dir(dir.path());
+        // 已安装的文件被修改过，补丁后与完整服务包不一致
+        std::fs::write(docker_dir.join("images/frontend.tar"), "modified").unwrap();
+        let patch_path = dir.path().join("docker-patch.zip");
+        write_patch(
+            &patch_path,
+            &target_manifest(),
+            &[
+                ("docker-compose.yml", "services: v2"),
+                ("images/backend.tar", "backend v2"),
+                ("config/new.conf", "new"),
+            ],
+        );
+
+        assert!(
+            apply_patch(&patch_path, &docker_dir, "1.0.0", &full_files())
+                .await
+                .is_err()
+        );
+        let read = |path: &str| std::fs::read_to_string(docker_dir.join(path)).unwrap();
+        assert_eq!(read("docker-compose.yml"), "services: v1");
+        assert!(docker_dir.join("images/old.tar").exists());
+        assert!(!docker_dir.join("config/new.conf").exists());
+
+        // 补丁中的文件内容与清单不一致
+        std::fs::write(docker_dir.join("images/frontend.tar"), "frontend").unwrap();
+        write_patch(
+            &patch_path,
+            &target_manifest(),
+            &[
+                ("docker-compose.yml", "services: broken"),
+                ("images/backend.tar", "backend v2"),
+                ("config/new.conf", "new"),
+            ],
+        );
+        assert!(
+            apply_patch(&patch_path, &docker_dir, "1.0.0", &full_files())
+                .await
+                .is_err()
+        );
+        assert_eq!(read("docker-compose.yml"), "services: v1");
+
+        // 补丁本身完整，但与服务清单中的完整服务包不一致
+        write_patch(
+            &patch_path,
+            &target_manifest(),
+            &[
+                ("docker-compose.yml", "services: v2"),
+                ("images/backend.tar", "backend v2"),
+                ("config/new.conf", "new"),
+            ],
+        );
+        let mut full_files = full_files();
+        full_files.insert("config/new.conf".to_string(), sha256("new v2"));
+        assert!(
+            apply_patch(&patch_path, &docker_dir, "1.0.0", &full_files)
+                .await
+                .is_err()
+        );
+        // 服务清单没有文件清单时不应用补丁
+        assert!(
+            apply_patch(&patch_path, &docker_dir, "1.0.0", &BTreeMap::new())
+                .await
+                .is_err()
+        );
+        assert_eq!(read("docker-compose.yml"), "services: v1");
+    }
+}
@@ -1,265 +1,872 @@
-use super::generator::{generate_column_sql, generate_create_table_sql};
-use super::types::{TableColumn, TableDefinition, TableIndex};
+use super::renderer::render_mysql_diff;
+use super::types::{
+    ColumnPosition, DatabaseDefinition, DatabaseDiff, DatabaseOptions, DefinitionChange,
+    DiffStatement, NameChange, ObjectDefinition, RenameHints, SchemaDefinition, SchemaDiff,
+    SeedDataDiff, SeedRow, TableColumn, TableDefinition, TableDiff,
+};
 use crate::error::DuckError;
 use std::collections::HashMap;
-use tracing::info;
+use tracing::{info, warn};
 
 /// 生成MySQL差异SQL
 pub fn generate_mysql_diff(
     from_tables: &HashMap<String, TableDefinition>,
     to_tables: &HashMap<String, TableDefinition>,
 ) -> Result<String, DuckError> {
-    let mut diff_sql = Vec::new();
-
-    // 添加注释头
-    diff_sql.push("-- 数据库架构差异SQL".to_string());
-    diff_sql.push(format!(
-        "-- 生成时间: {}",
-        chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC")
-    ));
-    diff_sql.push("".to_string());
-
-    // 1. 检查新增的表
-    for (table_name, table_def) in to_tables {
-        if !from_tables.contains_key(table_name) {
-            info!("发现新增表: {}", table_name);
-            diff_sql.push(format!("-- 新增表: {table_name}"));
-            diff_sql.push(generate_create_table_sql(table_def));
-            diff_sql.push("".to_string());
+    generate_mysql_diff_with_hints(from_tables, to_tables, &RenameHints::default())
+        .map(|(diff_sql, _)| diff_sql)
+}
+
+/// 生成MySQL差异SQL，同时返回每条语句的安全级别
+///
+/// 所有表视为同一个（默认）数据库；先计算结构化的架构差异（见 [`diff_schemas`]），再渲染为SQL。
+pub fn generate_mysql_diff_with_hints(
+    from_tables: &HashMap<String, TableDefinition>,
+    to_tables: &HashMap<String, TableDefinition>,
+    rename_hints: &RenameHints,
+) -> Result<(String, Vec<DiffStatement>), DuckError> {
+    let diff = diff_schemas(
+        &SchemaDefinition::from_tables(from_tables.clone()),
+        &SchemaDefinition::from_tables(to_tables.clone()),
+        rename_hints,
+    );
+    Ok(render_mysql_diff(&diff))
+}
+
+/// 生成回滚（down）差异SQL：把目标结构还原为原结构
+///
+/// 所有表视为同一个（默认）数据库，见 [`diff_schemas_down`]。
+pub fn generate_mysql_down_diff(
+    from_tables: &HashMap<String, TableDefinition>,
+    to_tables: &HashMap<String, TableDefinition>,
+    rename_hints: &RenameHints,
+) -> Result<(String, Vec<DiffStatement>), DuckError> {
+    let diff = diff_schemas_down(
+        &SchemaDefinition::from_tables(from_tables.clone()),
+        &SchemaDefinition::from_tables(to_tables.clone()),
+        rename_hints,
+    );
+    Ok(render_mysql_diff(&diff))
+}
+
+/// 计算结构化的架构差异
+///
+/// 按数据库分别比较表结构（见 [`diff_tables`]），并比较 `CREATE DATABASE` 定义：
+/// - 新脚本新建的数据库记录为新增数据库
+/// - 旧脚本创建、新脚本中不再出现的数据库记录为删除数据库（不再单独删除其中的表）
+/// - 字符集或排序规则变化的数据库记录为修改数据库
+///
+/// 重命名提示按表名匹配，适用于所有数据库。
+pub fn diff_schemas(
+    from: &SchemaDefinition,
+    to: &SchemaDefinition,
+    rename_hints: &RenameHints,
+) -> SchemaDiff {
+    diff_schemas_with(from, to, |_| rename_hints.clone())
+}
+
+/// 计算回滚（down）差异：把目标结构还原为原结构
+///
+/// 使用与升级差异相同的重命名识别结果（反向作为提示），保证回滚时按原名称改回，
+/// 删除的列、表按原定义重建（数据需要从备份恢复），修改的列、索引恢复为原定义。
+pub fn diff_schemas_down(
+    from: &SchemaDefinition,
+    to: &SchemaDefinition,
+    rename_hints: &RenameHints,
+) -> SchemaDiff {
+    let up_diff = diff_schemas(from, to, rename_hints);
+
+    let mut reverse_hints: HashMap<Option<String>, RenameHints> = HashMap::new();
+    for database_diff in &up_diff.databases {
+        let hints = reverse_hints
+            .entry(database_diff.database.clone())
+            .or_default();
+        for rename in &database_diff.renamed_tables {
+            hints.tables.insert(rename.to.clone(), rename.from.clone());
+        }
+        for table_diff in &database_diff.modified_tables {
+            // 回滚时列重命名发生在表改回原名之后
+            let original_table = table_diff.renamed_from.as_ref().unwrap_or(&table_diff.name);
+            for rename in &table_diff.renamed_columns {
+                hints
+                    .columns
+                    .entry(original_table.clone())
+                    .or_default()
+                    .insert(rename.to.clone(), rename.from.clone());
+            }
         }
     }
 
-    // 2. 检查删除的表
-    for table_name in from_tables.keys() {
-        if !to_tables.contains_key(table_name) {
-            info!("发现删除表: {}", table_name);
-            diff_sql.push(format!("-- 删除表: {table_name}"));
-            diff_sql.push(format!("DROP TABLE IF EXISTS `{table_name}`;"));
-            diff_sql.push("".to_string());
+    diff_schemas_with(to, from, |database| {
+        reverse_hints
+            .get(&database.map(|name| name.to_string()))
+            .cloned()
+            .unwrap_or_default()
+    })
+}
+
+/// 计算结构化的架构差异，每个数据库使用各自的重命名提示
+fn diff_schemas_with(
+    from: &SchemaDefinition,
+    to: &SchemaDefinition,
+    rename_hints_for: impl Fn(Option<&str>) -> RenameHints,
+) -> SchemaDiff {
+    let mut diff = SchemaDiff::default();
+    let no_tables = HashMap::new();
+
+    for to_database in &to.databases {
+        let database_name = to_database.name.as_deref();
+        let from_database = from.database(database_name);
+
+        match (
+            from_database.and_then(|database| database.options.as_ref()),
+            &to_database.options,
+        ) {
+            (None, Some(options)) => {
+                info!("发现新增数据库: {}", options.name);
+                diff.added_databases.push(options.clone());
+            }
+            (Some(before), Some(after)) if database_options_changed(before, after) => {
+                info!("发现数据库选项变化: {}", after.name);
+                diff.modified_databases.push(DefinitionChange {
+                    before: before.clone(),
+                    after: after.clone(),
+                });
+            }
+            _ => {}
+        }
+
+        let from_tables = from_database.map_or(&no_tables, |database| &database.tables);
+        let mut database_diff = diff_tables(
+            from_tables,
+            &to_database.tables,
+            &rename_hints_for(database_name),
+        );
+        database_diff.database = to_database.name.clone();
+        diff_objects(
+            from_database.map_or(&[][..], |database| &database.objects),
+            &to_database.objects,
+            &mut database_diff,
+        );
+        diff_seed_data(from_database, to_database, &mut database_diff);
+        if !database_diff.is_empty() {
+            diff.databases.push(database_diff);
         }
     }
 
-    // 3. 检查修改的表
-    for (table_name, new_table_def) in to_tables {
-        if let Some(old_table_def) = from_tables.get(table_name) {
-            let table_diffs = generate_table_diff(old_table_def, new_table_def);
-            if !table_diffs.is_empty() {
-                info!("发现表结构变化: {}", table_name);
-                diff_sql.push(format!("-- 修改表: {table_name}"));
-                diff_sql.extend(table_diffs);
-                diff_sql.push("".to_string());
+    for from_database in &from.databases {
+        let database_name = from_database.name.as_deref();
+        if to.database(database_name).is_some() {
+            continue;
+        }
+
+        match &from_database.options {
+            // 删除数据库时其中的表一并删除
+            Some(options) => {
+                info!("发现删除数据库: {}", options.name);
+                diff.removed_databases.push(options.clone());
+            }
+            // 只通过 USE 引用的数据库不一定由脚本创建，只删除其中的表
+            None => {
+                let mut database_diff = diff_tables(
+                    &from_database.tables,
+                    &no_tables,
+                    &rename_hints_for(database_name),
+                );
+                database_diff.database = from_database.name.clone();
+                diff_objects(&from_database.objects, &[], &mut database_diff);
+                if !database_diff.is_empty() {
+                    diff.databases.push(database_diff);
+                }
             }
         }
     }
 
-    let result = diff_sql.join("\n");
+    // 默认数据库的语句必须在任何 USE 之前执行
+    diff.databases
+        .sort_by_key(|database_diff| database_diff.database.is_some());
+
+    diff
+}
+
+/// 判断数据库的字符集或排序规则是否变化
+fn database_options_changed(before: &DatabaseOptions, after: &DatabaseOptions) -> bool {
+    option_changed(&before.charset, &after.charset)
+        || option_changed(&before.collation, &after.collation)
+}
 
-    // 如果只有注释头，说明没有实际差异
-    let meaningful_lines: Vec<&str> = result
-        .lines()
-        .filter(|line| !line.trim().is_empty() && !line.trim().starts_with("--"))
+/// 计算使列顺序与新表定义一致所需的位置调整
+///
+/// 按新表的列顺序模拟执行：删除的列不影响其余列的相对顺序，依次检查每一列是否紧跟在
+/// 目标的前一列之后，新增的列和位置不对的列记录目标位置（`FIRST` 或 `AFTER 前一列`）。
+/// 返回只需要调整位置（定义未变）的列，以及按执行顺序排列的列位置。
+fn resolve_column_positions(
+    old_table: &TableDefinition,
+    new_table: &TableDefinition,
+    modified_columns: &[DefinitionChange<TableColumn>],
+) -> (Vec<TableColumn>, Vec<ColumnPosition>) {
+    let mut current: Vec<&str> = old_table
+        .columns
+        .iter()
+        .map(|column| column.name.as_str())
+        .filter(|name| new_table.columns.iter().any(|column| column.name == *name))
         .collect();
+    let mut moved_columns = Vec::new();
+    let mut column_positions = Vec::new();
 
-    if meaningful_lines.is_empty() {
-        info!("没有发现实际的表结构差异");
-        return Ok(String::new());
+    for (index, column) in new_table.columns.iter().enumerate() {
+        let after = index
+            .checked_sub(1)
+            .map(|previous| new_table.columns[previous].name.as_str());
+        let current_index = current.iter().position(|name| *name == column.name);
+        // 前面的列已按目标顺序排好，当前列应该正好位于 index 处
+        if current_index == Some(index) {
+            continue;
+        }
+
+        if let Some(current_index) = current_index {
+            current.remove(current_index);
+            if !modified_columns
+                .iter()
+                .any(|change| change.after.name == column.name)
+            {
+                moved_columns.push(column.clone());
+            }
+        }
+        current.insert(index, &column.name);
+        column_positions.push(ColumnPosition {
+            column: column.name.clone(),
+            after: after.map(|name| name.to_string()),
+        });
     }
 
-    Ok(result)
+    (moved_columns, column_positions)
 }
 
-/// 生成表差异SQL
-pub fn generate_table_diff(
-    old_table: &TableDefinition,
-    new_table: &TableDefinition,
-) -> Vec<String> {
-    let mut diffs = Vec::new();
+/// 比较视图、触发器、存储过程和函数（按规范化后的定义比较）
+fn diff_objects(
+    from_objects: &[ObjectDefinition],
+    to_objects: &[ObjectDefinition],
+    diff: &mut DatabaseDiff,
+) {
+    let find = |objects: &'_ [ObjectDefinition], object: &ObjectDefinition| {
+        objects
+            .iter()
+            .find(|other| {
+                other.kind == object.kind && other.name.eq_ignore_ascii_case(&object.name)
+            })
+            .cloned()
+    };
+
+    for new_object in to_objects {
+        match find(from_objects, new_object) {
+            None => {
+                info!(
+                    "发现新增{}: {}",
+                    new_object.kind.description(),
+                    new_object.name
+                );
+                diff.added_objects.push(new_object.clone());
+            }
+            Some(old_object)
+                if normalize_object_definition(&old_object.definition)
+                    != normalize_object_definition(&new_object.definition) =>
+            {
+                info!(
+                    "发现{}定义变化: {}",
+                    new_object.kind.description(),
+                    new_object.name
+                );
+                diff.modified_objects.push(DefinitionChange {
+                    before: old_object,
+                    after: new_object.clone(),
+                });
+            }
+            Some(_) => {}
+        }
+    }
+
+    for old_object in from_objects {
+        if find(to_objects, old_object).is_none() {
+            info!(
+                "发现删除{}: {}",
+                old_object.kind.description(),
+                old_object.name
+            );
+            diff.removed_objects.push(old_object.clone());
+        }
+    }
+}
+
+/// 比较参考数据表中的数据（只比较新脚本中标记为参考数据的表）
+///
+/// 按新表定义的主键匹配行：新主键记录为新增，值变化的行记录为更新，新脚本中不再包含的行
+/// 记录为删除（只保留新表中仍存在的列，用于生成删除条件）。表被重命名时按原表名查找旧数据。
+fn diff_seed_data(
+    from_database: Option<&DatabaseDefinition>,
+    to_database: &DatabaseDefinition,
+    diff: &mut DatabaseDiff,
+) {
+    for seed_data in &to_database.seed_data {
+        let Some(table) = to_database.tables.get(&seed_data.table) else {
+            warn!("参考数据表 {} 没有表定义，跳过数据比较", seed_data.table);
+            continue;
+        };
+        let Some(key_columns) = table
+            .indexes
+            .iter()
+            .find(|index| index.is_primary)
+            .map(|index| index.columns.clone())
+        else {
+            warn!("参考数据表 {} 没有主键，跳过数据比较", seed_data.table);
+            continue;
+        };
 
-    // 比较列差异
-    let column_diffs = generate_column_diffs(old_table, new_table);
-    diffs.extend(column_diffs);
+        let old_table = diff
+            .renamed_tables
+            .iter()
+            .find(|rename| rename.to == seed_data.table)
+            .map_or(seed_data.table.as_str(), |rename| rename.from.as_str());
+        let old_rows = from_database
+            .and_then(|database| {
+                database
+                    .seed_data
+                    .iter()
+                    .find(|old_seed_data| old_seed_data.table == old_table)
+            })
+            .map_or(&[][..], |old_seed_data| &old_seed_data.rows);
 
-    // 比较索引差异
-    let index_diffs = generate_index_diffs(old_table, new_table);
-    diffs.extend(index_diffs);
+        let row_key = |row: &SeedRow| -> Option<Vec<String>> {
+            key_columns
+                .iter()
+                .map(|column| row.get(column).cloned())
+                .collect()
+        };
+        let keyed_rows = |rows: &[SeedRow]| -> Vec<(Vec<String>, SeedRow)> {
+            rows.iter()
+                .filter_map(|row| match row_key(row) {
+                    Some(key) => Some((key, row.clone())),
+                    None => {
+                        warn!("参考数据表 {} 中有缺少主键列的行，已忽略", seed_data.table);
+                        None
+                    }
+                })
+                .collect()
+        };
+        let old_rows = keyed_rows(old_rows);
+        let new_rows = keyed_rows(&seed_data.rows);
 
-    diffs
+        let mut seed_diff = SeedDataDiff {
+            table: seed_data.table.clone(),
+            key_columns: key_columns.clone(),
+            ..Default::default()
+        };
+        for (key, new_row) in &new_rows {
+            match old_rows.iter().find(|(old_key, _)| old_key == key) {
+                None => seed_diff.inserted_rows.push(new_row.clone()),
+                Some((_, old_row)) if old_row != new_row => {
+                    seed_diff.updated_rows.push(DefinitionChange {
+                        before: old_row.clone(),
+                        after: new_row.clone(),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        for (key, old_row) in &old_rows {
+            if !new_rows.iter().any(|(new_key, _)| new_key == key) {
+                seed_diff.deleted_rows.push(
+                    old_row
+                        .iter()
+                        .filter(|(column, _)| table.columns.iter().any(|c| &c.name == *column))
+                        .map(|(column, value)| (column.clone(), value.clone()))
+                        .collect(),
+                );
+            }
+        }
+
+        if !seed_diff.is_empty() {
+            info!(
+                "发现参考数据变化: {} (新增 {} 行，更新 {} 行，删除 {} 行)",
+                seed_diff.table,
+                seed_diff.inserted_rows.len(),
+                seed_diff.updated_rows.len(),
+                seed_diff.deleted_rows.len()
+            );
+            diff.seed_data.push(seed_diff);
+        }
+    }
 }
 
-/// 生成列差异SQL
-fn generate_column_diffs(old_table: &TableDefinition, new_table: &TableDefinition) -> Vec<String> {
-    let mut diffs = Vec::new();
-    let table_name = &new_table.name;
+/// 规范化对象定义，忽略不影响语义的差异：
+/// 空白和换行、标识符的反引号、关键字大小写（引号内的字符串保持不变）、
+/// `OR REPLACE`/`IF NOT EXISTS` 以及 `DEFINER` 子句
+pub(super) fn normalize_object_definition(definition: &str) -> String {
+    let mut normalized = String::new();
+    let mut chars = definition.chars().peekable();
+    let mut quote: Option<char> = None;
+    let mut pending_space = false;
 
-    // 创建列名到列定义的映射
-    let old_columns: HashMap<String, &TableColumn> = old_table
-        .columns
+    while let Some(ch) = chars.next() {
+        if let Some(quote_char) = quote {
+            normalized.push(ch);
+            if ch == '\\' {
+                if let Some(next) = chars.next() {
+                    normalized.push(next);
+                }
+            } else if ch == quote_char {
+                quote = None;
+            }
+            continue;
+        }
+
+        if ch.is_whitespace() {
+            pending_space = true;
+            continue;
+        }
+        if ch == '`' {
+            continue;
+        }
+
+        // 标点符号两侧的空白没有意义
+        let is_punctuation = |c: char| "(),;=<>+-*/.".contains(c);
+        if pending_space
+            && !normalized.is_empty()
+            && !is_punctuation(ch)
+            && !normalized.ends_with(is_punctuation)
+        {
+            normalized.push(' ');
+        }
+        pending_space = false;
+
+        if ch == '\'' || ch == '"' {
+            quote = Some(ch);
+            normalized.push(ch);
+        } else {
+            normalized.extend(ch.to_lowercase());
+        }
+    }
+
+    strip_definition_header(&normalized)
+}
+
+/// 去掉规范化后的定义头中不影响语义的部分（`OR REPLACE`、`DEFINER`、`IF NOT EXISTS`）
+fn strip_definition_header(normalized: &str) -> String {
+    let words: Vec<&str> = normalized.split(' ').collect();
+    let Some(kind_position) = words
         .iter()
-        .map(|c| (c.name.clone(), c))
-        .collect();
-    let new_columns: HashMap<String, &TableColumn> = new_table
-        .columns
+        .position(|word| matches!(*word, "view" | "trigger" | "procedure" | "function"))
+    else {
+        return normalized.to_string();
+    };
+
+    let mut result: Vec<&str> = words[..kind_position]
         .iter()
-        .map(|c| (c.name.clone(), c))
+        .filter(|word| !matches!(**word, "or" | "replace") && !word.starts_with("definer="))
+        .copied()
         .collect();
+    result.push(words[kind_position]);
+
+    let mut rest = &words[kind_position + 1..];
+    if rest.starts_with(&["if", "not", "exists"]) {
+        rest = &rest[3..];
+    }
+    result.extend(rest);
+    result.join(" ")
+}
 
-    // 检查新增的列
-    for (col_name, col_def) in &new_columns {
-        if !old_columns.contains_key(col_name) {
-            diffs.push(format!(
-                "ALTER TABLE `{}` ADD COLUMN {};",
-                table_name,
-                generate_column_sql(col_def)
-            ));
+/// 计算同一个数据库内的表结构差异
+///
+/// 重命名的表和列根据注释提示和结构相同（列定义、位置）的启发式规则识别，
+/// 记录为重命名而不是删除后新增，避免丢失数据。
+/// 列、索引和约束按在表定义中的位置排序，保证结果稳定。
+pub fn diff_tables(
+    from_tables: &HashMap<String, TableDefinition>,
+    to_tables: &HashMap<String, TableDefinition>,
+    rename_hints: &RenameHints,
+) -> DatabaseDiff {
+    // 识别重命名的表和列，并将旧表结构按新名称对齐
+    let RenameResolution {
+        renamed_from,
+        table_renames,
+        column_renames,
+    } = resolve_renames(from_tables, to_tables, rename_hints);
+
+    let mut diff = DatabaseDiff {
+        renamed_tables: table_renames
+            .iter()
+            .map(|(old_name, new_name)| NameChange {
+                from: old_name.clone(),
+                to: new_name.clone(),
+            })
+            .collect(),
+        ..Default::default()
+    };
+    let mut table_names: Vec<&String> = to_tables.keys().collect();
+    table_names.sort();
+    for table_name in table_names {
+        let new_table = &to_tables[table_name];
+        let Some(old_table) = renamed_from.get(table_name) else {
+            info!("发现新增表: {}", table_name);
+            diff.added_tables.push(new_table.clone());
+            continue;
+        };
+
+        let mut table_diff = diff_table(old_table, new_table);
+        table_diff.renamed_from = table_renames
+            .iter()
+            .find(|(_, new_name)| new_name == table_name)
+            .map(|(old_name, _)| old_name.clone());
+        table_diff.renamed_columns = column_renames
+            .iter()
+            .filter(|(renamed_table, _, _)| renamed_table == table_name)
+            .map(|(_, old_column, new_column)| NameChange {
+                from: old_column.clone(),
+                to: new_column.clone(),
+            })
+            .collect();
+
+        if !table_diff.is_empty() {
+            info!("发现表结构变化: {}", table_name);
+            diff.modified_tables.push(table_diff);
         }
     }
 
-    // 检查删除的列
-    for col_name in old_columns.keys() {
-        if !new_columns.contains_key(col_name) {
-            diffs.push(format!(
-                "ALTER TABLE `{table_name}` DROP COLUMN `{col_name}`;"
-            ));
+    let mut dropped_tables: Vec<&String> = renamed_from
+        .keys()
+        .filter(|name| !to_tables.contains_key(*name))
+        .collect();
+    dropped_tables.sort();
+    for table_name in dropped_tables {
+        info!("发现删除表: {}", table_name);
+        // 删除的表没有被重命名，使用原始定义
+        diff.removed_tables.push(from_tables[table_name].clone());
+    }
+
+    diff
+}
+
+/// 重命名识别结果
+struct RenameResolution {
+    /// 按新名称对齐后的旧表结构
+    renamed_from: HashMap<String, TableDefinition>,
+    /// (旧表名, 新表名)
+    table_renames: Vec<(String, String)>,
+    /// (新表名, 旧列名, 新列名)
+    column_renames: Vec<(String, String, String)>,
+}
+
+/// 识别重命名的表和列
+fn resolve_renames(
+    from_tables: &HashMap<String, TableDefinition>,
+    to_tables: &HashMap<String, TableDefinition>,
+    rename_hints: &RenameHints,
+) -> RenameResolution {
+    let table_renames = detect_table_renames(from_tables, to_tables, rename_hints);
+    let mut renamed_from = from_tables.clone();
+    for (old_name, new_name) in &table_renames {
+        info!("发现重命名表: {} -> {}", old_name, new_name);
+        rename_table(&mut renamed_from, old_name, new_name);
+    }
+
+    let mut kept_tables: Vec<&String> = to_tables
+        .keys()
+        .filter(|name| renamed_from.contains_key(*name))
+        .collect();
+    kept_tables.sort();
+
+    let mut column_renames = Vec::new();
+    for table_name in kept_tables {
+        let renames = detect_column_renames(
+            &renamed_from[table_name],
+            &to_tables[table_name],
+            rename_hints.columns.get(table_name),
+        );
+        for (old_column, new_column) in renames {
+            info!(
+                "发现重命名列: {}.{} -> {}",
+                table_name, old_column, new_column
+            );
+            rename_column(&mut renamed_from, table_name, &old_column, &new_column);
+            column_renames.push((table_name.clone(), old_column, new_column));
         }
     }
 
-    // 检查修改的列
-    for (col_name, new_col) in &new_columns {
-        if let Some(old_col) = old_columns.get(col_name) {
-            if old_col != new_col {
-                diffs.push(format!(
-                    "ALTER TABLE `{}` MODIFY COLUMN {};",
-                    table_name,
-                    generate_column_sql(new_col)
-                ));
+    RenameResolution {
+        renamed_from,
+        table_renames,
+        column_renames,
+    }
+}
+
+/// 识别重命名的表：优先使用注释提示，其次匹配列和索引完全相同且一一对应的删除表和新增表
+fn detect_table_renames(
+    from_tables: &HashMap<String, TableDefinition>,
+    to_tables: &HashMap<String, TableDefinition>,
+    rename_hints: &RenameHints,
+) -> Vec<(String, String)> {
+    let mut renames = Vec::new();
+
+    let mut hinted: Vec<(&String, &String)> = rename_hints.tables.iter().collect();
+    hinted.sort();
+    for (old_name, new_name) in hinted {
+        if from_tables.contains_key(old_name)
+            && !to_tables.contains_key(old_name)
+            && to_tables.contains_key(new_name)
+            && !from_tables.contains_key(new_name)
+        {
+            renames.push((old_name.clone(), new_name.clone()));
+        } else {
+            warn!("忽略无效的重命名表提示: {} -> {}", old_name, new_name);
+        }
+    }
+
+    let mut dropped_tables: Vec<&String> = from_tables
+        .keys()
+        .filter(|name| !to_tables.contains_key(*name))
+        .filter(|name| !renames.iter().any(|(old_name, _)| old_name == *name))
+        .collect();
+    dropped_tables.sort();
+    let mut added_tables: Vec<&String> = to_tables
+        .keys()
+        .filter(|name| !from_tables.contains_key(*name))
+        .filter(|name| !renames.iter().any(|(_, new_name)| new_name == *name))
+        .collect();
+    added_tables.sort();
+
+    let same_structure = |old_name: &String, new_name: &String| {
+        let old_table = &from_tables[old_name];
+        let new_table = &to_tables[new_name];
+        old_table.columns == new_table.columns && old_table.indexes == new_table.indexes
+    };
+
+    for old_name in &dropped_tables {
+        let candidates: Vec<&&String> = added_tables
+            .iter()
+            .filter(|new_name| same_structure(old_name, new_name))
+            .collect();
+        if let [new_name] = candidates.as_slice() {
+            let competitors = dropped_tables
+                .iter()
+                .filter(|other| same_structure(other, new_name))
+                .count();
+            if competitors == 1 {
+                renames.push(((*old_name).clone(), (**new_name).clone()));
             }
         }
     }
 
-    diffs
+    renames
 }
 
-/// 生成索引差异SQL
-fn generate_index_diffs(old_table: &TableDefinition, new_table: &TableDefinition) -> Vec<String> {
-    let mut diffs = Vec::new();
-    let table_name = &new_table.name;
+/// 识别表中重命名的列：优先使用注释提示，其次匹配位置相同且定义相同（忽略名称和注释）的删除列和新增列
+fn detect_column_renames(
+    old_table: &TableDefinition,
+    new_table: &TableDefinition,
+    hints: Option<&HashMap<String, String>>,
+) -> Vec<(String, String)> {
+    let has_old = |name: &str| old_table.columns.iter().any(|c| c.name == name);
+    let has_new = |name: &str| new_table.columns.iter().any(|c| c.name == name);
+    let mut renames: Vec<(String, String)> = Vec::new();
+
+    if let Some(hints) = hints {
+        let mut hinted: Vec<(&String, &String)> = hints.iter().collect();
+        hinted.sort();
+        for (old_column, new_column) in hinted {
+            if has_old(old_column)
+                && !has_new(old_column)
+                && has_new(new_column)
+                && !has_old(new_column)
+            {
+                renames.push((old_column.clone(), new_column.clone()));
+            } else {
+                warn!(
+                    "忽略无效的重命名列提示: {}.{} -> {}",
+                    new_table.name, old_column, new_column
+                );
+            }
+        }
+    }
 
-    // 创建索引名到索引定义的映射
-    let old_indexes: HashMap<String, &TableIndex> = old_table
-        .indexes
+    let removed: Vec<(usize, &TableColumn)> = old_table
+        .columns
         .iter()
-        .map(|i| (i.name.clone(), i))
+        .enumerate()
+        .filter(|(_, c)| !has_new(&c.name) && !renames.iter().any(|(old, _)| *old == c.name))
         .collect();
-    let new_indexes: HashMap<String, &TableIndex> = new_table
-        .indexes
+    let added: Vec<(usize, &TableColumn)> = new_table
+        .columns
         .iter()
-        .map(|i| (i.name.clone(), i))
+        .enumerate()
+        .filter(|(_, c)| !has_old(&c.name) && !renames.iter().any(|(_, new)| *new == c.name))
         .collect();
 
-    // 检查新增的索引
-    for (idx_name, idx_def) in &new_indexes {
-        if !old_indexes.contains_key(idx_name) {
-            if idx_def.is_primary {
-                diffs.push(format!(
-                    "ALTER TABLE `{}` ADD PRIMARY KEY ({});",
-                    table_name,
-                    idx_def
-                        .columns
-                        .iter()
-                        .map(|c| format!("`{c}`"))
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                ));
-            } else if idx_def.is_unique {
-                diffs.push(format!(
-                    "ALTER TABLE `{}` ADD UNIQUE KEY `{}` ({});",
-                    table_name,
-                    idx_name,
-                    idx_def
-                        .columns
-                        .iter()
-                        .map(|c| format!("`{c}`"))
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                ));
-            } else {
-                diffs.push(format!(
-                    "ALTER TABLE `{}` ADD KEY `{}` ({});",
-                    table_name,
-                    idx_name,
-                    idx_def
-                        .columns
-                        .iter()
-                        .map(|c| format!("`{c}`"))
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                ));
-            }
+    for (old_position, old_column) in &removed {
+        let chosen = added
+            .iter()
+            .find(|(new_position, c)| {
+                new_position == old_position && same_column_definition(old_column, c)
+            })
+            .map(|(_, new_column)| *new_column);
+
+        if let Some(new_column) = chosen {
+            renames.push((old_column.name.clone(), new_column.name.clone()));
         }
     }
 
-    // 检查删除的索引
-    for (idx_name, idx_def) in &old_indexes {
-        if !new_indexes.contains_key(idx_name) {
-            if idx_def.is_primary {
-                diffs.push(format!("ALTER TABLE `{table_name}` DROP PRIMARY KEY;"));
-            } else {
-                diffs.push(format!("ALTER TABLE `{table_name}` DROP KEY `{idx_name}`;"));
+    renames
+}
+
+/// 比较列定义是否相同（忽略列名和注释）
+fn same_column_definition(old_column: &TableColumn, new_column: &TableColumn) -> bool {
+    old_column
+        .data_type
+        .eq_ignore_ascii_case(&new_column.data_type)
+        && old_column.nullable == new_column.nullable
+        && old_column.default_value == new_column.default_value
+        && old_column.auto_increment == new_column.auto_increment
+}
+
+/// 在表结构集合中重命名表，并更新引用该表的外键
+fn rename_table(tables: &mut HashMap<String, TableDefinition>, old_name: &str, new_name: &str) {
+    if let Some(mut table_def) = tables.remove(old_name) {
+        table_def.name = new_name.to_string();
+        tables.insert(new_name.to_string(), table_def);
+    }
+
+    for table_def in tables.values_mut() {
+        for foreign_key in &mut table_def.foreign_keys {
+            if foreign_key.referenced_table == old_name {
+                foreign_key.referenced_table = new_name.to_string();
             }
         }
     }
+}
 
-    // 检查修改的索引（删除旧的，添加新的）
-    for (idx_name, new_idx) in &new_indexes {
-        if let Some(old_idx) = old_indexes.get(idx_name) {
-            if old_idx != new_idx {
-                // 先删除旧索引
-                if old_idx.is_primary {
-                    diffs.push(format!("ALTER TABLE `{table_name}` DROP PRIMARY KEY;"));
-                } else {
-                    diffs.push(format!("ALTER TABLE `{table_name}` DROP KEY `{idx_name}`;"));
-                }
+/// 在表结构集合中重命名列，同步更新索引、外键和引用该列的外键
+fn rename_column(
+    tables: &mut HashMap<String, TableDefinition>,
+    table_name: &str,
+    old_column: &str,
+    new_column: &str,
+) {
+    let rename = |columns: &mut Vec<String>| {
+        for column in columns.iter_mut() {
+            if column == old_column {
+                *column = new_column.to_string();
+            }
+        }
+    };
 
-                // 再添加新索引
-                if new_idx.is_primary {
-                    diffs.push(format!(
-                        "ALTER TABLE `{}` ADD PRIMARY KEY ({});",
-                        table_name,
-                        new_idx
-                            .columns
-                            .iter()
-                            .map(|c| format!("`{c}`"))
-                            .collect::<Vec<_>>()
-                            .join(", ")
-                    ));
-                } else if new_idx.is_unique {
-                    diffs.push(format!(
-                        "ALTER TABLE `{}` ADD UNIQUE KEY `{}` ({});",
-                        table_name,
-                        idx_name,
-                        new_idx
-                            .columns
-                            .iter()
-                            .map(|c| format!("`{c}`"))
-                            .collect::<Vec<_>>()
-                            .join(", ")
-                    ));
-                } else {
-                    diffs.push(format!(
-                        "ALTER TABLE `{}` ADD KEY `{}` ({});",
-                        table_name,
-                        idx_name,
-                        new_idx
-                            .columns
-                            .iter()
-                            .map(|c| format!("`{c}`"))
-                            .collect::<Vec<_>>()
-                            .join(", ")
-                    ));
-                }
+    if let Some(table_def) = tables.get_mut(table_name) {
+        for column in &mut table_def.columns {
+            if column.name == old_column {
+                column.name = new_column.to_string();
+            }
+        }
+        for index in &mut table_def.indexes {
+            rename(&mut index.columns);
+        }
+        for foreign_key in &mut table_def.foreign_keys {
+            rename(&mut foreign_key.columns);
+        }
+    }
+
+    for table_def in tables.values_mut() {
+        for foreign_key in &mut table_def.foreign_keys {
+            if foreign_key.referenced_table == table_name {
+                rename(&mut foreign_key.referenced_columns);
             }
         }
     }
+}
+
+/// 比较两个表定义（旧表已按重命名对齐），生成表差异（不含重命名信息）
+fn diff_table(old_table: &TableDefinition, new_table: &TableDefinition) -> TableDiff {
+    let (added_columns, removed_columns, modified_columns) =
+        diff_by_name(&old_table.columns, &new_table.columns, |c| &c.name);
+    let (moved_columns, column_positions) =
+        resolve_column_positions(old_table, new_table, &modified_columns);
+    let (added_indexes, removed_indexes, modified_indexes) =
+        diff_by_name(&old_table.indexes, &new_table.indexes, |i| &i.name);
+    let (added_foreign_keys, removed_foreign_keys, modified_foreign_keys) =
+        diff_by_name(&old_table.foreign_keys, &new_table.foreign_keys, |fk| {
+            &fk.name
+        });
+    let (added_checks, removed_checks, modified_checks) =
+        diff_by_name(&old_table.checks, &new_table.checks, |check| &check.name);
+
+    let options_changed = option_changed(&old_table.engine, &new_table.engine)
+        || option_changed(&old_table.charset, &new_table.charset)
+        || option_changed(&old_table.collation, &new_table.collation)
+        || option_changed(&old_table.row_format, &new_table.row_format)
+        || old_table.comment != new_table.comment;
+    let options = options_changed.then(|| DefinitionChange {
+        before: old_table.options(),
+        after: new_table.options(),
+    });
+
+    TableDiff {
+        name: new_table.name.clone(),
+        renamed_from: None,
+        renamed_columns: Vec::new(),
+        added_columns,
+        removed_columns,
+        modified_columns,
+        moved_columns,
+        column_positions,
+        added_indexes,
+        removed_indexes,
+        modified_indexes,
+        added_foreign_keys,
+        removed_foreign_keys,
+        modified_foreign_keys,
+        added_checks,
+        removed_checks,
+        modified_checks,
+        options,
+    }
+}
+
+/// 按名称比较两组定义，返回 (新增, 删除, 修改)
+///
+/// 新增和修改按新定义中的顺序排列，删除按旧定义中的顺序排列。
+fn diff_by_name<T: Clone + PartialEq>(
+    old_items: &[T],
+    new_items: &[T],
+    name: impl Fn(&T) -> &str,
+) -> (Vec<T>, Vec<T>, Vec<DefinitionChange<T>>) {
+    let find = |items: &[T], item_name: &str| -> Option<T> {
+        items.iter().find(|item| name(item) == item_name).cloned()
+    };
+
+    let mut added = Vec::new();
+    let mut modified = Vec::new();
+    for new_item in new_items {
+        match find(old_items, name(new_item)) {
+            None => added.push(new_item.clone()),
+            Some(old_item) if old_item != *new_item => modified.push(DefinitionChange {
+                before: old_item,
+                after: new_item.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+
+    let removed = old_items
+        .iter()
+        .filter(|old_item| find(new_items, name(old_item)).is_none())
+        .cloned()
+        .collect();
 
-    diffs
+    (added, removed, modified)
+}
+
+/// 比较表选项是否变化（忽略大小写，未指定的新选项视为不变）
+pub(super) fn option_changed(old_value: &Option<String>, new_value: &Option<String>) -> bool {
+    match (old_value, new_value) {
+        (_, None) => false,
+        (None, Some(_)) => true,
+        (Some(old), Some(new)) => !old.eq_ignore_ascii_case(new),
+    }
 }
@@ -1,6 +1,10 @@
-use super::differ::generate_mysql_diff;
-use super::parser::parse_sql_tables;
-use super::types::{TableColumn, TableDefinition, TableIndex};
+use super::differ::{diff_schemas, diff_schemas_down};
+use super::parser::{parse_rename_hints, parse_sql_schema};
+use super::renderer::render_mysql_diff;
+use super::types::{
+    ChangeSafety, ObjectKind, SchemaDefinition, SchemaDiff, SchemaDiffReport, TableCheck,
+    TableColumn, TableDefinition, TableForeignKey, TableIndex,
+};
 use crate::error::DuckError;
 use tracing::info;
 
@@ -12,12 +16,44 @@ pub fn generate_schema_diff(
     from_version: Option<&str>,
     to_version: &str,
 ) -> Result<(String, String), DuckError> {
+    let report = analyze_schema_diff(from_sql, to_sql, from_version, to_version)?;
+    Ok((report.diff_sql, report.description))
+}
+
+/// 分析SQL架构差异，返回结构化差异、差异SQL、描述以及每条语句的安全级别
+///
+/// 目标SQL中的 `-- @rename-table`/`-- @rename-column` 注释会作为重命名提示。
+pub fn analyze_schema_diff(
+    from_sql: Option<&str>,
+    to_sql: &str,
+    from_version: Option<&str>,
+    to_version: &str,
+) -> Result<SchemaDiffReport, DuckError> {
+    analyze_schema_diff_for_database(from_sql, to_sql, from_version, to_version, None)
+}
+
+/// 分析SQL架构差异，可以只比较指定的数据库
+///
+/// `database` 为 `None` 时比较脚本中的所有数据库。
+pub fn analyze_schema_diff_for_database(
+    from_sql: Option<&str>,
+    to_sql: &str,
+    from_version: Option<&str>,
+    to_version: &str,
+    database: Option<&str>,
+) -> Result<SchemaDiffReport, DuckError> {
     match from_sql {
         None => {
             // 初始版本，返回完整的创建脚本
             info!("生成初始版本 {} 的完整数据库架构", to_version);
             let description = format!("初始版本 {to_version} 的完整数据库架构");
-            Ok((to_sql.to_string(), description))
+            Ok(SchemaDiffReport {
+                diff_sql: to_sql.to_string(),
+                description,
+                statements: Vec::new(),
+                down_sql: String::new(),
+                diff: SchemaDiff::default(),
+            })
         }
         Some(from_content) => {
             info!(
@@ -29,22 +65,34 @@ pub fn generate_schema_diff(
             // 如果内容完全相同，返回空差异
             if from_content.trim() == to_sql.trim() {
                 info!("版本内容完全相同，无需生成差异");
-                return Ok((
-                    String::new(),
-                    format!(
+                return Ok(SchemaDiffReport {
+                    diff_sql: String::new(),
+                    description: format!(
                         "版本 {} 到 {}: 无变化",
                         from_version.unwrap_or("unknown"),
                         to_version
                     ),
-                ));
+                    statements: Vec::new(),
+                    down_sql: String::new(),
+                    diff: SchemaDiff::default(),
+                });
             }
 
-            // 解析两个SQL文件的表结构
-            let from_tables = parse_sql_tables(from_content)?;
-            let to_tables = parse_sql_tables(to_sql)?;
+            // 解析两个SQL文件的数据库架构和重命名提示
+            let mut from_schema = parse_sql_schema(from_content)?;
+            let mut to_schema = parse_sql_schema(to_sql)?;
+            let rename_hints = parse_rename_hints(to_sql)?;
+            if let Some(database) = database {
+                info!("只比较数据库: {}", database);
+                retain_database(&mut from_schema, database);
+                retain_database(&mut to_schema, database);
+            }
 
-            // 生成差异SQL
-            let diff_sql = generate_mysql_diff(&from_tables, &to_tables)?;
+            // 计算结构化差异并渲染为差异SQL
+            let diff = diff_schemas(&from_schema, &to_schema, &rename_hints);
+            let (diff_sql, statements) = render_mysql_diff(&diff);
+            let (down_sql, _) =
+                render_mysql_diff(&diff_schemas_down(&from_schema, &to_schema, &rename_hints));
 
             let description = if diff_sql.trim().is_empty() {
                 format!(
@@ -60,12 +108,52 @@ pub fn generate_schema_diff(
 
                 // 分析差异类型
                 let mut change_types = Vec::new();
+                if !diff.added_databases.is_empty() {
+                    change_types.push("新增数据库");
+                }
+                if !diff.removed_databases.is_empty() {
+                    change_types.push("删除数据库");
+                }
+                if !diff.modified_databases.is_empty() {
+                    change_types.push("修改数据库");
+                }
+                for kind in [
+                    ObjectKind::View,
+                    ObjectKind::Trigger,
+                    ObjectKind::Procedure,
+                    ObjectKind::Function,
+                ] {
+                    let changed = diff.databases.iter().any(|database_diff| {
+                        database_diff
+                            .added_objects
+                            .iter()
+                            .chain(database_diff.removed_objects.iter())
+                            .chain(
+                                database_diff
+                                    .modified_objects
+                                    .iter()
+                                    .map(|change| &change.after),
+                            )
+                            .any(|object| object.kind == kind)
+                    });
+                    if changed {
+                        change_types.push(match kind {
+                            ObjectKind::View => "视图变更",
+                            ObjectKind::Trigger => "触发器变更",
+                            ObjectKind::Procedure => "存储过程变更",
+                            ObjectKind::Function => "函数变更",
+                        });
+                    }
+                }
                 if diff_sql.contains("CREATE TABLE") {
                     change_types.push("新增表");
                 }
                 if diff_sql.contains("DROP TABLE") {
                     change_types.push("删除表");
                 }
+                if diff_sql.contains("RENAME TABLE") {
+                    change_types.push("重命名表");
+                }
                 if diff_sql.contains("ALTER TABLE") && diff_sql.contains("ADD COLUMN") {
                     change_types.push("新增列");
                 }
@@ -75,12 +163,38 @@ pub fn generate_schema_diff(
                 if diff_sql.contains("ALTER TABLE") && diff_sql.contains("MODIFY COLUMN") {
                     change_types.push("修改列");
                 }
+                if diff_sql.contains("ALTER TABLE") && diff_sql.contains("RENAME COLUMN") {
+                    change_types.push("重命名列");
+                }
                 if diff_sql.contains("ALTER TABLE") && diff_sql.contains("ADD KEY") {
                     change_types.push("新增索引");
                 }
                 if diff_sql.contains("ALTER TABLE") && diff_sql.contains("DROP KEY") {
                     change_types.push("删除索引");
                 }
+                if diff_sql.contains("ADD CONSTRAINT") && diff_sql.contains("FOREIGN KEY") {
+                    change_types.push("新增外键");
+                }
+                if diff_sql.contains("DROP FOREIGN KEY") {
+                    change_types.push("删除外键");
+                }
+                if diff_sql.contains("CHECK (") || diff_sql.contains("DROP CHECK") {
+                    change_types.push("CHECK约束变更");
+                }
+                if diff_sql.contains("CONVERT TO CHARACTER SET")
+                    || diff_sql.contains("ENGINE=")
+                    || diff_sql.contains("ROW_FORMAT=")
+                    || diff_sql.contains("COMMENT=")
+                {
+                    change_types.push("表选项变更");
+                }
+                if diff
+                    .databases
+                    .iter()
+                    .any(|database_diff| !database_diff.seed_data.is_empty())
+                {
+                    change_types.push("参考数据变更");
+                }
 
                 let change_summary = if change_types.is_empty() {
                     "架构变更".to_string()
@@ -88,21 +202,45 @@ pub fn generate_schema_diff(
                     change_types.join("、")
                 };
 
+                let data_loss_count = statements
+                    .iter()
+                    .filter(|statement| statement.safety == ChangeSafety::DataLoss)
+                    .count();
+                let safety_summary = if data_loss_count > 0 {
+                    format!(" (⚠️ 其中 {data_loss_count} 行可能导致数据丢失)")
+                } else {
+                    String::new()
+                };
+
                 format!(
-                    "版本 {} 到 {}: {} - 生成 {} 行可执行的差异SQL",
+                    "版本 {} 到 {}: {} - 生成 {} 行可执行的差异SQL{}",
                     from_version.unwrap_or("unknown"),
                     to_version,
                     change_summary,
-                    lines_count
+                    lines_count,
+                    safety_summary
                 )
             };
 
             info!("差异生成完成: {}", description);
-            Ok((diff_sql, description))
+            Ok(SchemaDiffReport {
+                diff_sql,
+                description,
+                statements,
+                down_sql,
+                diff,
+            })
         }
     }
 }
 
+/// 只保留指定数据库的架构
+fn retain_database(schema: &mut SchemaDefinition, database: &str) {
+    schema
+        .databases
+        .retain(|definition| definition.name.as_deref() == Some(database));
+}
+
 /// 生成CREATE TABLE SQL
 pub fn generate_create_table_sql(table: &TableDefinition) -> String {
     let mut sql = format!("CREATE TABLE `{}` (", table.name);
@@ -118,6 +256,16 @@ pub fn generate_create_table_sql(table: &TableDefinition) -> String {
         parts.push(format!("  {}", generate_index_sql(index)));
     }
 
+    // 添加外键约束
+    for foreign_key in &table.foreign_keys {
+        parts.push(format!("  {}", generate_foreign_key_sql(foreign_key)));
+    }
+
+    // 添加CHECK约束
+    for check in &table.checks {
+        parts.push(format!("  {}", generate_check_sql(check)));
+    }
+
     sql.push_str(&parts.join(",\n"));
     sql.push_str("\n)");
 
@@ -128,6 +276,15 @@ pub fn generate_create_table_sql(table: &TableDefinition) -> String {
     if let Some(charset) = &table.charset {
         sql.push_str(&format!(" DEFAULT CHARSET={charset}"));
     }
+    if let Some(collation) = &table.collation {
+        sql.push_str(&format!(" COLLATE={collation}"));
+    }
+    if let Some(row_format) = &table.row_format {
+        sql.push_str(&format!(" ROW_FORMAT={row_format}"));
+    }
+    if let Some(comment) = &table.comment {
+        sql.push_str(&format!(" COMMENT='{}'", escape_sql_string(comment)));
+    }
 
     sql.push(';');
     sql
@@ -141,8 +298,13 @@ pub fn generate_column_sql(column: &TableColumn) -> String {
         sql.push_str(" NOT NULL");
     }
 
+    // 默认值保存的是SQL表达式原文（字符串已带引号），不能再加引号
     if let Some(default) = &column.default_value {
-        sql.push_str(&format!(" DEFAULT '{default}'"));
+        sql.push_str(&format!(" DEFAULT {default}"));
+    }
+
+    if let Some(on_update) = &column.on_update {
+        sql.push_str(&format!(" ON UPDATE {on_update}"));
     }
 
     if column.auto_increment {
@@ -150,7 +312,7 @@ pub fn generate_column_sql(column: &TableColumn) -> String {
     }
 
     if let Some(comment) = &column.comment {
-        sql.push_str(&format!(" COMMENT '{comment}'"));
+        sql.push_str(&format!(" COMMENT '{}'", escape_sql_string(comment)));
     }
 
     sql
@@ -192,3 +354,50 @@ pub fn generate_index_sql(index: &TableIndex) -> String {
         )
     }
 }
+
+/// 生成外键约束定义SQL
+pub fn generate_foreign_key_sql(foreign_key: &TableForeignKey) -> String {
+    let mut sql = format!(
+        "CONSTRAINT `{}` FOREIGN KEY ({}) REFERENCES `{}` ({})",
+        foreign_key.name,
+        quote_columns(&foreign_key.columns),
+        foreign_key.referenced_table,
+        quote_columns(&foreign_key.referenced_columns)
+    );
+
+    if let Some(on_delete) = &foreign_key.on_delete {
+        sql.push_str(&format!(" ON DELETE {on_delete}"));
+    }
+    if let Some(on_update) = &foreign_key.on_update {
+        sql.push_str(&format!(" ON UPDATE {on_update}"));
+    }
+
+    sql
+}
+
+/// 生成CHECK约束定义SQL
+pub fn generate_check_sql(check: &TableCheck) -> String {
+    let mut sql = format!("CONSTRAINT `{}` CHECK ({})", check.name, check.expression);
+
+    match check.enforced {
+        Some(true) => sql.push_str(" ENFORCED"),
+        Some(false) => sql.push_str(" NOT ENFORCED"),
+        None => {}
+    }
+
+    sql
+}
+
+/// 生成带反引号的列名列表
+fn quote_columns(columns: &[String]) -> String {
+    columns
+        .iter()
+        .map(|c| format!("`{c}`"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// 转义SQL字符串字面量中的单引号和反斜杠
+pub fn escape_sql_string(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\'', "''")
+}
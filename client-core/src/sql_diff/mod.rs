@@ -1,10 +1,26 @@
 mod differ;
 mod generator;
 mod parser;
+mod renderer;
 mod types;
 
 #[cfg(test)]
 mod tests;
 
 // 重新导出公共接口
-pub use generator::generate_schema_diff;
+pub use differ::{
+    diff_schemas, diff_schemas_down, diff_tables, generate_mysql_diff,
+    generate_mysql_diff_with_hints, generate_mysql_down_diff,
+};
+pub use generator::{analyze_schema_diff, analyze_schema_diff_for_database, generate_schema_diff};
+pub use parser::{
+    extract_use_database, parse_reference_tables, parse_rename_hints, parse_sql_schema,
+    parse_sql_tables,
+};
+pub use renderer::render_mysql_diff;
+pub use types::{
+    ChangeSafety, ColumnPosition, DatabaseDefinition, DatabaseDiff, DatabaseOptions,
+    DefinitionChange, DiffStatement, NameChange, ObjectDefinition, ObjectKind, RenameHints,
+    SchemaDefinition, SchemaDiff, SchemaDiffReport, SeedData, SeedDataDiff, SeedRow, TableCheck,
+    TableColumn, TableDefinition, TableDiff, TableForeignKey, TableIndex, TableOptions,
+};
@@ -1,206 +1,549 @@
-use super::types::{TableColumn, TableDefinition, TableIndex};
+use super::types::{
+    DatabaseOptions, ObjectDefinition, ObjectKind, RenameHints, SchemaDefinition, SeedData,
+    SeedRow, TableCheck, TableColumn, TableDefinition, TableForeignKey, TableIndex,
+};
 use crate::error::DuckError;
 use regex::Regex;
-use sqlparser::ast::{ColumnDef, DataType, Statement, TableConstraint};
+use sqlparser::ast::{
+    ColumnDef, CommentDef, CreateTable, CreateTableOptions, DataType, Expr, ObjectName, SetExpr,
+    SqlOption, Statement, TableConstraint, TableObject,
+};
 use sqlparser::dialect::MySqlDialect;
 use sqlparser::parser::Parser;
 use std::collections::HashMap;
 use tracing::{debug, info, warn};
 
-/// 解析SQL文件中的表结构
+/// 解析SQL文件中的表结构（不区分数据库）
+///
+/// 多个数据库中存在同名表时，后出现的表会覆盖前面的表；
+/// 需要按数据库区分时请使用 [`parse_sql_schema`]。
 pub fn parse_sql_tables(sql_content: &str) -> Result<HashMap<String, TableDefinition>, DuckError> {
+    let schema = parse_sql_schema(sql_content)?;
+
     let mut tables = HashMap::new();
+    for database in schema.databases {
+        for (table_name, table_def) in database.tables {
+            if tables.insert(table_name.clone(), table_def).is_some() {
+                warn!("多个数据库中存在同名表 {}，只保留最后一个", table_name);
+            }
+        }
+    }
+    Ok(tables)
+}
 
-    // 使用正则表达式找到 USE 语句的位置，然后从该位置开始解析后续的 CREATE TABLE 语句
-    let create_table_statements = extract_create_table_statements_with_regex(sql_content)?;
+/// 解析SQL文件中的数据库架构，按数据库分组
+///
+/// 按语句顺序跟踪 `USE` 切换的当前数据库：
+/// - `CREATE DATABASE`/`CREATE SCHEMA` 记录数据库及其字符集、排序规则
+/// - `CREATE TABLE db.table` 使用限定的数据库，否则使用当前 `USE` 的数据库
+/// - 第一个 `USE` 之前创建的表属于连接的默认数据库（`name` 为 `None`）
+pub fn parse_sql_schema(sql_content: &str) -> Result<SchemaDefinition, DuckError> {
+    let use_regex = Regex::new(r"(?i)^USE\s+`?([^`;\s]+)`?\s*;?$")
+        .map_err(|e| DuckError::custom(format!("正则表达式编译失败: {e}")))?;
+    let create_table_regex = Regex::new(r"(?i)^CREATE\s+TABLE\s")
+        .map_err(|e| DuckError::custom(format!("正则表达式编译失败: {e}")))?;
+
+    let insert_regex = Regex::new(r"(?i)^(?:INSERT|REPLACE)\s")
+        .map_err(|e| DuckError::custom(format!("正则表达式编译失败: {e}")))?;
+    let reference_tables = parse_reference_tables(sql_content)?;
 
+    let mut schema = SchemaDefinition::default();
+    let mut current_database: Option<String> = None;
     let dialect = MySqlDialect {};
 
-    for create_table_sql in create_table_statements {
-        debug!("解析 CREATE TABLE 语句: {}", create_table_sql);
+    for statement_sql in split_sql_statements(sql_content) {
+        if let Some(caps) = use_regex.captures(&statement_sql) {
+            debug!("切换数据库: {}", &caps[1]);
+            current_database = Some(caps[1].to_string());
+            continue;
+        }
+
+        if let Some(options) = parse_create_database(&statement_sql)? {
+            debug!("解析数据库: {}", options.name);
+            let name = options.name.clone();
+            schema.database_mut(Some(&name)).options = Some(options);
+            continue;
+        }
+
+        if let Some((database, object)) = parse_create_object(&statement_sql)? {
+            debug!("解析{}: {}", object.kind.description(), object.name);
+            let database = database.or_else(|| current_database.clone());
+            let objects = &mut schema.database_mut(database.as_deref()).objects;
+            // 同名对象重复定义时（如先删除再创建）以最后一次为准
+            objects.retain(|existing| {
+                existing.kind != object.kind || !existing.name.eq_ignore_ascii_case(&object.name)
+            });
+            objects.push(object);
+            continue;
+        }
+
+        if insert_regex.is_match(&statement_sql) {
+            if !reference_tables.is_empty() {
+                parse_seed_rows(
+                    &statement_sql,
+                    current_database.as_deref(),
+                    &reference_tables,
+                    &mut schema,
+                );
+            }
+            continue;
+        }
+
+        if !create_table_regex.is_match(&statement_sql) {
+            continue;
+        }
 
-        match Parser::parse_sql(&dialect, &create_table_sql) {
+        debug!("解析 CREATE TABLE 语句: {}", statement_sql);
+        match Parser::parse_sql(&dialect, &statement_sql) {
             Ok(statements) => {
                 for statement in statements {
                     if let Statement::CreateTable(create_table) = statement {
-                        let table_name = create_table.name.to_string();
-                        debug!("解析表: {}", table_name);
-
-                        let mut table_columns = Vec::new();
-                        let mut table_indexes = Vec::new();
-                        let mut primary_key_columns = Vec::new();
-
-                        // 解析列定义
-                        for column in &create_table.columns {
-                            let column_def = parse_column_definition(column)?;
-
-                            // 检查是否是列级别的主键
-                            if is_column_primary_key(column) {
-                                primary_key_columns.push(column.name.to_string());
-                            }
-
-                            table_columns.push(column_def);
-                        }
-
-                        // 如果有列级别的主键，添加到索引列表
-                        if !primary_key_columns.is_empty() {
-                            table_indexes.push(TableIndex {
-                                name: "PRIMARY".to_string(),
-                                columns: primary_key_columns,
-                                is_primary: true,
-                                is_unique: true,
-                                index_type: Some("PRIMARY".to_string()),
-                            });
-                        }
-
-                        // 解析约束（包括索引）
-                        for constraint in &create_table.constraints {
-                            if let Some(index) = parse_table_constraint(constraint)? {
-                                table_indexes.push(index);
-                            }
-                        }
-
-                        let table_def = TableDefinition {
-                            name: table_name.clone(),
-                            columns: table_columns,
-                            indexes: table_indexes,
-                            engine: None,  // 可以从原始SQL字符串中提取
-                            charset: None, // 可以从原始SQL字符串中提取
-                        };
-
-                        tables.insert(table_name, table_def);
+                        let table_def = parse_create_table(&create_table)?;
+                        let database = object_name_to_database_name(&create_table.name)
+                            .or_else(|| current_database.clone());
+                        debug!(
+                            "解析表: {}.{}",
+                            database.as_deref().unwrap_or("<默认数据库>"),
+                            table_def.name
+                        );
+
+                        schema
+                            .database_mut(database.as_deref())
+                            .tables
+                            .insert(table_def.name.clone(), table_def);
                     }
                 }
             }
             Err(e) => {
-                warn!("解析 SQL 语句失败: {} - 错误: {}", create_table_sql, e);
+                warn!("解析 SQL 语句失败: {} - 错误: {}", statement_sql, e);
             }
         }
     }
 
-    info!("成功解析 {} 个表", tables.len());
-    Ok(tables)
+    info!(
+        "成功解析 {} 个数据库中的 {} 个表",
+        schema.databases.len(),
+        schema
+            .databases
+            .iter()
+            .map(|database| database.tables.len())
+            .sum::<usize>()
+    );
+    Ok(schema)
 }
 
-/// 使用正则表达式找到 USE 语句位置，然后提取后续的 CREATE TABLE 语句
-fn extract_create_table_statements_with_regex(sql_content: &str) -> Result<Vec<String>, DuckError> {
-    // 创建正则表达式来匹配 USE 语句
-    let use_regex = Regex::new(r"(?i)^\s*USE\s+[^;]+;\s*$")
-        .map_err(|e| DuckError::custom(format!("正则表达式编译失败: {e}")))?;
+/// 解析 `CREATE VIEW`/`TRIGGER`/`PROCEDURE`/`FUNCTION` 语句，返回限定的数据库名和对象定义
+///
+/// sqlparser 不能完整解析MySQL的触发器和存储程序，这里用正则表达式识别对象类型和名称，
+/// 定义体按原文保存，比较时再规范化。
+fn parse_create_object(
+    statement_sql: &str,
+) -> Result<Option<(Option<String>, ObjectDefinition)>, DuckError> {
+    let object_regex = Regex::new(
+        r"(?is)^CREATE\s+(?:OR\s+REPLACE\s+)?(?:ALGORITHM\s*=\s*\w+\s+)?(?:DEFINER\s*=\s*\S+\s+)?(?:SQL\s+SECURITY\s+\w+\s+)?(VIEW|TRIGGER|PROCEDURE|FUNCTION)\s+(?:IF\s+NOT\s+EXISTS\s+)?((?:`[^`]+`|[\w$]+)(?:\s*\.\s*(?:`[^`]+`|[\w$]+))?)",
+    )
+    .map_err(|e| DuckError::custom(format!("正则表达式编译失败: {e}")))?;
+
+    let Some(caps) = object_regex.captures(statement_sql) else {
+        return Ok(None);
+    };
 
-    let lines: Vec<&str> = sql_content.lines().collect();
-    let mut start_parsing_from_line = 0;
+    let kind = match caps[1].to_uppercase().as_str() {
+        "VIEW" => ObjectKind::View,
+        "TRIGGER" => ObjectKind::Trigger,
+        "PROCEDURE" => ObjectKind::Procedure,
+        _ => ObjectKind::Function,
+    };
+    let mut name_parts: Vec<String> = caps[2]
+        .split('.')
+        .map(|part| part.trim().trim_matches('`').to_string())
+        .collect();
+    let name = name_parts.pop().unwrap_or_default();
+    let database = name_parts.pop();
+
+    Ok(Some((
+        database,
+        ObjectDefinition {
+            kind,
+            name,
+            definition: statement_sql.to_string(),
+        },
+    )))
+}
 
-    // 查找 USE 语句
-    for (line_idx, line) in lines.iter().enumerate() {
-        if use_regex.is_match(line) {
-            debug!("找到 USE 语句在第 {} 行: {}", line_idx + 1, line);
-            start_parsing_from_line = line_idx + 1; // 从下一行开始
-            break;
+/// 把 `CREATE TABLE` 语句转换为表定义
+fn parse_create_table(create_table: &CreateTable) -> Result<TableDefinition, DuckError> {
+    let table_name = object_name_to_table_name(&create_table.name);
+
+    let mut table_columns = Vec::new();
+    let mut table_indexes = Vec::new();
+    let mut foreign_keys = Vec::new();
+    let mut checks = Vec::new();
+    let mut primary_key_columns = Vec::new();
+
+    // 解析列定义
+    for column in &create_table.columns {
+        let column_def = parse_column_definition(column)?;
+
+        // 检查是否是列级别的主键
+        if is_column_primary_key(column) {
+            primary_key_columns.push(column.name.value.clone());
         }
+
+        table_columns.push(column_def);
     }
 
-    // 如果没有找到 USE 语句，从头开始解析
-    if start_parsing_from_line == 0 {
-        debug!("未找到 USE 语句，从头开始解析整个文件");
+    // 如果有列级别的主键，添加到索引列表
+    if !primary_key_columns.is_empty() {
+        table_indexes.push(TableIndex {
+            name: "PRIMARY".to_string(),
+            columns: primary_key_columns,
+            is_primary: true,
+            is_unique: true,
+            index_type: Some("PRIMARY".to_string()),
+        });
     }
 
-    // 从指定位置开始提取内容
-    let content_to_parse = if start_parsing_from_line < lines.len() {
-        lines[start_parsing_from_line..].join("\n")
-    } else {
-        sql_content.to_string()
+    // 解析约束（包括索引、外键和CHECK约束）
+    for constraint in &create_table.constraints {
+        if let Some(index) = parse_table_constraint(constraint)? {
+            table_indexes.push(index);
+        } else if let Some(foreign_key) =
+            parse_foreign_key(constraint, &table_name, foreign_keys.len())
+        {
+            foreign_keys.push(foreign_key);
+        } else if let Some(check) = parse_check_constraint(constraint, &table_name, checks.len()) {
+            checks.push(check);
+        }
+    }
+
+    let mut table_def = TableDefinition {
+        name: table_name,
+        columns: table_columns,
+        indexes: table_indexes,
+        foreign_keys,
+        checks,
+        engine: None,
+        charset: None,
+        collation: None,
+        row_format: None,
+        comment: None,
     };
 
-    extract_create_table_statements_from_content(&content_to_parse)
+    // 解析表选项（ENGINE、CHARSET、COLLATE、ROW_FORMAT、COMMENT）
+    apply_table_options(&mut table_def, &create_table.table_options);
+
+    Ok(table_def)
 }
 
-/// 从指定内容中提取 CREATE TABLE 语句
-fn extract_create_table_statements_from_content(content: &str) -> Result<Vec<String>, DuckError> {
-    let mut statements = Vec::new();
+/// 解析 `CREATE DATABASE`/`CREATE SCHEMA` 语句
+///
+/// sqlparser 不支持MySQL的字符集选项，这里用正则表达式解析。
+fn parse_create_database(statement_sql: &str) -> Result<Option<DatabaseOptions>, DuckError> {
+    let database_regex = Regex::new(
+        r"(?is)^CREATE\s+(?:DATABASE|SCHEMA)\s+(?:IF\s+NOT\s+EXISTS\s+)?`?([^`\s;]+)`?(.*)$",
+    )
+    .map_err(|e| DuckError::custom(format!("正则表达式编译失败: {e}")))?;
+    let charset_regex = Regex::new(r"(?i)(?:CHARACTER\s+SET|CHARSET)\s*=?\s*['\x22]?(\w+)")
+        .map_err(|e| DuckError::custom(format!("正则表达式编译失败: {e}")))?;
+    let collation_regex = Regex::new(r"(?i)COLLATE\s*=?\s*['\x22]?(\w+)")
+        .map_err(|e| DuckError::custom(format!("正则表达式编译失败: {e}")))?;
 
-    // 创建正则表达式来匹配 CREATE TABLE 语句的开始
-    let create_table_regex = Regex::new(r"(?i)^\s*CREATE\s+TABLE")
+    let Some(caps) = database_regex.captures(statement_sql) else {
+        return Ok(None);
+    };
+    let options = &caps[2];
+
+    Ok(Some(DatabaseOptions {
+        name: caps[1].to_string(),
+        charset: charset_regex
+            .captures(options)
+            .map(|caps| caps[1].to_lowercase()),
+        collation: collation_regex
+            .captures(options)
+            .map(|caps| caps[1].to_lowercase()),
+    }))
+}
+
+/// 解析参考数据提示（`-- @reference-data: roles, app.dict_items`），返回标记的表名
+///
+/// 标记的表中由 `INSERT` 写入的数据会按主键比较并生成数据差异；
+/// 表名可以用 `库名.表名` 限定，否则匹配所有数据库中的同名表。
+pub fn parse_reference_tables(sql_content: &str) -> Result<Vec<String>, DuckError> {
+    let reference_regex = Regex::new(r"(?im)^\s*--\s*@reference-data\s*:?\s*(.+?)\s*$")
         .map_err(|e| DuckError::custom(format!("正则表达式编译失败: {e}")))?;
 
-    let lines: Vec<&str> = content.lines().collect();
-    let mut current_statement = String::new();
-    let mut in_create_table = false;
-    let mut paren_count = 0;
-    let mut in_string = false;
-    let mut escape_next = false;
+    let mut tables = Vec::new();
+    for caps in reference_regex.captures_iter(sql_content) {
+        for table in caps[1].split(',') {
+            let table = table.trim().replace('`', "");
+            if !table.is_empty() && !tables.contains(&table) {
+                debug!("发现参考数据表: {}", table);
+                tables.push(table);
+            }
+        }
+    }
 
-    for line in lines {
-        let trimmed = line.trim();
+    Ok(tables)
+}
+
+/// 判断表是否标记为参考数据
+fn is_reference_table(reference_tables: &[String], database: Option<&str>, table: &str) -> bool {
+    reference_tables.iter().any(|reference| {
+        reference == table
+            || database.is_some_and(|database| *reference == format!("{database}.{table}"))
+    })
+}
+
+/// 解析参考数据表的 `INSERT` 语句，按行记录列值
+///
+/// 没有列出列名的 `INSERT` 按表定义中的列顺序对应；无法对应的语句会被忽略。
+fn parse_seed_rows(
+    statement_sql: &str,
+    current_database: Option<&str>,
+    reference_tables: &[String],
+    schema: &mut SchemaDefinition,
+) {
+    let statements = match Parser::parse_sql(&MySqlDialect {}, statement_sql) {
+        Ok(statements) => statements,
+        Err(e) => {
+            warn!("解析 INSERT 语句失败: {} - 错误: {}", statement_sql, e);
+            return;
+        }
+    };
 
-        // 跳过空行和注释
-        if trimmed.is_empty() || trimmed.starts_with("--") || trimmed.starts_with("/*") {
+    for statement in statements {
+        let Statement::Insert(insert) = statement else {
+            continue;
+        };
+        let TableObject::TableName(table_name) = &insert.table else {
+            continue;
+        };
+        let table = object_name_to_table_name(table_name);
+        let database = object_name_to_database_name(table_name)
+            .or_else(|| current_database.map(|name| name.to_string()));
+        if !is_reference_table(reference_tables, database.as_deref(), &table) {
             continue;
         }
+        let Some(SetExpr::Values(values)) = insert.source.as_ref().map(|query| query.body.as_ref())
+        else {
+            warn!("参考数据表 {} 的 INSERT 语句没有 VALUES，已忽略", table);
+            continue;
+        };
+
+        let database_def = schema.database_mut(database.as_deref());
+        let columns: Vec<String> = if insert.columns.is_empty() {
+            database_def
+                .tables
+                .get(&table)
+                .map(|table_def| table_def.columns.iter().map(|c| c.name.clone()).collect())
+                .unwrap_or_default()
+        } else {
+            insert
+                .columns
+                .iter()
+                .map(|column| column.value.clone())
+                .collect()
+        };
+
+        let mut rows = Vec::new();
+        for row in &values.rows {
+            if row.len() != columns.len() {
+                warn!(
+                    "参考数据表 {} 的 INSERT 语句列数与值的数量不一致，已忽略: {}",
+                    table, statement_sql
+                );
+                return;
+            }
+            rows.push(
+                columns
+                    .iter()
+                    .cloned()
+                    .zip(row.iter().map(|value| value.to_string()))
+                    .collect::<SeedRow>(),
+            );
+        }
 
-        // 检查是否是 CREATE TABLE 语句的开始
-        if !in_create_table && create_table_regex.is_match(line) {
-            in_create_table = true;
-            current_statement.clear();
-            paren_count = 0;
-            in_string = false;
-            escape_next = false;
+        debug!("解析参考数据: {} ({} 行)", table, rows.len());
+        match database_def
+            .seed_data
+            .iter_mut()
+            .find(|seed_data| seed_data.table == table)
+        {
+            Some(seed_data) => seed_data.rows.extend(rows),
+            None => database_def.seed_data.push(SeedData { table, rows }),
         }
+    }
+}
+
+/// 提取SQL脚本中第一个 USE 语句指定的数据库名
+pub fn extract_use_database(sql_content: &str) -> Option<String> {
+    let use_regex = Regex::new(r"(?im)^\s*USE\s+`?([^`;\s]+)`?\s*;").ok()?;
+    use_regex
+        .captures(sql_content)
+        .map(|caps| caps[1].to_string())
+}
 
-        if in_create_table {
-            current_statement.push_str(line);
-            current_statement.push('\n');
+/// 解析SQL注释中的重命名提示（`-- @rename-table: old -> new`、`-- @rename-column: table.old -> new`）
+pub fn parse_rename_hints(sql_content: &str) -> Result<RenameHints, DuckError> {
+    let mut hints = RenameHints::default();
+
+    let table_regex =
+        Regex::new(r"(?im)^\s*--\s*@rename-table\s*:?\s*`?(\w+)`?\s*->\s*`?(\w+)`?\s*$")
+            .map_err(|e| DuckError::custom(format!("正则表达式编译失败: {e}")))?;
+    let column_regex = Regex::new(
+        r"(?im)^\s*--\s*@rename-column\s*:?\s*`?(\w+)`?\.`?(\w+)`?\s*->\s*`?(\w+)`?\s*$",
+    )
+    .map_err(|e| DuckError::custom(format!("正则表达式编译失败: {e}")))?;
+
+    for caps in table_regex.captures_iter(sql_content) {
+        debug!("发现重命名表提示: {} -> {}", &caps[1], &caps[2]);
+        hints
+            .tables
+            .insert(caps[1].to_string(), caps[2].to_string());
+    }
 
-            // 逐字符分析以正确处理括号平衡
-            for ch in line.chars() {
-                if escape_next {
-                    escape_next = false;
-                    continue;
+    for caps in column_regex.captures_iter(sql_content) {
+        debug!(
+            "发现重命名列提示: {}.{} -> {}",
+            &caps[1], &caps[2], &caps[3]
+        );
+        hints
+            .columns
+            .entry(caps[1].to_string())
+            .or_default()
+            .insert(caps[2].to_string(), caps[3].to_string());
+    }
+
+    Ok(hints)
+}
+
+/// 把SQL脚本拆分为单条语句（不含结尾的分隔符）
+///
+/// 跳过 `--`、`#` 和 `/* */` 注释，引号（`'`、`"`、`` ` ``）内的分隔符和注释符号不作处理。
+/// 支持MySQL客户端的 `DELIMITER` 命令（存储过程、触发器的定义体中包含分号）。
+pub(super) fn split_sql_statements(sql_content: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut delimiter = ";".to_string();
+    let mut chars = sql_content.char_indices().peekable();
+    let mut quote: Option<char> = None;
+
+    while let Some((index, ch)) = chars.next() {
+        if let Some(quote_char) = quote {
+            current.push(ch);
+            if ch == '\\' && quote_char != '`' {
+                // 转义字符，原样保留下一个字符
+                if let Some((_, next)) = chars.next() {
+                    current.push(next);
                 }
+            } else if ch == quote_char {
+                if chars.peek().map(|(_, next)| *next) == Some(quote_char) {
+                    // 连续两个引号表示转义的引号
+                    current.push(quote_char);
+                    chars.next();
+                } else {
+                    quote = None;
+                }
+            }
+            continue;
+        }
 
-                match ch {
-                    '\\' if in_string => {
-                        escape_next = true;
-                    }
-                    '\'' | '"' | '`' => {
-                        in_string = !in_string;
-                    }
-                    '(' if !in_string => {
-                        paren_count += 1;
-                    }
-                    ')' if !in_string => {
-                        paren_count -= 1;
-                    }
-                    ';' if !in_string && paren_count <= 0 => {
-                        // 找到语句结束
-                        statements.push(current_statement.trim().to_string());
-                        current_statement.clear();
-                        in_create_table = false;
-                        paren_count = 0;
+        let rest = &sql_content[index..];
+
+        // DELIMITER 是客户端命令，只能出现在语句开头，作用到行尾
+        if current.trim().is_empty() && is_delimiter_command(rest) {
+            let line = rest.lines().next().unwrap_or_default();
+            let new_delimiter = line["DELIMITER".len()..].trim();
+            if !new_delimiter.is_empty() {
+                debug!("切换语句分隔符: {}", new_delimiter);
+                delimiter = new_delimiter.to_string();
+            }
+            skip_line_comment(&mut chars);
+            current.clear();
+            continue;
+        }
+
+        if rest.starts_with(delimiter.as_str()) {
+            for _ in 1..delimiter.chars().count() {
+                chars.next();
+            }
+            push_statement(&mut statements, &mut current);
+            continue;
+        }
+
+        match ch {
+            '\'' | '"' | '`' => {
+                quote = Some(ch);
+                current.push(ch);
+            }
+            // `--` 后需要跟空白字符才是注释
+            '-' if rest.starts_with("--")
+                && rest[2..]
+                    .chars()
+                    .next()
+                    .is_none_or(|next| next.is_whitespace()) =>
+            {
+                skip_line_comment(&mut chars);
+                current.push('\n');
+            }
+            '#' => {
+                skip_line_comment(&mut chars);
+                current.push('\n');
+            }
+            '/' if rest.starts_with("/*") => {
+                chars.next();
+                let mut previous = '\0';
+                for (_, next) in chars.by_ref() {
+                    if previous == '*' && next == '/' {
                         break;
                     }
-                    _ => {}
+                    previous = next;
                 }
+                current.push(' ');
             }
+            _ => current.push(ch),
         }
     }
+    push_statement(&mut statements, &mut current);
 
-    // 处理可能没有分号结尾的语句
-    if in_create_table && !current_statement.trim().is_empty() {
-        statements.push(current_statement.trim().to_string());
+    debug!("拆分出 {} 条SQL语句", statements.len());
+    statements
+}
+
+/// 判断是否为 `DELIMITER` 命令
+fn is_delimiter_command(rest: &str) -> bool {
+    rest.get(.."DELIMITER".len())
+        .is_some_and(|keyword| keyword.eq_ignore_ascii_case("DELIMITER"))
+        && rest["DELIMITER".len()..].starts_with([' ', '\t'])
+}
+
+/// 跳过行注释直到行尾
+fn skip_line_comment(chars: &mut impl Iterator<Item = (usize, char)>) {
+    for (_, next) in chars {
+        if next == '\n' {
+            break;
+        }
     }
+}
 
-    debug!("提取到 {} 个 CREATE TABLE 语句", statements.len());
-    Ok(statements)
+/// 保存非空语句并清空缓冲区
+fn push_statement(statements: &mut Vec<String>, current: &mut String) {
+    let statement = current.trim();
+    if !statement.is_empty() {
+        statements.push(statement.to_string());
+    }
+    current.clear();
 }
 
 /// 解析列定义
-fn parse_column_definition(column: &ColumnDef) -> Result<TableColumn, DuckError> {
-    let column_name = column.name.to_string();
+pub(super) fn parse_column_definition(column: &ColumnDef) -> Result<TableColumn, DuckError> {
+    let column_name = column.name.value.clone();
     let data_type = format_data_type(&column.data_type);
 
     let mut nullable = true;
     let mut default_value = None;
+    let mut on_update = None;
     let mut comment = None;
     let mut auto_increment = false;
 
@@ -213,6 +556,9 @@ fn parse_column_definition(column: &ColumnDef) -> Result<TableColumn, DuckError>
             sqlparser::ast::ColumnOption::Default(expr) => {
                 default_value = Some(format!("{expr}"));
             }
+            sqlparser::ast::ColumnOption::OnUpdate(expr) => {
+                on_update = Some(format!("{expr}"));
+            }
             sqlparser::ast::ColumnOption::Comment(c) => {
                 comment = Some(c.clone());
             }
@@ -242,6 +588,7 @@ fn parse_column_definition(column: &ColumnDef) -> Result<TableColumn, DuckError>
         data_type,
         nullable,
         default_value,
+        on_update,
         auto_increment,
         comment,
     })
@@ -251,7 +598,7 @@ fn parse_column_definition(column: &ColumnDef) -> Result<TableColumn, DuckError>
 fn parse_table_constraint(constraint: &TableConstraint) -> Result<Option<TableIndex>, DuckError> {
     match constraint {
         TableConstraint::PrimaryKey { columns, .. } => {
-            let column_names: Vec<String> = columns.iter().map(|col| col.to_string()).collect();
+            let column_names: Vec<String> = columns.iter().map(|col| col.value.clone()).collect();
 
             Ok(Some(TableIndex {
                 name: "PRIMARY".to_string(),
@@ -262,11 +609,11 @@ fn parse_table_constraint(constraint: &TableConstraint) -> Result<Option<TableIn
             }))
         }
         TableConstraint::Unique { columns, name, .. } => {
-            let column_names: Vec<String> = columns.iter().map(|col| col.to_string()).collect();
+            let column_names: Vec<String> = columns.iter().map(|col| col.value.clone()).collect();
 
             let index_name = name
                 .as_ref()
-                .map(|n| n.to_string())
+                .map(|n| n.value.clone())
                 .unwrap_or_else(|| format!("unique_{}", column_names.join("_")));
 
             Ok(Some(TableIndex {
@@ -278,11 +625,11 @@ fn parse_table_constraint(constraint: &TableConstraint) -> Result<Option<TableIn
             }))
         }
         TableConstraint::Index { name, columns, .. } => {
-            let column_names: Vec<String> = columns.iter().map(|col| col.to_string()).collect();
+            let column_names: Vec<String> = columns.iter().map(|col| col.value.clone()).collect();
 
             let index_name = name
                 .as_ref()
-                .map(|n| n.to_string())
+                .map(|n| n.value.clone())
                 .unwrap_or_else(|| format!("idx_{}", column_names.join("_")));
 
             Ok(Some(TableIndex {
@@ -297,6 +644,140 @@ fn parse_table_constraint(constraint: &TableConstraint) -> Result<Option<TableIn
     }
 }
 
+/// 获取对象名中限定的数据库名（`db.table` 中的 `db`）
+fn object_name_to_database_name(name: &ObjectName) -> Option<String> {
+    match name.0.as_slice() {
+        [database, _] => database.as_ident().map(|ident| ident.value.clone()),
+        _ => None,
+    }
+}
+
+/// 获取对象名中的表名（不带引号，忽略数据库前缀）
+fn object_name_to_table_name(name: &ObjectName) -> String {
+    name.0
+        .last()
+        .and_then(|part| part.as_ident())
+        .map(|ident| ident.value.clone())
+        .unwrap_or_else(|| name.to_string())
+}
+
+/// 解析外键约束
+///
+/// 未命名的外键按MySQL的规则自动命名为 `{表名}_ibfk_{序号}`，
+/// 保证生成的 `DROP FOREIGN KEY` 语句能在实际数据库上执行。
+fn parse_foreign_key(
+    constraint: &TableConstraint,
+    table_name: &str,
+    existing_count: usize,
+) -> Option<TableForeignKey> {
+    if let TableConstraint::ForeignKey {
+        name,
+        columns,
+        foreign_table,
+        referred_columns,
+        on_delete,
+        on_update,
+        ..
+    } = constraint
+    {
+        let fk_name = name
+            .as_ref()
+            .map(|n| n.value.clone())
+            .unwrap_or_else(|| format!("{table_name}_ibfk_{}", existing_count + 1));
+
+        Some(TableForeignKey {
+            name: fk_name,
+            columns: columns.iter().map(|col| col.value.clone()).collect(),
+            referenced_table: object_name_to_table_name(foreign_table),
+            referenced_columns: referred_columns
+                .iter()
+                .map(|col| col.value.clone())
+                .collect(),
+            on_delete: on_delete.map(|action| action.to_string()),
+            on_update: on_update.map(|action| action.to_string()),
+        })
+    } else {
+        None
+    }
+}
+
+/// 解析CHECK约束，未命名的约束按MySQL的规则自动命名为 `{表名}_chk_{序号}`
+fn parse_check_constraint(
+    constraint: &TableConstraint,
+    table_name: &str,
+    existing_count: usize,
+) -> Option<TableCheck> {
+    if let TableConstraint::Check {
+        name,
+        expr,
+        enforced,
+    } = constraint
+    {
+        let check_name = name
+            .as_ref()
+            .map(|n| n.value.clone())
+            .unwrap_or_else(|| format!("{table_name}_chk_{}", existing_count + 1));
+
+        Some(TableCheck {
+            name: check_name,
+            expression: expr.to_string(),
+            enforced: *enforced,
+        })
+    } else {
+        None
+    }
+}
+
+/// 从 CreateTable 的表选项中提取 ENGINE、字符集、排序规则、行格式和表注释
+fn apply_table_options(table_def: &mut TableDefinition, table_options: &CreateTableOptions) {
+    let options = match table_options {
+        CreateTableOptions::Plain(options)
+        | CreateTableOptions::With(options)
+        | CreateTableOptions::Options(options)
+        | CreateTableOptions::TableProperties(options) => options,
+        CreateTableOptions::None => return,
+    };
+
+    for option in options {
+        match option {
+            SqlOption::NamedParenthesizedList(list)
+                if list.key.value.eq_ignore_ascii_case("ENGINE") =>
+            {
+                table_def.engine = list.name.as_ref().map(|name| name.value.clone());
+            }
+            SqlOption::KeyValue { key, value } => {
+                let value = option_value_to_string(value);
+                match key.value.to_uppercase().as_str() {
+                    "ENGINE" => table_def.engine = Some(value),
+                    "CHARSET" | "DEFAULT CHARSET" | "CHARACTER SET" | "DEFAULT CHARACTER SET" => {
+                        table_def.charset = Some(value)
+                    }
+                    "COLLATE" | "DEFAULT COLLATE" => table_def.collation = Some(value),
+                    "ROW_FORMAT" => table_def.row_format = Some(value.to_uppercase()),
+                    _ => {}
+                }
+            }
+            SqlOption::Comment(CommentDef::WithEq(comment) | CommentDef::WithoutEq(comment)) => {
+                table_def.comment = Some(comment.clone());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 将表选项的值转换为字符串（去掉引号）
+fn option_value_to_string(value: &Expr) -> String {
+    match value {
+        Expr::Identifier(ident) => ident.value.clone(),
+        Expr::Value(value) => value
+            .value
+            .clone()
+            .into_string()
+            .unwrap_or_else(|| value.to_string()),
+        _ => value.to_string(),
+    }
+}
+
 /// 格式化数据类型
 fn format_data_type(data_type: &DataType) -> String {
     match data_type {
@@ -319,17 +800,16 @@ fn format_data_type(data_type: &DataType) -> String {
         DataType::BigInt(_) => "BIGINT".to_string(),
         DataType::Float(_) => "FLOAT".to_string(),
         DataType::Double(_) => "DOUBLE".to_string(),
-        DataType::Decimal(_exact_number_info) => {
-            // ExactNumberInfo 包含精度和刻度信息
-            "DECIMAL".to_string() // 简化处理，可以后续优化
-        }
+        // 保留精度和刻度，如 DECIMAL(10,2)
+        DataType::Decimal(_) => data_type.to_string().replace(", ", ","),
         DataType::Boolean => "BOOLEAN".to_string(),
         DataType::Date => "DATE".to_string(),
         DataType::Time(_, _) => "TIME".to_string(),
         DataType::Timestamp(_, _) => "TIMESTAMP".to_string(),
         DataType::Datetime(_) => "DATETIME".to_string(),
         DataType::JSON => "JSON".to_string(),
-        _ => format!("{data_type:?}"), // 对于其他类型，使用 Debug 格式
+        // 其他类型使用SQL格式（如 TINYINT(1)、INT UNSIGNED、ENUM('a', 'b')）
+        _ => data_type.to_string(),
     }
 }
 
@@ -344,29 +824,3 @@ fn is_column_primary_key(column: &ColumnDef) -> bool {
     }
     false
 }
-
-/// 检查列是否是主键列
-fn is_primary_key_column(column: &ColumnDef, constraints: &[TableConstraint]) -> bool {
-    // 首先检查列级别的主键定义
-    for option in &column.options {
-        if let sqlparser::ast::ColumnOption::Unique { is_primary, .. } = &option.option {
-            if *is_primary {
-                return true;
-            }
-        }
-    }
-
-    // 然后检查表级别的主键约束
-    let column_name = column.name.to_string();
-    for constraint in constraints {
-        if let TableConstraint::PrimaryKey { columns, .. } = constraint {
-            for pk_column in columns {
-                if pk_column.to_string() == column_name {
-                    return true;
-                }
-            }
-        }
-    }
-
-    false
-}
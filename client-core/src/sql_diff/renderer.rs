@@ -0,0 +1,820 @@
+use super::differ::{normalize_object_definition, option_changed};
+use super::generator::{
+    escape_sql_string, generate_check_sql, generate_column_sql, generate_create_table_sql,
+    generate_foreign_key_sql,
+};
+use super::types::{
+    ChangeSafety, DatabaseDiff, DatabaseOptions, DefinitionChange, DiffStatement, ObjectDefinition,
+    ObjectKind, SchemaDiff, SeedDataDiff, SeedRow, TableColumn, TableDiff, TableIndex,
+    TableOptions,
+};
+use tracing::info;
+
+/// 差异SQL中的一组语句（输出时带注释标题）
+struct DiffSection {
+    title: String,
+    statements: Vec<DiffStatement>,
+}
+
+/// 把结构化的架构差异渲染为MySQL差异SQL，同时返回每条语句的安全级别
+///
+/// 先新建和修改数据库，再按数据库输出表差异：默认数据库的语句在最前，
+/// 其他数据库的语句前加上 `USE` 切换数据库，最后删除数据库。
+pub fn render_mysql_diff(diff: &SchemaDiff) -> (String, Vec<DiffStatement>) {
+    // (切换到的数据库, 语句分组)
+    let mut blocks: Vec<(Option<&str>, Vec<DiffSection>)> = Vec::new();
+
+    let mut database_sections = Vec::new();
+    for options in &diff.added_databases {
+        push_section(
+            &mut database_sections,
+            format!("新增数据库: {}", options.name),
+            vec![DiffStatement::new(
+                generate_create_database_sql(options),
+                ChangeSafety::Safe,
+            )],
+        );
+    }
+    for change in &diff.modified_databases {
+        push_section(
+            &mut database_sections,
+            format!("修改数据库: {}", change.after.name),
+            generate_alter_database_sql(&change.after)
+                .into_iter()
+                .collect(),
+        );
+    }
+    blocks.push((None, database_sections));
+
+    for database_diff in &diff.databases {
+        blocks.push((
+            database_diff.database.as_deref(),
+            render_database_sections(database_diff),
+        ));
+    }
+
+    let mut drop_sections = Vec::new();
+    for options in &diff.removed_databases {
+        push_section(
+            &mut drop_sections,
+            format!("删除数据库: {}", options.name),
+            vec![DiffStatement::new(
+                format!("DROP DATABASE IF EXISTS `{}`;", options.name),
+                ChangeSafety::DataLoss,
+            )],
+        );
+    }
+    blocks.push((None, drop_sections));
+
+    blocks.retain(|(_, sections)| !sections.is_empty());
+    if blocks.is_empty() {
+        info!("没有发现实际的表结构差异");
+        return (String::new(), Vec::new());
+    }
+
+    // 添加注释头
+    let mut diff_sql = vec![
+        "-- 数据库架构差异SQL".to_string(),
+        format!(
+            "-- 生成时间: {}",
+            chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC")
+        ),
+        "".to_string(),
+    ];
+    let mut statements = Vec::new();
+    for (database, sections) in blocks {
+        if let Some(database) = database {
+            diff_sql.push(format!("USE `{database}`;"));
+            diff_sql.push("".to_string());
+        }
+        for section in sections {
+            diff_sql.push(format!("-- {}", section.title));
+            diff_sql.extend(section.statements.iter().map(|s| s.sql.clone()));
+            diff_sql.push("".to_string());
+            statements.extend(section.statements);
+        }
+    }
+
+    (diff_sql.join("\n"), statements)
+}
+
+/// 生成 `CREATE DATABASE` SQL
+fn generate_create_database_sql(options: &DatabaseOptions) -> String {
+    let mut sql = format!("CREATE DATABASE IF NOT EXISTS `{}`", options.name);
+    if let Some(charset) = &options.charset {
+        sql.push_str(&format!(" DEFAULT CHARACTER SET {charset}"));
+    }
+    if let Some(collation) = &options.collation {
+        sql.push_str(&format!(" COLLATE {collation}"));
+    }
+    sql.push(';');
+    sql
+}
+
+/// 生成修改数据库默认字符集的SQL（只影响之后新建的表，不会转换现有数据）
+fn generate_alter_database_sql(options: &DatabaseOptions) -> Option<DiffStatement> {
+    if options.charset.is_none() && options.collation.is_none() {
+        return None;
+    }
+
+    let mut sql = format!("ALTER DATABASE `{}`", options.name);
+    if let Some(charset) = &options.charset {
+        sql.push_str(&format!(" CHARACTER SET {charset}"));
+    }
+    if let Some(collation) = &options.collation {
+        sql.push_str(&format!(" COLLATE {collation}"));
+    }
+    sql.push(';');
+    Some(DiffStatement::new(sql, ChangeSafety::Safe))
+}
+
+/// 渲染单个数据库内的表差异
+///
+/// 语句按依赖安全的顺序输出：
+/// 1. 删除已移除或定义变化的触发器、存储过程和函数，以及已移除的视图
+/// 2. 删除外键和CHECK约束（避免阻塞后续的重命名、列修改和删表）
+/// 3. 重命名表和列
+/// 4. 新增表（不含外键）
+/// 5. 修改表选项、列和索引
+/// 6. 新增CHECK约束和外键（此时被引用的表和列都已存在）
+/// 7. 同步参考数据（先按逆序删除，再更新，最后按顺序插入）
+/// 8. 创建新增和定义变化的对象（此时依赖的表和列都已就绪，视图使用 `CREATE OR REPLACE`）
+/// 9. 删除表
+fn render_database_sections(diff: &DatabaseDiff) -> Vec<DiffSection> {
+    let mut sections = Vec::new();
+
+    // 1. 删除对象（视图的修改用 CREATE OR REPLACE 完成，不需要先删除）
+    let dropped_objects = diff.removed_objects.iter().chain(
+        diff.modified_objects
+            .iter()
+            .map(|change| &change.before)
+            .filter(|object| object.kind != ObjectKind::View),
+    );
+    for object in dropped_objects {
+        push_section(
+            &mut sections,
+            format!("删除{}: {}", object.kind.description(), object.name),
+            vec![DiffStatement::new(
+                format!("DROP {} IF EXISTS `{}`;", object.kind.as_str(), object.name),
+                ChangeSafety::Safe,
+            )],
+        );
+    }
+
+    // 2. 删除变化或移除的外键和CHECK约束（此时表还未重命名，使用旧表名）
+    for table_diff in &diff.modified_tables {
+        let old_name = table_diff
+            .renamed_from
+            .as_deref()
+            .unwrap_or(&table_diff.name);
+        push_section(
+            &mut sections,
+            format!("删除约束: {old_name}"),
+            generate_constraint_drops(old_name, table_diff),
+        );
+    }
+    // 待删除的表之间的外键需要先删除，否则删表顺序会受外键限制
+    for table_def in &diff.removed_tables {
+        let table_name = &table_def.name;
+        let drops = table_def
+            .foreign_keys
+            .iter()
+            .filter(|fk| {
+                fk.referenced_table != *table_name
+                    && diff
+                        .removed_tables
+                        .iter()
+                        .any(|removed| removed.name == fk.referenced_table)
+            })
+            .map(|fk| {
+                DiffStatement::new(
+                    format!("ALTER TABLE `{table_name}` DROP FOREIGN KEY `{}`;", fk.name),
+                    ChangeSafety::Safe,
+                )
+            })
+            .collect();
+        push_section(&mut sections, format!("删除约束: {table_name}"), drops);
+    }
+
+    // 3. 重命名表和列
+    let rename_statements = diff
+        .renamed_tables
+        .iter()
+        .map(|rename| {
+            DiffStatement::new(
+                format!("RENAME TABLE `{}` TO `{}`;", rename.from, rename.to),
+                ChangeSafety::Safe,
+            )
+        })
+        .chain(diff.modified_tables.iter().flat_map(|table_diff| {
+            table_diff.renamed_columns.iter().map(|rename| {
+                DiffStatement::new(
+                    format!(
+                        "ALTER TABLE `{}` RENAME COLUMN `{}` TO `{}`;",
+                        table_diff.name, rename.from, rename.to
+                    ),
+                    ChangeSafety::Safe,
+                )
+            })
+        }))
+        .collect();
+    push_section(&mut sections, "重命名表和列".to_string(), rename_statements);
+
+    // 4. 新增表（外键在所有表创建完成后统一添加）
+    for table_def in &diff.added_tables {
+        let mut table_def = table_def.clone();
+        table_def.foreign_keys.clear();
+        push_section(
+            &mut sections,
+            format!("新增表: {}", table_def.name),
+            vec![DiffStatement::new(
+                generate_create_table_sql(&table_def),
+                ChangeSafety::Safe,
+            )],
+        );
+    }
+
+    // 5. 修改表
+    for table_diff in &diff.modified_tables {
+        push_section(
+            &mut sections,
+            format!("修改表: {}", table_diff.name),
+            generate_table_diff(table_diff),
+        );
+    }
+
+    // 6. 新增CHECK约束和外键
+    for table_diff in &diff.modified_tables {
+        push_section(
+            &mut sections,
+            format!("新增约束: {}", table_diff.name),
+            generate_constraint_adds(table_diff),
+        );
+    }
+    for table_def in &diff.added_tables {
+        let table_name = &table_def.name;
+        let adds = table_def
+            .foreign_keys
+            .iter()
+            .map(|foreign_key| {
+                DiffStatement::new(
+                    format!(
+                        "ALTER TABLE `{table_name}` ADD {};",
+                        generate_foreign_key_sql(foreign_key)
+                    ),
+                    ChangeSafety::Locking,
+                )
+            })
+            .collect();
+        push_section(&mut sections, format!("新增约束: {table_name}"), adds);
+    }
+
+    // 7. 参考数据（删除按表的逆序，避免先删除被引用的行）
+    for seed_diff in diff.seed_data.iter().rev() {
+        let deletes = seed_diff
+            .deleted_rows
+            .iter()
+            .map(|row| {
+                DiffStatement::new(
+                    format!(
+                        "DELETE FROM `{}` WHERE {};",
+                        seed_diff.table,
+                        seed_row_condition(seed_diff, row, row.keys())
+                    ),
+                    ChangeSafety::DataLoss,
+                )
+            })
+            .collect();
+        push_section(
+            &mut sections,
+            format!("删除参考数据: {}", seed_diff.table),
+            deletes,
+        );
+    }
+    for seed_diff in &diff.seed_data {
+        push_section(
+            &mut sections,
+            format!("更新参考数据: {}", seed_diff.table),
+            seed_diff
+                .updated_rows
+                .iter()
+                .filter_map(|change| generate_seed_update_sql(seed_diff, change))
+                .collect(),
+        );
+        push_section(
+            &mut sections,
+            format!("新增参考数据: {}", seed_diff.table),
+            seed_diff
+                .inserted_rows
+                .iter()
+                .map(|row| {
+                    DiffStatement::new(
+                        format!(
+                            "INSERT IGNORE INTO `{}` ({}) VALUES ({});",
+                            seed_diff.table,
+                            row.keys()
+                                .map(|column| format!("`{column}`"))
+                                .collect::<Vec<_>>()
+                                .join(", "),
+                            row.values().cloned().collect::<Vec<_>>().join(", ")
+                        ),
+                        ChangeSafety::Safe,
+                    )
+                })
+                .collect(),
+        );
+    }
+
+    // 8. 创建对象
+    let created_objects = diff
+        .added_objects
+        .iter()
+        .map(|object| (object, false))
+        .chain(
+            diff.modified_objects
+                .iter()
+                .map(|change| (&change.after, true)),
+        )
+        .collect();
+    for (object, modified) in order_by_dependency(created_objects) {
+        let action = if modified { "修改" } else { "新增" };
+        push_section(
+            &mut sections,
+            format!("{action}{}: {}", object.kind.description(), object.name),
+            vec![generate_create_object_sql(
+                object,
+                modified && object.kind == ObjectKind::View,
+            )],
+        );
+    }
+
+    // 9. 删除表
+    for table_def in &diff.removed_tables {
+        push_section(
+            &mut sections,
+            format!("删除表: {}", table_def.name),
+            vec![DiffStatement::new(
+                format!("DROP TABLE IF EXISTS `{}`;", table_def.name),
+                ChangeSafety::DataLoss,
+            )],
+        );
+    }
+
+    sections
+}
+
+/// 生成创建对象的SQL
+///
+/// 定义体中包含分号时（存储过程、触发器的 `BEGIN ... END`），用 `DELIMITER` 包裹，
+/// 保证MySQL客户端按完整语句执行。
+fn generate_create_object_sql(object: &ObjectDefinition, replace: bool) -> DiffStatement {
+    let mut definition = object.definition.trim().to_string();
+    if replace {
+        let create_len = "CREATE".len();
+        let rest = definition[create_len..].trim_start();
+        let has_replace = rest
+            .get(.."OR REPLACE".len())
+            .is_some_and(|keyword| keyword.eq_ignore_ascii_case("OR REPLACE"));
+        if !has_replace {
+            definition = format!("CREATE OR REPLACE {rest}");
+        }
+    }
+
+    let sql = if definition.contains(';') {
+        format!("DELIMITER $$\n{definition}$$\nDELIMITER ;")
+    } else {
+        format!("{definition};")
+    };
+    DiffStatement::new(sql, ChangeSafety::Safe)
+}
+
+/// 按依赖关系排序待创建的对象：定义中引用了其他待创建对象的排在后面，
+/// 无法确定依赖顺序时保持原顺序
+fn order_by_dependency(
+    mut pending: Vec<(&ObjectDefinition, bool)>,
+) -> Vec<(&ObjectDefinition, bool)> {
+    let mut ordered = Vec::new();
+
+    while !pending.is_empty() {
+        let ready = pending.iter().position(|(object, _)| {
+            let definition = normalize_object_definition(&object.definition);
+            !pending.iter().any(|(other, _)| {
+                !std::ptr::eq(*other, *object)
+                    && references_name(&definition, &other.name.to_lowercase())
+            })
+        });
+        match ready {
+            Some(position) => ordered.push(pending.remove(position)),
+            None => {
+                ordered.append(&mut pending);
+            }
+        }
+    }
+
+    ordered
+}
+
+/// 判断规范化后的定义中是否以完整标识符的形式引用了指定名称
+fn references_name(definition: &str, name: &str) -> bool {
+    let is_identifier_char = |c: char| c.is_alphanumeric() || c == '_' || c == '$';
+    definition.match_indices(name).any(|(start, _)| {
+        let before = definition[..start].chars().next_back();
+        let after = definition[start + name.len()..].chars().next();
+        !before.is_some_and(is_identifier_char) && !after.is_some_and(is_identifier_char)
+    })
+}
+
+/// 生成更新参考数据的SQL
+///
+/// 只更新值有变化的列，并要求这些列仍是旧脚本中的值，用户修改过的行保持不变。
+fn generate_seed_update_sql(
+    seed_diff: &SeedDataDiff,
+    change: &DefinitionChange<SeedRow>,
+) -> Option<DiffStatement> {
+    let changed_columns: Vec<&String> = change
+        .after
+        .iter()
+        .filter(|(column, value)| {
+            !seed_diff.key_columns.contains(column) && change.before.get(*column) != Some(*value)
+        })
+        .map(|(column, _)| column)
+        .collect();
+    if changed_columns.is_empty() {
+        return None;
+    }
+
+    let assignments = changed_columns
+        .iter()
+        .map(|column| format!("`{column}` = {}", change.after[*column]))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let guarded_columns = changed_columns
+        .into_iter()
+        .filter(|column| change.before.contains_key(*column));
+    Some(DiffStatement::new(
+        format!(
+            "UPDATE `{}` SET {assignments} WHERE {};",
+            seed_diff.table,
+            seed_row_condition(seed_diff, &change.before, guarded_columns)
+        ),
+        ChangeSafety::Safe,
+    ))
+}
+
+/// 生成匹配参考数据行的条件：按主键定位，并要求指定的列仍是脚本中的值
+/// （用 `<=>` 比较以支持 `NULL`）
+fn seed_row_condition<'a>(
+    seed_diff: &SeedDataDiff,
+    row: &SeedRow,
+    guarded_columns: impl Iterator<Item = &'a String>,
+) -> String {
+    let key_conditions = seed_diff
+        .key_columns
+        .iter()
+        .filter_map(|column| row.get(column).map(|value| format!("`{column}` = {value}")));
+    let guard_conditions = guarded_columns
+        .filter(|column| !seed_diff.key_columns.contains(column))
+        .map(|column| format!("`{column}` <=> {}", row[column]));
+    key_conditions
+        .chain(guard_conditions)
+        .collect::<Vec<_>>()
+        .join(" AND ")
+}
+
+/// 添加非空的语句分组
+fn push_section(sections: &mut Vec<DiffSection>, title: String, statements: Vec<DiffStatement>) {
+    if !statements.is_empty() {
+        sections.push(DiffSection { title, statements });
+    }
+}
+
+/// 生成表差异SQL（表选项、列和索引，不包括外键和CHECK约束）
+fn generate_table_diff(table_diff: &TableDiff) -> Vec<DiffStatement> {
+    let mut diffs = Vec::new();
+    let table_name = &table_diff.name;
+
+    // 表选项差异
+    if let Some(options) = &table_diff.options {
+        diffs.extend(generate_table_option_diffs(table_name, options));
+    }
+
+    // 列差异：按目标列顺序新增列和调整位置（带 FIRST/AFTER），再删除列，最后修改其余列
+    for position in &table_diff.column_positions {
+        let placement = match &position.after {
+            Some(after) => format!("AFTER `{after}`"),
+            None => "FIRST".to_string(),
+        };
+        let column_name = &position.column;
+
+        if let Some(column) = table_diff
+            .added_columns
+            .iter()
+            .find(|column| column.name == *column_name)
+        {
+            diffs.push(DiffStatement::new(
+                format!(
+                    "ALTER TABLE `{table_name}` ADD COLUMN {} {placement};",
+                    generate_column_sql(column)
+                ),
+                ChangeSafety::Safe,
+            ));
+        } else if let Some(change) = table_diff
+            .modified_columns
+            .iter()
+            .find(|change| change.after.name == *column_name)
+        {
+            // 调整列位置需要重建表
+            diffs.push(DiffStatement::new(
+                format!(
+                    "ALTER TABLE `{table_name}` MODIFY COLUMN {} {placement};",
+                    generate_column_sql(&change.after)
+                ),
+                classify_column_change(&change.before, &change.after).max(ChangeSafety::Locking),
+            ));
+        } else if let Some(column) = table_diff
+            .moved_columns
+            .iter()
+            .find(|column| column.name == *column_name)
+        {
+            diffs.push(DiffStatement::new(
+                format!(
+                    "ALTER TABLE `{table_name}` MODIFY COLUMN {} {placement};",
+                    generate_column_sql(column)
+                ),
+                ChangeSafety::Locking,
+            ));
+        }
+    }
+    for column in &table_diff.removed_columns {
+        diffs.push(DiffStatement::new(
+            format!("ALTER TABLE `{table_name}` DROP COLUMN `{}`;", column.name),
+            ChangeSafety::DataLoss,
+        ));
+    }
+    for change in &table_diff.modified_columns {
+        if table_diff
+            .column_positions
+            .iter()
+            .any(|position| position.column == change.after.name)
+        {
+            continue;
+        }
+        diffs.push(DiffStatement::new(
+            format!(
+                "ALTER TABLE `{}` MODIFY COLUMN {};",
+                table_name,
+                generate_column_sql(&change.after)
+            ),
+            classify_column_change(&change.before, &change.after),
+        ));
+    }
+
+    // 索引差异（修改的索引先删除旧的，再添加新的）
+    for index in &table_diff.added_indexes {
+        diffs.push(generate_add_index_sql(table_name, index));
+    }
+    for index in &table_diff.removed_indexes {
+        diffs.push(generate_drop_index_sql(table_name, index));
+    }
+    for change in &table_diff.modified_indexes {
+        diffs.push(generate_drop_index_sql(table_name, &change.before));
+        diffs.push(generate_add_index_sql(table_name, &change.after));
+    }
+
+    diffs
+}
+
+/// 生成表选项差异SQL（ENGINE、字符集、排序规则、行格式、表注释）
+fn generate_table_option_diffs(
+    table_name: &str,
+    options: &DefinitionChange<TableOptions>,
+) -> Vec<DiffStatement> {
+    let mut diffs = Vec::new();
+    let (old, new) = (&options.before, &options.after);
+
+    // 修改存储引擎、字符集和行格式都需要重建表
+    if let Some(engine) = &new.engine
+        && option_changed(&old.engine, &new.engine)
+    {
+        diffs.push(DiffStatement::new(
+            format!("ALTER TABLE `{table_name}` ENGINE={engine};"),
+            ChangeSafety::Locking,
+        ));
+    }
+
+    if option_changed(&old.charset, &new.charset) || option_changed(&old.collation, &new.collation)
+    {
+        // 只指定排序规则时，字符集取排序规则的前缀（如 utf8mb4_unicode_ci -> utf8mb4）
+        let charset = new.charset.clone().or_else(|| {
+            new.collation
+                .as_ref()
+                .and_then(|collation| collation.split('_').next())
+                .map(|charset| charset.to_string())
+        });
+
+        if let Some(charset) = charset {
+            let mut sql = format!("ALTER TABLE `{table_name}` CONVERT TO CHARACTER SET {charset}");
+            if let Some(collation) = &new.collation {
+                sql.push_str(&format!(" COLLATE {collation}"));
+            }
+            sql.push(';');
+            diffs.push(DiffStatement::new(sql, ChangeSafety::Locking));
+        }
+    }
+
+    if let Some(row_format) = &new.row_format
+        && option_changed(&old.row_format, &new.row_format)
+    {
+        diffs.push(DiffStatement::new(
+            format!("ALTER TABLE `{table_name}` ROW_FORMAT={row_format};"),
+            ChangeSafety::Locking,
+        ));
+    }
+
+    if old.comment != new.comment {
+        diffs.push(DiffStatement::new(
+            format!(
+                "ALTER TABLE `{}` COMMENT='{}';",
+                table_name,
+                escape_sql_string(new.comment.as_deref().unwrap_or(""))
+            ),
+            ChangeSafety::Safe,
+        ));
+    }
+
+    diffs
+}
+
+/// 生成需要先删除的外键和CHECK约束（已移除或定义变化的约束）
+fn generate_constraint_drops(table_name: &str, table_diff: &TableDiff) -> Vec<DiffStatement> {
+    let foreign_keys = table_diff.removed_foreign_keys.iter().chain(
+        table_diff
+            .modified_foreign_keys
+            .iter()
+            .map(|change| &change.before),
+    );
+    let checks = table_diff.removed_checks.iter().chain(
+        table_diff
+            .modified_checks
+            .iter()
+            .map(|change| &change.before),
+    );
+
+    foreign_keys
+        .map(|fk| format!("ALTER TABLE `{table_name}` DROP FOREIGN KEY `{}`;", fk.name))
+        .chain(
+            checks.map(|check| format!("ALTER TABLE `{table_name}` DROP CHECK `{}`;", check.name)),
+        )
+        .map(|sql| DiffStatement::new(sql, ChangeSafety::Safe))
+        .collect()
+}
+
+/// 生成需要新增的CHECK约束和外键（新增或定义变化的约束）
+///
+/// 新增约束时MySQL需要校验全表数据，因此标记为锁表操作。
+fn generate_constraint_adds(table_diff: &TableDiff) -> Vec<DiffStatement> {
+    let table_name = &table_diff.name;
+    let checks = table_diff.added_checks.iter().chain(
+        table_diff
+            .modified_checks
+            .iter()
+            .map(|change| &change.after),
+    );
+    let foreign_keys = table_diff.added_foreign_keys.iter().chain(
+        table_diff
+            .modified_foreign_keys
+            .iter()
+            .map(|change| &change.after),
+    );
+
+    checks
+        .map(generate_check_sql)
+        .chain(foreign_keys.map(generate_foreign_key_sql))
+        .map(|definition| {
+            DiffStatement::new(
+                format!("ALTER TABLE `{table_name}` ADD {definition};"),
+                ChangeSafety::Locking,
+            )
+        })
+        .collect()
+}
+
+/// 整数类型，按取值范围从小到大排列
+const INTEGER_TYPES: [&str; 5] = ["TINYINT", "SMALLINT", "MEDIUMINT", "INT", "BIGINT"];
+
+/// 文本类型，按最大长度从小到大排列
+const TEXT_TYPES: [&str; 4] = ["TINYTEXT", "TEXT", "MEDIUMTEXT", "LONGTEXT"];
+
+/// 判断列修改的安全级别
+///
+/// - 收窄或改变数据类型可能截断数据：数据丢失
+/// - 放宽数据类型、改为 NOT NULL、修改自增属性需要重建表或校验数据：锁表
+/// - 只修改默认值或注释：安全
+fn classify_column_change(old_column: &TableColumn, new_column: &TableColumn) -> ChangeSafety {
+    if !old_column
+        .data_type
+        .eq_ignore_ascii_case(&new_column.data_type)
+    {
+        return if is_widening_type_change(&old_column.data_type, &new_column.data_type) {
+            ChangeSafety::Locking
+        } else {
+            ChangeSafety::DataLoss
+        };
+    }
+
+    if (old_column.nullable && !new_column.nullable)
+        || old_column.auto_increment != new_column.auto_increment
+    {
+        return ChangeSafety::Locking;
+    }
+
+    ChangeSafety::Safe
+}
+
+/// 判断数据类型修改是否只放宽取值范围（不会截断现有数据）
+fn is_widening_type_change(old_type: &str, new_type: &str) -> bool {
+    let (old_base, old_args) = split_data_type(old_type);
+    let (new_base, new_args) = split_data_type(new_type);
+    let rank = |types: &[&str], base: &str| {
+        let base = if base == "INTEGER" { "INT" } else { base };
+        types.iter().position(|t| *t == base)
+    };
+
+    if let (Some(old_rank), Some(new_rank)) = (
+        rank(&INTEGER_TYPES, &old_base),
+        rank(&INTEGER_TYPES, &new_base),
+    ) {
+        return new_rank >= old_rank;
+    }
+    if let (Some(old_rank), Some(new_rank)) =
+        (rank(&TEXT_TYPES, &old_base), rank(&TEXT_TYPES, &new_base))
+    {
+        return new_rank >= old_rank;
+    }
+
+    match (old_base.as_str(), new_base.as_str()) {
+        ("CHAR" | "VARCHAR", "CHAR" | "VARCHAR") => matches!(
+            (old_args.first(), new_args.first()),
+            (Some(old_len), Some(new_len)) if new_len >= old_len
+        ),
+        ("CHAR" | "VARCHAR", base) => TEXT_TYPES.contains(&base),
+        ("FLOAT", "DOUBLE") | ("DATE", "DATETIME") => true,
+        _ => false,
+    }
+}
+
+/// 拆分数据类型为基础类型和数字参数（如 `VARCHAR(255)` -> (`VARCHAR`, [255])）
+fn split_data_type(data_type: &str) -> (String, Vec<u64>) {
+    let data_type = data_type.trim().to_uppercase();
+    match data_type.split_once('(') {
+        Some((base, args)) => (
+            base.trim().to_string(),
+            args.trim_end_matches(')')
+                .split(',')
+                .filter_map(|arg| arg.trim().parse().ok())
+                .collect(),
+        ),
+        None => (data_type, Vec::new()),
+    }
+}
+
+/// 生成新增索引SQL（新增主键需要重建表，新增索引需要扫描全表，均标记为锁表）
+fn generate_add_index_sql(table_name: &str, index: &TableIndex) -> DiffStatement {
+    let columns = index
+        .columns
+        .iter()
+        .map(|c| format!("`{c}`"))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let sql = if index.is_primary {
+        format!("ALTER TABLE `{table_name}` ADD PRIMARY KEY ({columns});")
+    } else if index.is_unique {
+        format!(
+            "ALTER TABLE `{table_name}` ADD UNIQUE KEY `{}` ({columns});",
+            index.name
+        )
+    } else {
+        format!(
+            "ALTER TABLE `{table_name}` ADD KEY `{}` ({columns});",
+            index.name
+        )
+    };
+
+    DiffStatement::new(sql, ChangeSafety::Locking)
+}
+
+/// 生成删除索引SQL（删除主键需要重建表）
+fn generate_drop_index_sql(table_name: &str, index: &TableIndex) -> DiffStatement {
+    if index.is_primary {
+        DiffStatement::new(
+            format!("ALTER TABLE `{table_name}` DROP PRIMARY KEY;"),
+            ChangeSafety::Locking,
+        )
+    } else {
+        DiffStatement::new(
+            format!("ALTER TABLE `{table_name}` DROP KEY `{}`;", index.name),
+            ChangeSafety::Safe,
+        )
+    }
+}
@@ -1,5 +1,9 @@
-use super::parser::parse_sql_tables;
+use super::parser::{
+    parse_column_definition, parse_rename_hints, parse_sql_schema, parse_sql_tables,
+    split_sql_statements,
+};
 use super::*;
+use std::collections::HashMap;
 
 #[test]
 fn test_simple_diff() {
@@ -60,14 +64,14 @@ CREATE USER IF NOT EXISTS 'test_user'@'%' IDENTIFIED BY 'password123';
 GRANT ALL PRIVILEGES ON test_db.* TO 'test_user'@'%';
 FLUSH PRIVILEGES;
 
--- 这些语句应该被忽略，因为在USE语句之前
-CREATE TABLE should_be_ignored (
+-- USE语句之前的表属于连接的默认数据库
+CREATE TABLE default_table (
     id INT PRIMARY KEY
 );
 
 USE test_db;
 
--- 从这里开始才是我们要解析的内容
+-- 之后的表属于 test_db
 CREATE TABLE users (
     id INT NOT NULL AUTO_INCREMENT,
     name VARCHAR(255) NOT NULL,
@@ -76,15 +80,23 @@ CREATE TABLE users (
     "#;
 
     let tables = parse_sql_tables(sql).unwrap();
-    assert_eq!(tables.len(), 1);
+    assert_eq!(tables.len(), 2);
 
     let users_table = tables.get("users").unwrap();
     assert_eq!(users_table.name, "users");
     assert_eq!(users_table.columns.len(), 2);
     assert_eq!(users_table.indexes.len(), 1);
 
-    // 确保被忽略的表没有被解析
-    assert!(tables.get("should_be_ignored").is_none());
+    // 按数据库分组：USE之前的表不再被忽略，而是归入默认数据库
+    let schema = parse_sql_schema(sql).unwrap();
+    let default_database = schema.database(None).unwrap();
+    assert!(default_database.tables.contains_key("default_table"));
+    let test_db = schema.database(Some("test_db")).unwrap();
+    assert_eq!(test_db.options.as_ref().unwrap().name, "test_db");
+    assert_eq!(
+        test_db.tables.keys().collect::<Vec<_>>(),
+        vec![&"users".to_string()]
+    );
 }
 
 #[test]
@@ -228,6 +240,7 @@ CREATE TABLE users (
 
     assert!(diff_sql.contains("ALTER TABLE") && diff_sql.contains("ADD"));
     assert!(diff_sql.contains("KEY") || diff_sql.contains("INDEX"));
+    assert!(description.contains("新增索引"));
 }
 
 #[test]
@@ -505,13 +518,13 @@ GRANT ALL PRIVILEGES ON logs_app.* TO 'app_user'@'%';
 GRANT SELECT, INSERT ON cache_app.* TO 'app_user'@'%';
 FLUSH PRIVILEGES;
 
--- 这些表定义应该被忽略，因为在USE语句之前
-CREATE TABLE ignored_table1 (
+-- 这些表在USE语句之前，属于连接的默认数据库
+CREATE TABLE default_table1 (
     id INT PRIMARY KEY,
     data VARCHAR(100)
 );
 
-CREATE TABLE ignored_table2 (
+CREATE TABLE default_table2 (
     id BIGINT AUTO_INCREMENT PRIMARY KEY,
     name VARCHAR(255) NOT NULL
 ) ENGINE=InnoDB;
@@ -523,7 +536,7 @@ SET GLOBAL innodb_file_per_table = ON;
 -- 现在切换到目标数据库
 USE main_app;
 
--- 从这里开始才是我们要解析的表定义
+-- 之后的表属于 main_app
 CREATE TABLE users (
     id BIGINT AUTO_INCREMENT PRIMARY KEY,
     username VARCHAR(64) NOT NULL COMMENT '用户名',
@@ -553,14 +566,18 @@ CREATE TABLE posts (
     println!("解析到的表数量: {}", tables.len());
     println!("解析到的表: {:?}", tables.keys().collect::<Vec<_>>());
 
-    // 应该只解析到USE语句之后的表
-    assert_eq!(tables.len(), 2);
+    assert_eq!(tables.len(), 4);
     assert!(tables.contains_key("users"));
     assert!(tables.contains_key("posts"));
 
-    // 确保被忽略的表没有被解析
-    assert!(!tables.contains_key("ignored_table1"));
-    assert!(!tables.contains_key("ignored_table2"));
+    // USE语句之前的表归入默认数据库，之后的表归入 main_app
+    let schema = parse_sql_schema(sql_with_interference).unwrap();
+    let qualified_tables = schema.qualified_tables();
+    assert!(qualified_tables.contains_key("default_table1"));
+    assert!(qualified_tables.contains_key("default_table2"));
+    assert!(qualified_tables.contains_key("main_app.users"));
+    assert!(qualified_tables.contains_key("main_app.posts"));
+    assert!(schema.database(Some("logs_app")).unwrap().tables.is_empty());
 
     // 验证users表的结构
     let users_table = tables.get("users").unwrap();
@@ -780,3 +797,1240 @@ CREATE TABLE comments (
 
     assert!(diff_sql.contains("posts"));
 }
+
+#[test]
+fn test_extract_use_database() {
+    let sql = r#"
+CREATE DATABASE IF NOT EXISTS test_platform;
+-- USE commented_db;
+USE `test_platform`;
+
+CREATE TABLE users (id INT PRIMARY KEY);
+    "#;
+
+    assert_eq!(extract_use_database(sql), Some("test_platform".to_string()));
+    assert_eq!(
+        extract_use_database("CREATE TABLE users (id INT PRIMARY KEY);"),
+        None
+    );
+}
+
+#[test]
+fn test_parse_constraints_and_table_options() {
+    let sql = r#"
+CREATE TABLE `orders` (
+    `id` INT NOT NULL AUTO_INCREMENT,
+    `user_id` INT NOT NULL,
+    `amount` DECIMAL(10,2) NOT NULL,
+    PRIMARY KEY (`id`),
+    CONSTRAINT `fk_orders_user` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`) ON DELETE CASCADE,
+    FOREIGN KEY (`user_id`) REFERENCES `accounts` (`id`),
+    CONSTRAINT `chk_amount` CHECK (`amount` >= 0)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci ROW_FORMAT=dynamic COMMENT='订单表';
+    "#;
+
+    let tables = parse_sql_tables(sql).unwrap();
+    let table = tables.get("orders").unwrap();
+
+    assert_eq!(table.name, "orders");
+    assert_eq!(table.engine.as_deref(), Some("InnoDB"));
+    assert_eq!(table.charset.as_deref(), Some("utf8mb4"));
+    assert_eq!(table.collation.as_deref(), Some("utf8mb4_unicode_ci"));
+    assert_eq!(table.row_format.as_deref(), Some("DYNAMIC"));
+    assert_eq!(table.comment.as_deref(), Some("订单表"));
+
+    assert_eq!(table.foreign_keys.len(), 2);
+    let fk = &table.foreign_keys[0];
+    assert_eq!(fk.name, "fk_orders_user");
+    assert_eq!(fk.columns, vec!["user_id".to_string()]);
+    assert_eq!(fk.referenced_table, "users");
+    assert_eq!(fk.referenced_columns, vec!["id".to_string()]);
+    assert_eq!(fk.on_delete.as_deref(), Some("CASCADE"));
+    assert_eq!(fk.on_update, None);
+    // 未命名外键使用MySQL的自动命名规则
+    assert_eq!(table.foreign_keys[1].name, "orders_ibfk_2");
+
+    assert_eq!(table.checks.len(), 1);
+    assert_eq!(table.checks[0].name, "chk_amount");
+    assert!(table.checks[0].expression.contains("amount"));
+}
+
+#[test]
+fn test_foreign_key_diff_ordering() {
+    let from_sql = r#"
+CREATE TABLE users (
+    id INT NOT NULL AUTO_INCREMENT,
+    PRIMARY KEY (id)
+) ENGINE=InnoDB;
+
+CREATE TABLE orders (
+    id INT NOT NULL AUTO_INCREMENT,
+    user_id INT NOT NULL,
+    coupon_id INT,
+    PRIMARY KEY (id),
+    CONSTRAINT fk_orders_user FOREIGN KEY (user_id) REFERENCES users (id)
+) ENGINE=InnoDB;
+
+CREATE TABLE coupons (
+    id INT NOT NULL AUTO_INCREMENT,
+    PRIMARY KEY (id)
+) ENGINE=InnoDB;
+
+CREATE TABLE coupon_logs (
+    id INT NOT NULL AUTO_INCREMENT,
+    coupon_id INT NOT NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT fk_log_coupon FOREIGN KEY (coupon_id) REFERENCES coupons (id)
+) ENGINE=InnoDB;
+    "#;
+
+    let to_sql = r#"
+CREATE TABLE users (
+    id INT NOT NULL AUTO_INCREMENT,
+    PRIMARY KEY (id)
+) ENGINE=InnoDB;
+
+CREATE TABLE orders (
+    id INT NOT NULL AUTO_INCREMENT,
+    user_id INT NOT NULL,
+    shop_id INT NOT NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT fk_orders_user FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+    CONSTRAINT fk_orders_shop FOREIGN KEY (shop_id) REFERENCES shops (id)
+) ENGINE=InnoDB;
+
+CREATE TABLE shops (
+    id INT NOT NULL AUTO_INCREMENT,
+    owner_id INT NOT NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT fk_shop_owner FOREIGN KEY (owner_id) REFERENCES users (id)
+) ENGINE=InnoDB;
+    "#;
+
+    let (diff_sql, description) =
+        generate_schema_diff(Some(from_sql), to_sql, Some("1.0.0"), "1.1.0").unwrap();
+
+    let position = |needle: &str| {
+        diff_sql
+            .find(needle)
+            .unwrap_or_else(|| panic!("差异SQL中缺少: {needle}\n{diff_sql}"))
+    };
+
+    // 修改的外键先删除后重建
+    let drop_fk = position("ALTER TABLE `orders` DROP FOREIGN KEY `fk_orders_user`;");
+    let add_fk = position(
+        "ALTER TABLE `orders` ADD CONSTRAINT `fk_orders_user` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`) ON DELETE CASCADE;",
+    );
+    assert!(drop_fk < add_fk);
+
+    // 新表创建时不带外键，外键在被引用表和列都存在后再添加
+    let create_shops = position("CREATE TABLE `shops`");
+    let add_shop_column = position("ALTER TABLE `orders` ADD COLUMN `shop_id`");
+    let add_shop_fk = position("ADD CONSTRAINT `fk_orders_shop` FOREIGN KEY (`shop_id`)");
+    let add_owner_fk = position("ALTER TABLE `shops` ADD CONSTRAINT `fk_shop_owner`");
+    assert!(create_shops < add_shop_fk);
+    assert!(add_shop_column < add_shop_fk);
+    assert!(create_shops < add_owner_fk);
+    let create_shops_sql = &diff_sql[create_shops..];
+    let create_shops_sql = &create_shops_sql[..create_shops_sql.find(';').unwrap()];
+    assert!(!create_shops_sql.contains("FOREIGN KEY"));
+
+    // 被删除表之间的外键先删除，删表放在最后
+    let drop_log_fk = position("ALTER TABLE `coupon_logs` DROP FOREIGN KEY `fk_log_coupon`;");
+    let drop_coupons = position("DROP TABLE IF EXISTS `coupons`;");
+    let drop_logs = position("DROP TABLE IF EXISTS `coupon_logs`;");
+    assert!(drop_log_fk < drop_coupons);
+    assert!(drop_log_fk < drop_logs);
+    assert!(add_fk < drop_coupons);
+
+    assert!(description.contains("新增外键"));
+    assert!(description.contains("删除外键"));
+}
+
+#[test]
+fn test_check_constraint_diff() {
+    let from_sql = r#"
+CREATE TABLE products (
+    id INT NOT NULL,
+    price DECIMAL(10,2) NOT NULL,
+    stock INT NOT NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT chk_price CHECK (price > 0)
+) ENGINE=InnoDB;
+    "#;
+
+    let to_sql = r#"
+CREATE TABLE products (
+    id INT NOT NULL,
+    price DECIMAL(10,2) NOT NULL,
+    stock INT NOT NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT chk_price CHECK (price >= 0),
+    CONSTRAINT chk_stock CHECK (stock >= 0) NOT ENFORCED
+) ENGINE=InnoDB;
+    "#;
+
+    let (diff_sql, description) =
+        generate_schema_diff(Some(from_sql), to_sql, Some("1.0.0"), "1.1.0").unwrap();
+
+    let drop_check = diff_sql
+        .find("ALTER TABLE `products` DROP CHECK `chk_price`;")
+        .unwrap();
+    let add_check = diff_sql
+        .find("ALTER TABLE `products` ADD CONSTRAINT `chk_price` CHECK (price >= 0);")
+        .unwrap();
+    assert!(drop_check < add_check);
+    assert!(diff_sql.contains(
+        "ALTER TABLE `products` ADD CONSTRAINT `chk_stock` CHECK (stock >= 0) NOT ENFORCED;"
+    ));
+    assert!(description.contains("CHECK约束变更"));
+}
+
+#[test]
+fn test_table_options_diff() {
+    let from_sql = r#"
+CREATE TABLE users (
+    id INT NOT NULL,
+    PRIMARY KEY (id)
+) ENGINE=MyISAM DEFAULT CHARSET=utf8 COMMENT='用户';
+    "#;
+
+    let to_sql = r#"
+CREATE TABLE users (
+    id INT NOT NULL,
+    PRIMARY KEY (id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci ROW_FORMAT=DYNAMIC COMMENT='用户''表';
+    "#;
+
+    let (diff_sql, description) =
+        generate_schema_diff(Some(from_sql), to_sql, Some("1.0.0"), "1.1.0").unwrap();
+
+    assert!(diff_sql.contains("ALTER TABLE `users` ENGINE=InnoDB;"));
+    assert!(diff_sql.contains(
+        "ALTER TABLE `users` CONVERT TO CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;"
+    ));
+    assert!(diff_sql.contains("ALTER TABLE `users` ROW_FORMAT=DYNAMIC;"));
+    assert!(diff_sql.contains("ALTER TABLE `users` COMMENT='用户''表';"));
+    assert!(description.contains("表选项变更"));
+
+    // 只修改排序规则时，字符集取排序规则前缀
+    let collate_only = to_sql.replace(
+        "DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci",
+        "COLLATE=utf8mb4_general_ci",
+    );
+    let (diff_sql, _) =
+        generate_schema_diff(Some(to_sql), &collate_only, Some("1.1.0"), "1.2.0").unwrap();
+    assert!(diff_sql.contains(
+        "ALTER TABLE `users` CONVERT TO CHARACTER SET utf8mb4 COLLATE utf8mb4_general_ci;"
+    ));
+
+    // 选项大小写不同不算变化
+    let lowercase = to_sql.replace("ENGINE=InnoDB", "ENGINE=innodb");
+    let (diff_sql, _) =
+        generate_schema_diff(Some(to_sql), &lowercase, Some("1.1.0"), "1.2.0").unwrap();
+    assert!(diff_sql.is_empty());
+}
+
+#[test]
+fn test_backtick_identifiers_not_double_quoted() {
+    let from_sql = "CREATE TABLE `users` (`id` INT NOT NULL, PRIMARY KEY (`id`));";
+    let to_sql = "CREATE TABLE `users` (`id` INT NOT NULL, `nick_name` VARCHAR(32), PRIMARY KEY (`id`), KEY `idx_nick` (`nick_name`));";
+
+    let (diff_sql, _) =
+        generate_schema_diff(Some(from_sql), to_sql, Some("1.0.0"), "1.1.0").unwrap();
+
+    assert!(diff_sql.contains("ALTER TABLE `users` ADD COLUMN `nick_name`"));
+    assert!(diff_sql.contains("`idx_nick`"));
+    assert!(!diff_sql.contains("``"));
+}
+
+#[test]
+fn test_parse_rename_hints() {
+    let sql = r#"
+-- @rename-table: old_users -> users
+-- @rename-column: `users`.`user_name` -> `nick_name`
+-- 普通注释 old -> new
+CREATE TABLE users (id INT PRIMARY KEY, nick_name VARCHAR(32));
+    "#;
+
+    let hints = parse_rename_hints(sql).unwrap();
+    assert_eq!(hints.tables.len(), 1);
+    assert_eq!(
+        hints.tables.get("old_users").map(String::as_str),
+        Some("users")
+    );
+    assert_eq!(
+        hints.columns["users"].get("user_name").map(String::as_str),
+        Some("nick_name")
+    );
+}
+
+#[test]
+fn test_rename_with_hints() {
+    let from_sql = r#"
+CREATE TABLE members (
+    id INT NOT NULL AUTO_INCREMENT,
+    user_name VARCHAR(32) NOT NULL,
+    PRIMARY KEY (id),
+    KEY idx_user_name (user_name)
+) ENGINE=InnoDB;
+
+CREATE TABLE orders (
+    id INT NOT NULL AUTO_INCREMENT,
+    member_id INT NOT NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT fk_orders_member FOREIGN KEY (member_id) REFERENCES members (id)
+) ENGINE=InnoDB;
+    "#;
+
+    let to_sql = r#"
+-- @rename-table: members -> users
+-- @rename-column: users.user_name -> nick_name
+CREATE TABLE users (
+    id INT NOT NULL AUTO_INCREMENT,
+    nick_name VARCHAR(64) NOT NULL,
+    email VARCHAR(128),
+    PRIMARY KEY (id),
+    KEY idx_user_name (nick_name)
+) ENGINE=InnoDB;
+
+CREATE TABLE orders (
+    id INT NOT NULL AUTO_INCREMENT,
+    member_id INT NOT NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT fk_orders_member FOREIGN KEY (member_id) REFERENCES users (id)
+) ENGINE=InnoDB;
+    "#;
+
+    let report = analyze_schema_diff(Some(from_sql), to_sql, Some("1.0.0"), "1.1.0").unwrap();
+    let diff_sql = &report.diff_sql;
+
+    let rename_table = diff_sql.find("RENAME TABLE `members` TO `users`;").unwrap();
+    let rename_column = diff_sql
+        .find("ALTER TABLE `users` RENAME COLUMN `user_name` TO `nick_name`;")
+        .unwrap();
+    let modify_column = diff_sql
+        .find("ALTER TABLE `users` MODIFY COLUMN `nick_name` VARCHAR(64) NOT NULL;")
+        .unwrap();
+    assert!(rename_table < rename_column);
+    assert!(rename_column < modify_column);
+    assert!(diff_sql.contains("ALTER TABLE `users` ADD COLUMN `email`"));
+
+    // 重命名后表、列、索引和外键都与新结构一致，不应删除重建
+    assert!(!diff_sql.contains("DROP TABLE"));
+    assert!(!diff_sql.contains("CREATE TABLE"));
+    assert!(!diff_sql.contains("DROP COLUMN"));
+    assert!(!diff_sql.contains("DROP KEY"));
+    assert!(!diff_sql.contains("FOREIGN KEY"));
+    assert!(report.description.contains("重命名表"));
+    assert!(report.description.contains("重命名列"));
+    assert_eq!(report.max_safety(), ChangeSafety::Locking);
+}
+
+#[test]
+fn test_detect_renames_by_structure() {
+    let from_sql = r#"
+CREATE TABLE user_logs (
+    id BIGINT NOT NULL AUTO_INCREMENT,
+    content TEXT,
+    PRIMARY KEY (id)
+) ENGINE=InnoDB;
+
+CREATE TABLE users (
+    id INT NOT NULL AUTO_INCREMENT,
+    user_name VARCHAR(32) NOT NULL,
+    phone VARCHAR(20),
+    PRIMARY KEY (id)
+) ENGINE=InnoDB;
+    "#;
+
+    let to_sql = r#"
+CREATE TABLE audit_logs (
+    id BIGINT NOT NULL AUTO_INCREMENT,
+    content TEXT,
+    PRIMARY KEY (id)
+) ENGINE=InnoDB;
+
+CREATE TABLE users (
+    id INT NOT NULL AUTO_INCREMENT,
+    nick_name VARCHAR(32) NOT NULL COMMENT '昵称',
+    mobile VARCHAR(20),
+    PRIMARY KEY (id)
+) ENGINE=InnoDB;
+    "#;
+
+    let report = analyze_schema_diff(Some(from_sql), to_sql, Some("1.0.0"), "1.1.0").unwrap();
+    let diff_sql = &report.diff_sql;
+
+    assert!(diff_sql.contains("RENAME TABLE `user_logs` TO `audit_logs`;"));
+    assert!(diff_sql.contains("ALTER TABLE `users` RENAME COLUMN `user_name` TO `nick_name`;"));
+    assert!(diff_sql.contains("ALTER TABLE `users` RENAME COLUMN `phone` TO `mobile`;"));
+    // 只有注释变化，重命名后再修改列注释
+    assert!(diff_sql.contains("MODIFY COLUMN `nick_name`"));
+    assert!(!diff_sql.contains("DROP"));
+    assert!(
+        report
+            .statements_with_safety(ChangeSafety::DataLoss)
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_ambiguous_rename_falls_back_to_drop_and_add() {
+    let from_sql = r#"
+CREATE TABLE users (
+    id INT NOT NULL,
+    phone VARCHAR(20),
+    PRIMARY KEY (id)
+) ENGINE=InnoDB;
+    "#;
+
+    // 两个同类型的新列都不在原位置，无法确定是哪一个重命名
+    let to_sql = r#"
+CREATE TABLE users (
+    id INT NOT NULL,
+    nick_name VARCHAR(64),
+    mobile VARCHAR(20),
+    backup_mobile VARCHAR(20),
+    PRIMARY KEY (id)
+) ENGINE=InnoDB;
+    "#;
+
+    let report = analyze_schema_diff(Some(from_sql), to_sql, Some("1.0.0"), "1.1.0").unwrap();
+
+    assert!(!report.diff_sql.contains("RENAME COLUMN"));
+    assert!(
+        report
+            .diff_sql
+            .contains("ALTER TABLE `users` DROP COLUMN `phone`;")
+    );
+    assert_eq!(report.max_safety(), ChangeSafety::DataLoss);
+    assert!(report.description.contains("数据丢失"));
+}
+
+#[test]
+fn test_column_at_different_position_is_not_rename() {
+    let from_sql = r#"
+CREATE TABLE users (
+    id INT NOT NULL,
+    legacy_note VARCHAR(255) NULL,
+    name VARCHAR(64) NOT NULL,
+    PRIMARY KEY (id)
+) ENGINE=InnoDB;
+    "#;
+
+    // 删除旧列并在末尾追加定义相同的新列，位置不同，不能当作重命名
+    let to_sql = r#"
+CREATE TABLE users (
+    id INT NOT NULL,
+    name VARCHAR(64) NOT NULL,
+    email VARCHAR(255) NULL,
+    PRIMARY KEY (id)
+) ENGINE=InnoDB;
+    "#;
+
+    let report = analyze_schema_diff(Some(from_sql), to_sql, Some("1.0.0"), "1.1.0").unwrap();
+
+    assert!(!report.diff_sql.contains("RENAME COLUMN"));
+    assert!(
+        report
+            .diff_sql
+            .contains("ALTER TABLE `users` DROP COLUMN `legacy_note`;")
+    );
+    assert!(report.diff_sql.contains("ADD COLUMN `email`"));
+    assert_eq!(report.max_safety(), ChangeSafety::DataLoss);
+}
+
+#[test]
+fn test_statement_safety_classification() {
+    let from_sql = r#"
+CREATE TABLE products (
+    id INT NOT NULL,
+    code VARCHAR(64) NOT NULL,
+    title VARCHAR(100),
+    stock SMALLINT,
+    status INT DEFAULT 0,
+    note VARCHAR(255),
+    PRIMARY KEY (id)
+) ENGINE=InnoDB;
+    "#;
+
+    let to_sql = r#"
+CREATE TABLE products (
+    id INT NOT NULL,
+    code VARCHAR(32) NOT NULL,
+    title VARCHAR(200),
+    stock BIGINT,
+    status INT DEFAULT 1,
+    price DECIMAL(10,2),
+    PRIMARY KEY (id),
+    KEY idx_code (code)
+) ENGINE=InnoDB;
+    "#;
+
+    let report = analyze_schema_diff(Some(from_sql), to_sql, Some("1.0.0"), "1.1.0").unwrap();
+    let safety_of = |needle: &str| {
+        report
+            .statements
+            .iter()
+            .find(|statement| statement.sql.contains(needle))
+            .unwrap_or_else(|| panic!("差异语句中缺少: {needle}"))
+            .safety
+    };
+
+    assert_eq!(safety_of("MODIFY COLUMN `code`"), ChangeSafety::DataLoss);
+    assert_eq!(safety_of("MODIFY COLUMN `title`"), ChangeSafety::Locking);
+    assert_eq!(safety_of("MODIFY COLUMN `stock`"), ChangeSafety::Locking);
+    assert_eq!(safety_of("MODIFY COLUMN `status`"), ChangeSafety::Safe);
+    assert_eq!(safety_of("ADD COLUMN `price`"), ChangeSafety::Safe);
+    assert_eq!(safety_of("DROP COLUMN `note`"), ChangeSafety::DataLoss);
+    assert_eq!(safety_of("ADD KEY `idx_code`"), ChangeSafety::Locking);
+    assert_eq!(
+        report.statements_with_safety(ChangeSafety::DataLoss).len(),
+        2
+    );
+}
+
+#[test]
+fn test_down_migration_reverts_changes() {
+    let from_sql = r#"
+CREATE TABLE members (
+    id INT NOT NULL AUTO_INCREMENT,
+    user_name VARCHAR(32) NOT NULL,
+    phone VARCHAR(20) NOT NULL DEFAULT '',
+    PRIMARY KEY (id),
+    KEY idx_phone (phone)
+) ENGINE=InnoDB;
+
+CREATE TABLE legacy_logs (
+    id INT NOT NULL,
+    PRIMARY KEY (id)
+) ENGINE=InnoDB;
+    "#;
+
+    let to_sql = r#"
+-- @rename-table: members -> users
+-- @rename-column: users.user_name -> nick_name
+CREATE TABLE users (
+    id INT NOT NULL AUTO_INCREMENT,
+    nick_name VARCHAR(64) NOT NULL,
+    email VARCHAR(128),
+    PRIMARY KEY (id),
+    KEY idx_email (email)
+) ENGINE=InnoDB;
+
+CREATE TABLE posts (
+    id INT NOT NULL AUTO_INCREMENT,
+    user_id INT NOT NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT fk_posts_user FOREIGN KEY (user_id) REFERENCES users (id)
+) ENGINE=InnoDB;
+    "#;
+
+    let report = analyze_schema_diff(Some(from_sql), to_sql, Some("1.0.0"), "1.1.0").unwrap();
+    let down_sql = &report.down_sql;
+    let position = |needle: &str| {
+        down_sql
+            .find(needle)
+            .unwrap_or_else(|| panic!("回滚SQL中缺少: {needle}\n{down_sql}"))
+    };
+
+    // 重命名按原名称改回，列类型恢复原定义
+    let rename_table = position("RENAME TABLE `users` TO `members`;");
+    let rename_column = position("ALTER TABLE `members` RENAME COLUMN `nick_name` TO `user_name`;");
+    let modify_column =
+        position("ALTER TABLE `members` MODIFY COLUMN `user_name` VARCHAR(32) NOT NULL;");
+    assert!(rename_table < rename_column);
+    assert!(rename_column < modify_column);
+
+    // 删除的列和索引按原定义重建，新增的列和索引被删除
+    position("ALTER TABLE `members` ADD COLUMN `phone` VARCHAR(20) NOT NULL");
+    position("ALTER TABLE `members` ADD KEY `idx_phone` (`phone`);");
+    position("ALTER TABLE `members` DROP COLUMN `email`;");
+    position("ALTER TABLE `members` DROP KEY `idx_email`;");
+
+    // 删除的表重建，新增的表被删除
+    position("CREATE TABLE `legacy_logs`");
+    position("DROP TABLE IF EXISTS `posts`;");
+    assert_eq!(report.max_safety(), ChangeSafety::DataLoss);
+}
+
+#[test]
+fn test_down_migration_empty_without_changes() {
+    let sql = "CREATE TABLE users (id INT PRIMARY KEY);";
+    let report = analyze_schema_diff(Some(sql), sql, Some("1.0.0"), "1.0.1").unwrap();
+    assert!(report.down_sql.is_empty());
+
+    let report = analyze_schema_diff(None, sql, None, "1.0.0").unwrap();
+    assert!(report.down_sql.is_empty());
+}
+
+#[test]
+fn test_structured_schema_diff() {
+    let from_sql = r#"
+CREATE TABLE members (
+    id INT NOT NULL AUTO_INCREMENT,
+    user_name VARCHAR(32) NOT NULL,
+    phone VARCHAR(20),
+    status VARCHAR(16),
+    PRIMARY KEY (id),
+    KEY idx_phone (phone)
+) ENGINE=InnoDB;
+
+CREATE TABLE legacy_logs (
+    id INT NOT NULL,
+    PRIMARY KEY (id)
+);
+    "#;
+
+    let to_sql = r#"
+CREATE TABLE members (
+    id INT NOT NULL AUTO_INCREMENT,
+    nick_name VARCHAR(32) NOT NULL,
+    status VARCHAR(32),
+    email VARCHAR(128),
+    PRIMARY KEY (id),
+    KEY idx_email (email)
+) ENGINE=InnoDB COMMENT='会员';
+
+CREATE TABLE posts (
+    id INT NOT NULL,
+    title VARCHAR(128) NOT NULL,
+    PRIMARY KEY (id)
+);
+    "#;
+
+    let from_tables = parse_sql_tables(from_sql).unwrap();
+    let to_tables = parse_sql_tables(to_sql).unwrap();
+    let from_schema = parse_sql_schema(from_sql).unwrap();
+    let to_schema = parse_sql_schema(to_sql).unwrap();
+    let diff = diff_schemas(&from_schema, &to_schema, &RenameHints::default());
+
+    let table_names =
+        |tables: &[TableDefinition]| tables.iter().map(|t| t.name.clone()).collect::<Vec<_>>();
+    assert_eq!(diff.databases.len(), 1);
+    let database_diff = diff.database(None).unwrap();
+    assert_eq!(table_names(&database_diff.added_tables), vec!["posts"]);
+    assert_eq!(
+        table_names(&database_diff.removed_tables),
+        vec!["legacy_logs"]
+    );
+    assert!(database_diff.renamed_tables.is_empty());
+    assert_eq!(database_diff.modified_tables.len(), 1);
+
+    let members = diff.table("members").unwrap();
+    assert_eq!(
+        members.renamed_columns,
+        vec![NameChange {
+            from: "user_name".to_string(),
+            to: "nick_name".to_string(),
+        }]
+    );
+    assert_eq!(members.added_columns[0].name, "email");
+    assert_eq!(members.removed_columns[0].name, "phone");
+    assert_eq!(members.modified_columns.len(), 1);
+    assert_eq!(members.modified_columns[0].before.data_type, "VARCHAR(16)");
+    assert_eq!(members.modified_columns[0].after.data_type, "VARCHAR(32)");
+    assert_eq!(members.added_indexes[0].name, "idx_email");
+    assert_eq!(members.removed_indexes[0].name, "idx_phone");
+
+    let options = members.options.as_ref().unwrap();
+    assert_eq!(options.before.comment, None);
+    assert_eq!(options.after.comment.as_deref(), Some("会员"));
+
+    // SQL由结构化差异渲染，与直接生成的结果一致
+    let (rendered_sql, rendered_statements) = render_mysql_diff(&diff);
+    let (diff_sql, statements) =
+        generate_mysql_diff_with_hints(&from_tables, &to_tables, &RenameHints::default()).unwrap();
+    assert_eq!(rendered_statements, statements);
+    assert!(
+        rendered_sql.contains("ALTER TABLE `members` RENAME COLUMN `user_name` TO `nick_name`;")
+    );
+    assert!(diff_sql.contains("ALTER TABLE `members` RENAME COLUMN `user_name` TO `nick_name`;"));
+
+    // 无变化时差异为空
+    let diff = diff_schemas(&from_schema, &from_schema, &RenameHints::default());
+    assert!(diff.is_empty());
+    assert_eq!(render_mysql_diff(&diff).0, "");
+}
+
+#[test]
+fn test_schema_diff_json() {
+    let from_sql = "CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(32));";
+    let to_sql = "CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(64), age INT);";
+
+    let report = analyze_schema_diff(Some(from_sql), to_sql, Some("1.0.0"), "1.1.0").unwrap();
+    let json = report.diff.to_json().unwrap();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+    let users = &value["databases"][0]["modified_tables"][0];
+    assert_eq!(users["name"], "users");
+    assert_eq!(users["added_columns"][0]["name"], "age");
+    assert_eq!(
+        users["modified_columns"][0]["before"]["data_type"],
+        "VARCHAR(32)"
+    );
+    assert_eq!(
+        users["modified_columns"][0]["after"]["data_type"],
+        "VARCHAR(64)"
+    );
+
+    // JSON可以还原为相同的结构化差异
+    let parsed: SchemaDiff = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, report.diff);
+
+    // 语句的安全级别序列化为与 as_str 一致的值
+    let report_json = serde_json::to_value(&report).unwrap();
+    assert_eq!(
+        report_json["statements"][0]["safety"],
+        ChangeSafety::Safe.as_str()
+    );
+}
+
+#[test]
+fn test_split_sql_statements() {
+    let sql = r#"
+-- 注释中的分号; 不会拆分语句
+# MySQL风格的注释;
+INSERT INTO config VALUES ('a;b', "c\";d", 'it''s');
+/* 块注释; */ CREATE TABLE `semi;colon` (id INT);
+SELECT 1--1;
+USE app
+    "#;
+
+    let statements = split_sql_statements(sql);
+    assert_eq!(
+        statements,
+        vec![
+            r#"INSERT INTO config VALUES ('a;b', "c\";d", 'it''s')"#,
+            "CREATE TABLE `semi;colon` (id INT)",
+            "SELECT 1--1",
+            "USE app",
+        ]
+    );
+}
+
+#[test]
+fn test_multi_database_schema_diff() {
+    let from_sql = r#"
+CREATE DATABASE IF NOT EXISTS app DEFAULT CHARACTER SET utf8 COLLATE utf8_general_ci;
+CREATE DATABASE IF NOT EXISTS legacy;
+
+CREATE TABLE settings (id INT PRIMARY KEY);
+
+USE app;
+CREATE TABLE users (
+    id INT NOT NULL,
+    name VARCHAR(32) NOT NULL,
+    PRIMARY KEY (id)
+);
+
+USE legacy;
+CREATE TABLE archive (id INT PRIMARY KEY);
+    "#;
+
+    let to_sql = r#"
+CREATE DATABASE IF NOT EXISTS app DEFAULT CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
+CREATE DATABASE IF NOT EXISTS logs DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE settings (id INT PRIMARY KEY, value VARCHAR(255));
+
+USE app;
+CREATE TABLE users (
+    id INT NOT NULL,
+    name VARCHAR(32) NOT NULL,
+    email VARCHAR(128),
+    PRIMARY KEY (id)
+);
+
+USE logs;
+-- 与 app 中的表同名，不能互相覆盖
+CREATE TABLE users (
+    id BIGINT NOT NULL,
+    action VARCHAR(64) NOT NULL,
+    PRIMARY KEY (id)
+);
+CREATE TABLE app.audit (id INT PRIMARY KEY);
+    "#;
+
+    let from_schema = parse_sql_schema(from_sql).unwrap();
+    let to_schema = parse_sql_schema(to_sql).unwrap();
+    assert_eq!(
+        to_schema.database(Some("app")).unwrap().tables.len(),
+        2,
+        "限定了数据库名的表归入对应的数据库"
+    );
+    assert!(to_schema.qualified_tables().contains_key("logs.users"));
+
+    let diff = diff_schemas(&from_schema, &to_schema, &RenameHints::default());
+    assert_eq!(diff.added_databases.len(), 1);
+    assert_eq!(diff.added_databases[0].name, "logs");
+    assert_eq!(diff.added_databases[0].charset.as_deref(), Some("utf8mb4"));
+    assert_eq!(diff.removed_databases[0].name, "legacy");
+    assert_eq!(diff.modified_databases[0].after.name, "app");
+
+    // 默认数据库在最前，其余按新脚本中的出现顺序
+    let databases: Vec<_> = diff
+        .databases
+        .iter()
+        .map(|database| database.database.as_deref())
+        .collect();
+    assert_eq!(databases, vec![None, Some("app"), Some("logs")]);
+    assert!(diff.table("settings").is_some());
+    assert_eq!(
+        diff.table("app.users").unwrap().added_columns[0].name,
+        "email"
+    );
+    assert_eq!(
+        diff.database(Some("app")).unwrap().added_tables[0].name,
+        "audit"
+    );
+    assert_eq!(
+        diff.database(Some("logs")).unwrap().added_tables[0].name,
+        "users"
+    );
+
+    let (diff_sql, statements) = render_mysql_diff(&diff);
+    let position = |needle: &str| {
+        diff_sql
+            .find(needle)
+            .unwrap_or_else(|| panic!("差异SQL中缺少: {needle}\n{diff_sql}"))
+    };
+    let create_logs =
+        position("CREATE DATABASE IF NOT EXISTS `logs` DEFAULT CHARACTER SET utf8mb4;");
+    let alter_app =
+        position("ALTER DATABASE `app` CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;");
+    let alter_settings = position("ALTER TABLE `settings` ADD COLUMN `value`");
+    let use_app = position("USE `app`;");
+    let alter_users = position("ALTER TABLE `users` ADD COLUMN `email`");
+    let use_logs = position("USE `logs`;");
+    let create_users = position("CREATE TABLE `users`");
+    let drop_legacy = position("DROP DATABASE IF EXISTS `legacy`;");
+    assert!(create_logs < alter_settings && alter_app < alter_settings);
+    assert!(alter_settings < use_app);
+    assert!(use_app < alter_users && alter_users < use_logs);
+    assert!(use_logs < create_users && create_users < drop_legacy);
+    assert!(!diff_sql.contains("DROP TABLE IF EXISTS `archive`"));
+    assert!(
+        statements
+            .iter()
+            .all(|statement| !statement.sql.starts_with("USE "))
+    );
+
+    // 只比较指定的数据库
+    let report = analyze_schema_diff_for_database(
+        Some(from_sql),
+        to_sql,
+        Some("1.0.0"),
+        "1.1.0",
+        Some("logs"),
+    )
+    .unwrap();
+    assert_eq!(report.diff.databases.len(), 1);
+    assert!(report.diff_sql.contains("USE `logs`;"));
+    assert!(!report.diff_sql.contains("`settings`"));
+    assert!(!report.diff_sql.contains("USE `app`;"));
+
+    // 回滚时删除新建的数据库，重建删除的数据库
+    let down_diff = diff_schemas_down(&from_schema, &to_schema, &RenameHints::default());
+    assert_eq!(down_diff.removed_databases[0].name, "logs");
+    assert_eq!(down_diff.added_databases[0].name, "legacy");
+    assert_eq!(
+        down_diff.database(Some("legacy")).unwrap().added_tables[0].name,
+        "archive"
+    );
+}
+
+#[test]
+fn test_parse_views_and_routines() {
+    let sql = r#"
+USE app;
+CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(64), status INT);
+
+CREATE ALGORITHM=UNDEFINED DEFINER=`root`@`%` SQL SECURITY DEFINER VIEW `active_users` AS
+    SELECT id, name FROM users WHERE status = 1;
+
+DELIMITER $$
+CREATE TRIGGER trg_users_insert BEFORE INSERT ON users FOR EACH ROW
+BEGIN
+    SET NEW.status = IFNULL(NEW.status, 1);
+END$$
+
+CREATE PROCEDURE cleanup_users(IN days INT)
+BEGIN
+    DELETE FROM users WHERE status = 0;
+    SELECT ROW_COUNT();
+END $$
+DELIMITER ;
+
+CREATE FUNCTION app.user_count() RETURNS INT DETERMINISTIC RETURN (SELECT COUNT(*) FROM users);
+    "#;
+
+    let schema = parse_sql_schema(sql).unwrap();
+    let app = schema.database(Some("app")).unwrap();
+    assert_eq!(app.tables.len(), 1);
+
+    let objects: Vec<_> = app
+        .objects
+        .iter()
+        .map(|object| (object.kind, object.name.as_str()))
+        .collect();
+    assert_eq!(
+        objects,
+        vec![
+            (ObjectKind::View, "active_users"),
+            (ObjectKind::Trigger, "trg_users_insert"),
+            (ObjectKind::Procedure, "cleanup_users"),
+            (ObjectKind::Function, "user_count"),
+        ]
+    );
+    assert!(app.objects[2].definition.contains("SELECT ROW_COUNT();"));
+    assert!(app.objects[2].definition.ends_with("END"));
+}
+
+#[test]
+fn test_object_diff() {
+    let from_sql = r#"
+CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(64), status INT);
+CREATE TABLE legacy (id INT PRIMARY KEY);
+
+CREATE VIEW active_users AS SELECT id, name FROM users WHERE status = 1;
+CREATE VIEW legacy_view AS SELECT id FROM legacy;
+
+DELIMITER //
+CREATE PROCEDURE cleanup_users()
+BEGIN
+    DELETE FROM users WHERE status = 0;
+END//
+DELIMITER ;
+
+CREATE FUNCTION user_count() RETURNS INT DETERMINISTIC RETURN (SELECT COUNT(*) FROM users);
+    "#;
+
+    let to_sql = r#"
+CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(64), status INT, email VARCHAR(128));
+
+-- 只有空白、大小写、反引号和DEFINER不同，视为未变化
+create definer=`app`@`%` function `user_count`() returns int deterministic
+    return (select count(*) from `users`);
+
+CREATE VIEW active_users AS SELECT id, name, email FROM users WHERE status = 1;
+CREATE VIEW active_user_emails AS SELECT email FROM active_users;
+
+DELIMITER //
+CREATE PROCEDURE cleanup_users()
+BEGIN
+    DELETE FROM users WHERE status = 0 AND email IS NULL;
+END//
+DELIMITER ;
+    "#;
+
+    let from_schema = parse_sql_schema(from_sql).unwrap();
+    let to_schema = parse_sql_schema(to_sql).unwrap();
+    let diff = diff_schemas(&from_schema, &to_schema, &RenameHints::default());
+    let database_diff = diff.database(None).unwrap();
+
+    let names = |objects: &[ObjectDefinition]| {
+        objects
+            .iter()
+            .map(|object| object.name.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        names(&database_diff.added_objects),
+        vec!["active_user_emails"]
+    );
+    assert_eq!(names(&database_diff.removed_objects), vec!["legacy_view"]);
+    let modified: Vec<_> = database_diff
+        .modified_objects
+        .iter()
+        .map(|change| change.after.name.as_str())
+        .collect();
+    assert_eq!(modified, vec!["active_users", "cleanup_users"]);
+
+    let (diff_sql, statements) = render_mysql_diff(&diff);
+    let position = |needle: &str| {
+        diff_sql
+            .find(needle)
+            .unwrap_or_else(|| panic!("差异SQL中缺少: {needle}\n{diff_sql}"))
+    };
+    let drop_view = position("DROP VIEW IF EXISTS `legacy_view`;");
+    let drop_procedure = position("DROP PROCEDURE IF EXISTS `cleanup_users`;");
+    let add_column = position("ALTER TABLE `users` ADD COLUMN `email`");
+    let replace_view = position(
+        "CREATE OR REPLACE VIEW active_users AS SELECT id, name, email FROM users WHERE status = 1;",
+    );
+    let create_dependent_view = position("CREATE VIEW active_user_emails AS");
+    let create_procedure = position("DELIMITER $$\nCREATE PROCEDURE cleanup_users()");
+    let drop_table = position("DROP TABLE IF EXISTS `legacy`;");
+
+    // 先删除对象，再修改表，然后按依赖顺序创建对象，最后删除表
+    assert!(drop_view < add_column && drop_procedure < add_column);
+    assert!(add_column < replace_view && add_column < create_procedure);
+    assert!(replace_view < create_dependent_view);
+    assert!(create_dependent_view < drop_table && create_procedure < drop_table);
+    assert!(diff_sql.contains("END$$\nDELIMITER ;"));
+    assert!(!diff_sql.contains("DROP VIEW IF EXISTS `active_users`"));
+    assert!(!diff_sql.contains("user_count"));
+    assert!(
+        statements
+            .iter()
+            .filter(|statement| statement.sql.contains("PROCEDURE"))
+            .all(|statement| statement.safety == ChangeSafety::Safe)
+    );
+
+    // 回滚时删除新增的视图，按原定义恢复修改的对象
+    let down_diff = diff_schemas_down(&from_schema, &to_schema, &RenameHints::default());
+    let (down_sql, _) = render_mysql_diff(&down_diff);
+    assert!(down_sql.contains("DROP VIEW IF EXISTS `active_user_emails`;"));
+    assert!(down_sql.contains("CREATE VIEW legacy_view AS SELECT id FROM legacy;"));
+    assert!(down_sql.contains("DELETE FROM users WHERE status = 0;"));
+}
+
+/// 在解析出的表结构上执行差异SQL中的建表、删表和列操作，模拟迁移后的数据库
+fn apply_diff_sql(tables: &mut HashMap<String, TableDefinition>, diff_sql: &str) {
+    use sqlparser::ast::{
+        AlterTableOperation, ColumnDef, ColumnOptionDef, MySQLColumnPosition, Statement,
+    };
+    use sqlparser::dialect::MySqlDialect;
+    use sqlparser::parser::Parser;
+
+    let table_name = |name: &sqlparser::ast::ObjectName| {
+        name.0
+            .last()
+            .and_then(|part| part.as_ident())
+            .map(|ident| ident.value.clone())
+            .unwrap()
+    };
+
+    for statement_sql in split_sql_statements(diff_sql) {
+        if statement_sql.starts_with("CREATE TABLE") {
+            tables.extend(parse_sql_tables(&statement_sql).unwrap());
+            continue;
+        }
+        let Ok(statements) = Parser::parse_sql(&MySqlDialect {}, &statement_sql) else {
+            continue;
+        };
+        for statement in statements {
+            match statement {
+                Statement::Drop { names, .. } => {
+                    for name in &names {
+                        tables.remove(&table_name(name));
+                    }
+                }
+                Statement::AlterTable {
+                    name, operations, ..
+                } => {
+                    let table = tables.get_mut(&table_name(&name)).unwrap();
+                    for operation in operations {
+                        let (column_def, position) = match operation {
+                            AlterTableOperation::AddColumn {
+                                column_def,
+                                column_position,
+                                ..
+                            } => (column_def, column_position),
+                            AlterTableOperation::ModifyColumn {
+                                col_name,
+                                data_type,
+                                options,
+                                column_position,
+                            } => {
+                                let column_def = ColumnDef {
+                                    name: col_name,
+                                    data_type,
+                                    options: options
+                                        .into_iter()
+                                        .map(|option| ColumnOptionDef { name: None, option })
+                                        .collect(),
+                                };
+                                (column_def, column_position)
+                            }
+                            AlterTableOperation::DropColumn { column_name, .. } => {
+                                table.columns.retain(|c| c.name != column_name.value);
+                                continue;
+                            }
+                            _ => continue,
+                        };
+
+                        let column = parse_column_definition(&column_def).unwrap();
+                        let existing = table.columns.iter().position(|c| c.name == column.name);
+                        let index = match &position {
+                            Some(MySQLColumnPosition::First) => 0,
+                            Some(MySQLColumnPosition::After(after)) => {
+                                let columns =
+                                    table.columns.iter().filter(|c| c.name != column.name);
+                                columns.take_while(|c| c.name != after.value).count() + 1
+                            }
+                            None => existing.unwrap_or(table.columns.len()),
+                        };
+                        if let Some(existing) = existing {
+                            table.columns.remove(existing);
+                        }
+                        table.columns.insert(index, column);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[test]
+fn test_migrated_schema_matches_fresh_install() {
+    let from_sql = r#"
+CREATE TABLE users (
+    id INT NOT NULL AUTO_INCREMENT,
+    name VARCHAR(32) NOT NULL,
+    phone VARCHAR(20),
+    status TINYINT(1) DEFAULT '1',
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id)
+) ENGINE=InnoDB;
+
+CREATE TABLE legacy (id INT PRIMARY KEY);
+    "#;
+
+    let to_sql = r#"
+CREATE TABLE users (
+    tenant_id INT UNSIGNED NOT NULL DEFAULT 0,
+    id INT NOT NULL AUTO_INCREMENT,
+    code VARCHAR(16) NOT NULL DEFAULT '' COMMENT '编码',
+    status TINYINT(1) DEFAULT '1',
+    name VARCHAR(64) NOT NULL,
+    email VARCHAR(128) DEFAULT 'n/a' COMMENT 'user''s email',
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    price DECIMAL(10,2) DEFAULT 0.00,
+    PRIMARY KEY (id)
+) ENGINE=InnoDB;
+
+CREATE TABLE orders (
+    id BIGINT NOT NULL,
+    amount DECIMAL(12,4) NOT NULL,
+    state ENUM('new', 'paid') DEFAULT 'new',
+    PRIMARY KEY (id)
+);
+    "#;
+
+    let from_tables = parse_sql_tables(from_sql).unwrap();
+    let fresh_tables = parse_sql_tables(to_sql).unwrap();
+    let (diff_sql, statements) =
+        generate_mysql_diff_with_hints(&from_tables, &fresh_tables, &RenameHints::default())
+            .unwrap();
+
+    // 数据类型保留完整定义，默认值不重复加引号
+    let users = &fresh_tables["users"];
+    assert_eq!(users.columns[0].data_type, "INT UNSIGNED");
+    assert_eq!(users.columns[3].data_type, "TINYINT(1)");
+    assert_eq!(users.columns[8].data_type, "DECIMAL(10,2)");
+    assert!(diff_sql.contains(
+        "ALTER TABLE `users` ADD COLUMN `code` VARCHAR(16) NOT NULL DEFAULT '' COMMENT '编码' AFTER `id`;"
+    ));
+    assert!(diff_sql.contains("ADD COLUMN `tenant_id` INT UNSIGNED NOT NULL DEFAULT 0 FIRST;"));
+    assert!(diff_sql.contains("COMMENT 'user''s email' AFTER `name`;"));
+    assert!(diff_sql.contains(
+        "`updated_at` DATETIME DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP AFTER `created_at`;"
+    ));
+    assert!(diff_sql.contains("MODIFY COLUMN `status` TINYINT(1) DEFAULT '1' AFTER `code`;"));
+
+    // 迁移后的结构与全新安装完全一致（包括列顺序）
+    let mut migrated_tables = from_tables.clone();
+    apply_diff_sql(&mut migrated_tables, &diff_sql);
+    assert_eq!(migrated_tables, fresh_tables);
+
+    // 多次生成的结果完全相同
+    for _ in 0..5 {
+        let (_, again) =
+            generate_mysql_diff_with_hints(&from_tables, &fresh_tables, &RenameHints::default())
+                .unwrap();
+        assert_eq!(again, statements);
+    }
+
+    // 回滚后恢复为旧结构
+    let (down_sql, _) =
+        generate_mysql_down_diff(&from_tables, &fresh_tables, &RenameHints::default()).unwrap();
+    apply_diff_sql(&mut migrated_tables, &down_sql);
+    assert_eq!(migrated_tables, from_tables);
+}
+
+#[test]
+fn test_reference_data_diff() {
+    let from_sql = r#"
+-- @reference-data: roles, `app`.`dict_items`
+CREATE TABLE roles (id INT PRIMARY KEY, code VARCHAR(32), name VARCHAR(64));
+CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(64));
+
+INSERT INTO roles (id, code, name) VALUES (1, 'admin', '管理员'), (2, 'guest', '访客');
+INSERT INTO roles VALUES (3, 'legacy', '旧角色');
+INSERT INTO users (id, name) VALUES (1, 'alice');
+
+USE app;
+CREATE TABLE dict_items (type VARCHAR(16), code VARCHAR(16), label VARCHAR(64), PRIMARY KEY (type, code));
+INSERT INTO dict_items (type, code, label) VALUES ('status', 'on', '启用');
+    "#;
+
+    let to_sql = r#"
+-- @reference-data: roles
+-- @reference-data: app.dict_items
+CREATE TABLE roles (id INT PRIMARY KEY, code VARCHAR(32), name VARCHAR(64));
+CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(64));
+
+INSERT INTO roles (id, code, name) VALUES (1, 'admin', '系统管理员'), (2, 'guest', '访客');
+INSERT INTO roles (id, code, name) VALUES (4, 'auditor', '审计员');
+INSERT INTO users (id, name) VALUES (1, 'bob');
+
+USE app;
+CREATE TABLE dict_items (type VARCHAR(16), code VARCHAR(16), label VARCHAR(64), PRIMARY KEY (type, code));
+INSERT INTO dict_items (type, code, label) VALUES ('status', 'on', '启用'), ('status', 'off', '停用');
+    "#;
+
+    assert_eq!(
+        parse_reference_tables(from_sql).unwrap(),
+        vec!["roles", "app.dict_items"]
+    );
+
+    let from_schema = parse_sql_schema(from_sql).unwrap();
+    let to_schema = parse_sql_schema(to_sql).unwrap();
+    // 未标记为参考数据的表不记录数据；没有列名的 INSERT 按表定义的列顺序对应
+    let roles = &from_schema.database(None).unwrap().seed_data;
+    assert_eq!(roles.len(), 1);
+    assert_eq!(roles[0].rows.len(), 3);
+    assert_eq!(roles[0].rows[2]["code"], "'legacy'");
+
+    let diff = diff_schemas(&from_schema, &to_schema, &RenameHints::default());
+    let roles_diff = &diff.database(None).unwrap().seed_data[0];
+    assert_eq!(roles_diff.key_columns, vec!["id"]);
+    assert_eq!(roles_diff.inserted_rows.len(), 1);
+    assert_eq!(roles_diff.updated_rows.len(), 1);
+    assert_eq!(roles_diff.deleted_rows.len(), 1);
+    let dict_diff = &diff.database(Some("app")).unwrap().seed_data[0];
+    assert_eq!(dict_diff.key_columns, vec!["type", "code"]);
+    assert_eq!(dict_diff.inserted_rows.len(), 1);
+
+    let (diff_sql, statements) = render_mysql_diff(&diff);
+    // 只在用户未修改时更新和删除默认数据，插入时不覆盖已有的行
+    assert!(diff_sql.contains(
+        "UPDATE `roles` SET `name` = '系统管理员' WHERE `id` = 1 AND `name` <=> '管理员';"
+    ));
+    assert!(diff_sql.contains(
+        "DELETE FROM `roles` WHERE `id` = 3 AND `code` <=> 'legacy' AND `name` <=> '旧角色';"
+    ));
+    assert!(diff_sql.contains(
+        "INSERT IGNORE INTO `roles` (`code`, `id`, `name`) VALUES ('auditor', 4, '审计员');"
+    ));
+    assert!(diff_sql.contains(
+        "INSERT IGNORE INTO `dict_items` (`code`, `label`, `type`) VALUES ('off', '停用', 'status');"
+    ));
+    assert!(!diff_sql.contains("users"));
+    let delete = statements
+        .iter()
+        .find(|statement| statement.sql.starts_with("DELETE FROM"))
+        .unwrap();
+    assert_eq!(delete.safety, ChangeSafety::DataLoss);
+
+    // 回滚时删除新增的默认数据，恢复被删除的数据
+    let down_diff = diff_schemas_down(&from_schema, &to_schema, &RenameHints::default());
+    let (down_sql, _) = render_mysql_diff(&down_diff);
+    assert!(down_sql.contains("DELETE FROM `roles` WHERE `id` = 4"));
+    assert!(down_sql.contains("VALUES ('legacy', 3, '旧角色');"));
+}
@@ -1,16 +1,24 @@
+use crate::error::DuckError;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
 /// 表列定义
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TableColumn {
     pub name: String,
     pub data_type: String,
     pub nullable: bool,
+    /// 默认值表达式（保留SQL原文，字符串带引号）
     pub default_value: Option<String>,
+    /// `ON UPDATE` 表达式（如 `CURRENT_TIMESTAMP`）
+    #[serde(default)]
+    pub on_update: Option<String>,
     pub auto_increment: bool,
     pub comment: Option<String>,
 }
 
 /// 表索引定义
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TableIndex {
     pub name: String,
     pub columns: Vec<String>,
@@ -19,12 +27,507 @@ pub struct TableIndex {
     pub index_type: Option<String>,
 }
 
+/// 外键约束定义
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableForeignKey {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub referenced_table: String,
+    pub referenced_columns: Vec<String>,
+    pub on_delete: Option<String>,
+    pub on_update: Option<String>,
+}
+
+/// CHECK约束定义
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableCheck {
+    pub name: String,
+    pub expression: String,
+    pub enforced: Option<bool>,
+}
+
 /// 表定义
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TableDefinition {
     pub name: String,
     pub columns: Vec<TableColumn>,
     pub indexes: Vec<TableIndex>,
+    pub foreign_keys: Vec<TableForeignKey>,
+    pub checks: Vec<TableCheck>,
+    pub engine: Option<String>,
+    pub charset: Option<String>,
+    pub collation: Option<String>,
+    pub row_format: Option<String>,
+    pub comment: Option<String>,
+}
+
+impl TableDefinition {
+    /// 表选项
+    pub fn options(&self) -> TableOptions {
+        TableOptions {
+            engine: self.engine.clone(),
+            charset: self.charset.clone(),
+            collation: self.collation.clone(),
+            row_format: self.row_format.clone(),
+            comment: self.comment.clone(),
+        }
+    }
+}
+
+/// 表选项（ENGINE、字符集、排序规则、行格式、表注释）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TableOptions {
     pub engine: Option<String>,
     pub charset: Option<String>,
+    pub collation: Option<String>,
+    pub row_format: Option<String>,
+    pub comment: Option<String>,
+}
+
+/// 结构定义的变化，包含变化前后的完整定义
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DefinitionChange<T> {
+    pub before: T,
+    pub after: T,
+}
+
+/// 表或列的重命名
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NameChange {
+    pub from: String,
+    pub to: String,
+}
+
+/// 列在表中的目标位置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnPosition {
+    pub column: String,
+    /// 前一列的列名；`None` 表示第一列（`FIRST`）
+    pub after: Option<String>,
+}
+
+/// 单个表的结构差异
+///
+/// 变化前的定义已按识别出的重命名对齐（使用新的表名和列名）。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TableDiff {
+    /// 表名（重命名后的名称）
+    pub name: String,
+    /// 重命名前的表名
+    pub renamed_from: Option<String>,
+    pub renamed_columns: Vec<NameChange>,
+    pub added_columns: Vec<TableColumn>,
+    pub removed_columns: Vec<TableColumn>,
+    pub modified_columns: Vec<DefinitionChange<TableColumn>>,
+    /// 定义未变、只需要调整位置的列
+    pub moved_columns: Vec<TableColumn>,
+    /// 新增和需要调整位置的列的目标位置（按执行顺序），使列顺序与目标 `CREATE TABLE` 一致
+    pub column_positions: Vec<ColumnPosition>,
+    pub added_indexes: Vec<TableIndex>,
+    pub removed_indexes: Vec<TableIndex>,
+    pub modified_indexes: Vec<DefinitionChange<TableIndex>>,
+    pub added_foreign_keys: Vec<TableForeignKey>,
+    pub removed_foreign_keys: Vec<TableForeignKey>,
+    pub modified_foreign_keys: Vec<DefinitionChange<TableForeignKey>>,
+    pub added_checks: Vec<TableCheck>,
+    pub removed_checks: Vec<TableCheck>,
+    pub modified_checks: Vec<DefinitionChange<TableCheck>>,
+    /// 表选项变化（任一选项变化时包含全部选项）
+    pub options: Option<DefinitionChange<TableOptions>>,
+}
+
+impl TableDiff {
+    /// 是否没有任何结构变化（仅重命名表不算在内）
+    pub fn is_empty(&self) -> bool {
+        self.renamed_columns.is_empty()
+            && self.added_columns.is_empty()
+            && self.removed_columns.is_empty()
+            && self.modified_columns.is_empty()
+            && self.moved_columns.is_empty()
+            && self.added_indexes.is_empty()
+            && self.removed_indexes.is_empty()
+            && self.modified_indexes.is_empty()
+            && self.added_foreign_keys.is_empty()
+            && self.removed_foreign_keys.is_empty()
+            && self.modified_foreign_keys.is_empty()
+            && self.added_checks.is_empty()
+            && self.removed_checks.is_empty()
+            && self.modified_checks.is_empty()
+            && self.options.is_none()
+    }
+}
+
+/// 视图、触发器、存储过程和函数的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ObjectKind {
+    View,
+    Trigger,
+    Procedure,
+    Function,
+}
+
+impl ObjectKind {
+    /// SQL关键字
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ObjectKind::View => "VIEW",
+            ObjectKind::Trigger => "TRIGGER",
+            ObjectKind::Procedure => "PROCEDURE",
+            ObjectKind::Function => "FUNCTION",
+        }
+    }
+
+    /// 中文描述，用于日志和提示
+    pub fn description(&self) -> &'static str {
+        match self {
+            ObjectKind::View => "视图",
+            ObjectKind::Trigger => "触发器",
+            ObjectKind::Procedure => "存储过程",
+            ObjectKind::Function => "函数",
+        }
+    }
+}
+
+/// 视图、触发器、存储过程或函数的定义
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ObjectDefinition {
+    pub kind: ObjectKind,
+    pub name: String,
+    /// 完整的 `CREATE` 语句（不含结尾的分隔符）
+    pub definition: String,
+}
+
+/// 参考数据中的一行：列名 -> SQL字面量（字符串带引号，如 `'admin'`、`1`、`NULL`）
+pub type SeedRow = BTreeMap<String, String>;
+
+/// 参考数据表中的数据（来自 `INSERT` 语句，按脚本中的出现顺序）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SeedData {
+    pub table: String,
+    pub rows: Vec<SeedRow>,
+}
+
+/// 参考数据表的差异（按主键匹配行）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SeedDataDiff {
+    pub table: String,
+    /// 主键列
+    pub key_columns: Vec<String>,
+    /// 新增的行
+    pub inserted_rows: Vec<SeedRow>,
+    /// 值有变化的行
+    pub updated_rows: Vec<DefinitionChange<SeedRow>>,
+    /// 新脚本中不再包含的行
+    pub deleted_rows: Vec<SeedRow>,
+}
+
+impl SeedDataDiff {
+    /// 是否没有任何数据变化
+    pub fn is_empty(&self) -> bool {
+        self.inserted_rows.is_empty()
+            && self.updated_rows.is_empty()
+            && self.deleted_rows.is_empty()
+    }
+}
+
+/// 数据库选项（来自 `CREATE DATABASE` 语句）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DatabaseOptions {
+    pub name: String,
+    pub charset: Option<String>,
+    pub collation: Option<String>,
+}
+
+/// 单个数据库中的表定义
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DatabaseDefinition {
+    /// 数据库名；`None` 表示在第一个 `USE` 之前创建的表，属于连接的默认数据库
+    pub name: Option<String>,
+    /// 脚本中的 `CREATE DATABASE` 定义（只通过 `USE` 引用的数据库为 `None`）
+    pub options: Option<DatabaseOptions>,
+    /// 表名 -> 表定义
+    pub tables: HashMap<String, TableDefinition>,
+    /// 视图、触发器、存储过程和函数（按脚本中的出现顺序）
+    pub objects: Vec<ObjectDefinition>,
+    /// 标记为参考数据（`-- @reference-data`）的表中的数据
+    pub seed_data: Vec<SeedData>,
+}
+
+/// 初始化脚本中的完整数据库架构，按数据库分组（保持脚本中的出现顺序）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SchemaDefinition {
+    pub databases: Vec<DatabaseDefinition>,
+}
+
+impl SchemaDefinition {
+    /// 由不区分数据库的表集合构造（所有表属于默认数据库）
+    pub fn from_tables(tables: HashMap<String, TableDefinition>) -> Self {
+        Self {
+            databases: vec![DatabaseDefinition {
+                tables,
+                ..Default::default()
+            }],
+        }
+    }
+
+    /// 按数据库名查找（`None` 为默认数据库）
+    pub fn database(&self, name: Option<&str>) -> Option<&DatabaseDefinition> {
+        self.databases
+            .iter()
+            .find(|database| database.name.as_deref() == name)
+    }
+
+    /// 获取数据库，不存在时按出现顺序追加
+    pub fn database_mut(&mut self, name: Option<&str>) -> &mut DatabaseDefinition {
+        let position = match self
+            .databases
+            .iter()
+            .position(|database| database.name.as_deref() == name)
+        {
+            Some(position) => position,
+            None => {
+                self.databases.push(DatabaseDefinition {
+                    name: name.map(|name| name.to_string()),
+                    ..Default::default()
+                });
+                self.databases.len() - 1
+            }
+        };
+        &mut self.databases[position]
+    }
+
+    /// 所有表，键为 `库名.表名`（默认数据库中的表只有表名）
+    pub fn qualified_tables(&self) -> HashMap<String, &TableDefinition> {
+        self.databases
+            .iter()
+            .flat_map(|database| {
+                database.tables.iter().map(|(table_name, table_def)| {
+                    let key = match &database.name {
+                        Some(database_name) => format!("{database_name}.{table_name}"),
+                        None => table_name.clone(),
+                    };
+                    (key, table_def)
+                })
+            })
+            .collect()
+    }
+}
+
+/// 单个数据库内的结构化差异（表、视图、触发器、存储过程和函数）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DatabaseDiff {
+    /// 数据库名；`None` 表示连接的默认数据库
+    pub database: Option<String>,
+    /// 新增的表（按表名排序）
+    pub added_tables: Vec<TableDefinition>,
+    /// 删除的表（按表名排序）
+    pub removed_tables: Vec<TableDefinition>,
+    /// 重命名的表
+    pub renamed_tables: Vec<NameChange>,
+    /// 结构有变化的表（按表名排序）
+    pub modified_tables: Vec<TableDiff>,
+    /// 新增的视图、触发器、存储过程和函数（按新脚本中的顺序）
+    pub added_objects: Vec<ObjectDefinition>,
+    /// 删除的视图、触发器、存储过程和函数（按旧脚本中的顺序）
+    pub removed_objects: Vec<ObjectDefinition>,
+    /// 定义有变化的视图、触发器、存储过程和函数（按新脚本中的顺序）
+    pub modified_objects: Vec<DefinitionChange<ObjectDefinition>>,
+    /// 参考数据表的数据差异（按新脚本中的顺序）
+    pub seed_data: Vec<SeedDataDiff>,
+}
+
+impl DatabaseDiff {
+    /// 是否没有任何结构变化
+    pub fn is_empty(&self) -> bool {
+        self.added_tables.is_empty()
+            && self.removed_tables.is_empty()
+            && self.renamed_tables.is_empty()
+            && self.modified_tables.is_empty()
+            && self.added_objects.is_empty()
+            && self.removed_objects.is_empty()
+            && self.modified_objects.is_empty()
+            && self.seed_data.iter().all(SeedDataDiff::is_empty)
+    }
+
+    /// 按表名查找表差异
+    pub fn table(&self, name: &str) -> Option<&TableDiff> {
+        self.modified_tables.iter().find(|table| table.name == name)
+    }
+}
+
+/// 结构化的数据库架构差异，可序列化为JSON供界面展示和CI检查使用，
+/// 差异SQL由它渲染生成（见 `render_mysql_diff`）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SchemaDiff {
+    /// 新增的数据库（新脚本中的 `CREATE DATABASE`）
+    pub added_databases: Vec<DatabaseOptions>,
+    /// 删除的数据库（旧脚本创建、新脚本中不再出现的数据库）
+    pub removed_databases: Vec<DatabaseOptions>,
+    /// 字符集或排序规则有变化的数据库
+    pub modified_databases: Vec<DefinitionChange<DatabaseOptions>>,
+    /// 每个数据库内的表差异（默认数据库在最前）
+    pub databases: Vec<DatabaseDiff>,
+}
+
+impl SchemaDiff {
+    /// 是否没有任何架构变化
+    pub fn is_empty(&self) -> bool {
+        self.added_databases.is_empty()
+            && self.removed_databases.is_empty()
+            && self.modified_databases.is_empty()
+            && self.databases.iter().all(DatabaseDiff::is_empty)
+    }
+
+    /// 按数据库名查找表差异（`None` 为默认数据库）
+    pub fn database(&self, name: Option<&str>) -> Option<&DatabaseDiff> {
+        self.databases
+            .iter()
+            .find(|database| database.database.as_deref() == name)
+    }
+
+    /// 差异涉及的数据库名（不含默认数据库）
+    pub fn database_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        let changed = self
+            .added_databases
+            .iter()
+            .chain(self.modified_databases.iter().map(|change| &change.after))
+            .map(|options| options.name.as_str())
+            .chain(
+                self.databases
+                    .iter()
+                    .filter_map(|database_diff| database_diff.database.as_deref()),
+            )
+            .chain(
+                self.removed_databases
+                    .iter()
+                    .map(|options| options.name.as_str()),
+            );
+        for name in changed {
+            if !names.iter().any(|existing| existing == name) {
+                names.push(name.to_string());
+            }
+        }
+        names
+    }
+
+    /// 是否有需要在默认数据库中执行的变化（差异SQL中不带 USE 语句的部分）
+    pub fn uses_default_database(&self) -> bool {
+        self.database(None)
+            .is_some_and(|database_diff| !database_diff.is_empty())
+    }
+
+    /// 按表名查找表差异，表名可以用 `库名.表名` 限定
+    pub fn table(&self, name: &str) -> Option<&TableDiff> {
+        match name.split_once('.') {
+            Some((database, table)) => self.database(Some(database))?.table(table),
+            None => self
+                .databases
+                .iter()
+                .find_map(|database| database.table(name)),
+        }
+    }
+
+    /// 序列化为格式化的JSON
+    pub fn to_json(&self) -> Result<String, DuckError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// 差异语句的安全级别（按严重程度递增排序）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ChangeSafety {
+    /// 只修改元数据或新增结构，不影响现有数据
+    Safe,
+    /// 需要重建表或校验全表数据，执行期间可能长时间锁表
+    Locking,
+    /// 会删除或截断现有数据
+    DataLoss,
+}
+
+impl ChangeSafety {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeSafety::Safe => "SAFE",
+            ChangeSafety::Locking => "LOCKING",
+            ChangeSafety::DataLoss => "DATA_LOSS",
+        }
+    }
+
+    /// 中文描述，用于日志和提示
+    pub fn description(&self) -> &'static str {
+        match self {
+            ChangeSafety::Safe => "安全",
+            ChangeSafety::Locking => "锁表",
+            ChangeSafety::DataLoss => "数据丢失",
+        }
+    }
+}
+
+/// 带安全级别的差异语句
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiffStatement {
+    pub sql: String,
+    pub safety: ChangeSafety,
+}
+
+impl DiffStatement {
+    pub fn new(sql: impl Into<String>, safety: ChangeSafety) -> Self {
+        Self {
+            sql: sql.into(),
+            safety,
+        }
+    }
+}
+
+/// 重命名提示，来自目标SQL中的注释：
+///
+/// ```sql
+/// -- @rename-table: old_users -> users
+/// -- @rename-column: users.user_name -> nick_name
+/// ```
+///
+/// 列重命名提示中的表名为重命名后的表名。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenameHints {
+    /// 旧表名 -> 新表名
+    pub tables: HashMap<String, String>,
+    /// 表名 -> (旧列名 -> 新列名)
+    pub columns: HashMap<String, HashMap<String, String>>,
+}
+
+/// 架构差异分析结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaDiffReport {
+    /// 可执行的差异SQL
+    pub diff_sql: String,
+    /// 差异描述
+    pub description: String,
+    /// 差异SQL中的每条语句及其安全级别
+    pub statements: Vec<DiffStatement>,
+    /// 回滚（down）差异SQL，把目标结构还原为原结构；初始版本或无变化时为空
+    pub down_sql: String,
+    /// 结构化的架构差异；初始版本或无变化时为空
+    pub diff: SchemaDiff,
+}
+
+impl SchemaDiffReport {
+    /// 所有语句中最高的风险级别
+    pub fn max_safety(&self) -> ChangeSafety {
+        self.statements
+            .iter()
+            .map(|statement| statement.safety)
+            .max()
+            .unwrap_or(ChangeSafety::Safe)
+    }
+
+    /// 指定安全级别的语句
+    pub fn statements_with_safety(&self, safety: ChangeSafety) -> Vec<&DiffStatement> {
+        self.statements
+            .iter()
+            .filter(|statement| statement.safety == safety)
+            .collect()
+    }
 }
@@ -1,12 +1,15 @@
 use crate::{
     DuckError, Result,
     api::ApiClient,
-    backup::{BackupManager, BackupOptions, RestoreOptions},
+    backup::{BackupManager, BackupOptions, RestoreFilter, RestoreOptions},
     config::AppConfig,
     constants::timeout,
     container::DockerManager,
-    database::{BackupType, Database},
+    database::{BackupMode, BackupRecord, BackupType, Database},
+    package_signature,
+    schema_migration::{self, SchemaMigrationPlan},
 };
+use std::cmp::Ordering;
 use std::path::{Path, PathBuf};
 use tempfile::TempDir;
 use tracing::{info, warn};
@@ -20,7 +23,6 @@ pub struct UpgradeManager {
     docker_manager: DockerManager,
     backup_manager: BackupManager,
     api_client: ApiClient,
-    #[allow(dead_code)]
     database: Database,
 }
 
@@ -32,6 +34,37 @@ pub struct UpgradeOptions {
     pub use_incremental: bool,
     pub backup_dir: Option<PathBuf>,
     pub download_only: bool,
+    /// 允许执行可能导致数据丢失的数据库架构迁移
+    pub allow_data_loss: bool,
+    /// 安装指定版本（为空时安装最新版本）；低于当前版本时为降级，需要该版本的升级前备份
+    pub target_version: Option<String>,
+    /// 跳过服务包签名校验（仅用于开发环境）
+    pub skip_signature_verification: bool,
+}
+
+/// 比较服务版本号（`1.2.3` 或 `v1.2.3`），按数字逐段比较，每段只取开头的数字
+pub fn compare_service_versions(a: &str, b: &str) -> Ordering {
+    let parse = |version: &str| -> Vec<u64> {
+        version
+            .trim()
+            .trim_start_matches('v')
+            .split('.')
+            .map(|part| {
+                let digits: String = part.chars().take_while(char::is_ascii_digit).collect();
+                digits.parse().unwrap_or(0)
+            })
+            .collect()
+    };
+
+    let (mut a, mut b) = (parse(a), parse(b));
+    // 忽略末尾的0，使 1.2 与 1.2.0 相等
+    while a.last() == Some(&0) {
+        a.pop();
+    }
+    while b.last() == Some(&0) {
+        b.pop();
+    }
+    a.cmp(&b)
 }
 
 pub type ProgressCallback = Box<dyn Fn(UpgradeStep, &str) + Send + Sync>;
@@ -46,6 +79,7 @@ pub enum UpgradeStep {
     LoadingImages,
     StartingServices,
     VerifyingServices,
+    MigratingSchema,
     CleaningUp,
     Completed,
     Failed(String),
@@ -98,23 +132,30 @@ impl UpgradeManager {
         let callback = progress_callback.as_ref();
 
         self.send_progress(callback, UpgradeStep::CheckingUpdates, "检查服务更新");
-        let has_update = self.check_for_updates().await?;
+        // 安装指定版本时不检查最新版本
+        if options.target_version.is_none() {
+            let has_update = self.check_for_updates().await?;
+
+            if !has_update {
+                info!("服务已是最新版本");
+                self.send_progress(callback, UpgradeStep::Completed, "服务已是最新版本");
+                return Ok(UpgradeResult {
+                    success: true,
+                    from_version: from_version.clone(),
+                    to_version: from_version,
+                    error: None,
+                    backup_id: None,
+                });
+            }
 
-        if !has_update {
-            info!("服务已是最新版本");
-            self.send_progress(callback, UpgradeStep::Completed, "服务已是最新版本");
-            return Ok(UpgradeResult {
-                success: true,
-                from_version: from_version.clone(),
-                to_version: from_version,
-                error: None,
-                backup_id: None,
-            });
+            info!("发现新版本可用");
         }
 
-        info!("发现新版本可用");
         let download_url = self.api_client.get_service_download_url();
-        let to_version = "latest".to_string();
+        let to_version = options
+            .target_version
+            .clone()
+            .unwrap_or_else(|| "latest".to_string());
 
         self.perform_upgrade_flow(options, &from_version, &to_version, &download_url, callback)
             .await
@@ -126,7 +167,10 @@ impl UpgradeManager {
         progress_callback: Option<ProgressCallback>,
     ) -> Result<UpgradeResult> {
         let from_version = self.config.versions.docker_service.clone();
-        let to_version = "latest".to_string();
+        let to_version = options
+            .target_version
+            .clone()
+            .unwrap_or_else(|| "latest".to_string());
         let download_url = self.api_client.get_service_download_url();
         let callback = progress_callback.as_ref();
 
@@ -144,7 +188,11 @@ impl UpgradeManager {
     ) -> Result<UpgradeResult> {
         let mut backup_id: Option<i64> = None;
         let mut services_stopped = false;
+        let mut schema_migrated = false;
         let temp_dir = TempDir::new()?;
+        let downgrade_backup = self
+            .find_downgrade_backup(&options, from_version, to_version)
+            .await?;
 
         let result: Result<()> = async {
             backup_id = self
@@ -159,6 +207,7 @@ impl UpgradeManager {
                 let download_path = temp_dir.path().join(package_filename);
                 self.download_and_extract(
                     download_url,
+                    &options,
                     &download_path,
                     temp_dir.path(),
                     progress_callback,
@@ -178,17 +227,38 @@ impl UpgradeManager {
             let download_path = temp_dir.path().join(package_filename);
             self.download_and_extract(
                 download_url,
+                &options,
                 &download_path,
                 temp_dir.path(),
                 progress_callback,
             )
             .await?;
 
+            // 应用新文件前比较新旧服务包的MySQL初始化SQL（降级时数据从备份恢复，不迁移架构）
+            let schema_plan = if downgrade_backup.is_some() {
+                None
+            } else {
+                self.plan_schema_migration(temp_dir.path(), from_version, to_version)?
+            };
+            if let Some(plan) = &schema_plan {
+                schema_migration::ensure_migration_allowed(plan, options.allow_data_loss)?;
+                if let Some(id) = backup_id {
+                    self.save_schema_rollback(id, plan).await;
+                }
+            }
+
             self.load_new_images(temp_dir.path(), progress_callback)
                 .await?;
             self.apply_files(temp_dir.path(), progress_callback).await?;
             self.start_services(progress_callback).await?;
+            if let Some(backup) = &downgrade_backup {
+                self.restore_downgrade_data(backup).await?;
+            }
             self.verify_services(progress_callback).await?;
+            if let Some(plan) = &schema_plan {
+                schema_migrated = true;
+                self.migrate_schema(plan, progress_callback).await?;
+            }
             self.cleanup(&download_path, progress_callback).await?;
 
             Ok(())
@@ -204,7 +274,10 @@ impl UpgradeManager {
             );
 
             if let Some(id) = backup_id {
-                match self.rollback_from_backup(id, progress_callback).await {
+                match self
+                    .rollback_from_backup(id, schema_migrated, progress_callback)
+                    .await
+                {
                     Ok(_) => {
                         let final_error_msg = format!("升级失败 ({e})，但已成功回滚到备份 ID {id}");
                         return Ok(UpgradeResult {
@@ -291,6 +364,7 @@ impl UpgradeManager {
     async fn rollback_from_backup(
         &self,
         backup_id: i64,
+        schema_migrated: bool,
         progress_callback: Option<&ProgressCallback>,
     ) -> Result<()> {
         warn!("从备份 ID {} 进行回滚。", backup_id);
@@ -308,6 +382,7 @@ impl UpgradeManager {
         let options = RestoreOptions {
             target_dir: docker_dir.to_path_buf(),
             force_overwrite: true,
+            filter: RestoreFilter::default(),
         };
 
         self.docker_manager.stop_services().await.ok();
@@ -322,10 +397,88 @@ impl UpgradeManager {
             "",
         );
         self.docker_manager.start_services().await?;
+
+        if schema_migrated {
+            self.rollback_schema(backup_id).await?;
+        }
+
         info!("回滚并重启服务成功。");
         Ok(())
     }
 
+    /// 安装低于当前版本的指定版本时，查找目标版本最近一次成功的升级前备份
+    ///
+    /// 旧版本无法使用新版本的数据，降级后从该备份恢复数据；没有备份时拒绝降级。
+    async fn find_downgrade_backup(
+        &self,
+        options: &UpgradeOptions,
+        from_version: &str,
+        to_version: &str,
+    ) -> Result<Option<BackupRecord>> {
+        if options.target_version.is_none()
+            || compare_service_versions(to_version, from_version) != Ordering::Less
+        {
+            return Ok(None);
+        }
+
+        let backup = self
+            .backup_manager
+            .find_pre_upgrade_backup(to_version)
+            .await?
+            .ok_or_else(|| {
+                DuckError::Custom(format!(
+                    "降级到 {to_version} 需要该版本的升级前备份用于恢复数据，没有找到可用的备份"
+                ))
+            })?;
+        info!(
+            "降级 {} -> {}，将从升级前备份 {} 恢复数据",
+            from_version, to_version, backup.id
+        );
+        Ok(Some(backup))
+    }
+
+    /// 降级后从目标版本的升级前备份恢复数据（配置文件使用新安装的版本）
+    async fn restore_downgrade_data(&self, backup: &BackupRecord) -> Result<()> {
+        let docker_dir = self
+            .docker_manager
+            .get_working_directory()
+            .ok_or_else(|| DuckError::Custom("无法确定 Docker 工作目录".to_string()))?;
+
+        self.backup_manager
+            .restore_data_only(backup.id, docker_dir, &RestoreFilter::default())
+            .await
+    }
+
+    /// 把数据库架构回滚脚本与升级前备份保存在一起（保存失败不影响升级）
+    async fn save_schema_rollback(&self, backup_id: i64, plan: &SchemaMigrationPlan) {
+        let Some(rollback_script) = plan.rollback_script() else {
+            return;
+        };
+
+        if let Err(e) = self
+            .backup_manager
+            .save_schema_rollback(backup_id, &rollback_script)
+            .await
+        {
+            warn!("保存数据库架构回滚脚本失败: {}", e);
+        }
+    }
+
+    /// 文件回滚后恢复数据库架构（MySQL数据已随备份恢复时跳过）
+    async fn rollback_schema(&self, backup_id: i64) -> Result<()> {
+        let applied = schema_migration::rollback_schema_after_restore(
+            &self.docker_manager,
+            &self.backup_manager,
+            backup_id,
+            &RestoreFilter::default(),
+        )
+        .await?;
+        if !applied {
+            info!("备份 {} 恢复后无需回滚数据库架构", backup_id);
+        }
+        Ok(())
+    }
+
     async fn create_backup_if_needed(
         &self,
         options: &UpgradeOptions,
@@ -348,9 +501,12 @@ impl UpgradeManager {
 
         let backup_options = BackupOptions {
             backup_type: BackupType::PreUpgrade,
+            backup_mode: BackupMode::Full,
             service_version: self.config.versions.docker_service.clone(),
             source_dirs: vec![docker_dir.to_path_buf()],
+            compression_type: self.backup_manager.configured_compression().await?,
             compression_level: 6,
+            hot_backup: false,
         };
 
         let backup_record = self.backup_manager.create_backup(backup_options).await?;
@@ -379,6 +535,7 @@ impl UpgradeManager {
     async fn download_and_extract(
         &self,
         _download_url: &str,
+        options: &UpgradeOptions,
         download_path: &Path,
         extract_dir: &Path,
         progress_callback: Option<&ProgressCallback>,
@@ -391,9 +548,15 @@ impl UpgradeManager {
 
         // 使用优化的下载方法，支持外链和智能判断
         self.api_client
-            .download_service_update_optimized(download_path, None)
+            .download_service_update_optimized(download_path, options.target_version.as_deref())
             .await?;
 
+        if options.skip_signature_verification {
+            warn!("⚠️ 已跳过服务包签名校验，仅用于开发环境");
+        } else {
+            package_signature::verify_package(download_path).await?;
+        }
+
         self.send_progress(
             progress_callback,
             UpgradeStep::ExtractingUpdate,
@@ -463,6 +626,45 @@ impl UpgradeManager {
         self.docker_manager.check_services_health().await
     }
 
+    fn plan_schema_migration(
+        &self,
+        package_dir: &Path,
+        from_version: &str,
+        to_version: &str,
+    ) -> Result<Option<SchemaMigrationPlan>> {
+        let working_dir = self
+            .docker_manager
+            .get_working_directory()
+            .ok_or_else(|| DuckError::Custom("无法确定 Docker 工作目录".to_string()))?;
+
+        let old_sql = schema_migration::load_init_sql(working_dir)?;
+        let new_sql = schema_migration::load_init_sql(package_dir)?;
+        let plan = schema_migration::plan_schema_migration(
+            old_sql.as_deref(),
+            new_sql.as_deref(),
+            from_version,
+            to_version,
+        )?;
+
+        if let Some(plan) = &plan {
+            schema_migration::print_migration_plan(plan);
+        }
+        Ok(plan)
+    }
+
+    async fn migrate_schema(
+        &self,
+        plan: &SchemaMigrationPlan,
+        progress_callback: Option<&ProgressCallback>,
+    ) -> Result<()> {
+        self.send_progress(
+            progress_callback,
+            UpgradeStep::MigratingSchema,
+            "正在迁移数据库架构",
+        );
+        schema_migration::apply_schema_migration(&self.docker_manager, &self.database, plan).await
+    }
+
     async fn cleanup(
         &self,
         download_path: &Path,
@@ -530,3 +732,19 @@ impl UpgradeManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_service_versions() {
+        assert_eq!(compare_service_versions("1.2.0", "1.10.0"), Ordering::Less);
+        assert_eq!(compare_service_versions("v2.0", "1.9.9"), Ordering::Greater);
+        assert_eq!(compare_service_versions("1.2", "v1.2.0"), Ordering::Equal);
+        assert_eq!(
+            compare_service_versions("1.3.0-beta", "1.3.1"),
+            Ordering::Less
+        );
+    }
+}
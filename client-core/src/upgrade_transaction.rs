@@ -0,0 +1,414 @@
+//! 服务升级事务
+//!
+//! 升级部署修改 `docker/` 目录前，把当前服务包文件保存到旁边的 `.docker.pre-upgrade` 目录，
+//! 并记录 docker-compose.yml 中各镜像当前的镜像ID。新版本服务未能在配置的时间内健康运行时，
+//! 恢复旧的服务包文件、把镜像标签指回旧镜像、从升级前备份恢复数据并重启服务，
+//! 升级历史记录为 `ROLLED_BACK`。
+//!
+//! 数据、日志、上传和备份目录属于运行时数据，不随服务包文件保存和恢复；`images/` 下的镜像tar包
+//! 使用硬链接保存，不额外占用磁盘空间（解压和补丁都是替换文件，不会修改已链接的旧文件）。
+
+use crate::backup::{BackupManager, RestoreFilter};
+use crate::constants::{docker, timeout, upgrade};
+use crate::container::DockerManager;
+use crate::database::{Database, UpgradeStatus};
+use crate::{DuckError, Result};
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+/// 一次升级部署的事务
+#[derive(Debug)]
+pub struct UpgradeTransaction {
+    upgrade_id: String,
+    from_version: String,
+    to_version: String,
+    docker_dir: PathBuf,
+    previous_dir: PathBuf,
+    /// 升级前的镜像引用及其镜像ID
+    previous_images: Vec<(String, String)>,
+    /// 升级前备份，回滚时从该备份恢复数据
+    backup_id: Option<i64>,
+}
+
+impl UpgradeTransaction {
+    /// 开始升级事务：保存当前服务包文件和镜像ID，创建升级历史记录（状态为RUNNING）
+    pub async fn begin(
+        database: &Database,
+        docker_manager: &DockerManager,
+        docker_dir: &Path,
+        from_version: &str,
+        to_version: &str,
+    ) -> Result<Self> {
+        let previous_dir = Self::previous_dir(docker_dir)?;
+        info!("💾 正在保存当前服务包文件到 {}...", previous_dir.display());
+        let (source, target) = (docker_dir.to_path_buf(), previous_dir.clone());
+        tokio::task::spawn_blocking(move || preserve_package_files(&source, &target)).await??;
+
+        let mut previous_images = Vec::new();
+        match docker_manager.get_compose_images().await {
+            Ok(images) => {
+                for image in images {
+                    match docker_manager.get_image_id(&image).await {
+                        Ok(Some(id)) => previous_images.push((image, id)),
+                        Ok(None) => {}
+                        Err(e) => warn!("⚠️ 获取镜像 {} 的ID失败: {}", image, e),
+                    }
+                }
+            }
+            Err(e) => warn!("⚠️ 获取当前服务镜像失败，回滚时将无法恢复镜像标签: {}", e),
+        }
+
+        let upgrade_id = database
+            .create_upgrade_history(from_version, to_version, "FULL")
+            .await?;
+        info!(
+            "📝 升级事务已开始: {} -> {} (记录了 {} 个镜像)",
+            from_version,
+            to_version,
+            previous_images.len()
+        );
+
+        Ok(Self {
+            upgrade_id,
+            from_version: from_version.to_string(),
+            to_version: to_version.to_string(),
+            docker_dir: docker_dir.to_path_buf(),
+            previous_dir,
+            previous_images,
+            backup_id: None,
+        })
+    }
+
+    /// 升级前服务包文件的保存目录（`docker/` 旁的 `.docker.pre-upgrade`）
+    pub fn previous_dir(docker_dir: &Path) -> Result<PathBuf> {
+        let dir_name = docker_dir
+            .file_name()
+            .ok_or_else(|| DuckError::Upgrade(format!("无效的服务目录: {}", docker_dir.display())))?
+            .to_string_lossy();
+        Ok(docker_dir.with_file_name(format!(".{dir_name}.{}", upgrade::PRE_UPGRADE_SUFFIX)))
+    }
+
+    /// 升级ID
+    pub fn upgrade_id(&self) -> &str {
+        &self.upgrade_id
+    }
+
+    /// 关联升级前备份，回滚时从该备份恢复数据
+    pub async fn set_backup(&mut self, database: &Database, backup_id: i64) -> Result<()> {
+        // 记录失败时回滚仍然从该备份恢复数据
+        self.backup_id = Some(backup_id);
+        database
+            .set_upgrade_history_backup(&self.upgrade_id, backup_id)
+            .await
+    }
+
+    /// 升级后等待服务健康运行的时间（`upgrade.health_check_timeout`，秒）
+    pub async fn health_check_timeout(database: &Database) -> Result<Duration> {
+        let seconds = database
+            .get_config("upgrade.health_check_timeout")
+            .await?
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .filter(|seconds| *seconds > 0)
+            .unwrap_or(timeout::HEALTH_CHECK_TIMEOUT);
+        Ok(Duration::from_secs(seconds))
+    }
+
+    /// 升级失败时是否自动回滚（`upgrade.rollback_enabled`，默认启用）
+    pub async fn rollback_enabled(database: &Database) -> Result<bool> {
+        Ok(database
+            .get_config("upgrade.rollback_enabled")
+            .await?
+            .is_none_or(|value| value.trim() != "false"))
+    }
+
+    /// 升级成功：记录为SUCCESS并删除保存的旧服务包文件
+    pub async fn commit(self, database: &Database, upgrade_log: String) -> Result<()> {
+        database
+            .complete_upgrade_history(
+                &self.upgrade_id,
+                UpgradeStatus::Success,
+                None,
+                Some(upgrade_log),
+            )
+            .await?;
+
+        if let Err(e) = tokio::fs::remove_dir_all(&self.previous_dir).await {
+            warn!(
+                "⚠️ 清理升级前服务包文件失败 {}: {}",
+                self.previous_dir.display(),
+                e
+            );
+        }
+        info!(
+            "✅ 升级事务已完成: {} -> {}",
+            self.from_version, self.to_version
+        );
+        Ok(())
+    }
+
+    /// 升级失败但不回滚：记录为FAILED，保留旧服务包文件供手动恢复
+    pub async fn fail(self, database: &Database, error_message: String) -> Result<()> {
+        database
+            .complete_upgrade_history(
+                &self.upgrade_id,
+                UpgradeStatus::Failed,
+                Some(error_message),
+                None,
+            )
+            .await?;
+        warn!(
+            "⚠️ 升级前的服务包文件保留在: {}",
+            self.previous_dir.display()
+        );
+        Ok(())
+    }
+
+    /// 修改服务包文件前中止升级：记录为FAILED并删除保存的旧服务包文件，服务无需回滚
+    pub async fn cancel(self, database: &Database, error_message: String) -> Result<()> {
+        database
+            .complete_upgrade_history(
+                &self.upgrade_id,
+                UpgradeStatus::Failed,
+                Some(error_message),
+                None,
+            )
+            .await?;
+
+        if let Err(e) = tokio::fs::remove_dir_all(&self.previous_dir).await {
+            warn!(
+                "⚠️ 清理升级前服务包文件失败 {}: {}",
+                self.previous_dir.display(),
+                e
+            );
+        }
+        info!(
+            "⏹️ 升级 {} -> {} 已中止，服务包文件未修改",
+            self.from_version, self.to_version
+        );
+        Ok(())
+    }
+
+    /// 回滚升级：恢复旧服务包文件和镜像标签，从升级前备份恢复数据并重启服务，记录为ROLLED_BACK
+    ///
+    /// 回滚失败时记录为FAILED，旧服务包文件保留在 `.docker.pre-upgrade`。
+    pub async fn rollback(
+        self,
+        database: &Database,
+        docker_manager: &DockerManager,
+        backup_manager: &BackupManager,
+        error_message: String,
+        rollback_reason: &str,
+    ) -> Result<()> {
+        warn!(
+            "↩️ 正在回滚升级 {} -> {}: {}",
+            self.from_version, self.to_version, rollback_reason
+        );
+
+        if let Err(e) = self.restore_previous(docker_manager, backup_manager).await {
+            error!("❌ 升级回滚失败: {}", e);
+            database
+                .complete_upgrade_history(
+                    &self.upgrade_id,
+                    UpgradeStatus::Failed,
+                    Some(format!("{error_message}；回滚失败: {e}")),
+                    None,
+                )
+                .await?;
+            if self.previous_dir.exists() {
+                warn!(
+                    "⚠️ 升级前的服务包文件保留在: {}",
+                    self.previous_dir.display()
+                );
+            }
+            return Err(e);
+        }
+
+        database
+            .roll_back_upgrade_history(&self.upgrade_id, Some(error_message), rollback_reason)
+            .await?;
+        info!("✅ 已回滚到 {} 版本", self.from_version);
+        Ok(())
+    }
+
+    async fn restore_previous(
+        &self,
+        docker_manager: &DockerManager,
+        backup_manager: &BackupManager,
+    ) -> Result<()> {
+        if let Err(e) = docker_manager.stop_services().await {
+            warn!("⚠️ 停止新版本服务失败，继续回滚: {}", e);
+        }
+
+        let (docker_dir, previous_dir) = (self.docker_dir.clone(), self.previous_dir.clone());
+        tokio::task::spawn_blocking(move || restore_package_files(&docker_dir, &previous_dir))
+            .await??;
+        info!("📦 已恢复 {} 版本的服务包文件", self.from_version);
+
+        for (image, id) in &self.previous_images {
+            if let Err(e) = docker_manager.tag_image(id, image).await {
+                warn!("⚠️ 恢复镜像标签 {} 失败: {}", image, e);
+            }
+        }
+        if !self.previous_images.is_empty() {
+            info!("🏷️ 已恢复 {} 个镜像标签", self.previous_images.len());
+        }
+
+        // 从备份恢复数据后会重新启动服务
+        match self.backup_id {
+            Some(backup_id) => {
+                info!("🛡️ 正在从升级前备份 {} 恢复数据...", backup_id);
+                backup_manager
+                    .restore_data_only(backup_id, &self.docker_dir, &RestoreFilter::default())
+                    .await
+            }
+            None => {
+                info!("▶️ 没有升级前备份，直接启动旧版本服务");
+                docker_manager.start_services().await
+            }
+        }
+    }
+}
+
+/// 是否为运行时数据目录（不属于服务包）
+fn is_runtime_dir(name: &OsStr) -> bool {
+    docker::RUNTIME_DIRS.iter().any(|dir| name == *dir)
+}
+
+/// 把服务包文件（运行时数据目录之外的顶层条目）保存到 `previous_dir`
+fn preserve_package_files(docker_dir: &Path, previous_dir: &Path) -> Result<()> {
+    if previous_dir.exists() {
+        fs::remove_dir_all(previous_dir)?;
+    }
+    fs::create_dir_all(previous_dir)?;
+
+    for entry in fs::read_dir(docker_dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if is_runtime_dir(&name) {
+            continue;
+        }
+        let hard_link = name == docker::IMAGES_DIR_NAME;
+        copy_entry(&entry.path(), &previous_dir.join(&name), hard_link)?;
+    }
+    Ok(())
+}
+
+/// 复制文件或目录；`hard_link` 为真时优先创建硬链接，跨文件系统等情况下退回复制
+fn copy_entry(src: &Path, dst: &Path, hard_link: bool) -> Result<()> {
+    if src.is_dir() {
+        fs::create_dir_all(dst)?;
+        for entry in fs::read_dir(src)? {
+            let entry = entry?;
+            copy_entry(&entry.path(), &dst.join(entry.file_name()), hard_link)?;
+        }
+    } else if !hard_link || fs::hard_link(src, dst).is_err() {
+        fs::copy(src, dst)?;
+    }
+    Ok(())
+}
+
+/// 用 `previous_dir` 中保存的文件替换 `docker_dir` 中的服务包文件，运行时数据目录保持不变
+fn restore_package_files(docker_dir: &Path, previous_dir: &Path) -> Result<()> {
+    if !previous_dir.exists() {
+        return Err(DuckError::Upgrade(format!(
+            "升级前服务包文件不存在: {}",
+            previous_dir.display()
+        )));
+    }
+    fs::create_dir_all(docker_dir)?;
+
+    for entry in fs::read_dir(docker_dir)? {
+        let entry = entry?;
+        if is_runtime_dir(&entry.file_name()) {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            fs::remove_dir_all(entry.path())?;
+        } else {
+            fs::remove_file(entry.path())?;
+        }
+    }
+
+    // 保存目录与docker目录在同一文件系统，直接移动
+    for entry in fs::read_dir(previous_dir)? {
+        let entry = entry?;
+        fs::rename(entry.path(), docker_dir.join(entry.file_name()))?;
+    }
+    fs::remove_dir(previous_dir)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema_migration;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_preserve_and_restore_package_files() {
+        let dir = tempdir().unwrap();
+        let docker_dir = dir.path().join("docker");
+        let previous_dir = UpgradeTransaction::previous_dir(&docker_dir).unwrap();
+        assert_eq!(previous_dir, dir.path().join(".docker.pre-upgrade"));
+
+        fs::create_dir_all(docker_dir.join("images")).unwrap();
+        fs::create_dir_all(docker_dir.join("data/mysql")).unwrap();
+        fs::write(docker_dir.join("docker-compose.yml"), "v1").unwrap();
+        fs::write(docker_dir.join("images/app.tar"), "image v1").unwrap();
+        fs::write(docker_dir.join("data/mysql/ibdata1"), "data v1").unwrap();
+        preserve_package_files(&docker_dir, &previous_dir).unwrap();
+        assert!(!previous_dir.join("data").exists());
+
+        // 模拟升级：替换服务包文件（镜像tar包被替换而不是原地修改）并写入新数据
+        fs::remove_file(docker_dir.join("images/app.tar")).unwrap();
+        fs::write(docker_dir.join("images/app.tar"), "image v2").unwrap();
+        fs::write(docker_dir.join("docker-compose.yml"), "v2").unwrap();
+        fs::write(docker_dir.join("new-service.yml"), "v2").unwrap();
+        fs::write(docker_dir.join("data/mysql/ibdata1"), "data v2").unwrap();
+
+        restore_package_files(&docker_dir, &previous_dir).unwrap();
+        let read = |path: &str| fs::read_to_string(docker_dir.join(path)).unwrap();
+        assert_eq!(read("docker-compose.yml"), "v1");
+        assert_eq!(read("images/app.tar"), "image v1");
+        assert!(!docker_dir.join("new-service.yml").exists());
+        // 数据目录由升级前备份恢复，不随服务包文件回滚
+        assert_eq!(read("data/mysql/ibdata1"), "data v2");
+        assert!(!previous_dir.exists());
+    }
+
+    #[test]
+    fn test_failed_migration_is_retried_after_rollback() {
+        let dir = tempdir().unwrap();
+        let docker_dir = dir.path().join("docker");
+        let previous_dir = UpgradeTransaction::previous_dir(&docker_dir).unwrap();
+        let init_sql = docker_dir.join("config/mysql/01_schema.sql");
+        let new_sql = "CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(64));";
+        fs::create_dir_all(init_sql.parent().unwrap()).unwrap();
+        fs::write(&init_sql, "CREATE TABLE users (id INT PRIMARY KEY);").unwrap();
+        let plan_upgrade = || {
+            let old_sql = schema_migration::load_init_sql(&docker_dir).unwrap();
+            schema_migration::plan_schema_migration(
+                old_sql.as_deref(),
+                Some(new_sql),
+                "1.0.0",
+                "1.1.0",
+            )
+            .unwrap()
+        };
+
+        // 安装新版本服务包后架构迁移失败
+        let plan = plan_upgrade().unwrap();
+        preserve_package_files(&docker_dir, &previous_dir).unwrap();
+        fs::write(&init_sql, new_sql).unwrap();
+        // 保留新服务包时，下次升级会把新的初始化SQL当作已迁移的架构
+        assert!(plan_upgrade().is_none());
+
+        // 升级事务回滚旧的服务包文件后，下次升级重新生成同样的迁移
+        restore_package_files(&docker_dir, &previous_dir).unwrap();
+        let retry = plan_upgrade().unwrap();
+        assert_eq!(retry.sql_lines(), plan.sql_lines());
+    }
+}
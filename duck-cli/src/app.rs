@@ -1,13 +1,13 @@
 use client_core::{
     api::ApiClient, authenticated_client::AuthenticatedClient, backup::BackupManager,
-    config::AppConfig, constants::config, container::DockerManager, database::Database,
-    error::Result, upgrade::UpgradeManager,
+    backup_encryption::BackupEncryption, backup_storage, config::AppConfig, constants::config,
+    container::DockerManager, database::Database, error::Result, upgrade::UpgradeManager,
 };
 use std::path::PathBuf;
 
 use crate::cli::Commands;
 use crate::commands;
-use tracing::debug;
+use tracing::{debug, warn};
 
 #[derive(Clone)]
 pub struct CliApp {
@@ -18,6 +18,8 @@ pub struct CliApp {
     pub docker_manager: DockerManager,
     pub backup_manager: BackupManager,
     pub upgrade_manager: UpgradeManager,
+    /// 跳过服务包签名校验（`--insecure-skip-signature`）
+    pub insecure_skip_signature: bool,
 }
 
 impl CliApp {
@@ -47,11 +49,18 @@ impl CliApp {
 
         // 创建其他管理器
         let docker_manager = DockerManager::new(PathBuf::from(&config.docker.compose_file))?;
-        let backup_manager = BackupManager::new(
+        let mut backup_manager = BackupManager::new(
             PathBuf::from(&config.backup.storage_dir),
             database.clone(),
             docker_manager.clone(),
-        )?;
+        )?
+        .with_encryption(BackupEncryption::from_config(&config.backup)?);
+        if let Some(remote) = &config.backup.remote {
+            match backup_storage::from_config(remote) {
+                Ok(storage) => backup_manager = backup_manager.with_remote_storage(storage),
+                Err(e) => warn!("远程备份存储不可用，备份只保存在本地: {}", e),
+            }
+        }
         let upgrade_manager = UpgradeManager::new(
             config.clone(),
             PathBuf::from("config.toml"), // 使用默认配置路径
@@ -69,6 +78,7 @@ impl CliApp {
             docker_manager,
             backup_manager,
             upgrade_manager,
+            insecure_skip_signature: false,
         })
     }
 
@@ -85,14 +95,48 @@ impl CliApp {
                         client_core::error::DuckError::custom(format!("检查更新失败: {e}"))
                     })
             }
-            Commands::Upgrade { full, force, check } => {
-                commands::run_upgrade(self, full, force, check).await
+            Commands::Upgrade {
+                full,
+                force,
+                check,
+                list,
+                to,
+                allow_data_loss,
+            } => {
+                if list {
+                    commands::run_list_versions(self).await
+                } else if let Some(version) = to {
+                    commands::run_install_version(self, &version, allow_data_loss).await
+                } else {
+                    commands::run_upgrade(self, full, force, check, None).await
+                }
             }
-            Commands::Backup => commands::run_backup(self).await,
-            Commands::ListBackups => commands::run_list_backups(self).await,
-            Commands::Rollback { backup_id, force } => {
-                commands::run_rollback(self, backup_id, force).await
+            Commands::Backup {
+                command: Some(backup_cmd),
+                ..
+            } => commands::handle_backup_command(self, backup_cmd).await,
+            Commands::Backup {
+                command: None,
+                incremental,
+                differential,
+                hot,
+                compression,
+            } => {
+                commands::run_backup(
+                    self,
+                    commands::backup_mode(incremental, differential),
+                    hot,
+                    compression.map(Into::into),
+                )
+                .await
             }
+            Commands::ListBackups => commands::run_list_backups(self).await,
+            Commands::Rollback {
+                backup_id,
+                force,
+                only,
+                services,
+            } => commands::run_rollback(self, backup_id, force, &only, &services).await,
             Commands::DockerService(docker_cmd) => {
                 commands::run_docker_service_command(self, docker_cmd).await
             }
@@ -104,6 +148,7 @@ impl CliApp {
                 commands::handle_auto_upgrade_deploy_command(self, auto_upgrade_deploy_cmd).await
             }
             Commands::Cache(cache_cmd) => commands::handle_cache_command(self, cache_cmd).await,
+            Commands::Schema(schema_cmd) => commands::handle_schema_command(self, schema_cmd).await,
         }
     }
 }
@@ -1,12 +1,23 @@
 use crate::project_info::{metadata, version_info};
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use client_core::database::CompressionType;
 use std::path::PathBuf;
 
 /// 自动备份相关命令
 #[derive(Subcommand, Debug)]
 pub enum AutoBackupCommand {
     /// 立即执行一次自动备份
-    Run,
+    Run {
+        /// 增量备份：只备份相对上一个备份变化的文件
+        #[arg(long, conflicts_with = "differential")]
+        incremental: bool,
+        /// 差异备份：只备份相对最近一次全量备份变化的文件
+        #[arg(long)]
+        differential: bool,
+        /// 热备份：不停止服务，MySQL数据通过mysqldump导出（默认取 auto_backup_hot 配置）
+        #[arg(long)]
+        hot: bool,
+    },
     /// 设置定时备份的 cron 表达式
     Cron {
         /// cron 表达式，例如 "0 2 * * *" 表示每天凌晨2点
@@ -23,6 +34,65 @@ pub enum AutoBackupCommand {
     Status,
 }
 
+/// 备份管理相关命令
+#[derive(Subcommand, Debug)]
+pub enum BackupCommand {
+    /// 按保留策略清理旧备份
+    Prune {
+        /// 只显示将要清理的备份，不实际删除
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// 校验备份完整性
+    Verify {
+        /// 备份ID
+        backup_id: i64,
+        /// 深度校验：重新计算每个文件的哈希，与备份清单比较
+        #[arg(long)]
+        deep: bool,
+    },
+    /// 列出远程备份存储中的备份文件
+    RemoteList,
+    /// 从远程备份存储下载备份到本地
+    Download {
+        /// 备份ID
+        backup_id: i64,
+    },
+    /// 列出备份中的文件（不解压）
+    Ls {
+        /// 备份ID
+        backup_id: i64,
+        /// 只列出该路径下的文件（如 data/redis）
+        prefix: Option<String>,
+    },
+    /// 输出备份中单个文件的内容（不解压）
+    Cat {
+        /// 备份ID
+        backup_id: i64,
+        /// 备份内的文件路径（如 data/redis/dump.rdb）
+        path: String,
+    },
+    /// 比较两个备份之间新增、删除和变化的文件
+    Diff {
+        /// 较早的备份ID
+        from_id: i64,
+        /// 较新的备份ID
+        to_id: i64,
+    },
+    /// 导入其他机器上导出的备份文件
+    Import {
+        /// 备份文件路径
+        file: PathBuf,
+    },
+    /// 导出备份为可在其他机器上导入的独立备份文件
+    Export {
+        /// 备份ID
+        backup_id: i64,
+        /// 导出的文件路径或目录
+        destination: PathBuf,
+    },
+}
+
 /// 自动升级部署相关命令
 #[derive(Subcommand, Debug)]
 pub enum AutoUpgradeDeployCommand {
@@ -34,6 +104,9 @@ pub enum AutoUpgradeDeployCommand {
             help = "指定frontend服务的端口号，对应docker-compose.yml中的FRONTEND_HOST_PORT变量（默认: 80端口）"
         )]
         port: Option<u16>,
+        /// 允许执行可能导致数据丢失的数据库架构迁移（删除表、删除列、收窄列类型等）
+        #[arg(long)]
+        allow_data_loss: bool,
     },
     /// 延迟执行自动升级部署
     DelayTimeDeploy {
@@ -117,6 +190,67 @@ pub enum CacheCommand {
     },
 }
 
+/// 备份压缩格式
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BackupCompression {
+    /// gzip（单线程）
+    Gzip,
+    /// zstd（多线程，速度更快）
+    Zstd,
+    /// 不压缩
+    None,
+}
+
+impl From<BackupCompression> for CompressionType {
+    fn from(compression: BackupCompression) -> Self {
+        match compression {
+            BackupCompression::Gzip => CompressionType::Gzip,
+            BackupCompression::Zstd => CompressionType::Zstd,
+            BackupCompression::None => CompressionType::None,
+        }
+    }
+}
+
+/// 数据库架构差异的输出格式
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SchemaDiffFormat {
+    /// 可执行的差异SQL
+    Sql,
+    /// 结构化差异（JSON），便于界面展示和CI检查
+    Json,
+    /// 变更摘要
+    Summary,
+}
+
+/// 数据库架构相关命令
+#[derive(Subcommand, Debug)]
+pub enum SchemaCommand {
+    /// 比较数据库架构差异：两个SQL文件，或当前部署与已下载（尚未部署）的服务包
+    Diff {
+        /// 旧版本的SQL文件
+        #[arg(long, requires = "to")]
+        from: Option<PathBuf>,
+        /// 新版本的SQL文件
+        #[arg(long, requires = "from")]
+        to: Option<PathBuf>,
+        /// 比较当前部署的服务包与已下载的服务包中的MySQL初始化SQL
+        #[arg(long, conflicts_with_all = ["from", "to"])]
+        package: bool,
+        /// 已下载服务包的版本（默认使用服务端的最新版本）
+        #[arg(long, requires = "package")]
+        version: Option<String>,
+        /// 已下载服务包的docker.zip文件路径（默认按版本查找下载目录）
+        #[arg(long, requires = "package")]
+        file: Option<PathBuf>,
+        /// 输出格式
+        #[arg(long, value_enum, default_value = "summary")]
+        format: SchemaDiffFormat,
+        /// 只比较指定的数据库（默认比较脚本中的所有数据库）
+        #[arg(long)]
+        database: Option<String>,
+    },
+}
+
 /// Duck Client CLI - Docker 服务管理和升级工具
 #[derive(Parser)]
 #[command(name = "duck-cli")]
@@ -133,6 +267,10 @@ pub struct Cli {
     #[arg(short, long)]
     pub verbose: bool,
 
+    /// 跳过服务包签名校验（仅用于开发环境，生产环境不要使用）
+    #[arg(long, global = true)]
+    pub insecure_skip_signature: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -163,9 +301,33 @@ pub enum Commands {
         /// 只检查是否有可用的升级版本，不执行下载
         #[arg(long)]
         check: bool,
+        /// 列出可安装的版本（发布日期和更新说明）
+        #[arg(long, conflicts_with_all = ["full", "force", "check", "to"])]
+        list: bool,
+        /// 下载并安装指定版本；低于当前版本时为降级，需要该版本的升级前备份
+        #[arg(long, value_name = "VERSION", conflicts_with_all = ["full", "force", "check"])]
+        to: Option<String>,
+        /// 允许执行可能导致数据丢失的数据库架构迁移（与 --to 一起使用）
+        #[arg(long, requires = "to")]
+        allow_data_loss: bool,
     },
     /// 手动创建备份
-    Backup,
+    Backup {
+        #[command(subcommand)]
+        command: Option<BackupCommand>,
+        /// 增量备份：只备份相对上一个备份变化的文件
+        #[arg(long, conflicts_with = "differential")]
+        incremental: bool,
+        /// 差异备份：只备份相对最近一次全量备份变化的文件
+        #[arg(long)]
+        differential: bool,
+        /// 热备份：不停止服务，MySQL数据通过mysqldump导出
+        #[arg(long)]
+        hot: bool,
+        /// 压缩格式（默认使用配置 backup.compression）
+        #[arg(long, value_enum)]
+        compression: Option<BackupCompression>,
+    },
     /// 列出所有备份
     ListBackups,
     /// 从备份恢复
@@ -175,6 +337,12 @@ pub enum Commands {
         /// 强制覆盖
         #[arg(long)]
         force: bool,
+        /// 只恢复指定路径（相对于docker目录，如 data/redis、upload/），可重复指定
+        #[arg(long = "only", value_name = "PATH")]
+        only: Vec<String>,
+        /// 只恢复指定服务的数据（mysql、redis、milvus、upload、config），可重复指定
+        #[arg(long = "service", value_name = "SERVICE")]
+        services: Vec<String>,
     },
     /// Docker服务相关命令
     #[command(subcommand)]
@@ -198,4 +366,21 @@ pub enum Commands {
     /// 缓存管理
     #[command(subcommand)]
     Cache(CacheCommand),
+
+    /// 数据库架构管理
+    #[command(subcommand)]
+    Schema(SchemaCommand),
+}
+
+impl Commands {
+    /// 命令是否向标准输出写入机器可读的结果（SQL、JSON）
+    pub fn writes_machine_output(&self) -> bool {
+        matches!(
+            self,
+            Commands::Schema(SchemaCommand::Diff {
+                format: SchemaDiffFormat::Sql | SchemaDiffFormat::Json,
+                ..
+            })
+        )
+    }
 }
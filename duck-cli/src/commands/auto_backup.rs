@@ -3,6 +3,7 @@ use crate::cli::AutoBackupCommand;
 use crate::commands::{backup, docker_service};
 use crate::docker_utils;
 use client_core::constants::{cron, timeout};
+use client_core::database::BackupMode;
 use client_core::error::Result;
 
 use tracing::{debug, error, info, instrument, warn};
@@ -10,9 +11,25 @@ use tracing::{debug, error, info, instrument, warn};
 /// 运行自动备份相关命令的统一入口
 pub async fn handle_auto_backup_command(app: &mut CliApp, cmd: AutoBackupCommand) -> Result<()> {
     match cmd {
-        AutoBackupCommand::Run => {
+        AutoBackupCommand::Run {
+            incremental,
+            differential,
+            hot,
+        } => {
             info!("🔄 开始自动备份流程...");
-            run_auto_backup(app).await
+            // 未指定 --hot 时使用 auto_backup_hot 配置
+            let hot_backup = hot
+                || app
+                    .database
+                    .get_config("auto_backup_hot")
+                    .await?
+                    .is_some_and(|value| value.trim() == "true");
+            run_auto_backup(
+                app,
+                backup::backup_mode(incremental, differential),
+                hot_backup,
+            )
+            .await
         }
         AutoBackupCommand::Cron { expression } => {
             info!("配置自动备份 cron 表达式");
@@ -30,8 +47,14 @@ pub async fn handle_auto_backup_command(app: &mut CliApp, cmd: AutoBackupCommand
 }
 
 /// 执行自动备份流程：停止服务 -> 备份 -> 重启服务
+///
+/// 热备份时服务保持运行：MySQL数据通过mysqldump导出，Redis先执行BGSAVE。
 #[instrument(skip(app))]
-pub async fn run_auto_backup(app: &mut CliApp) -> Result<()> {
+pub async fn run_auto_backup(
+    app: &mut CliApp,
+    backup_mode: BackupMode,
+    hot_backup: bool,
+) -> Result<()> {
     info!("开始自动备份流程");
 
     let backup_start_time = chrono::Utc::now();
@@ -40,8 +63,14 @@ pub async fn run_auto_backup(app: &mut CliApp) -> Result<()> {
     // 1. 检查Docker服务状态
     debug!("检查Docker服务状态");
     let service_running = check_docker_service_status(app).await?;
-
-    if service_running {
+    // 服务未运行时不需要热备份，直接冷备份
+    let hot_backup = hot_backup && service_running;
+    // 冷备份需要停止正在运行的服务，备份后重新启动
+    let restart_services = service_running && !hot_backup;
+
+    if hot_backup {
+        info!("使用热备份，服务保持运行");
+    } else if service_running {
         // 2. 停止Docker服务
         info!("停止Docker服务以进行备份");
         docker_service::stop_docker_services(app).await?;
@@ -63,7 +92,7 @@ pub async fn run_auto_backup(app: &mut CliApp) -> Result<()> {
 
     // 3. 执行备份
     info!("开始执行备份操作");
-    match backup::run_backup(app).await {
+    match backup::run_backup(app, backup_mode, hot_backup, None).await {
         Ok(_) => {
             backup_success = true;
             info!("备份执行成功");
@@ -86,7 +115,7 @@ pub async fn run_auto_backup(app: &mut CliApp) -> Result<()> {
         }
     }
 
-    if service_running {
+    if restart_services {
         // 4. 重新启动Docker服务
         info!("重新启动Docker服务");
         docker_service::start_docker_services(app).await?;
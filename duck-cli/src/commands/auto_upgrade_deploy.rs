@@ -1,11 +1,18 @@
 use crate::app::CliApp;
 use crate::cli::AutoUpgradeDeployCommand;
 use crate::commands::{backup, docker_service, update};
+use crate::docker_service::HealthChecker;
 use crate::docker_utils;
-use client_core::constants::{docker, timeout};
-use client_core::error::Result;
+use client_core::backup::RestoreFilter;
+use client_core::constants::{docker, timeout, upgrade};
+use client_core::database::{BackupMode, BackupType};
+use client_core::error::{DuckError, Result};
+use client_core::schema_migration::SchemaMigrationPlan;
+use client_core::upgrade_transaction::UpgradeTransaction;
+use client_core::{package_signature, schema_migration, service_patch};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use tokio::time::sleep;
 use tracing::{error, info, warn};
@@ -16,9 +23,12 @@ pub async fn handle_auto_upgrade_deploy_command(
     cmd: AutoUpgradeDeployCommand,
 ) -> Result<()> {
     match cmd {
-        AutoUpgradeDeployCommand::Run { port } => {
+        AutoUpgradeDeployCommand::Run {
+            port,
+            allow_data_loss,
+        } => {
             info!("🚀 开始自动升级部署流程...");
-            run_auto_upgrade_deploy(app, port).await
+            run_auto_upgrade_deploy(app, port, allow_data_loss, None).await
         }
         AutoUpgradeDeployCommand::DelayTimeDeploy { time, unit } => {
             info!("配置延迟自动升级部署: {} {}", time, unit);
@@ -31,8 +41,24 @@ pub async fn handle_auto_upgrade_deploy_command(
     }
 }
 
+/// 安装指定版本时的部署目标
+#[derive(Debug, Clone)]
+pub struct DeployTarget {
+    /// 要安装的版本
+    pub version: String,
+    /// 降级时用于恢复数据的升级前备份（部署后从该备份恢复数据，不迁移数据库架构）
+    pub restore_backup_id: Option<i64>,
+}
+
 /// 执行自动升级部署流程
-pub async fn run_auto_upgrade_deploy(app: &mut CliApp, frontend_port: Option<u16>) -> Result<()> {
+///
+/// `target` 为空时部署最新版本。
+pub async fn run_auto_upgrade_deploy(
+    app: &mut CliApp,
+    frontend_port: Option<u16>,
+    allow_data_loss: bool,
+    target: Option<DeployTarget>,
+) -> Result<()> {
     info!("🚀 开始自动升级部署流程...");
 
     // 如果指定了端口，显示端口信息
@@ -40,34 +66,31 @@ pub async fn run_auto_upgrade_deploy(app: &mut CliApp, frontend_port: Option<u16
         info!("🔌 自定义frontend端口: {}", port);
     }
 
-    // 1. 获取最新版本信息并下载
-    info!("开始下载最新的Docker服务版本");
-    info!("📥 正在下载最新的Docker服务版本...");
-
-    // 获取最新版本信息
-    let latest_version = match app
-        .api_client
-        .check_docker_version(&app.config.versions.docker_service)
-        .await
-    {
-        Ok(version_info) => {
-            info!(
-                "📋 版本信息: {} -> {}",
-                version_info.current_version, version_info.latest_version
-            );
-            version_info.latest_version
-        }
-        Err(e) => {
-            warn!("⚠️ 获取版本信息失败，使用配置版本: {}", e);
-            app.config.versions.docker_service.clone()
+    let restore_backup_id = target.as_ref().and_then(|target| target.restore_backup_id);
+    let target_version = target.as_ref().map(|target| target.version.clone());
+    let latest_version = if let Some(version) = &target_version {
+        version.clone()
+    } else {
+        // 获取最新版本信息
+        match app
+            .api_client
+            .check_docker_version(&app.config.versions.docker_service)
+            .await
+        {
+            Ok(version_info) => {
+                info!(
+                    "📋 版本信息: {} -> {}",
+                    version_info.current_version, version_info.latest_version
+                );
+                version_info.latest_version
+            }
+            Err(e) => {
+                warn!("⚠️ 获取版本信息失败，使用配置版本: {}", e);
+                app.config.versions.docker_service.clone()
+            }
         }
     };
 
-    update::run_upgrade(app, true, false, false).await?; // 全量下载
-
-    // 1.5. 解压下载的docker.zip文件
-    info!("📦 正在解压Docker服务包...");
-
     // 🔍 检测部署类型：第一次部署 vs 升级部署
     let is_first_deployment = is_first_deployment().await;
     if is_first_deployment {
@@ -76,156 +99,361 @@ pub async fn run_auto_upgrade_deploy(app: &mut CliApp, frontend_port: Option<u16
         info!("🔄 检测到升级部署，需要保护现有数据");
     }
 
-    // 🛡️ 数据保护：只在升级部署时备份现有的数据目录
-    let temp_data_backup = if is_first_deployment {
+    let docker_dir = std::path::Path::new("docker");
+
+    // 🗄️ 修改docker目录前读取当前服务包的MySQL初始化SQL，用于和新版本比较数据库架构
+    let from_version = app.config.versions.docker_service.clone();
+    let old_init_sql = if is_first_deployment {
         None
     } else {
-        backup_data_before_cleanup().await?
+        schema_migration::load_init_sql(docker_dir).unwrap_or_else(|e| {
+            warn!("⚠️ 读取当前MySQL初始化SQL失败，跳过架构迁移: {}", e);
+            None
+        })
     };
 
-    // 清理现有的docker目录以避免路径冲突
-    let docker_dir = std::path::Path::new("docker");
-    if docker_dir.exists() {
-        info!("🧹 清理现有docker目录以避免文件冲突...");
-        match std::fs::remove_dir_all(docker_dir) {
-            Ok(_) => info!("✅ docker目录清理完成"),
-            Err(e) => {
-                warn!("⚠️ 清理docker目录失败: {}, 尝试继续解压", e);
-                // 清理失败时，恢复备份的数据（仅在升级部署时）
-                if !is_first_deployment {
-                    restore_data_after_cleanup(&temp_data_backup).await?;
+    // 🧾 升级部署作为一个事务：修改docker目录前保存当前服务包文件和镜像，失败时可以回滚
+    let mut transaction = if is_first_deployment {
+        None
+    } else {
+        Some(
+            UpgradeTransaction::begin(
+                &app.database,
+                &app.docker_manager,
+                docker_dir,
+                &from_version,
+                &latest_version,
+            )
+            .await?,
+        )
+    };
+
+    // 安装新版本服务包前出错时docker目录未被修改，中止升级即可，无需回滚
+    let mut docker_modified = false;
+    let installed: Result<(bool, Option<SchemaMigrationPlan>)> = async {
+        // 1. 升级部署时优先使用补丁包，补丁包不可用时下载完整服务包（下载不修改docker目录）
+        let patch = if is_first_deployment {
+            None
+        } else {
+            download_patch_package(
+                app,
+                target_version.as_deref(),
+                &from_version,
+                &latest_version,
+            )
+            .await
+        };
+        let mut package = match patch {
+            Some((patch_path, full_files)) => ServicePackage::Patch {
+                patch_path,
+                full_files,
+            },
+            None => ServicePackage::Full(
+                download_full_package(app, target_version.as_deref(), &latest_version).await?,
+            ),
+        };
+
+        // 🗄️ 修改docker目录前生成并检查架构迁移计划，破坏性迁移被拒绝时已安装的服务保持不变
+        // （降级时数据从备份恢复，不迁移架构）
+        let migrate_schema = restore_backup_id.is_none();
+        if !migrate_schema {
+            info!("⬇️  降级部署将从升级前备份恢复数据，跳过数据库架构迁移");
+        }
+        let mut schema_plan = if migrate_schema {
+            check_schema_migration(
+                &package,
+                docker_dir,
+                old_init_sql.as_deref(),
+                &from_version,
+                &latest_version,
+                allow_data_loss,
+            )?
+        } else {
+            None
+        };
+
+        // 安装新版本服务包，补丁校验失败时改用完整服务包
+        if let ServicePackage::Patch {
+            patch_path,
+            full_files,
+        } = &package
+        {
+            info!(
+                "🩹 正在应用补丁包 {} -> {}...",
+                from_version, latest_version
+            );
+            match service_patch::apply_patch(patch_path, docker_dir, &from_version, full_files)
+                .await
+            {
+                Ok(_) => {
+                    docker_modified = true;
+                    info!("✅ 补丁包应用完成，已按完整服务包清单校验");
+                }
+                Err(e) => {
+                    warn!("⚠️ 补丁包应用失败，改用完整服务包: {}", e);
+                    package = ServicePackage::Full(
+                        download_full_package(app, target_version.as_deref(), &latest_version)
+                            .await?,
+                    );
+                    // 按完整服务包重新生成并检查架构迁移计划
+                    if migrate_schema {
+                        schema_plan = check_schema_migration(
+                            &package,
+                            docker_dir,
+                            old_init_sql.as_deref(),
+                            &from_version,
+                            &latest_version,
+                            allow_data_loss,
+                        )?;
+                    }
                 }
-                return Err(client_core::error::DuckError::custom(format!(
-                    "清理docker目录失败: {e}"
-                )));
             }
         }
-    }
-
-    // 解压新的Docker服务包（使用最新版本）
-    match docker_service::extract_docker_service(app, None, Some(latest_version.clone())).await {
-        Ok(_) => {
-            info!("✅ Docker服务包解压完成");
+        let patched = matches!(package, ServicePackage::Patch { .. });
+        if let ServicePackage::Full(zip_path) = &package {
+            docker_modified = true;
+            replace_docker_dir(
+                zip_path,
+                docker_dir,
+                is_first_deployment,
+                app.insecure_skip_signature,
+            )
+            .await?;
+        }
 
-            // 🛡️ 数据恢复：仅在升级部署时恢复备份的数据目录
-            if !is_first_deployment {
-                restore_data_after_cleanup(&temp_data_backup).await?;
-            } else {
-                info!("🆕 第一次部署，无需数据恢复");
+        // 2. 检查Docker服务状态
+        info!("检查Docker服务状态");
+        let service_running = check_docker_service_status(app).await?;
+
+        // 3. 检查是否需要备份
+        let need_backup = if service_running {
+            // 服务运行中，需要先停止服务再备份
+            info!("Docker服务正在运行，准备停止服务进行备份");
+            info!("⏹️  正在停止Docker服务以进行备份...");
+            docker_service::stop_docker_services(app).await?;
+
+            // 等待服务完全停止（最多等待30秒）
+            info!("⏳ 等待Docker服务完全停止...");
+            let compose_path = client_core::constants::docker::get_compose_file_path();
+            if !docker_utils::wait_for_compose_services_stopped(
+                &compose_path,
+                timeout::SERVICE_STOP_TIMEOUT,
+            )
+            .await?
+            {
+                warn!("等待服务停止超时，但继续进行备份");
+                warn!("⚠️  等待服务停止超时，但继续进行备份");
             }
 
-            // 📝 更新配置文件中的Docker服务版本
-            if latest_version != app.config.versions.docker_service {
-                info!(
-                    "📝 更新Docker服务版本: {} -> {}",
-                    app.config.versions.docker_service, latest_version
-                );
+            true
+        } else {
+            info!("Docker服务未运行，检查是否有文件需要备份");
+            info!("ℹ️  Docker服务未运行，检查是否有文件需要备份...");
+
+            // 检查docker目录是否存在且有文件需要备份
+            check_docker_files_exist().await?
+        };
+
+        // 4. 根据需要执行备份
+        if need_backup {
+            info!("开始执行备份");
+            info!("💾 正在创建备份...");
+            let backup_record = backup::run_backup_with_type(
+                app,
+                BackupType::PreUpgrade,
+                BackupMode::Full,
+                false,
+                None,
+            )
+            .await?;
 
-                // 更新内存中的版本信息
-                app.config.versions.docker_service = latest_version.clone();
+            // 升级失败回滚时从升级前备份恢复数据
+            if let (Some(transaction), Some(record)) = (transaction.as_mut(), &backup_record) {
+                transaction.set_backup(&app.database, record.id).await?;
+            }
 
-                // 持久化到配置文件
-                match app.config.save_to_file("config.toml") {
-                    Ok(_) => {
-                        info!("✅ 配置文件版本号已更新并保存");
-                    }
-                    Err(e) => {
-                        warn!("⚠️ 保存配置文件失败: {}", e);
-                        warn!("   版本号已在内存中更新，但配置文件未同步");
-                    }
-                }
-            } else {
-                info!("📝 版本号无需更新 (已是最新版本: {})", latest_version);
+            // 🗄️ 数据库架构回滚脚本随升级前备份保存，回滚时用于恢复数据库架构
+            if let (Some(record), Some(rollback_script)) = (
+                &backup_record,
+                schema_plan.as_ref().and_then(|plan| plan.rollback_script()),
+            ) && let Err(e) = app
+                .backup_manager
+                .save_schema_rollback(record.id, &rollback_script)
+                .await
+            {
+                warn!("⚠️ 保存数据库架构回滚脚本失败: {}", e);
             }
+        } else {
+            info!("跳过备份步骤，没有需要备份的文件");
+            info!("⏭️  跳过备份步骤，没有需要备份的文件");
         }
-        Err(e) => {
-            error!("❌ Docker服务包解压失败: {}", e);
-            // 解压失败时，恢复备份的数据（仅在升级部署时）
-            if !is_first_deployment {
-                restore_data_after_cleanup(&temp_data_backup).await?;
+
+        // 5. 自动部署服务
+        info!("开始部署Docker服务");
+        info!("🔄 正在部署Docker服务...");
+        docker_service::deploy_docker_services(app, frontend_port).await?;
+
+        // 6. 启动服务
+        info!("启动Docker服务");
+        info!("▶️  正在启动Docker服务...");
+        docker_service::start_docker_services(app).await?;
+
+        // 等待服务健康运行，升级部署时超时视为升级失败
+        let health_timeout = UpgradeTransaction::health_check_timeout(&app.database).await?;
+        info!(
+            "⏳ 等待Docker服务健康运行（最多 {} 秒）...",
+            health_timeout.as_secs()
+        );
+        let health = HealthChecker::new(app.docker_manager.clone())
+            .wait_for_services_ready(
+                health_timeout,
+                Duration::from_secs(timeout::HEALTH_CHECK_INTERVAL),
+            )
+            .await;
+        match health {
+            Ok(_) => {
+                info!("✅ 自动升级部署完成，服务已成功启动");
+                info!("自动升级部署流程成功完成");
+            }
+            Err(e) if transaction.is_some() => {
+                return Err(DuckError::upgrade(format!(
+                    "新版本服务未能在 {} 秒内健康运行: {e}",
+                    health_timeout.as_secs()
+                )));
+            }
+            Err(e) => {
+                warn!("⚠️  等待服务启动超时，请手动检查服务状态: {}", e);
+                info!("📊 详细状态检查:");
+                let _ = docker_service::check_docker_services_status(app).await;
             }
-            return Err(e);
         }
-    }
 
-    // 2. 检查Docker服务状态
-    info!("检查Docker服务状态");
-    let service_running = check_docker_service_status(app).await?;
+        Ok((patched, schema_plan))
+    }
+    .await;
 
-    // 3. 检查是否需要备份
-    let need_backup = if service_running {
-        // 服务运行中，需要先停止服务再备份
-        info!("Docker服务正在运行，准备停止服务进行备份");
-        info!("⏹️  正在停止Docker服务以进行备份...");
-        docker_service::stop_docker_services(app).await?;
+    let (patched, schema_plan) = match installed {
+        Ok(installed) => installed,
+        Err(e) => {
+            return Err(abort_upgrade(app, transaction, &latest_version, docker_modified, e).await);
+        }
+    };
 
-        // 等待服务完全停止（最多等待30秒）
-        info!("⏳ 等待Docker服务完全停止...");
-        let compose_path = client_core::constants::docker::get_compose_file_path();
-        if !docker_utils::wait_for_compose_services_stopped(
-            &compose_path,
-            timeout::SERVICE_STOP_TIMEOUT,
-        )
-        .await?
-        {
-            warn!("等待服务停止超时，但继续进行备份");
-            warn!("⚠️  等待服务停止超时，但继续进行备份");
+    // 7. 降级时从目标版本的升级前备份恢复数据，否则在MySQL就绪后执行数据库架构迁移
+    let finished: Result<()> = async {
+        if let Some(backup_id) = restore_backup_id {
+            info!("🛡️ 正在从升级前备份 {} 恢复数据...", backup_id);
+            app.backup_manager
+                .restore_data_only(backup_id, docker_dir, &RestoreFilter::default())
+                .await?;
+            info!("✅ 数据已恢复到 {} 版本", latest_version);
+        } else if let Some(plan) = &schema_plan {
+            info!("🗄️ 正在执行数据库架构迁移...");
+            schema_migration::apply_schema_migration(&app.docker_manager, &app.database, plan)
+                .await?;
         }
+        Ok(())
+    }
+    .await;
 
-        true
-    } else {
-        info!("Docker服务未运行，检查是否有文件需要备份");
-        info!("ℹ️  Docker服务未运行，检查是否有文件需要备份...");
+    // 数据恢复或架构迁移失败时按升级失败处理，配置中的版本保持不变，下次升级会重新执行迁移
+    if let Err(e) = finished {
+        return Err(abort_upgrade(app, transaction, &latest_version, true, e).await);
+    }
 
-        // 检查docker目录是否存在且有文件需要备份
-        check_docker_files_exist().await?
-    };
+    // 8. 升级完成后更新配置文件中的Docker服务版本（升级前备份记录的是部署前的版本）
+    if latest_version != app.config.versions.docker_service {
+        info!(
+            "📝 更新Docker服务版本: {} -> {}",
+            app.config.versions.docker_service, latest_version
+        );
+
+        // 更新内存中的版本信息
+        app.config.versions.docker_service = latest_version.clone();
 
-    // 4. 根据需要执行备份
-    if need_backup {
-        info!("开始执行备份");
-        info!("💾 正在创建备份...");
-        backup::run_backup(app).await?;
+        // 持久化到配置文件
+        match app.config.save_to_file("config.toml") {
+            Ok(_) => {
+                info!("✅ 配置文件版本号已更新并保存");
+            }
+            Err(e) => {
+                warn!("⚠️ 保存配置文件失败: {}", e);
+                warn!("   版本号已在内存中更新，但配置文件未同步");
+            }
+        }
     } else {
-        info!("跳过备份步骤，没有需要备份的文件");
-        info!("⏭️  跳过备份步骤，没有需要备份的文件");
+        info!("📝 版本号无需更新 (已是最新版本: {})", latest_version);
     }
 
-    // 5. 自动部署服务
-    info!("开始部署Docker服务");
-    info!("🔄 正在部署Docker服务...");
-    docker_service::deploy_docker_services(app, frontend_port).await?;
+    if let Some(transaction) = transaction {
+        let package = if patched {
+            "补丁包"
+        } else {
+            "完整服务包"
+        };
+        if let Err(e) = transaction
+            .commit(
+                &app.database,
+                format!("使用{package}升级 {from_version} -> {latest_version}"),
+            )
+            .await
+        {
+            warn!("⚠️ 记录升级历史失败: {}", e);
+        }
+    }
 
-    // 6. 启动服务
-    info!("启动Docker服务");
-    info!("▶️  正在启动Docker服务...");
-    docker_service::start_docker_services(app).await?;
+    Ok(())
+}
 
-    // 等待服务启动完成（最多等待90秒，因为部署后启动可能需要更长时间）
-    info!("⏳ 等待Docker服务完全启动...");
-    let compose_path = client_core::constants::docker::get_compose_file_path();
-    if docker_utils::wait_for_compose_services_started(&compose_path, timeout::DEPLOY_START_TIMEOUT)
-        .await?
-    {
-        info!("✅ 自动升级部署完成，服务已成功启动");
-        info!("自动升级部署流程成功完成");
-    } else {
-        warn!("⚠️  等待服务启动超时，请手动检查服务状态");
-        warn!("⚠️  等待服务启动超时，请手动检查服务状态");
-
-        // 最后再检查一次状态
-        match check_docker_service_status(app).await {
-            Ok(true) => info!("🔍 最终检查：服务似乎已正常启动"),
-            Ok(false) => {
-                info!("🔍 最终检查：服务可能未正常启动");
-                info!("📊 详细状态检查:");
-                let _ = docker_service::check_docker_services_status(app).await;
-            }
-            Err(e) => warn!("🔍 最终检查失败: {}", e),
+/// 升级部署失败：docker目录未被修改时直接中止升级；启用自动回滚（`upgrade.rollback_enabled`）时
+/// 回滚到升级前的版本，否则记录为失败并保留升级前的服务包文件，返回给调用方的错误
+async fn abort_upgrade(
+    app: &CliApp,
+    transaction: Option<UpgradeTransaction>,
+    latest_version: &str,
+    docker_modified: bool,
+    error: DuckError,
+) -> DuckError {
+    let Some(transaction) = transaction else {
+        return error;
+    };
+    let from_version = &app.config.versions.docker_service;
+    error!("❌ 升级到 {} 失败: {}", latest_version, error);
+
+    if !docker_modified {
+        if let Err(e) = transaction.cancel(&app.database, error.to_string()).await {
+            warn!("⚠️ 记录升级历史失败: {}", e);
         }
+        return error;
     }
 
-    Ok(())
+    let rollback_enabled = UpgradeTransaction::rollback_enabled(&app.database)
+        .await
+        .unwrap_or(true);
+    if !rollback_enabled {
+        warn!("⚠️ 已禁用升级失败自动回滚 (upgrade.rollback_enabled)，请手动恢复服务");
+        if let Err(e) = transaction.fail(&app.database, error.to_string()).await {
+            warn!("⚠️ 记录升级历史失败: {}", e);
+        }
+        return error;
+    }
+
+    let rollback_reason = format!("升级到 {latest_version} 失败，自动回滚到 {from_version}");
+    match transaction
+        .rollback(
+            &app.database,
+            &app.docker_manager,
+            &app.backup_manager,
+            error.to_string(),
+            &rollback_reason,
+        )
+        .await
+    {
+        Ok(()) => DuckError::upgrade(format!("升级失败，已自动回滚到 {from_version}: {error}")),
+        Err(rollback_error) => DuckError::upgrade(format!(
+            "升级失败且自动回滚失败: {error}；回滚错误: {rollback_error}"
+        )),
+    }
 }
 
 /// 预约延迟执行自动升级部署
@@ -299,7 +527,7 @@ pub async fn schedule_delayed_deploy(app: &mut CliApp, time: u32, unit: &str) ->
     info!("延迟时间到，开始执行自动升级部署，任务ID: {}", task.task_id);
 
     // 执行自动升级部署
-    match run_auto_upgrade_deploy(app, None).await {
+    match run_auto_upgrade_deploy(app, None, false, None).await {
         Ok(_) => {
             let config_manager =
                 client_core::config_manager::ConfigManager::new_with_database(app.database.clone());
@@ -329,7 +557,9 @@ pub async fn show_status(app: &mut CliApp) -> Result<()> {
 
     info!("📊 自动升级部署状态信息:");
     info!("   功能状态: 已实现");
-    info!("   流程说明: 下载最新版本 -> 智能备份 -> 部署服务 -> 启动服务");
+    info!(
+        "   流程说明: 下载最新版本 -> 智能备份 -> 部署服务 -> 启动服务 -> 健康检查（失败自动回滚）"
+    );
 
     // 显示待执行的升级任务
     match config_manager.get_pending_upgrade_tasks().await {
@@ -423,6 +653,181 @@ async fn check_docker_files_exist() -> Result<bool> {
     Ok(false)
 }
 
+/// 下载从已安装版本到目标版本的补丁包，返回补丁包路径和目标版本完整服务包的文件清单，
+/// 补丁包不可用时返回 `None`
+async fn download_patch_package(
+    app: &CliApp,
+    target_version: Option<&str>,
+    installed_version: &str,
+    latest_version: &str,
+) -> Option<(PathBuf, BTreeMap<String, String>)> {
+    let patch_path = app.config.get_version_download_file_path(
+        latest_version,
+        "patch",
+        upgrade::DOCKER_PATCH_PACKAGE,
+    );
+
+    let full_files = match app
+        .api_client
+        .download_service_patch(&patch_path, target_version, installed_version)
+        .await
+    {
+        Ok(Some(full_files)) => full_files,
+        Ok(None) => return None,
+        Err(e) => {
+            warn!("⚠️ 下载补丁包失败，改用完整服务包: {}", e);
+            return None;
+        }
+    };
+
+    if app.insecure_skip_signature {
+        warn!("⚠️ 已跳过补丁包签名校验（--insecure-skip-signature），仅用于开发环境");
+    } else if let Err(e) = package_signature::verify_package(&patch_path).await {
+        warn!("⚠️ 补丁包签名校验失败，改用完整服务包: {}", e);
+        return None;
+    }
+
+    match service_patch::read_patch_manifest(&patch_path) {
+        Ok(manifest) if manifest.target_version == latest_version => Some((patch_path, full_files)),
+        Ok(manifest) => {
+            warn!(
+                "⚠️ 补丁包目标版本 {} 与部署版本 {} 不一致，改用完整服务包",
+                manifest.target_version, latest_version
+            );
+            None
+        }
+        Err(e) => {
+            warn!("⚠️ 读取补丁包失败，改用完整服务包: {}", e);
+            None
+        }
+    }
+}
+
+/// 新版本服务包
+enum ServicePackage {
+    /// 从已安装版本升级的补丁包，按目标版本完整服务包的文件清单校验
+    Patch {
+        patch_path: PathBuf,
+        full_files: BTreeMap<String, String>,
+    },
+    /// 完整服务包
+    Full(PathBuf),
+}
+
+/// 下载完整服务包，返回服务包路径（不修改docker目录）
+async fn download_full_package(
+    app: &mut CliApp,
+    target_version: Option<&str>,
+    latest_version: &str,
+) -> Result<PathBuf> {
+    info!("📥 正在下载Docker服务版本 {}...", latest_version);
+    update::run_upgrade(app, true, false, false, target_version).await?; // 全量下载
+
+    Ok(app.config.get_version_download_file_path(
+        latest_version,
+        "full",
+        upgrade::DOCKER_SERVICE_PACKAGE,
+    ))
+}
+
+/// 比较当前服务包和新版本服务包的MySQL初始化SQL，生成并检查架构迁移计划
+///
+/// 新版本的初始化SQL直接从补丁包或完整服务包读取，在修改docker目录前调用：
+/// 破坏性迁移未被允许时返回错误，已安装的服务文件保持不变。
+fn check_schema_migration(
+    package: &ServicePackage,
+    docker_dir: &Path,
+    old_init_sql: Option<&str>,
+    from_version: &str,
+    latest_version: &str,
+    allow_data_loss: bool,
+) -> Result<Option<SchemaMigrationPlan>> {
+    let new_init_sql = match package {
+        ServicePackage::Patch {
+            patch_path,
+            full_files,
+        } => schema_migration::load_init_sql_from_patch(patch_path, docker_dir, full_files)?,
+        ServicePackage::Full(zip_path) => schema_migration::load_init_sql_from_package(zip_path)?,
+    };
+    let plan = schema_migration::plan_schema_migration(
+        old_init_sql,
+        new_init_sql.as_deref(),
+        from_version,
+        latest_version,
+    )?;
+    if let Some(plan) = &plan {
+        schema_migration::print_migration_plan(plan);
+        schema_migration::ensure_migration_allowed(plan, allow_data_loss)?;
+    }
+    Ok(plan)
+}
+
+/// 用完整服务包替换docker目录
+///
+/// 先校验服务包签名再清理docker目录，服务包不存在或签名校验失败时已安装的服务文件保持不变。
+async fn replace_docker_dir(
+    zip_path: &Path,
+    docker_dir: &Path,
+    is_first_deployment: bool,
+    skip_signature: bool,
+) -> Result<()> {
+    if !zip_path.exists() {
+        return Err(DuckError::custom(format!(
+            "Docker服务包文件不存在: {}",
+            zip_path.display()
+        )));
+    }
+    docker_service::verify_service_package(zip_path, skip_signature).await?;
+
+    info!("📦 正在解压Docker服务包...");
+
+    // 🛡️ 数据保护：只在升级部署时备份现有的数据目录
+    let temp_data_backup = if is_first_deployment {
+        None
+    } else {
+        backup_data_before_cleanup(docker_dir).await?
+    };
+
+    // 清理现有的docker目录以避免路径冲突
+    if docker_dir.exists() {
+        info!("🧹 清理现有docker目录以避免文件冲突...");
+        match std::fs::remove_dir_all(docker_dir) {
+            Ok(_) => info!("✅ docker目录清理完成"),
+            Err(e) => {
+                warn!("⚠️ 清理docker目录失败: {}, 尝试继续解压", e);
+                // 清理失败时，恢复备份的数据（仅在升级部署时）
+                if !is_first_deployment {
+                    restore_data_after_cleanup(docker_dir, &temp_data_backup).await?;
+                }
+                return Err(DuckError::custom(format!("清理docker目录失败: {e}")));
+            }
+        }
+    }
+
+    // 解压新的Docker服务包
+    match crate::utils::extract_docker_service(zip_path).await {
+        Ok(_) => {
+            info!("✅ Docker服务包解压完成");
+
+            // 🛡️ 数据恢复：仅在升级部署时恢复备份的数据目录
+            if !is_first_deployment {
+                restore_data_after_cleanup(docker_dir, &temp_data_backup).await?;
+            } else {
+                info!("🆕 第一次部署，无需数据恢复");
+            }
+            Ok(())
+        }
+        Err(e) => {
+            error!("❌ Docker服务包解压失败: {}", e);
+            // 解压失败时，恢复备份的数据（仅在升级部署时）
+            if !is_first_deployment {
+                restore_data_after_cleanup(docker_dir, &temp_data_backup).await?;
+            }
+            Err(e)
+        }
+    }
+}
+
 /// 格式化时间间隔为可读字符串
 fn format_duration(duration: Duration) -> String {
     let seconds = duration.as_secs();
@@ -490,8 +895,8 @@ async fn is_first_deployment() -> bool {
 }
 
 /// 在清理docker目录前备份数据目录
-async fn backup_data_before_cleanup() -> Result<Option<std::path::PathBuf>> {
-    let docker_data_dir = Path::new("docker/data");
+async fn backup_data_before_cleanup(docker_dir: &Path) -> Result<Option<std::path::PathBuf>> {
+    let docker_data_dir = &docker_dir.join(docker::DATA_DIR_NAME);
 
     if !docker_data_dir.exists() {
         info!("📁 无现有数据目录需要备份");
@@ -523,10 +928,13 @@ async fn backup_data_before_cleanup() -> Result<Option<std::path::PathBuf>> {
 }
 
 /// 解压完成后恢复备份的数据目录
-async fn restore_data_after_cleanup(temp_backup_path: &Option<std::path::PathBuf>) -> Result<()> {
+async fn restore_data_after_cleanup(
+    docker_dir: &Path,
+    temp_backup_path: &Option<std::path::PathBuf>,
+) -> Result<()> {
     if let Some(backup_path) = temp_backup_path {
         if backup_path.exists() {
-            let docker_data_dir = Path::new("docker/data");
+            let docker_data_dir = &docker_dir.join(docker::DATA_DIR_NAME);
 
             info!("🔄 正在恢复数据目录从: {}", backup_path.display());
 
@@ -601,3 +1009,49 @@ fn copy_dir_recursively(src: &Path, dst: &Path) -> std::io::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    // minisign 项目的测试签名（不是由内置公钥生成）
+    const UNTRUSTED_SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1556193335\tfile:test
+y/rUw2y8/hOUYjZU71eHp/Wo1KZ40fGy2VJEDl34XMJM+TX48Ss/17u3IvIfbVR1FkZZSNCisQbuQY+bHwhEBg==";
+
+    #[tokio::test]
+    async fn test_bad_signature_leaves_docker_dir_untouched() {
+        let dir = tempdir().unwrap();
+        let docker_dir = dir.path().join(docker::DOCKER_DIR_NAME);
+        fs::create_dir_all(docker_dir.join("data/mysql")).unwrap();
+        fs::write(docker_dir.join(docker::COMPOSE_FILE_NAME), "v1").unwrap();
+        fs::write(docker_dir.join("data/mysql/ibdata1"), "data").unwrap();
+
+        let zip_path = dir.path().join(upgrade::DOCKER_SERVICE_PACKAGE);
+        fs::write(&zip_path, "tampered").unwrap();
+
+        // 缺少签名文件
+        let result = replace_docker_dir(&zip_path, &docker_dir, false, false).await;
+        assert!(matches!(result, Err(DuckError::Signature(_))));
+
+        // 签名不是由内置公钥生成
+        fs::write(
+            package_signature::signature_path(&zip_path),
+            UNTRUSTED_SIGNATURE,
+        )
+        .unwrap();
+        let result = replace_docker_dir(&zip_path, &docker_dir, false, false).await;
+        assert!(matches!(result, Err(DuckError::Signature(_))));
+
+        assert_eq!(
+            fs::read_to_string(docker_dir.join(docker::COMPOSE_FILE_NAME)).unwrap(),
+            "v1"
+        );
+        assert_eq!(
+            fs::read_to_string(docker_dir.join("data/mysql/ibdata1")).unwrap(),
+            "data"
+        );
+    }
+}
@@ -1,10 +1,53 @@
 use crate::app::CliApp;
+use crate::cli::BackupCommand;
 use crate::docker_service::{DockerService, ServiceStatus};
-use client_core::{backup::BackupOptions, database::BackupType, error::Result};
+use client_core::{
+    backup::{BackupManager, BackupOptions, RestoreFilter},
+    backup_retention::RetentionPolicy,
+    constants::backup::{LOCAL_STORAGE_TARGET, SNAPSHOT_SUFFIX},
+    database::{BackupMode, BackupRecord, BackupType, CompressionType},
+    error::{DuckError, Result},
+    schema_migration,
+};
 use tracing::{error, info, warn};
 
-/// 创建备份
-pub async fn run_backup(app: &CliApp) -> Result<()> {
+/// 根据命令行参数确定备份模式
+pub fn backup_mode(incremental: bool, differential: bool) -> BackupMode {
+    if incremental {
+        BackupMode::Incremental
+    } else if differential {
+        BackupMode::Differential
+    } else {
+        BackupMode::Full
+    }
+}
+
+/// 创建备份（`hot_backup` 为true时不需要停止服务）
+pub async fn run_backup(
+    app: &CliApp,
+    backup_mode: BackupMode,
+    hot_backup: bool,
+    compression: Option<CompressionType>,
+) -> Result<()> {
+    run_backup_with_type(
+        app,
+        BackupType::Manual,
+        backup_mode,
+        hot_backup,
+        compression,
+    )
+    .await?;
+    Ok(())
+}
+
+/// 创建指定类型的备份，返回创建的备份记录（未创建备份时返回None）
+pub async fn run_backup_with_type(
+    app: &CliApp,
+    backup_type: BackupType,
+    backup_mode: BackupMode,
+    hot_backup: bool,
+    compression: Option<CompressionType>,
+) -> Result<Option<BackupRecord>> {
     info!("💾 创建数据备份");
     info!("===============");
 
@@ -13,65 +56,74 @@ pub async fn run_backup(app: &CliApp) -> Result<()> {
     if !compose_path.exists() {
         error!("❌ Docker Compose文件不存在: {}", compose_path.display());
         info!("💡 请先确保Docker服务已正确部署");
-        return Ok(());
+        return Ok(None);
     }
 
-    // 2. 检查Docker服务是否已停止
-    info!("检查Docker服务状态...");
+    // 2. 检查Docker服务状态：冷备份要求服务全部停止，热备份要求服务正在运行
     info!("🔍 检查Docker服务状态...");
 
     let docker_service = DockerService::new(app.config.clone(), app.docker_manager.clone())?;
-    match docker_service.get_service_status().await {
-        Ok(status) => {
-            if status.overall_status != ServiceStatus::AllStopped {
-                warn!("⚠️  Docker服务仍在运行中！");
-                error!("❌ 冷备份要求所有Docker服务必须处于停止状态");
-                info!("📝 运行中的容器:");
-
-                for container in status.containers.iter() {
-                    if container.status.is_healthy() {
-                        info!(
-                            "   - {} (状态: {})",
-                            container.name,
-                            container.status.display_name()
-                        );
+    if hot_backup {
+        let status = docker_service.get_service_status().await?;
+        if status.overall_status == ServiceStatus::AllStopped {
+            error!("❌ 热备份要求Docker服务正在运行");
+            info!("💡 服务已停止时直接创建冷备份: duck-cli backup");
+            return Ok(None);
+        }
+        info!("🔥 热备份模式：服务保持运行，MySQL数据通过mysqldump导出");
+    } else {
+        match docker_service.get_service_status().await {
+            Ok(status) => {
+                if status.overall_status != ServiceStatus::AllStopped {
+                    warn!("⚠️  Docker服务仍在运行中！");
+                    error!("❌ 冷备份要求所有Docker服务必须处于停止状态");
+                    info!("📝 运行中的容器:");
+
+                    for container in status.containers.iter() {
+                        if container.status.is_healthy() {
+                            info!(
+                                "   - {} (状态: {})",
+                                container.name,
+                                container.status.display_name()
+                            );
+                        }
                     }
-                }
 
-                info!("💡 请先停止所有Docker服务:");
-                info!("   duck-cli docker-service stop");
-                info!("   或者");
-                info!(
-                    "   cd {} && docker-compose down",
-                    compose_path
-                        .parent()
-                        .unwrap_or(std::path::Path::new("."))
-                        .display()
-                );
+                    info!("💡 请先停止所有Docker服务:");
+                    info!("   duck-cli docker-service stop");
+                    info!("   或者");
+                    info!(
+                        "   cd {} && docker-compose down",
+                        compose_path
+                            .parent()
+                            .unwrap_or(std::path::Path::new("."))
+                            .display()
+                    );
+
+                    return Ok(None);
+                }
 
-                return Ok(());
+                info!("✅ 所有Docker服务已停止，可以进行备份");
             }
-
-            info!("✅ 所有Docker服务已停止，可以进行备份");
-        }
-        Err(e) => {
-            warn!("⚠️  无法确定Docker服务状态: {}", e);
-            warn!("❓ 是否继续备份？这可能导致数据不一致");
-            info!("💡 建议手动确认所有容器已停止:");
-            info!("   docker ps");
-
-            // 简单的用户确认
-            info!("输入 'yes' 继续备份，其他任意键取消: ");
-            use std::io::{self, Write};
-            print!("输入 'yes' 继续备份，其他任意键取消: ");
-            io::stdout().flush().unwrap();
-
-            let mut input = String::new();
-            io::stdin().read_line(&mut input)?;
-
-            if input.trim().to_lowercase() != "yes" {
-                warn!("❌ 用户取消备份操作");
-                return Ok(());
+            Err(e) => {
+                warn!("⚠️  无法确定Docker服务状态: {}", e);
+                warn!("❓ 是否继续备份？这可能导致数据不一致");
+                info!("💡 建议手动确认所有容器已停止:");
+                info!("   docker ps");
+
+                // 简单的用户确认
+                info!("输入 'yes' 继续备份，其他任意键取消: ");
+                use std::io::{self, Write};
+                print!("输入 'yes' 继续备份，其他任意键取消: ");
+                io::stdout().flush().unwrap();
+
+                let mut input = String::new();
+                io::stdin().read_line(&mut input)?;
+
+                if input.trim().to_lowercase() != "yes" {
+                    warn!("❌ 用户取消备份操作");
+                    return Ok(None);
+                }
             }
         }
     }
@@ -84,7 +136,7 @@ pub async fn run_backup(app: &CliApp) -> Result<()> {
     if !docker_dir.exists() {
         error!("❌ Docker目录不存在: {}", docker_dir.display());
         info!("💡 请先确保Docker服务已正确部署");
-        return Ok(());
+        return Ok(None);
     }
 
     // 检查数据目录
@@ -144,7 +196,7 @@ pub async fn run_backup(app: &CliApp) -> Result<()> {
 
         if input.trim().to_lowercase() != "y" {
             warn!("❌ 用户取消备份操作");
-            return Ok(());
+            return Ok(None);
         }
     }
 
@@ -152,6 +204,18 @@ pub async fn run_backup(app: &CliApp) -> Result<()> {
     info!("开始创建备份...");
     info!("📦 开始创建备份...");
     info!("   备份策略: 精确备份关键数据目录");
+    match backup_mode {
+        BackupMode::Full => info!("   备份模式: 全量备份"),
+        BackupMode::Incremental => info!("   备份模式: 增量备份（只备份相对上一个备份变化的文件）"),
+        BackupMode::Differential => {
+            info!("   备份模式: 差异备份（只备份相对最近一次全量备份变化的文件）")
+        }
+    }
+    let compression_type = match compression {
+        Some(compression_type) => compression_type,
+        None => app.backup_manager.configured_compression().await?,
+    };
+    info!("   压缩格式: {}", compression_type.as_str());
     info!("   备份内容:");
     if has_data {
         info!("     ✅ 数据目录: {} (容器持久化数据)", data_dir.display());
@@ -162,6 +226,9 @@ pub async fn run_backup(app: &CliApp) -> Result<()> {
             app_dir.display()
         );
     }
+    if hot_backup {
+        info!("     ✅ MySQL数据: mysqldump导出（不打包 data/mysql 目录）");
+    }
     info!("   备份目录: {}", app.config.get_backup_dir().display());
 
     // 准备备份选项 - 只备份关键的数据目录
@@ -178,14 +245,17 @@ pub async fn run_backup(app: &CliApp) -> Result<()> {
         info!("💡 请确保以下目录至少有一个存在且包含数据:");
         info!("   - {}", data_dir.display());
         info!("   - {}", app_dir.display());
-        return Ok(());
+        return Ok(None);
     }
 
     let backup_options = BackupOptions {
-        backup_type: BackupType::Manual,
+        backup_type,
+        backup_mode,
         service_version: app.config.versions.docker_service.clone(),
         source_dirs,
+        compression_type,
         compression_level: 6, // 中等压缩级别
+        hot_backup,
     };
 
     match app.backup_manager.create_backup(backup_options).await {
@@ -195,12 +265,35 @@ pub async fn run_backup(app: &CliApp) -> Result<()> {
             info!("   备份文件: {}", backup_record.file_path);
             info!("   备份时间: {}", backup_record.created_at);
             info!("   服务版本: {}", backup_record.service_version);
+            if let Some(parent_id) = backup_record.parent_id {
+                info!(
+                    "   备份模式: {} (基于备份 {})",
+                    backup_mode_display(backup_record.backup_mode),
+                    parent_id
+                );
+            } else if backup_mode != BackupMode::Full {
+                warn!("   ⚠️ 没有可用的基准备份，已创建全量备份");
+            }
+            if let Some(fingerprint) = &backup_record.encryption_fingerprint {
+                info!("   🔒 备份已加密 (密钥指纹: {})", fingerprint);
+            }
+            if backup_record.storage_target != LOCAL_STORAGE_TARGET {
+                info!("   ☁️ 已上传到远程存储: {}", backup_record.storage_target);
+            } else if let Some(target) = app.backup_manager.remote_storage_target() {
+                warn!("   ⚠️ 上传到远程存储 {} 失败，备份只保存在本地", target);
+            }
 
             // 显示备份文件大小
             if let Ok(metadata) = std::fs::metadata(&backup_record.file_path) {
                 let size_mb = metadata.len() as f64 / (1024.0 * 1024.0);
                 info!("   文件大小: {:.2} MB", size_mb);
             }
+            if let Some(file_count) = backup_record.file_count {
+                info!("   文件数量: {}", file_count);
+            }
+            if let Some(backup_hash) = &backup_record.backup_hash {
+                info!("   SHA-256: {}", backup_hash);
+            }
 
             info!("📋 备份内容说明:");
             info!("   此备份包含关键的数据目录:");
@@ -213,6 +306,8 @@ pub async fn run_backup(app: &CliApp) -> Result<()> {
             info!("   💡 备份文件只包含数据，不包含配置文件");
             info!("💡 备份完成，现在可以安全地启动Docker服务:");
             info!("   duck-cli docker-service start");
+
+            Ok(Some(backup_record))
         }
         Err(e) => {
             error!("❌ 备份创建失败: {}", e);
@@ -220,11 +315,9 @@ pub async fn run_backup(app: &CliApp) -> Result<()> {
             info!("   - 备份目录是否有写入权限");
             info!("   - 磁盘空间是否充足");
             info!("   - 数据目录是否可读");
-            return Err(e);
+            Err(e)
         }
     }
-
-    Ok(())
 }
 
 /// 列出备份
@@ -249,8 +342,8 @@ pub async fn run_list_backups(app: &CliApp) -> Result<()> {
 
     // 详细信息表头
     info!(
-        "{:<4} {:<12} {:<20} {:<10} {:<8} {:<12} {}",
-        "ID", "类型", "创建时间", "版本", "状态", "大小", "文件路径"
+        "{:<4} {:<12} {:<12} {:<20} {:<10} {:<8} {:<12} {}",
+        "ID", "类型", "模式", "创建时间", "版本", "状态", "大小", "文件路径"
     );
     info!("{}", "-".repeat(100));
 
@@ -280,6 +373,10 @@ pub async fn run_list_backups(app: &CliApp) -> Result<()> {
             };
 
             ("✅ 可用", size)
+        } else if backup.storage_target != LOCAL_STORAGE_TARGET {
+            // 本地文件缺失但有远程副本，恢复时自动下载
+            valid_backups += 1;
+            ("☁️ 仅远程", "---".to_string())
         } else {
             invalid_backups += 1;
             ("❌ 文件缺失", "---".to_string())
@@ -291,16 +388,29 @@ pub async fn run_list_backups(app: &CliApp) -> Result<()> {
             client_core::database::BackupType::PreUpgrade => "升级前",
         };
 
-        // 获取文件名而不是完整路径用于显示
-        let filename = backup_path
+        // 获取文件名而不是完整路径用于显示，加密的备份标出密钥指纹
+        let mut filename = backup_path
             .file_name()
             .map(|n| n.to_string_lossy().to_string())
             .unwrap_or_else(|| backup.file_path.clone());
+        if let Some(fingerprint) = &backup.encryption_fingerprint {
+            filename.push_str(&format!(" 🔒{fingerprint}"));
+        }
+        if backup.storage_target != LOCAL_STORAGE_TARGET {
+            filename.push_str(" ☁️");
+        }
+
+        // 备份模式显示，增量/差异备份标出所基于的备份
+        let backup_mode_display = match backup.parent_id {
+            Some(parent_id) => format!("{}←{}", backup_mode_display(backup.backup_mode), parent_id),
+            None => backup_mode_display(backup.backup_mode).to_string(),
+        };
 
         info!(
-            "{:<4} {:<12} {:<20} {:<10} {:<8} {:<12} {}",
+            "{:<4} {:<12} {:<12} {:<20} {:<10} {:<8} {:<12} {}",
             backup.id,
             backup_type_display,
+            backup_mode_display,
             backup.created_at.format("%Y-%m-%d %H:%M:%S"),
             backup.service_version,
             status_display,
@@ -308,8 +418,8 @@ pub async fn run_list_backups(app: &CliApp) -> Result<()> {
             filename
         );
 
-        // 如果文件不存在，显示警告信息
-        if !file_exists {
+        // 如果文件不存在且没有远程副本，显示警告信息
+        if !file_exists && backup.storage_target == LOCAL_STORAGE_TARGET {
             warn!("     ⚠️  警告: 备份文件不存在，无法用于回滚！");
             warn!("         预期路径: {}", backup.file_path);
         }
@@ -317,6 +427,15 @@ pub async fn run_list_backups(app: &CliApp) -> Result<()> {
 
     info!("{}", "-".repeat(100));
 
+    // 备份链：从每个全量备份开始，列出依赖它的增量/差异备份
+    let has_chain = backups.iter().any(|backup| backup.parent_id.is_some());
+    if has_chain {
+        info!("🔗 备份链:");
+        for backup in backups.iter().rev().filter(|b| b.parent_id.is_none()) {
+            print_backup_chain(&backups, backup, 1);
+        }
+    }
+
     // 统计摘要
     info!("📊 备份统计:");
     info!("   总备份数: {}", total_backups);
@@ -339,8 +458,9 @@ pub async fn run_list_backups(app: &CliApp) -> Result<()> {
     // 操作提示
     if valid_backups > 0 {
         info!("💡 可用操作:");
-        info!("   - 从备份恢复: duck-cli rollback <备份ID>");
-        info!("   - 创建新备份: duck-cli backup");
+        info!("   - 从备份恢复: duck-cli rollback <备份ID>（增量/差异备份会自动恢复整个备份链）");
+        info!("   - 创建新备份: duck-cli backup [--incremental | --differential]");
+        info!("   - 清理旧备份: duck-cli backup prune [--dry-run]");
     }
 
     if invalid_backups > 0 {
@@ -357,10 +477,381 @@ pub async fn run_list_backups(app: &CliApp) -> Result<()> {
     Ok(())
 }
 
+/// 运行备份管理相关命令的统一入口
+pub async fn handle_backup_command(app: &CliApp, cmd: BackupCommand) -> Result<()> {
+    match cmd {
+        BackupCommand::Prune { dry_run } => run_prune_backups(app, dry_run).await,
+        BackupCommand::Verify { backup_id, deep } => run_verify_backup(app, backup_id, deep).await,
+        BackupCommand::RemoteList => run_list_remote_backups(app).await,
+        BackupCommand::Download { backup_id } => run_download_backup(app, backup_id).await,
+        BackupCommand::Ls { backup_id, prefix } => {
+            run_list_backup_files(app, backup_id, prefix.as_deref()).await
+        }
+        BackupCommand::Cat { backup_id, path } => run_cat_backup_file(app, backup_id, &path).await,
+        BackupCommand::Diff { from_id, to_id } => run_diff_backups(app, from_id, to_id).await,
+        BackupCommand::Import { file } => run_import_backup(app, &file).await,
+        BackupCommand::Export {
+            backup_id,
+            destination,
+        } => run_export_backup(app, backup_id, &destination).await,
+    }
+}
+
+/// 导入备份文件
+pub async fn run_import_backup(app: &CliApp, file: &std::path::Path) -> Result<()> {
+    info!("📥 导入备份: {}", file.display());
+    let backup = app.backup_manager.import_backup(file).await?;
+
+    info!("✅ 备份导入成功");
+    info!("   备份ID: {}", backup.id);
+    info!("   备份文件: {}", backup.file_path);
+    info!("   服务版本: {}", backup.service_version);
+    info!(
+        "   创建时间: {}",
+        backup.created_at.format("%Y-%m-%d %H:%M:%S")
+    );
+    if let Some(file_count) = backup.file_count {
+        info!("   文件数量: {}", file_count);
+    }
+    info!("💡 使用 duck-cli rollback {} 从该备份恢复", backup.id);
+    Ok(())
+}
+
+/// 导出备份文件
+pub async fn run_export_backup(
+    app: &CliApp,
+    backup_id: i64,
+    destination: &std::path::Path,
+) -> Result<()> {
+    info!("📤 导出备份 {}", backup_id);
+    let export_path = app
+        .backup_manager
+        .export_backup(backup_id, destination)
+        .await?;
+
+    info!("✅ 备份已导出: {}", export_path.display());
+    info!("💡 在其他机器上使用 duck-cli backup import <文件> 导入");
+    Ok(())
+}
+
+/// 格式化文件大小
+fn format_file_size(size: u64) -> String {
+    if size > 1024 * 1024 * 1024 {
+        format!("{:.1}GB", size as f64 / (1024.0 * 1024.0 * 1024.0))
+    } else if size > 1024 * 1024 {
+        format!("{:.1}MB", size as f64 / (1024.0 * 1024.0))
+    } else if size > 1024 {
+        format!("{:.1}KB", size as f64 / 1024.0)
+    } else {
+        format!("{size}B")
+    }
+}
+
+/// 列出备份中的文件
+pub async fn run_list_backup_files(
+    app: &CliApp,
+    backup_id: i64,
+    prefix: Option<&str>,
+) -> Result<()> {
+    let entries = app
+        .backup_manager
+        .list_backup_entries(backup_id, prefix)
+        .await?;
+
+    info!("📂 备份 {} 中的文件", backup_id);
+    info!("====================");
+    if entries.is_empty() {
+        info!("📦 没有找到文件");
+        return Ok(());
+    }
+
+    let mut total_size = 0;
+    for entry in &entries {
+        let modified = entry
+            .modified
+            .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| "-".to_string());
+        // 增量/差异备份中未变化的文件标出所在的备份
+        let source = if entry.backup_id == backup_id {
+            String::new()
+        } else {
+            format!("  (备份 {})", entry.backup_id)
+        };
+        info!(
+            "{:>10}  {}  {}{}",
+            format_file_size(entry.size),
+            modified,
+            entry.path,
+            source
+        );
+        total_size += entry.size;
+    }
+    info!("====================");
+    info!(
+        "📋 共 {} 个文件, {}",
+        entries.len(),
+        format_file_size(total_size)
+    );
+    Ok(())
+}
+
+/// 把备份中单个文件的内容输出到标准输出
+pub async fn run_cat_backup_file(app: &CliApp, backup_id: i64, path: &str) -> Result<()> {
+    app.backup_manager
+        .read_backup_file(backup_id, path, std::io::stdout())
+        .await?;
+    Ok(())
+}
+
+/// 比较两个备份的文件差异
+pub async fn run_diff_backups(app: &CliApp, from_id: i64, to_id: i64) -> Result<()> {
+    let diff = app.backup_manager.diff_backups(from_id, to_id).await?;
+
+    info!("🔀 备份 {} -> {} 的文件差异", from_id, to_id);
+    info!("====================");
+    if diff.is_empty() {
+        info!("✅ 两个备份的文件完全相同");
+        return Ok(());
+    }
+    for path in &diff.added {
+        info!("   + {}", path);
+    }
+    for path in &diff.removed {
+        info!("   - {}", path);
+    }
+    for path in &diff.changed {
+        info!("   ~ {}", path);
+    }
+    info!("====================");
+    info!(
+        "📋 新增 {} 个, 删除 {} 个, 变化 {} 个文件",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.changed.len()
+    );
+    Ok(())
+}
+
+/// 列出远程备份存储中的备份文件
+pub async fn run_list_remote_backups(app: &CliApp) -> Result<()> {
+    let objects = app.backup_manager.list_remote_backups().await?;
+    let target = app
+        .backup_manager
+        .remote_storage_target()
+        .unwrap_or_default();
+
+    info!("☁️ 远程备份: {}", target);
+    info!("====================");
+    let backups: Vec<_> = objects
+        .iter()
+        .filter(|object| !object.name.ends_with(SNAPSHOT_SUFFIX))
+        .collect();
+    if backups.is_empty() {
+        info!("📦 远程存储中没有备份文件");
+        return Ok(());
+    }
+
+    // 标出本地有记录的备份
+    let local_backups = app.backup_manager.list_backups().await?;
+    for object in &backups {
+        let backup_id = local_backups
+            .iter()
+            .find(|backup| {
+                std::path::Path::new(&backup.file_path)
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy() == object.name)
+            })
+            .map(|backup| backup.id.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let last_modified = object
+            .last_modified
+            .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| "未知".to_string());
+        info!(
+            "{:<4} {:<20} {:>10.2} MB  {}",
+            backup_id,
+            last_modified,
+            object.size as f64 / (1024.0 * 1024.0),
+            object.name
+        );
+    }
+    info!("📊 共 {} 个远程备份", backups.len());
+
+    Ok(())
+}
+
+/// 从远程备份存储下载备份到本地
+pub async fn run_download_backup(app: &CliApp, backup_id: i64) -> Result<()> {
+    info!("☁️ 下载备份 {}", backup_id);
+    let backup = app.backup_manager.download_backup(backup_id).await?;
+    info!("✅ 备份已保存到本地: {}", backup.file_path);
+    Ok(())
+}
+
+/// 校验备份完整性
+pub async fn run_verify_backup(app: &CliApp, backup_id: i64, deep: bool) -> Result<()> {
+    info!("🔍 校验备份 {}", backup_id);
+    info!("====================");
+
+    if !deep {
+        if app.backup_manager.verify_backup(backup_id).await? {
+            info!("✅ 备份归档可以正常读取");
+            info!("💡 使用 --deep 重新计算每个文件的哈希");
+            return Ok(());
+        }
+        error!("❌ 备份文件不存在或无法读取");
+        return Err(DuckError::Backup(format!("备份 {backup_id} 校验失败")));
+    }
+
+    let verification = app.backup_manager.verify_backup_deep(backup_id).await?;
+
+    match verification.backup_hash_matches {
+        Some(true) => info!("✅ 备份文件哈希与记录一致"),
+        Some(false) => error!("❌ 备份文件哈希与记录不一致"),
+        None => warn!("⚠️ 备份记录中没有文件哈希，跳过备份文件哈希校验"),
+    }
+    if let Some(archive_error) = &verification.archive_error {
+        error!("❌ 读取归档失败: {}", archive_error);
+    }
+    if !verification.manifest_found {
+        warn!("⚠️ 备份中没有文件清单（早期版本创建的备份），无法校验文件哈希");
+    }
+    info!("📋 已校验 {} 个文件", verification.checked_files);
+    for path in &verification.missing_files {
+        error!("   缺失: {}", path);
+    }
+    for path in &verification.corrupt_files {
+        error!("   损坏: {}", path);
+    }
+    for path in &verification.unexpected_files {
+        warn!("   清单外的文件: {}", path);
+    }
+
+    if verification.is_valid() {
+        info!("✅ 备份完整");
+        Ok(())
+    } else {
+        error!(
+            "❌ 备份校验失败: {} 个文件缺失, {} 个文件损坏",
+            verification.missing_files.len(),
+            verification.corrupt_files.len()
+        );
+        Err(DuckError::Backup(format!("备份 {backup_id} 校验失败")))
+    }
+}
+
+/// 按保留策略清理旧备份
+pub async fn run_prune_backups(app: &CliApp, dry_run: bool) -> Result<()> {
+    let policy = RetentionPolicy::from_database(&app.database).await?;
+
+    info!("🧹 按保留策略清理备份");
+    info!("====================");
+    if policy.is_empty() {
+        info!("📋 没有配置任何保留规则，不清理备份");
+        info!(
+            "💡 可配置: backup.retention_days、backup.keep_last、backup.keep_daily、backup.keep_weekly、backup.keep_monthly"
+        );
+        return Ok(());
+    }
+
+    info!("📋 保留策略:");
+    if policy.max_age_days > 0 {
+        info!("   保留最近 {} 天内的备份", policy.max_age_days);
+    }
+    if policy.keep_last > 0 {
+        info!("   保留最近 {} 个备份", policy.keep_last);
+    }
+    if policy.keep_daily > 0 {
+        info!("   按天保留: 最近 {} 天", policy.keep_daily);
+    }
+    if policy.keep_weekly > 0 {
+        info!("   按周保留: 最近 {} 周", policy.keep_weekly);
+    }
+    if policy.keep_monthly > 0 {
+        info!("   按月保留: 最近 {} 个月", policy.keep_monthly);
+    }
+    info!("   最近一次成功的升级前备份及增量/差异备份依赖的备份始终保留");
+
+    let backups = app.backup_manager.prune_backups(&policy, dry_run).await?;
+
+    if backups.is_empty() {
+        info!("✅ 没有需要清理的备份");
+        return Ok(());
+    }
+
+    if dry_run {
+        info!("🔍 以下 {} 个备份将被清理（未实际删除）:", backups.len());
+    } else {
+        info!("🗑️ 已清理 {} 个备份:", backups.len());
+    }
+    for backup in &backups {
+        info!(
+            "   {} [{}] {} ({})",
+            backup.id,
+            backup_mode_display(backup.backup_mode),
+            backup.created_at.format("%Y-%m-%d %H:%M:%S"),
+            backup.file_path
+        );
+    }
+    if dry_run {
+        info!("💡 确认后执行: duck-cli backup prune");
+    }
+
+    Ok(())
+}
+
+/// 备份模式的显示名称
+fn backup_mode_display(backup_mode: BackupMode) -> &'static str {
+    match backup_mode {
+        BackupMode::Full => "全量",
+        BackupMode::Incremental => "增量",
+        BackupMode::Differential => "差异",
+    }
+}
+
+/// 显示以指定备份为起点的备份链
+fn print_backup_chain(backups: &[BackupRecord], backup: &BackupRecord, depth: usize) {
+    info!(
+        "{}{} [{}] {} ({})",
+        "   ".repeat(depth),
+        if depth > 1 { "└─" } else { "●" },
+        backup_mode_display(backup.backup_mode),
+        backup.id,
+        backup.created_at.format("%Y-%m-%d %H:%M:%S")
+    );
+    for child in backups
+        .iter()
+        .rev()
+        .filter(|child| child.parent_id == Some(backup.id))
+    {
+        print_backup_chain(backups, child, depth + 1);
+    }
+}
+
 /// 从备份恢复
-pub async fn run_rollback(app: &CliApp, backup_id: i64, force: bool) -> Result<()> {
+///
+/// `only` 和 `services` 都为空时恢复全部数据，否则只恢复指定的路径和服务数据。
+pub async fn run_rollback(
+    app: &CliApp,
+    backup_id: i64,
+    force: bool,
+    only: &[String],
+    services: &[String],
+) -> Result<()> {
+    let mut filter = RestoreFilter::default();
+    for path in only {
+        filter = filter.with_path(path)?;
+    }
+    for service in services {
+        filter = filter.with_service(service)?;
+    }
+
     if !force {
-        warn!("⚠️  警告: 此操作将覆盖当前数据目录!");
+        if filter.is_empty() {
+            warn!("⚠️  警告: 此操作将覆盖当前数据目录!");
+        } else {
+            warn!("⚠️  警告: 此操作将覆盖: {}", filter.paths().join(", "));
+        }
         warn!("⚠️  注意: 此操作只恢复数据文件，不会影响配置文件");
 
         use std::io::{self, Write};
@@ -379,23 +870,32 @@ pub async fn run_rollback(app: &CliApp, backup_id: i64, force: bool) -> Result<(
     info!("开始数据回滚操作...");
 
     // 🔧 智能回滚：只恢复数据，保留配置文件
-    run_data_only_rollback(app, backup_id).await?;
+    run_data_only_rollback(app, backup_id, &filter).await?;
 
     info!("✅ 数据回滚完成");
     Ok(())
 }
 
 /// 只恢复数据的智能回滚
-async fn run_data_only_rollback(app: &CliApp, backup_id: i64) -> Result<()> {
+async fn run_data_only_rollback(
+    app: &CliApp,
+    backup_id: i64,
+    filter: &RestoreFilter,
+) -> Result<()> {
     info!("🛡️ 使用智能数据回滚模式");
-    info!("   📁 将恢复: data/, app/ 目录");
+    if filter.is_empty() {
+        info!("   📁 将恢复: data/, app/ 目录");
+    } else {
+        info!("   📁 将恢复: {}", filter.paths().join(", "));
+        info!("   📌 其他数据保持不变");
+    }
     info!("   🔧 将保留: docker-compose.yml, .env 等配置文件");
 
     // 使用 BackupManager 的智能数据恢复功能
     let docker_dir = std::path::Path::new("./docker");
     match app
         .backup_manager
-        .restore_data_only(backup_id, docker_dir)
+        .restore_data_only(backup_id, docker_dir, filter)
         .await
     {
         Ok(_) => {
@@ -416,15 +916,40 @@ async fn run_data_only_rollback(app: &CliApp, backup_id: i64) -> Result<()> {
                 }
             }
 
+            // MySQL数据没有随备份恢复时（如只恢复了app或Redis数据），回滚升级后的数据库架构
+            match schema_migration::rollback_schema_after_restore(
+                &app.docker_manager,
+                &app.backup_manager,
+                backup_id,
+                filter,
+            )
+            .await
+            {
+                Ok(true) => info!("⏪ 已执行随备份保存的数据库架构回滚脚本"),
+                Ok(false) => {}
+                Err(e) => {
+                    error!("❌ 数据库架构回滚失败: {}", e);
+                    warn!("💡 数据文件已恢复，MySQL仍是升级后的架构，请检查后手动执行回滚脚本");
+                    return Err(e);
+                }
+            }
+
             info!("💡 数据恢复说明:");
-            info!("   ✅ 所有数据库数据已恢复");
+            if filter.is_empty() {
+                info!("   ✅ 所有数据库数据已恢复");
+            } else {
+                info!("   ✅ 已恢复: {}", filter.paths().join(", "));
+            }
             info!("   ✅ 配置文件保持最新版本");
             info!("   ✅ Docker服务已自动启动");
+            if let Ok(pre_restore_dir) = BackupManager::pre_restore_dir(docker_dir) {
+                info!("   🛟 恢复前的数据已保存到: {}", pre_restore_dir.display());
+            }
         }
         Err(e) => {
             error!("❌ 数据恢复失败: {}", e);
             warn!("💡 建议操作:");
-            warn!("   1. 检查备份文件是否存在且完整");
+            warn!("   1. 检查备份文件是否存在且完整（解压或校验失败时现有数据未被修改）");
             warn!("   2. 确保有足够的磁盘空间");
             warn!("   3. 手动启动服务: duck-cli docker-service start");
             return Err(e);
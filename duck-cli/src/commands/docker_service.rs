@@ -1,7 +1,8 @@
 use crate::app::CliApp;
 use crate::cli::DockerServiceCommand;
 use crate::docker_service::{ContainerStatus, DockerService};
-use client_core::Result;
+use client_core::{Result, package_signature};
+use std::path::Path;
 use tracing::{error, info, warn};
 
 /// 运行 Docker 服务相关命令的统一入口
@@ -401,6 +402,9 @@ pub async fn extract_docker_service(
 
     info!("📦 找到Docker服务包: {}", zip_path.display());
 
+    // 解压前校验服务包签名
+    verify_service_package(&zip_path, app.insecure_skip_signature).await?;
+
     // 使用utils中的解压函数
     crate::utils::extract_docker_service(&zip_path).await?;
 
@@ -408,6 +412,25 @@ pub async fn extract_docker_service(
     Ok(())
 }
 
+/// 校验服务包签名，`skip_signature`（`--insecure-skip-signature`）为真时跳过
+///
+/// 必须在修改docker目录之前调用，签名校验失败时已安装的服务文件保持不变。
+pub async fn verify_service_package(zip_path: &Path, skip_signature: bool) -> Result<()> {
+    if skip_signature {
+        warn!("⚠️  已跳过服务包签名校验（--insecure-skip-signature），仅用于开发环境");
+        return Ok(());
+    }
+
+    if let Err(e) = package_signature::verify_package(zip_path).await {
+        error!("❌ 服务包签名校验失败，拒绝解压: {}", e);
+        error!(
+            "💡 请重新下载服务包（duck-cli upgrade --force）；开发环境可使用 --insecure-skip-signature 跳过校验"
+        );
+        return Err(e);
+    }
+    Ok(())
+}
+
 /// 获取系统架构信息
 pub async fn show_architecture_info(_app: &CliApp) -> Result<()> {
     let arch = crate::docker_service::get_system_architecture();
@@ -5,6 +5,7 @@ mod cache;
 mod check_update;
 mod docker_service;
 mod ducker;
+mod schema;
 mod status;
 mod update;
 
@@ -12,10 +13,12 @@ mod update;
 pub use status::{run_api_info, run_status, run_status_details, show_client_version};
 
 // Backup commands
-pub use backup::{run_backup, run_list_backups, run_rollback};
+pub use backup::{
+    backup_mode, handle_backup_command, run_backup, run_list_backups, run_rollback,
+};
 
 // Update commands
-pub use update::run_upgrade;
+pub use update::{run_install_version, run_list_versions, run_upgrade};
 
 // Docker service commands
 pub use docker_service::run_docker_service_command;
@@ -34,3 +37,6 @@ pub use cache::handle_cache_command;
 
 // Check update commands
 pub use check_update::handle_check_update_command;
+
+// Schema commands
+pub use schema::{handle_schema_command, run_schema_diff_files};
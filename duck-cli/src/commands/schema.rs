@@ -0,0 +1,417 @@
+use crate::app::CliApp;
+use crate::cli::{SchemaCommand, SchemaDiffFormat};
+use client_core::{
+    DuckError,
+    constants::{docker, upgrade},
+    error::Result,
+    schema_migration,
+    sql_diff::{
+        ChangeSafety, DatabaseDiff, SchemaDiffReport, TableDiff, analyze_schema_diff_for_database,
+    },
+};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// 比较的一侧：版本标识和SQL内容
+struct SchemaSource {
+    label: String,
+    sql: Option<String>,
+}
+
+/// JSON格式的差异输出
+#[derive(Serialize)]
+struct SchemaDiffOutput<'a> {
+    from: &'a str,
+    to: &'a str,
+    database: Option<&'a str>,
+    max_safety: ChangeSafety,
+    #[serde(flatten)]
+    report: &'a SchemaDiffReport,
+}
+
+/// 处理数据库架构命令
+pub async fn handle_schema_command(app: &CliApp, schema_cmd: SchemaCommand) -> Result<()> {
+    match schema_cmd {
+        SchemaCommand::Diff {
+            from: Some(from),
+            to: Some(to),
+            format,
+            database,
+            ..
+        } => run_schema_diff_files(&from, &to, format, database.as_deref()),
+        SchemaCommand::Diff {
+            package: true,
+            version,
+            file,
+            format,
+            database,
+            ..
+        } => run_schema_diff_package(app, version, file, format, database.as_deref()).await,
+        SchemaCommand::Diff { .. } => Err(DuckError::custom(
+            "请指定 --from <旧SQL> --to <新SQL>，或使用 --package 比较当前部署与已下载的服务包",
+        )),
+    }
+}
+
+/// 比较两个SQL文件的数据库架构差异（不需要加载客户端配置）
+pub fn run_schema_diff_files(
+    from: &Path,
+    to: &Path,
+    format: SchemaDiffFormat,
+    database: Option<&str>,
+) -> Result<()> {
+    let read_sql = |path: &Path| {
+        std::fs::read_to_string(path)
+            .map_err(|e| DuckError::custom(format!("读取SQL文件失败 {}: {e}", path.display())))
+    };
+
+    let from_source = SchemaSource {
+        label: from.display().to_string(),
+        sql: Some(read_sql(from)?),
+    };
+    let to_source = SchemaSource {
+        label: to.display().to_string(),
+        sql: Some(read_sql(to)?),
+    };
+
+    print_schema_diff(&from_source, &to_source, format, database)
+}
+
+/// 比较当前部署的服务包与已下载（尚未部署）的服务包中的MySQL初始化SQL，预览升级对数据库的影响
+async fn run_schema_diff_package(
+    app: &CliApp,
+    version: Option<String>,
+    file: Option<PathBuf>,
+    format: SchemaDiffFormat,
+    database: Option<&str>,
+) -> Result<()> {
+    let current_version = app.config.versions.docker_service.clone();
+
+    let (target_version, zip_path) = match file {
+        Some(file) => (version.unwrap_or_else(|| "downloaded".to_string()), file),
+        None => {
+            let target_version = match version {
+                Some(version) => version,
+                None => {
+                    let version_info = app
+                        .api_client
+                        .check_docker_version(&current_version)
+                        .await
+                        .map_err(|e| {
+                            DuckError::custom(format!(
+                                "获取最新版本信息失败，请使用 --version 指定已下载的版本: {e}"
+                            ))
+                        })?;
+                    version_info.latest_version
+                }
+            };
+            let zip_path = app.config.get_version_download_file_path(
+                &target_version,
+                "full",
+                upgrade::DOCKER_SERVICE_PACKAGE,
+            );
+            (target_version, zip_path)
+        }
+    };
+
+    if !zip_path.exists() {
+        return Err(DuckError::custom(format!(
+            "服务包文件不存在: {}，请先运行 'duck-cli upgrade --full' 下载",
+            zip_path.display()
+        )));
+    }
+
+    info!(
+        "🔍 比较当前部署 ({}) 与服务包 {} ({})",
+        current_version,
+        zip_path.display(),
+        target_version
+    );
+
+    let deployed_sql = schema_migration::load_init_sql(&docker::get_docker_work_dir())?;
+    if deployed_sql.is_none() {
+        warn!("⚠️ 当前部署中没有MySQL初始化SQL，将显示新服务包的完整数据库架构");
+    }
+    let package_sql = schema_migration::load_init_sql_from_package(&zip_path)?
+        .ok_or_else(|| DuckError::custom("服务包中没有MySQL初始化SQL"))?;
+
+    let from_source = SchemaSource {
+        label: current_version,
+        sql: deployed_sql,
+    };
+    let to_source = SchemaSource {
+        label: target_version,
+        sql: Some(package_sql),
+    };
+
+    print_schema_diff(&from_source, &to_source, format, database)
+}
+
+/// 生成并按指定格式输出架构差异（指定 `database` 时只比较该数据库）
+fn print_schema_diff(
+    from: &SchemaSource,
+    to: &SchemaSource,
+    format: SchemaDiffFormat,
+    database: Option<&str>,
+) -> Result<()> {
+    let to_sql = to.sql.as_deref().unwrap_or_default();
+    let report = analyze_schema_diff_for_database(
+        from.sql.as_deref(),
+        to_sql,
+        Some(&from.label),
+        &to.label,
+        database,
+    )?;
+
+    match format {
+        SchemaDiffFormat::Sql => {
+            if report.diff_sql.trim().is_empty() {
+                info!("✅ 数据库架构无变化");
+                return Ok(());
+            }
+            // SQL输出到标准输出，便于重定向到文件（差异SQL按数据库带有 USE 语句）
+            println!("{}", report.diff_sql);
+        }
+        SchemaDiffFormat::Json => {
+            let output = SchemaDiffOutput {
+                from: &from.label,
+                to: &to.label,
+                database,
+                max_safety: report.max_safety(),
+                report: &report,
+            };
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+        SchemaDiffFormat::Summary => print_summary(&report),
+    }
+
+    Ok(())
+}
+
+/// 显示架构差异摘要
+fn print_summary(report: &SchemaDiffReport) {
+    info!("🗄️ 数据库架构差异: {}", report.description);
+
+    let diff = &report.diff;
+    if diff.is_empty() {
+        return;
+    }
+
+    for options in &diff.added_databases {
+        info!("   ➕ 新增数据库: {}", options.name);
+    }
+    for change in &diff.modified_databases {
+        info!("   ✏️ 修改数据库字符集: {}", change.after.name);
+    }
+    for options in &diff.removed_databases {
+        info!("   ➖ 删除数据库: {}", options.name);
+    }
+
+    for database_diff in &diff.databases {
+        info!(
+            "   🗃️ 数据库 {}:",
+            database_diff.database.as_deref().unwrap_or("(默认数据库)")
+        );
+        print_database_summary(database_diff);
+    }
+
+    let count = |safety| report.statements_with_safety(safety).len();
+    info!(
+        "   风险统计: ✅ {} {}，🔒 {} {}，⚠️ {} {}",
+        ChangeSafety::Safe.description(),
+        count(ChangeSafety::Safe),
+        ChangeSafety::Locking.description(),
+        count(ChangeSafety::Locking),
+        ChangeSafety::DataLoss.description(),
+        count(ChangeSafety::DataLoss)
+    );
+    let data_loss_statements = report.statements_with_safety(ChangeSafety::DataLoss);
+    if !data_loss_statements.is_empty() {
+        info!("   ⚠️ 可能导致数据丢失的语句:");
+        for statement in data_loss_statements {
+            info!("      {}", statement.sql);
+        }
+    }
+}
+
+/// 显示单个数据库内的表和对象差异
+fn print_database_summary(diff: &DatabaseDiff) {
+    let table_names = |names: Vec<&str>| names.join(", ");
+    if !diff.added_tables.is_empty() {
+        info!(
+            "      ➕ 新增表: {}",
+            table_names(diff.added_tables.iter().map(|t| t.name.as_str()).collect())
+        );
+    }
+    if !diff.removed_tables.is_empty() {
+        info!(
+            "      ➖ 删除表: {}",
+            table_names(
+                diff.removed_tables
+                    .iter()
+                    .map(|t| t.name.as_str())
+                    .collect()
+            )
+        );
+    }
+    for rename in &diff.renamed_tables {
+        info!("      🔀 重命名表: {} -> {}", rename.from, rename.to);
+    }
+    for table_diff in &diff.modified_tables {
+        info!(
+            "      ✏️ 修改表 {}: {}",
+            table_diff.name,
+            table_changes(table_diff).join("; ")
+        );
+    }
+    for object in &diff.added_objects {
+        info!(
+            "      ➕ 新增{}: {}",
+            object.kind.description(),
+            object.name
+        );
+    }
+    for object in &diff.removed_objects {
+        info!(
+            "      ➖ 删除{}: {}",
+            object.kind.description(),
+            object.name
+        );
+    }
+    for change in &diff.modified_objects {
+        info!(
+            "      ✏️ 修改{}: {}",
+            change.after.kind.description(),
+            change.after.name
+        );
+    }
+    for seed_diff in &diff.seed_data {
+        info!(
+            "      📋 参考数据 {}: 新增 {} 行，更新 {} 行，删除 {} 行",
+            seed_diff.table,
+            seed_diff.inserted_rows.len(),
+            seed_diff.updated_rows.len(),
+            seed_diff.deleted_rows.len()
+        );
+    }
+}
+
+/// 单个表的变更说明
+fn table_changes(table_diff: &TableDiff) -> Vec<String> {
+    let mut changes = Vec::new();
+    let mut push = |label: &str, names: Vec<String>| {
+        if !names.is_empty() {
+            changes.push(format!("{label} {}", names.join(", ")));
+        }
+    };
+
+    push(
+        "重命名列",
+        table_diff
+            .renamed_columns
+            .iter()
+            .map(|rename| format!("{} -> {}", rename.from, rename.to))
+            .collect(),
+    );
+    push(
+        "新增列",
+        table_diff
+            .added_columns
+            .iter()
+            .map(|c| c.name.clone())
+            .collect(),
+    );
+    push(
+        "删除列",
+        table_diff
+            .removed_columns
+            .iter()
+            .map(|c| c.name.clone())
+            .collect(),
+    );
+    push(
+        "修改列",
+        table_diff
+            .modified_columns
+            .iter()
+            .map(|change| change.after.name.clone())
+            .collect(),
+    );
+    push(
+        "调整列顺序",
+        table_diff
+            .moved_columns
+            .iter()
+            .map(|c| c.name.clone())
+            .collect(),
+    );
+    push(
+        "新增索引",
+        table_diff
+            .added_indexes
+            .iter()
+            .map(|i| i.name.clone())
+            .collect(),
+    );
+    push(
+        "删除索引",
+        table_diff
+            .removed_indexes
+            .iter()
+            .map(|i| i.name.clone())
+            .collect(),
+    );
+    push(
+        "修改索引",
+        table_diff
+            .modified_indexes
+            .iter()
+            .map(|change| change.after.name.clone())
+            .collect(),
+    );
+    push(
+        "新增外键",
+        table_diff
+            .added_foreign_keys
+            .iter()
+            .map(|fk| fk.name.clone())
+            .collect(),
+    );
+    push(
+        "删除外键",
+        table_diff
+            .removed_foreign_keys
+            .iter()
+            .map(|fk| fk.name.clone())
+            .collect(),
+    );
+    push(
+        "修改外键",
+        table_diff
+            .modified_foreign_keys
+            .iter()
+            .map(|change| change.after.name.clone())
+            .collect(),
+    );
+    push(
+        "CHECK约束变更",
+        table_diff
+            .added_checks
+            .iter()
+            .chain(table_diff.removed_checks.iter())
+            .chain(
+                table_diff
+                    .modified_checks
+                    .iter()
+                    .map(|change| &change.after),
+            )
+            .map(|check| check.name.clone())
+            .collect(),
+    );
+    if table_diff.options.is_some() {
+        changes.push("表选项变更".to_string());
+    }
+
+    changes
+}
@@ -1,9 +1,20 @@
 use crate::app::CliApp;
-use client_core::error::Result;
+use crate::commands::auto_upgrade_deploy::{self, DeployTarget};
+use client_core::error::{DuckError, Result};
+use client_core::upgrade::compare_service_versions;
+use std::cmp::Ordering;
 use tracing::{error, info, warn};
 
 /// 下载Docker服务升级文件
-pub async fn run_upgrade(app: &mut CliApp, full: bool, force: bool, check: bool) -> Result<()> {
+///
+/// `target_version` 为空时下载最新版本，否则下载指定版本。
+pub async fn run_upgrade(
+    app: &mut CliApp,
+    full: bool,
+    force: bool,
+    check: bool,
+    target_version: Option<&str>,
+) -> Result<()> {
     if check {
         info!("🔍 检查Docker服务升级版本");
         info!("========================");
@@ -55,7 +66,7 @@ pub async fn run_upgrade(app: &mut CliApp, full: bool, force: bool, check: bool)
             }
 
             // 构建基于版本的下载路径
-            let target_version = &version_info.latest_version;
+            let target_version = target_version.unwrap_or(&version_info.latest_version);
             let download_type = "full"; // 暂时只支持全量下载
             let download_path = app.config.get_version_download_file_path(
                 target_version,
@@ -128,6 +139,8 @@ pub async fn run_upgrade(app: &mut CliApp, full: bool, force: bool, check: bool)
 
             if is_first_time {
                 info!("状态: 🆕 首次部署 - 下载完整服务包");
+            } else if target_version != version_info.latest_version {
+                info!("状态: 📌 下载指定版本 {}", target_version);
             } else if version_info.has_update {
                 info!("状态: 🎉 发现新版本，开始下载");
                 if let Some(notes) = version_info.release_notes {
@@ -243,3 +256,111 @@ pub async fn run_upgrade(app: &mut CliApp, full: bool, force: bool, check: bool)
 
     Ok(())
 }
+
+/// 列出可安装的Docker服务版本
+pub async fn run_list_versions(app: &CliApp) -> Result<()> {
+    info!("📋 可安装的Docker服务版本");
+    info!("========================");
+
+    let mut versions = app.api_client.get_docker_version_list().await?.versions;
+    if versions.is_empty() {
+        info!("📭 服务器没有可安装的版本");
+        return Ok(());
+    }
+    versions.sort_by(|a, b| compare_service_versions(&b.version, &a.version));
+
+    let current_version = &app.config.versions.docker_service;
+    for version in &versions {
+        let mut tags = Vec::new();
+        if version.is_latest {
+            tags.push("最新");
+        }
+        if compare_service_versions(&version.version, current_version) == Ordering::Equal {
+            tags.push("当前");
+        }
+        let tags = if tags.is_empty() {
+            String::new()
+        } else {
+            format!(" [{}]", tags.join(", "))
+        };
+
+        info!("🏷️  {} ({}){}", version.version, version.release_date, tags);
+        for line in version.notes.lines() {
+            info!("     {}", line);
+        }
+    }
+
+    info!("💡 运行 'duck-cli upgrade --to <版本>' 安装指定版本");
+    Ok(())
+}
+
+/// 下载并安装指定版本的Docker服务
+///
+/// 低于当前版本时为降级：旧版本无法使用新版本的数据，需要目标版本的升级前备份，
+/// 安装后从该备份恢复数据。
+pub async fn run_install_version(
+    app: &mut CliApp,
+    version: &str,
+    allow_data_loss: bool,
+) -> Result<()> {
+    info!("📦 安装Docker服务指定版本");
+    info!("========================");
+
+    let versions = app.api_client.get_docker_version_list().await?.versions;
+    let Some(target) = versions
+        .iter()
+        .find(|v| compare_service_versions(&v.version, version) == Ordering::Equal)
+    else {
+        error!("❌ 版本列表中没有版本 {}", version);
+        info!("💡 运行 'duck-cli upgrade --list' 查看可安装的版本");
+        return Err(DuckError::Custom(format!("版本列表中没有版本 {version}")));
+    };
+
+    let current_version = app.config.versions.docker_service.clone();
+    let restore_backup_id = match compare_service_versions(&target.version, &current_version) {
+        Ordering::Equal => {
+            info!("✅ 当前已是 {} 版本，无需安装", current_version);
+            return Ok(());
+        }
+        Ordering::Greater => {
+            info!("⬆️  升级: {} -> {}", current_version, target.version);
+            None
+        }
+        Ordering::Less => {
+            warn!("⬇️  降级: {} -> {}", current_version, target.version);
+            let Some(backup) = app
+                .backup_manager
+                .find_pre_upgrade_backup(&target.version)
+                .await?
+            else {
+                error!("❌ 没有找到版本 {} 的升级前备份", target.version);
+                info!("💡 旧版本无法使用新版本的数据，降级需要从该版本升级时创建的升级前备份");
+                info!("   运行 'duck-cli list-backups' 查看已有的备份");
+                return Err(DuckError::Custom(format!(
+                    "降级到 {} 需要该版本的升级前备份用于恢复数据，没有找到可用的备份",
+                    target.version
+                )));
+            };
+            info!(
+                "🛡️  安装后将从升级前备份恢复数据: ID {} ({})",
+                backup.id,
+                backup.created_at.format("%Y-%m-%d %H:%M:%S")
+            );
+            Some(backup.id)
+        }
+    };
+
+    info!("📅 发布日期: {}", target.release_date);
+    if !target.notes.is_empty() {
+        info!("📋 更新说明:");
+        for line in target.notes.lines() {
+            info!("   {}", line);
+        }
+    }
+
+    let target = DeployTarget {
+        version: target.version.clone(),
+        restore_backup_id,
+    };
+    auto_upgrade_deploy::run_auto_upgrade_deploy(app, None, allow_data_loss, Some(target)).await
+}
@@ -24,7 +24,7 @@ pub use environment::EnvironmentChecker;
 #[allow(unused_imports)]
 pub use error::{DockerServiceError, DockerServiceResult};
 #[allow(unused_imports)]
-pub use health_check::{ContainerStatus, HealthReport, ServiceStatus};
+pub use health_check::{ContainerStatus, HealthChecker, HealthReport, ServiceStatus};
 #[allow(unused_imports)]
 pub use image_loader::{ImageInfo, ImageLoader, ImageType, LoadResult, TagResult};
 pub use manager::DockerServiceManager;
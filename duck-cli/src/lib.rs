@@ -11,14 +11,14 @@ mod utils;
 
 // 通过 pub use 精确控制对外暴露的接口
 pub use app::CliApp;
-pub use cli::{Cli, Commands};
-pub use commands::{run_status_details, show_client_version}; // 导出status相关函数
+pub use cli::{Cli, Commands, SchemaCommand, SchemaDiffFormat};
+pub use commands::{run_schema_diff_files, run_status_details, show_client_version}; // 导出status和架构差异相关函数
 pub use docker_service::{
     ContainerStatus, DockerService, DockerServiceManager, get_architecture_suffix,
     get_system_architecture,
 };
 pub use init::run_init;
-pub use utils::{extract_docker_service, setup_logging}; // 导出解压函数
+pub use utils::{extract_docker_service, setup_logging, setup_logging_to_stderr}; // 导出解压和日志函数
 
 // 重新导出核心功能
 pub use client_core::{config_manager::ConfigManager, database_manager::DatabaseManager};
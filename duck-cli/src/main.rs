@@ -1,6 +1,9 @@
 use clap::Parser;
 use client_core::DuckError;
-use duck_cli::{Cli, CliApp, Commands, run_init, setup_logging};
+use duck_cli::{
+    Cli, CliApp, Commands, SchemaCommand, run_init, run_schema_diff_files, setup_logging,
+    setup_logging_to_stderr,
+};
 use std::error::Error;
 use tracing::{error, info};
 
@@ -9,8 +12,12 @@ async fn main() {
     // 解析命令行参数
     let cli = Cli::parse();
 
-    // 设置日志记录
-    setup_logging(cli.verbose);
+    // 设置日志记录（输出SQL、JSON的命令把日志写到stderr，避免混入结果）
+    if cli.command.writes_machine_output() {
+        setup_logging_to_stderr(cli.verbose);
+    } else {
+        setup_logging(cli.verbose);
+    }
 
     // `init` 命令是特例，它不需要预先加载配置
     if let Commands::Init { force } = cli.command {
@@ -21,6 +28,22 @@ async fn main() {
         return;
     }
 
+    // 比较两个SQL文件的架构差异不需要加载配置，便于在CI中使用
+    if let Commands::Schema(SchemaCommand::Diff {
+        from: Some(from),
+        to: Some(to),
+        format,
+        database,
+        ..
+    }) = &cli.command
+    {
+        if let Err(e) = run_schema_diff_files(from, to, *format, database.as_deref()) {
+            error!("❌ 架构差异比较失败: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     // `status` 命令特殊处理：即使应用初始化失败也要显示基本信息
     if let Commands::Status = cli.command {
         // 总是先显示客户端版本信息（内置的，不依赖配置）
@@ -79,6 +102,8 @@ async fn main() {
         }
     };
 
+    app.insecure_skip_signature = cli.insecure_skip_signature;
+
     // 运行命令
     if let Err(e) = app.run_command(cli.command).await {
         error!("❌ 操作失败: {}", e);
@@ -338,14 +338,7 @@ pub fn setup_logging(verbose: bool) {
     #[allow(unused_imports)]
     use tracing_subscriber::{EnvFilter, fmt, util::SubscriberInitExt};
 
-    // 根据verbose参数和环境变量确定日志级别
-    let default_level = if verbose { "debug" } else { "info" };
-    let env_filter = EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| EnvFilter::new(default_level))
-        // 过滤掉第三方库的详细日志，减少噪音
-        .add_directive("reqwest=warn".parse().unwrap())
-        .add_directive("tokio=warn".parse().unwrap())
-        .add_directive("hyper=warn".parse().unwrap());
+    let env_filter = build_env_filter(verbose);
 
     // 检查环境变量，决定是否输出到文件
     if let Ok(log_file) = std::env::var("DUCK_LOG_FILE") {
@@ -376,6 +369,43 @@ pub fn setup_logging(verbose: bool) {
     }
 }
 
+/// 设置日志记录系统，终端日志输出到stderr
+///
+/// 用于向标准输出写入SQL、JSON等机器可读结果的命令，保证标准输出可以直接重定向或解析。
+pub fn setup_logging_to_stderr(verbose: bool) {
+    #[allow(unused_imports)]
+    use tracing_subscriber::{EnvFilter, fmt, util::SubscriberInitExt};
+
+    if std::env::var("DUCK_LOG_FILE").is_ok() {
+        // 日志写入文件时不会影响标准输出
+        setup_logging(verbose);
+        return;
+    }
+
+    fmt()
+        .with_env_filter(build_env_filter(verbose))
+        .with_writer(std::io::stderr)
+        .with_target(false)
+        .with_thread_names(false)
+        .with_line_number(false)
+        .without_time()
+        .compact()
+        .init();
+}
+
+/// 根据verbose参数和环境变量确定日志级别
+fn build_env_filter(verbose: bool) -> tracing_subscriber::EnvFilter {
+    use tracing_subscriber::EnvFilter;
+
+    let default_level = if verbose { "debug" } else { "info" };
+    EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(default_level))
+        // 过滤掉第三方库的详细日志，减少噪音
+        .add_directive("reqwest=warn".parse().unwrap())
+        .add_directive("tokio=warn".parse().unwrap())
+        .add_directive("hyper=warn".parse().unwrap())
+}
+
 /// 为库使用提供的简化日志初始化
 ///
 /// 当duck-cli作为库使用时，可以调用此函数进行最小化的日志配置